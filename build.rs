@@ -1,6 +1,19 @@
-// Pure Rust build - no C compilation needed!
-// All functionality now implemented in Rust using fastga-rs for .1aln reading
+// Compile one_copy.c, which src/aln_writer.rs calls to write filtered .1aln
+// files. ONElib itself is the copy fastga-rs builds and links; one_copy.c only
+// needs its header, so ONElib.h must be the one fastga-rs (at the rev pinned
+// in Cargo.toml) compiles ONElib.c with - the OneFile layout is shared.
 
 fn main() {
-    // Nothing to build - pure Rust implementation
+    for source in ["ONElib.h", "one_copy.c"] {
+        println!("cargo:rerun-if-changed={source}");
+    }
+    // The binary declares its modules itself rather than using the library,
+    // so src/aln_writer.rs links the archive with #[link]; only say where it is
+    cc::Build::new()
+        .file("one_copy.c")
+        .warnings(false)
+        .cargo_metadata(false)
+        .compile("onecopy");
+    let out_dir = std::env::var("OUT_DIR").expect("cargo sets OUT_DIR");
+    println!("cargo:rustc-link-search=native={out_dir}");
 }
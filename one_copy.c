@@ -0,0 +1,31 @@
+/*  File: one_copy.c
+ *    Line copying for alnview's .1aln export (src/aln_writer.rs), which ONElib
+ *    declares as oneWriteLineFrom() but does not implement
+ *
+ *  Compiled against the in-tree ONElib.h but linked to the ONElib that
+ *    fastga-rs builds, so the header must match that copy (see build.rs)
+ */
+
+#include <string.h>
+
+#include "ONElib.h"
+
+//  Write the line most recently read from source to of, which must have been
+//    opened with oneFileOpenWriteFrom (of, source, ...) so both share a schema
+
+void oneCopyLine (OneFile *of, OneFile *source)
+{ char     t  = source->lineType ;
+  OneInfo *li = source->info[(int) t] ;
+
+  memcpy (of->field, source->field, li->nField * sizeof(OneField)) ;
+  if (li->listEltSize > 0)
+    oneWriteLine (of, t, oneLen(source), _oneList(source)) ;
+  else
+    oneWriteLine (of, t, 0, NULL) ;
+}
+
+//  Whether the line just read from of, of type t, is where this file's view of
+//    OneFile puts it; false means ONElib.h does not match the linked ONElib
+
+bool oneCopyLayoutMatches (OneFile *of, char t)
+{ return of->lineType == t ; }
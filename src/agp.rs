@@ -0,0 +1,424 @@
+// AGP scaffolding
+//
+// An AGP file (https://www.ncbi.nlm.nih.gov/assembly/agp/) places contigs in
+// scaffolds. Applied to a plot whose sequences are those contigs, it redraws
+// the plot in scaffold coordinates, so joins, breaks and flips an assembly
+// curator plans can be previewed before the assembly is rebuilt. Contigs the
+// file does not place stay as sequences of their own, after the scaffolds.
+//
+// Format: nine tab-separated columns per line, `object  object_beg  object_end
+// part_number  component_type  component_id  component_beg  component_end
+// orientation`, with '#' comment lines. Gap lines (component type N or U) only
+// take up scaffold length.
+use crate::aln_reader::AlnRecord;
+use crate::rust_plot::RustPlot;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// One contig stretch placed in a scaffold (0-based, end-exclusive)
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgpComponent {
+    pub contig: String,
+    pub start: i64, // In the scaffold
+    pub contig_start: i64,
+    pub contig_end: i64,
+    pub reverse: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgpScaffold {
+    pub name: String,
+    pub length: i64,
+    pub components: Vec<AgpComponent>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Agp {
+    pub scaffolds: Vec<AgpScaffold>, // In file order
+}
+
+/// Where contigs start inside each scaffold, for drawing their boundaries
+/// thinner than the scaffolds'
+#[derive(Clone, Debug, Default)]
+pub struct ContigBoundaries {
+    pub query: HashMap<String, (i64, Vec<i64>)>, // Scaffold -> (length, inner contig starts)
+    pub target: HashMap<String, (i64, Vec<i64>)>,
+}
+
+impl Agp {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read AGP file: {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid AGP file: {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut agp = Self::default();
+        let mut index: HashMap<String, usize> = HashMap::new();
+
+        for (line_no, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+            if fields.len() < 8 {
+                anyhow::bail!(
+                    "line {}: expected 9 tab-separated fields, got {}",
+                    line_no + 1,
+                    fields.len()
+                );
+            }
+            let number = |field: usize| -> Result<i64> {
+                fields[field].parse().with_context(|| {
+                    format!(
+                        "line {}: column {} must be a number, got '{}'",
+                        line_no + 1,
+                        field + 1,
+                        fields[field]
+                    )
+                })
+            };
+            let (object_beg, object_end) = (number(1)?, number(2)?);
+            if object_beg < 1 || object_end < object_beg {
+                anyhow::bail!(
+                    "line {}: bad scaffold range {object_beg}-{object_end}",
+                    line_no + 1
+                );
+            }
+
+            let idx = *index.entry(fields[0].to_string()).or_insert_with(|| {
+                agp.scaffolds.push(AgpScaffold {
+                    name: fields[0].to_string(),
+                    length: 0,
+                    components: Vec::new(),
+                });
+                agp.scaffolds.len() - 1
+            });
+            let scaffold = &mut agp.scaffolds[idx];
+            scaffold.length = scaffold.length.max(object_end);
+
+            if matches!(fields[4], "N" | "U") {
+                continue;
+            }
+            if fields.len() < 9 {
+                anyhow::bail!("line {}: component lines need 9 fields", line_no + 1);
+            }
+            let (contig_beg, contig_end) = (number(6)?, number(7)?);
+            if contig_beg < 1 || contig_end - contig_beg != object_end - object_beg {
+                anyhow::bail!(
+                    "line {}: component range {contig_beg}-{contig_end} does not fit scaffold range {object_beg}-{object_end}",
+                    line_no + 1
+                );
+            }
+            scaffold.components.push(AgpComponent {
+                contig: fields[5].to_string(),
+                start: object_beg - 1,
+                contig_start: contig_beg - 1,
+                contig_end,
+                reverse: fields[8] == "-",
+            });
+        }
+
+        Ok(agp)
+    }
+
+    /// The plot in scaffold coordinates, on each axis where the file places
+    /// any of its sequences, with where the contigs start in each scaffold;
+    /// None if it places none. Alignments are moved with the contig stretch
+    /// they start in.
+    pub fn scaffold(&self, plot: &RustPlot) -> Option<(RustPlot, ContigBoundaries)> {
+        let query = self.axis(&plot.query_sequences, &plot.query_lengths);
+        let target = self.axis(&plot.target_sequences, &plot.target_lengths);
+        if query.is_none() && target.is_none() {
+            return None;
+        }
+
+        let records: Vec<AlnRecord> = plot
+            .segments
+            .iter()
+            .map(|seg| {
+                let mut rec = plot.segment_record(seg);
+                // Work in forward target coordinates, as scaffolds are built
+                let mut reverse = rec.reverse != 0;
+                let (mut target_start, mut target_end) = if reverse {
+                    (
+                        rec.target_len - rec.target_end,
+                        rec.target_len - rec.target_start,
+                    )
+                } else {
+                    (rec.target_start, rec.target_end)
+                };
+                if let Some(ref query) = query {
+                    let (id, start, end, flip) =
+                        query.place(rec.query_id as usize, rec.query_start, rec.query_end);
+                    (rec.query_id, rec.query_start, rec.query_end) = (id as i64, start, end);
+                    reverse ^= flip;
+                }
+                if let Some(ref target) = target {
+                    let (id, start, end, flip) =
+                        target.place(rec.target_id as usize, target_start, target_end);
+                    (rec.target_id, target_start, target_end) = (id as i64, start, end);
+                    rec.target_len = target.lengths[id];
+                    reverse ^= flip;
+                }
+                (rec.target_start, rec.target_end) = if reverse {
+                    (rec.target_len - target_end, rec.target_len - target_start)
+                } else {
+                    (target_start, target_end)
+                };
+                rec.reverse = i32::from(reverse);
+                rec
+            })
+            .collect();
+
+        let names = |axis: &Option<AxisPlacement>, sequences: &[String], lengths: &[i64]| match axis
+        {
+            Some(axis) => (axis.names.clone(), axis.lengths.clone()),
+            None => (sequences.to_vec(), lengths.to_vec()),
+        };
+        let (query_sequences, query_lengths) =
+            names(&query, &plot.query_sequences, &plot.query_lengths);
+        let (target_sequences, target_lengths) =
+            names(&target, &plot.target_sequences, &plot.target_lengths);
+        let mut scaffolded = RustPlot::from_records(
+            &records,
+            query_sequences,
+            target_sequences,
+            query_lengths,
+            target_lengths,
+        );
+        scaffolded.cutoff = plot.cutoff;
+
+        let boundaries = ContigBoundaries {
+            query: query.map(|axis| axis.contig_starts).unwrap_or_default(),
+            target: target.map(|axis| axis.contig_starts).unwrap_or_default(),
+        };
+        Some((scaffolded, boundaries))
+    }
+
+    /// How one axis' sequences map onto scaffolds, if the file places any
+    fn axis(&self, sequences: &[String], lengths: &[i64]) -> Option<AxisPlacement> {
+        let index: HashMap<&str, usize> = sequences
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+        let mut placement = AxisPlacement {
+            names: Vec::new(),
+            lengths: Vec::new(),
+            stretches: vec![Vec::new(); sequences.len()],
+            contig_starts: HashMap::new(),
+        };
+
+        for scaffold in &self.scaffolds {
+            let placed: Vec<(usize, &AgpComponent)> = scaffold
+                .components
+                .iter()
+                .filter_map(|c| Some((*index.get(c.contig.as_str())?, c)))
+                .collect();
+            if placed.is_empty() {
+                continue;
+            }
+            let id = placement.names.len();
+            placement.names.push(scaffold.name.clone());
+            placement.lengths.push(scaffold.length);
+            for &(contig, component) in &placed {
+                placement.stretches[contig].push((id, component.clone()));
+            }
+            let starts = scaffold
+                .components
+                .iter()
+                .map(|c| c.start)
+                .filter(|&start| start > 0)
+                .collect();
+            placement
+                .contig_starts
+                .insert(scaffold.name.clone(), (scaffold.length, starts));
+        }
+        if placement.names.is_empty() {
+            return None;
+        }
+
+        // Unplaced contigs follow as they are
+        for (contig, name) in sequences.iter().enumerate() {
+            if placement.stretches[contig].is_empty() {
+                let length = lengths.get(contig).copied().unwrap_or(0);
+                placement.stretches[contig].push((
+                    placement.names.len(),
+                    AgpComponent {
+                        contig: name.clone(),
+                        start: 0,
+                        contig_start: 0,
+                        contig_end: length,
+                        reverse: false,
+                    },
+                ));
+                placement.names.push(name.clone());
+                placement.lengths.push(length);
+            }
+        }
+        Some(placement)
+    }
+}
+
+impl ContigBoundaries {
+    /// Exchange the axes, as when the plot's axes are swapped
+    pub fn transposed(self) -> Self {
+        Self {
+            query: self.target,
+            target: self.query,
+        }
+    }
+
+    /// Inner contig boundaries along (query, target) of `plot` as laid out
+    pub fn for_plot(&self, plot: &RustPlot) -> (Vec<i64>, Vec<i64>) {
+        (
+            Self::positions(
+                &self.query,
+                &plot.query_sequences,
+                &plot.query_boundaries,
+                &plot.query_reversed,
+            ),
+            Self::positions(
+                &self.target,
+                &plot.target_sequences,
+                &plot.target_boundaries,
+                &plot.target_reversed,
+            ),
+        )
+    }
+
+    /// Axis coordinates of the inner contig boundaries of the scaffolds as
+    /// laid out (`starts` is `query` or `target`), following flips and
+    /// equal-width stretching
+    pub fn positions(
+        starts: &HashMap<String, (i64, Vec<i64>)>,
+        sequences: &[String],
+        boundaries: &[i64],
+        reversed: &[bool],
+    ) -> Vec<i64> {
+        let mut positions = Vec::new();
+        for (idx, name) in sequences.iter().enumerate() {
+            let Some((length, inner)) = starts.get(name) else {
+                continue;
+            };
+            let (offset, width) = (boundaries[idx], boundaries[idx + 1] - boundaries[idx]);
+            for &start in inner {
+                let local = (start as i128 * width as i128 / (*length).max(1) as i128) as i64;
+                let flipped = reversed.get(idx).copied().unwrap_or(false);
+                positions.push(offset + if flipped { width - local } else { local });
+            }
+        }
+        positions
+    }
+}
+
+/// Scaffolds of one axis and where each of its contigs' stretches went
+struct AxisPlacement {
+    names: Vec<String>,
+    lengths: Vec<i64>,
+    stretches: Vec<Vec<(usize, AgpComponent)>>, // Per contig: (scaffold, component)
+    contig_starts: HashMap<String, (i64, Vec<i64>)>,
+}
+
+impl AxisPlacement {
+    /// Scaffold, interval and whether it was flipped for `start..end` on
+    /// `contig`, by the stretch holding its start
+    fn place(&self, contig: usize, start: i64, end: i64) -> (usize, i64, i64, bool) {
+        let stretches = &self.stretches[contig];
+        let (id, c) = stretches
+            .iter()
+            .find(|(_, c)| c.contig_start <= start && start < c.contig_end)
+            .unwrap_or(&stretches[0]);
+        if c.reverse {
+            (
+                *id,
+                c.start + c.contig_end - end,
+                c.start + c.contig_end - start,
+                true,
+            )
+        } else {
+            (
+                *id,
+                c.start + start - c.contig_start,
+                c.start + end - c.contig_start,
+                false,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aln_reader::test_record;
+
+    #[test]
+    fn test_scaffold_places_contigs() {
+        let agp = Agp::parse(
+            "# curated\n\
+             scaf1\t1\t1000\t1\tW\tctgA\t1\t1000\t+\n\
+             scaf1\t1001\t1100\t2\tN\t100\tscaffold\tyes\tpaired-ends\n\
+             scaf1\t1101\t1600\t3\tW\tctgB\t1\t500\t-\n",
+        )
+        .unwrap();
+        assert_eq!(agp.scaffolds[0].length, 1600);
+        assert_eq!(agp.scaffolds[0].components.len(), 2);
+
+        // ctgA and ctgB against one reference; ctgC is not placed
+        let records = vec![
+            test_record(0, 0, 100, 0, 0, 100),
+            test_record(1, 0, 100, 0, 1000, 1100),
+            test_record(2, 0, 100, 0, 0, 100),
+        ];
+        let plot = RustPlot::from_records(
+            &records,
+            vec!["ctgA".into(), "ctgB".into(), "ctgC".into()],
+            vec!["chr1".into()],
+            vec![1000, 500, 200],
+            vec![5000],
+        );
+        let (scaffolded, boundaries) = agp.scaffold(&plot).unwrap();
+        assert_eq!(scaffolded.query_sequences, vec!["scaf1", "ctgC"]);
+        assert_eq!(scaffolded.query_lengths, vec![1600, 200]);
+        assert_eq!(scaffolded.target_sequences, vec!["chr1"]);
+        assert!(boundaries.target.is_empty());
+
+        // ctgB's 0..100 lands at the end of the scaffold, reversed
+        let rec = scaffolded.segment_record(&scaffolded.segments[1]);
+        assert_eq!(
+            (rec.query_id, rec.query_start, rec.query_end),
+            (0, 1500, 1600)
+        );
+        assert_eq!(rec.reverse, 1);
+        assert_eq!(
+            (
+                rec.target_len - rec.target_end,
+                rec.target_len - rec.target_start
+            ),
+            (1000, 1100)
+        );
+        assert_eq!(
+            scaffolded.segment_record(&scaffolded.segments[2]).query_id,
+            1
+        );
+
+        assert_eq!(
+            ContigBoundaries::positions(
+                &boundaries.query,
+                &scaffolded.query_sequences,
+                &scaffolded.query_boundaries,
+                &[true, false],
+            ),
+            vec![1600 - 1100]
+        );
+
+        let elsewhere =
+            RustPlot::from_records(&[], vec!["x".into()], vec!["y".into()], vec![1], vec![1]);
+        assert!(agp.scaffold(&elsewhere).is_none());
+        assert!(Agp::parse("scaf1\t1\t100\t1\tW\tctgA\t1\t50\t+\n").is_err());
+    }
+}
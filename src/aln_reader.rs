@@ -3,6 +3,16 @@ use anyhow::{Context, Result};
 use fastga_rs::AlnReader;
 use std::path::Path;
 
+/// Give up on a damaged file after this many unreadable records in a row
+const MAX_CONSECUTIVE_ERRORS: usize = 16;
+
+/// Records read between progress reports
+const PROGRESS_INTERVAL: usize = 1 << 16;
+
+/// Rough size of one record in a .1aln file, for estimating a file's record
+/// count from its size before reading it
+pub const ESTIMATED_RECORD_BYTES: u64 = 256;
+
 #[derive(Debug, Clone)]
 pub struct AlnRecord {
     pub query_id: i64,
@@ -23,6 +33,37 @@ pub struct AlnRecord {
     pub diffs: i32,
 }
 
+/// Problems met while reading a possibly damaged file
+#[derive(Debug, Clone, Default)]
+pub struct ReadReport {
+    /// Records that failed to parse or had impossible coordinates
+    pub skipped: usize,
+    /// Error that stopped reading before the end of the file (e.g. truncation)
+    pub error: Option<String>,
+    /// Reading was stopped by the caller
+    pub cancelled: bool,
+}
+
+impl ReadReport {
+    pub fn is_clean(&self) -> bool {
+        self.skipped == 0 && self.error.is_none()
+    }
+
+    /// One-line description for warnings, given how many records were loaded
+    pub fn summary(&self, loaded: usize) -> String {
+        let mut text = format!("Loaded {loaded} alignments");
+        if self.skipped > 0 {
+            text.push_str(&format!("; skipped {} unreadable records", self.skipped));
+        }
+        if let Some(ref error) = self.error {
+            text.push_str(&format!(
+                "; stopped early, the file may be truncated ({error})"
+            ));
+        }
+        text
+    }
+}
+
 pub struct AlnFile {
     reader: AlnReader,
     pub query_sequences: Vec<String>,
@@ -119,6 +160,74 @@ impl AlnFile {
         }
         Ok(records)
     }
+
+    /// Read every record that can be read, skipping unparseable or implausible
+    /// ones instead of failing, and stopping at a run of errors (a truncated file)
+    pub fn read_records_lenient(&mut self) -> (Vec<AlnRecord>, ReadReport) {
+        self.read_records_with_progress(|_| true)
+    }
+
+    /// `read_records_lenient`, telling `progress` how many records have been
+    /// read every so often; reading stops, with the report marked cancelled,
+    /// once it returns false
+    pub fn read_records_with_progress(
+        &mut self,
+        mut progress: impl FnMut(usize) -> bool,
+    ) -> (Vec<AlnRecord>, ReadReport) {
+        let mut records = Vec::new();
+        let mut report = ReadReport::default();
+        let mut pending_errors = 0;
+
+        let mut read = 0;
+        loop {
+            read += 1;
+            if read % PROGRESS_INTERVAL == 0 && !progress(read) {
+                report.cancelled = true;
+                break;
+            }
+            match self.read_record() {
+                Ok(Some(rec)) => {
+                    // Errors followed by good records were isolated bad records
+                    report.skipped += pending_errors;
+                    pending_errors = 0;
+                    if is_plausible(
+                        &rec,
+                        self.query_sequences.len(),
+                        self.target_sequences.len(),
+                    ) {
+                        records.push(rec);
+                    } else {
+                        report.skipped += 1;
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    pending_errors += 1;
+                    if pending_errors >= MAX_CONSECUTIVE_ERRORS {
+                        report.error = Some(format!("{e:#}"));
+                        break;
+                    }
+                }
+            }
+        }
+        if pending_errors > 0 && report.error.is_none() {
+            report.error = Some("unreadable data at the end of the file".to_string());
+        }
+
+        (records, report)
+    }
+}
+
+/// Whether a record's ids and coordinates could be real (with `n_query` /
+/// `n_target` known sequence names, 0 if the file has none)
+fn is_plausible(rec: &AlnRecord, n_query: usize, n_target: usize) -> bool {
+    let id_ok = |id: i64, known: usize| id >= 0 && (known == 0 || (id as usize) < known);
+    id_ok(rec.query_id, n_query)
+        && id_ok(rec.target_id, n_target)
+        && 0 <= rec.query_start
+        && rec.query_start <= rec.query_end
+        && 0 <= rec.target_start
+        && rec.target_start <= rec.target_end
 }
 
 /// Calculate identity for an alignment record
@@ -130,3 +239,59 @@ pub fn calculate_identity(rec: &AlnRecord) -> f64 {
     let matches = aln_len - rec.diffs as f64;
     100.0 * matches / aln_len
 }
+
+/// A forward alignment with no differences between two intervals, for tests;
+/// set other fields with struct update syntax
+#[cfg(test)]
+pub(crate) fn test_record(
+    query_id: i64,
+    query_start: i64,
+    query_end: i64,
+    target_id: i64,
+    target_start: i64,
+    target_end: i64,
+) -> AlnRecord {
+    AlnRecord {
+        query_id,
+        target_id,
+        query_name: String::new(),
+        target_name: String::new(),
+        query_len: 0,
+        target_len: 0,
+        query_start,
+        query_end,
+        target_start,
+        target_end,
+        reverse: 0,
+        diffs: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_plausible() {
+        assert!(is_plausible(&test_record(0, 0, 100, 0, 0, 100), 1, 1));
+        assert!(is_plausible(&test_record(5, 0, 100, 0, 0, 100), 0, 0));
+        assert!(!is_plausible(&test_record(1, 0, 100, 0, 0, 100), 1, 1));
+        assert!(!is_plausible(&test_record(-1, 0, 100, 0, 0, 100), 0, 0));
+        assert!(!is_plausible(&test_record(0, 100, 50, 0, 0, 100), 1, 1));
+    }
+
+    #[test]
+    fn test_report_summary() {
+        let report = ReadReport {
+            skipped: 2,
+            error: Some("unexpected end of file".to_string()),
+            cancelled: false,
+        };
+        assert!(!report.is_clean());
+        assert_eq!(
+            report.summary(10),
+            "Loaded 10 alignments; skipped 2 unreadable records; stopped early, the file may be truncated (unexpected end of file)"
+        );
+        assert!(ReadReport::default().is_clean());
+    }
+}
@@ -0,0 +1,271 @@
+// Writing a subset of a .1aln file's alignments
+//
+// The subset is copied from the original file through ONElib (the copy
+// fastga-rs links, plus one_copy.c from build.rs) rather than rebuilt from the plot: the header, provenance and
+// sequence skeleton go over unchanged, then each alignment object - its A
+// line and the lines after it (strand, differences, trace points...) - only
+// if it is kept. The copy keeps every field of the alignments it holds, and
+// FastGA's tools and alnview open it like any other .1aln.
+use crate::aln_reader::AlnFile;
+use crate::rust_plot::RustPlot;
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int, c_void};
+use std::path::Path;
+
+#[repr(C)]
+struct OneFile {
+    _private: [u8; 0],
+}
+
+// Linked with fastga-rs, which builds ONElib for reading .1aln
+extern "C" {
+    fn oneFileOpenRead(
+        path: *const c_char,
+        schema: *mut c_void,
+        file_type: *const c_char,
+        nthreads: c_int,
+    ) -> *mut OneFile;
+    fn oneFileOpenWriteFrom(
+        path: *const c_char,
+        source: *mut OneFile,
+        is_binary: bool,
+        nthreads: c_int,
+    ) -> *mut OneFile;
+    fn oneAddProvenance(
+        of: *mut OneFile,
+        prog: *const c_char,
+        version: *const c_char,
+        format: *const c_char,
+        ...
+    ) -> bool;
+    fn oneReadLine(of: *mut OneFile) -> c_char;
+    fn oneFileClose(of: *mut OneFile);
+    fn oneErrorString() -> *mut c_char;
+}
+
+#[link(name = "onecopy", kind = "static")] // one_copy.c, built by build.rs
+extern "C" {
+    fn oneCopyLine(of: *mut OneFile, source: *mut OneFile);
+    fn oneCopyLayoutMatches(of: *mut OneFile, line_type: c_char) -> bool;
+}
+
+/// An open ONE file, closed when dropped
+struct Handle(*mut OneFile);
+
+impl Handle {
+    fn new(of: *mut OneFile, what: &str, path: &Path) -> Result<Self> {
+        if of.is_null() {
+            // SAFETY: ONElib's error text is a static buffer, set when an open fails
+            let error = unsafe { CStr::from_ptr(oneErrorString()) };
+            bail!(
+                "Failed to {what} {}: {}",
+                path.display(),
+                error.to_string_lossy().trim()
+            );
+        }
+        Ok(Self(of))
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        // SAFETY: the pointer came from a successful open and is closed once
+        unsafe { oneFileClose(self.0) };
+    }
+}
+
+/// Whether `path` names a .1aln file, the only kind a subset can be written from
+pub fn is_1aln_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "1aln")
+}
+
+fn c_path(path: &Path) -> Result<CString> {
+    CString::new(path.to_string_lossy().as_bytes())
+        .with_context(|| format!("Unusable path {}", path.display()))
+}
+
+/// Copy the alignments of .1aln `source` for which `keep` (given each one's
+/// index in the file, from 0) is true to a new .1aln at `dest`, returning how
+/// many alignments `source` holds and how many were written
+pub fn write_subset(
+    source: &Path,
+    dest: &Path,
+    mut keep: impl FnMut(usize) -> bool,
+) -> Result<(usize, usize)> {
+    if dest.exists() && dest.canonicalize()? == source.canonicalize()? {
+        bail!(
+            "Refusing to overwrite {} with a subset of itself",
+            source.display()
+        );
+    }
+    let (source_c, dest_c) = (c_path(source)?, c_path(dest)?);
+    let command = std::env::args().collect::<Vec<_>>().join(" ");
+    let command = CString::new(command.replace('\0', " ")).unwrap_or_default();
+    let version = CString::new(env!("CARGO_PKG_VERSION")).unwrap_or_default();
+    // SAFETY: the paths are NUL-terminated and outlive the calls; both
+    // handles stay open until the copy is done, the output closing first
+    unsafe {
+        let input = Handle::new(
+            oneFileOpenRead(source_c.as_ptr(), std::ptr::null_mut(), c"aln".as_ptr(), 1),
+            "open",
+            source,
+        )?;
+        let output = Handle::new(
+            oneFileOpenWriteFrom(dest_c.as_ptr(), input.0, true, 1),
+            "create",
+            dest,
+        )?;
+        oneAddProvenance(
+            output.0,
+            c"alnview".as_ptr(),
+            version.as_ptr(),
+            c"%s".as_ptr(),
+            command.as_ptr(),
+        );
+
+        // Lines before the first alignment (sequences, chains) are kept; the
+        // lines after an A belong to it until the next A or chain
+        let (mut alignments, mut written, mut copying) = (0, 0, true);
+        loop {
+            let line_type = oneReadLine(input.0);
+            if alignments == 0 && line_type != 0 && !oneCopyLayoutMatches(input.0, line_type) {
+                drop(output);
+                std::fs::remove_file(dest).ok();
+                bail!(
+                    "ONElib.h does not match the ONElib fastga-rs was built with; nothing written"
+                );
+            }
+            match line_type as u8 {
+                0 => break,
+                b'A' => {
+                    copying = keep(alignments);
+                    alignments += 1;
+                    written += usize::from(copying);
+                }
+                b'a' => copying = true,
+                _ => {}
+            }
+            if copying {
+                oneCopyLine(output.0, input.0);
+            }
+        }
+        drop(output);
+        Ok((alignments, written))
+    }
+}
+
+/// Copy the alignments of .1aln `source` that `plot` (read from it, then
+/// swept, filtered or rearranged) still holds and passes its cutoff to a new
+/// .1aln at `dest`, returning how many were written
+///
+/// Alignments are matched by sequences, coordinates and strand, so the plot
+/// may come from the file or from its segment index.
+pub fn write_plot(source: &Path, plot: &RustPlot, dest: &Path) -> Result<usize> {
+    type Key = (String, String, i64, i64, i64, i64, i32);
+    let mut wanted: HashMap<Key, usize> = HashMap::new();
+    for seg in plot.segments.iter().filter(|seg| plot.passes(seg)) {
+        let rec = plot.segment_record(seg);
+        let key = (
+            rec.query_name,
+            rec.target_name,
+            rec.query_start,
+            rec.query_end,
+            rec.target_start,
+            rec.target_end,
+            rec.reverse,
+        );
+        *wanted.entry(key).or_default() += 1;
+    }
+
+    let records = AlnFile::open(source)?
+        .read_all_records()
+        .with_context(|| format!("Failed to read {}", source.display()))?;
+    let (alignments, written) = write_subset(source, dest, |idx| {
+        let Some(rec) = records.get(idx) else {
+            return false;
+        };
+        let key = (
+            rec.query_name.clone(),
+            rec.target_name.clone(),
+            rec.query_start,
+            rec.query_end,
+            rec.target_start,
+            rec.target_end,
+            rec.reverse,
+        );
+        match wanted.get_mut(&key) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    })?;
+    if alignments != records.len() {
+        std::fs::remove_file(dest).ok();
+        bail!(
+            "{} holds {alignments} alignments but {} could be read; nothing written",
+            source.display(),
+            records.len()
+        );
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rust_plot::SegmentFilter;
+
+    #[test]
+    fn test_subset_keeps_whole_alignments() {
+        let source = Path::new("test.1aln");
+        let dest = std::env::temp_dir().join(format!("alnview-subset-{}.1aln", std::process::id()));
+        let mut plot = RustPlot::from_file(source).unwrap();
+        plot.cutoff = SegmentFilter {
+            min_identity: 99.0,
+            ..SegmentFilter::default()
+        };
+        let passing = plot.passing_segments();
+        assert!(passing > 0 && passing < plot.segments.len());
+
+        let written = write_plot(source, &plot, &dest).unwrap();
+        let copy = AlnFile::open(&dest).unwrap().read_all_records().unwrap();
+        std::fs::remove_file(&dest).ok();
+        assert_eq!((written, copy.len()), (passing, passing));
+
+        // The same alignments, differences and all, in file order
+        let original = AlnFile::open(source).unwrap().read_all_records().unwrap();
+        let kept: Vec<_> = original
+            .iter()
+            .zip(&plot.segments)
+            .filter(|(_, seg)| plot.passes(seg))
+            .map(|(rec, _)| rec)
+            .collect();
+        for (rec, orig) in copy.iter().zip(kept) {
+            assert_eq!(
+                (rec.query_name.as_str(), rec.target_name.as_str()),
+                (orig.query_name.as_str(), orig.target_name.as_str())
+            );
+            assert_eq!(
+                (
+                    rec.query_start,
+                    rec.query_end,
+                    rec.target_start,
+                    rec.target_end
+                ),
+                (
+                    orig.query_start,
+                    orig.query_end,
+                    orig.target_start,
+                    orig.target_end
+                )
+            );
+            assert_eq!((rec.reverse, rec.diffs), (orig.reverse, orig.diffs));
+        }
+
+        assert!(write_plot(source, &plot, source).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+// Text dot plots for terminals and job logs
+//
+// Each character is a 2x4 block of Unicode braille dots, so a plot W
+// characters wide has 2W dots across, with rows added to keep the genomes'
+// aspect ratio. Alignments are traced dot by dot; sequence starts are marked
+// on the axes and the query names written under the plot where they fit.
+use crate::rust_plot::{format_bp, RustPlot};
+
+const MIN_WIDTH: usize = 10;
+
+/// Widest target name column on the left
+const MAX_LABEL_WIDTH: usize = 12;
+
+/// Braille bit for the dot in column (0-1) and row (0-3, top down) of a cell
+const DOT_BITS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+/// The plot's passing alignments as a braille dot plot `width` characters
+/// wide (plus the target name column), query along x and target up y
+pub fn to_braille(plot: &RustPlot, width: usize) -> String {
+    let width = width.max(MIN_WIDTH);
+    let (genome_x, genome_y) = (
+        plot.query_genome_len.max(1) as f64,
+        plot.target_genome_len.max(1) as f64,
+    );
+    let dots_x = width * 2;
+    let rows = ((dots_x as f64 * genome_y / genome_x / 4.0).ceil() as usize).clamp(1, width);
+    let dots_y = rows * 4;
+    let (bp_x, bp_y) = (genome_x / dots_x as f64, genome_y / dots_y as f64);
+
+    let mut cells = vec![0u8; width * rows];
+    let mut set = |x: f64, y: f64| {
+        let dx = ((x / bp_x) as usize).min(dots_x - 1);
+        let dy = dots_y - 1 - ((y / bp_y) as usize).min(dots_y - 1); // Top down
+        cells[(dy / 4) * width + dx / 2] |= DOT_BITS[dx % 2][dy % 4];
+    };
+    for seg in plot.segments.iter().filter(|seg| plot.passes(seg)) {
+        let (x0, y0) = (seg.abeg as f64, seg.bbeg as f64);
+        let (x1, y1) = (seg.aend as f64, seg.bend as f64);
+        let steps = ((x1 - x0).abs() / bp_x)
+            .max((y1 - y0).abs() / bp_y)
+            .ceil()
+            .max(1.0) as usize;
+        for i in 0..=steps {
+            let t = i as f64 / steps as f64;
+            set(x0 + t * (x1 - x0), y0 + t * (y1 - y0));
+        }
+    }
+
+    // Target names on the row each sequence starts in
+    let label_width = plot
+        .target_sequences
+        .iter()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(MAX_LABEL_WIDTH);
+    let mut labels = vec![None; rows];
+    for (name, &start) in plot.target_sequences.iter().zip(&plot.target_boundaries) {
+        let row = rows - 1 - ((start as f64 / bp_y) as usize / 4).min(rows - 1);
+        labels[row].get_or_insert(name.as_str());
+    }
+
+    let mut text = String::new();
+    for (row, label) in labels.iter().enumerate() {
+        let name: String = label.unwrap_or("").chars().take(label_width).collect();
+        text.push_str(&format!("{name:>label_width$} "));
+        text.push(if label.is_some() { '┤' } else { '│' });
+        for &bits in &cells[row * width..(row + 1) * width] {
+            text.push(match bits {
+                0 => ' ',
+                bits => char::from_u32(0x2800 + bits as u32).unwrap_or(' '),
+            });
+        }
+        text.push('\n');
+    }
+
+    // X axis with a tick at each query start, and the names that fit
+    let columns: Vec<usize> = plot
+        .query_boundaries
+        .iter()
+        .take(plot.query_sequences.len())
+        .map(|&start| ((start as f64 / bp_x) as usize / 2).min(width - 1))
+        .collect();
+    let mut axis: Vec<char> = vec!['─'; width];
+    for &col in &columns {
+        axis[col] = '┬';
+    }
+    text.push_str(&format!("{:label_width$} └", ""));
+    text.extend(axis);
+    text.push('\n');
+
+    let mut names = vec![' '; width];
+    let mut free = 0; // First column not yet written
+    for (idx, (name, &col)) in plot.query_sequences.iter().zip(&columns).enumerate() {
+        if col < free {
+            continue;
+        }
+        let end = columns.get(idx + 1).copied().unwrap_or(width).max(col + 1);
+        for (offset, c) in name.chars().take(end - col).enumerate() {
+            if col + offset < width {
+                names[col + offset] = c;
+            }
+        }
+        free = end + 1;
+    }
+    text.push_str(&format!("{:label_width$}  ", ""));
+    text.push_str(names.iter().collect::<String>().trim_end());
+    text.push('\n');
+
+    text.push_str(&format!(
+        "x: {} query sequences, {}; y: {} target sequences, {}; one dot ≈ {}\n",
+        plot.query_sequences.len(),
+        format_bp(plot.query_genome_len),
+        plot.target_sequences.len(),
+        format_bp(plot.target_genome_len),
+        format_bp(bp_x.max(bp_y).round() as i64)
+    ));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aln_reader::test_record;
+
+    #[test]
+    fn test_diagonal_fills_corners() {
+        let records = vec![test_record(0, 0, 1000, 0, 0, 1000)];
+        let plot = RustPlot::from_records(
+            &records,
+            vec!["q1".to_string()],
+            vec!["t1".to_string()],
+            vec![1000],
+            vec![1000],
+        );
+
+        let text = to_braille(&plot, 10);
+        let lines: Vec<&str> = text.lines().collect();
+        // 20 x 20 dots: 5 rows, then the axis, names and summary lines
+        assert_eq!(lines.len(), 8);
+        // Bottom-left dot of the bottom row, top-right dot of the top row
+        assert_eq!(lines[4].chars().nth(4), Some('⡠'));
+        assert_eq!(lines[0].chars().last(), Some('⠊'));
+        assert!(lines[4].starts_with("t1 ┤"));
+        assert_eq!(lines[5], "   └┬─────────");
+        assert_eq!(lines[6], "    q1");
+    }
+}
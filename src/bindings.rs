@@ -0,0 +1,328 @@
+// Mouse bindings
+//
+// Which modifier keys and mouse gestures pan, zoom, box-zoom, box-select and
+// sum up boxes on the canvas, so the viewer can follow habits from other dotplot tools
+// (gnuplot zooms with Ctrl+wheel, IGV drags to pan, D-GENIES drags a box).
+// Kept next to the presets ($XDG_CONFIG_HOME/alnview/bindings.tsv, else
+// ~/.config/alnview/bindings.tsv) as tab-separated `action  binding` lines;
+// actions not listed keep their default:
+//
+//   pan       drag
+//   zoom      wheel
+//   box_zoom  shift+drag
+//   select    shift+ctrl+drag
+//   region    shift+alt+drag
+//   stats     ctrl+drag
+//
+// A binding is any of shift+, ctrl+ (Cmd on macOS) and alt+ before `drag`,
+// `right-drag`, `middle-drag` or, for zoom only, `wheel`.
+use crate::presets::PresetStore;
+use anyhow::{Context, Result};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gesture {
+    Drag,
+    RightDrag,
+    MiddleDrag,
+    Wheel,
+}
+
+impl Gesture {
+    pub const ALL: [Gesture; 4] = [
+        Gesture::Drag,
+        Gesture::RightDrag,
+        Gesture::MiddleDrag,
+        Gesture::Wheel,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Gesture::Drag => "drag",
+            Gesture::RightDrag => "right-drag",
+            Gesture::MiddleDrag => "middle-drag",
+            Gesture::Wheel => "wheel",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Binding {
+    pub modifiers: Modifiers,
+    pub gesture: Gesture,
+}
+
+impl Binding {
+    const fn new(shift: bool, ctrl: bool, alt: bool, gesture: Gesture) -> Self {
+        Self {
+            modifiers: Modifiers { shift, ctrl, alt },
+            gesture,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut modifiers = Modifiers::default();
+        let mut parts: Vec<&str> = text.trim().split('+').map(str::trim).collect();
+        let gesture = parts.pop().unwrap_or_default().to_ascii_lowercase();
+        let gesture = Gesture::ALL
+            .into_iter()
+            .find(|g| g.name() == gesture)
+            .with_context(|| {
+                format!("'{text}' must end in drag, right-drag, middle-drag or wheel")
+            })?;
+        for part in parts {
+            match part.to_ascii_lowercase().as_str() {
+                "shift" => modifiers.shift = true,
+                "ctrl" | "cmd" => modifiers.ctrl = true,
+                "alt" => modifiers.alt = true,
+                other => anyhow::bail!("unknown modifier '{other}' in '{text}'"),
+            }
+        }
+        Ok(Self { modifiers, gesture })
+    }
+
+    /// Whether the binding fires with exactly `held` down
+    pub fn matches(&self, held: Modifiers, gesture: Gesture) -> bool {
+        self.modifiers == held && self.gesture == gesture
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [
+            (self.modifiers.shift, "shift+"),
+            (self.modifiers.ctrl, "ctrl+"),
+            (self.modifiers.alt, "alt+"),
+        ] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+        f.write_str(self.gesture.name())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Pan,
+    Zoom,
+    BoxZoom,
+    Select,
+    Region,
+    Stats,
+}
+
+impl Action {
+    pub const ALL: [Action; 6] = [
+        Action::Pan,
+        Action::Zoom,
+        Action::BoxZoom,
+        Action::Select,
+        Action::Region,
+        Action::Stats,
+    ];
+
+    /// Name in the bindings file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Pan => "pan",
+            Action::Zoom => "zoom",
+            Action::BoxZoom => "box_zoom",
+            Action::Select => "select",
+            Action::Region => "region",
+            Action::Stats => "stats",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Pan => "Pan",
+            Action::Zoom => "Zoom",
+            Action::BoxZoom => "Box zoom",
+            Action::Select => "Select alignments in a box",
+            Action::Region => "Save a box as a region",
+            Action::Stats => "Sum up the alignments in a box",
+        }
+    }
+
+    fn default_binding(self) -> Binding {
+        match self {
+            Action::Pan => Binding::new(false, false, false, Gesture::Drag),
+            Action::Zoom => Binding::new(false, false, false, Gesture::Wheel),
+            Action::BoxZoom => Binding::new(true, false, false, Gesture::Drag),
+            Action::Select => Binding::new(true, true, false, Gesture::Drag),
+            Action::Region => Binding::new(true, false, true, Gesture::Drag),
+            Action::Stats => Binding::new(false, true, false, Gesture::Drag),
+        }
+    }
+}
+
+/// The binding of each action, in `Action::ALL` order
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bindings {
+    bindings: [Binding; 6],
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL.map(Action::default_binding),
+        }
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, action: Action) -> Binding {
+        self.bindings[action as usize]
+    }
+
+    pub fn set(&mut self, action: Action, binding: Binding) {
+        self.bindings[action as usize] = binding;
+    }
+
+    /// The action a gesture with `held` down starts, if any
+    pub fn action(&self, held: Modifiers, gesture: Gesture) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|&action| self.get(action).matches(held, gesture))
+    }
+
+    /// Zoom takes the wheel and nothing else does; no two actions share a
+    /// binding
+    pub fn validate(&self) -> Result<()> {
+        for action in Action::ALL {
+            let binding = self.get(action);
+            if (action == Action::Zoom) != (binding.gesture == Gesture::Wheel) {
+                anyhow::bail!(
+                    "{} can't be bound to {binding}: only zoom uses the wheel",
+                    action.name()
+                );
+            }
+            if let Some(other) = Action::ALL[..action as usize]
+                .iter()
+                .find(|&&other| self.get(other) == binding)
+            {
+                anyhow::bail!(
+                    "{} and {} are both bound to {binding}",
+                    other.name(),
+                    action.name()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Where bindings are kept, beside the presets
+    pub fn default_path() -> Option<PathBuf> {
+        Some(PresetStore::default_path()?.with_file_name("bindings.tsv"))
+    }
+
+    /// Bindings from the default path; none yet is not an error
+    pub fn load_default() -> Result<Self> {
+        match Self::default_path() {
+            Some(path) if path.exists() => Self::load(path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn save_default(&self) -> Result<()> {
+        let path = Self::default_path().context("No config directory (HOME is not set)")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        self.save(path)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read bindings: {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid bindings file: {}", path.display()))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, self.to_text())
+            .with_context(|| format!("Failed to write bindings: {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut bindings = Self::default();
+        for (line_no, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 2 {
+                anyhow::bail!("line {}: expected 'action<TAB>binding'", line_no + 1);
+            }
+            let action = Action::ALL
+                .into_iter()
+                .find(|action| action.name() == fields[0].trim())
+                .with_context(|| format!("line {}: unknown action '{}'", line_no + 1, fields[0]))?;
+            let binding =
+                Binding::parse(fields[1]).with_context(|| format!("line {}", line_no + 1))?;
+            bindings.set(action, binding);
+        }
+        bindings.validate()?;
+        Ok(bindings)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# alnview mouse bindings\n");
+        for action in Action::ALL {
+            text.push_str(&format!("{}\t{}\n", action.name(), self.get(action)));
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bindings_round_trip() {
+        let bindings =
+            Bindings::parse("# gnuplot habits\nzoom\tctrl+wheel\nbox_zoom\tright-drag\n").unwrap();
+        let zoom = bindings.get(Action::Zoom);
+        assert_eq!(zoom.to_string(), "ctrl+wheel");
+        let held = Modifiers {
+            ctrl: true,
+            ..Default::default()
+        };
+        assert_eq!(bindings.action(held, Gesture::Wheel), Some(Action::Zoom));
+        assert_eq!(
+            bindings.action(Modifiers::default(), Gesture::RightDrag),
+            Some(Action::BoxZoom)
+        );
+        assert_eq!(bindings.action(Modifiers::default(), Gesture::Wheel), None);
+        assert_eq!(Bindings::parse(&bindings.to_text()).unwrap(), bindings);
+        assert_eq!(
+            Bindings::default().get(Action::Select).to_string(),
+            "shift+ctrl+drag"
+        );
+        assert_eq!(
+            Bindings::default().action(held, Gesture::Drag),
+            Some(Action::Stats)
+        );
+
+        assert!(Bindings::parse("pan\tshift+drag\n")
+            .unwrap_err()
+            .to_string()
+            .contains("pan and box_zoom"));
+        assert!(Bindings::parse("pan\twheel\n").is_err());
+        assert!(Bindings::parse("pan\tmeta+drag\n").is_err());
+        assert!(Bindings::parse("spin\tdrag\n").is_err());
+    }
+}
@@ -0,0 +1,255 @@
+// Segment colour maps
+//
+// A layer draws its segments in its strand colours unless it colours them by
+// identity: each segment's percent identity is then placed between the low and
+// high ends of the layer's identity range and looked up in a colour map, as
+// minidot and D-GENIES do. Viridis and magma stay readable in greyscale and to
+// colour-blind readers; a custom map blends two chosen colours.
+//
+// A palette sets all of these at once: strand colours, the identity colour map
+// and the colours query sequences are told apart by. Okabe-Ito and the viridis
+// and magma palettes are safe for every common form of colour blindness.
+
+/// Viridis sampled at nine evenly spaced points
+const VIRIDIS: [[u8; 3]; 9] = [
+    [68, 1, 84],
+    [71, 45, 123],
+    [59, 82, 139],
+    [44, 114, 142],
+    [33, 145, 140],
+    [40, 174, 128],
+    [94, 201, 98],
+    [173, 220, 48],
+    [253, 231, 37],
+];
+
+/// Magma sampled at nine evenly spaced points
+const MAGMA: [[u8; 3]; 9] = [
+    [0, 0, 4],
+    [28, 16, 68],
+    [81, 18, 124],
+    [131, 38, 129],
+    [183, 55, 121],
+    [231, 82, 99],
+    [252, 137, 97],
+    [254, 194, 135],
+    [252, 253, 191],
+];
+
+/// Kelly's colours of maximum contrast, less the ones too dark to see on a
+/// black canvas
+const KELLY: [[u8; 3]; 17] = [
+    [243, 195, 0],
+    [135, 86, 146],
+    [243, 132, 0],
+    [161, 202, 241],
+    [190, 0, 50],
+    [194, 178, 128],
+    [132, 132, 130],
+    [0, 136, 86],
+    [230, 143, 172],
+    [0, 103, 165],
+    [249, 147, 121],
+    [96, 78, 151],
+    [246, 166, 0],
+    [179, 68, 108],
+    [220, 211, 0],
+    [141, 182, 0],
+    [226, 88, 34],
+];
+
+/// Okabe and Ito's colour-blind safe set, with grey for its black
+const OKABE_ITO: [[u8; 3]; 8] = [
+    [230, 159, 0],
+    [86, 180, 233],
+    [0, 158, 115],
+    [240, 228, 66],
+    [0, 114, 178],
+    [213, 94, 0],
+    [204, 121, 167],
+    [153, 153, 153],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Colormap {
+    Viridis,
+    Magma,
+    TwoColor([u8; 3], [u8; 3]), // Low, high
+}
+
+impl Colormap {
+    /// Name in preset files and menus; a custom map's colours are kept apart
+    pub fn name(&self) -> &'static str {
+        match self {
+            Colormap::Viridis => "viridis",
+            Colormap::Magma => "magma",
+            Colormap::TwoColor(..) => "custom",
+        }
+    }
+
+    /// The map called `name`, with `low` and `high` for a custom one
+    pub fn from_name(name: &str, low: [u8; 3], high: [u8; 3]) -> Option<Self> {
+        match name {
+            "viridis" => Some(Colormap::Viridis),
+            "magma" => Some(Colormap::Magma),
+            "custom" => Some(Colormap::TwoColor(low, high)),
+            _ => None,
+        }
+    }
+
+    /// Colour at `t` from 0 (low end) to 1 (high end)
+    pub fn color(&self, t: f32) -> [u8; 3] {
+        match self {
+            Colormap::Viridis => sample(&VIRIDIS, t),
+            Colormap::Magma => sample(&MAGMA, t),
+            Colormap::TwoColor(low, high) => sample(&[*low, *high], t),
+        }
+    }
+}
+
+/// Where a layer's identities fall on its colour map
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IdentityGradient {
+    pub colormap: Colormap,
+    pub min_identity: f32, // Percent; lower identities get the low end
+    pub max_identity: f32,
+}
+
+impl Default for IdentityGradient {
+    fn default() -> Self {
+        Self {
+            colormap: Colormap::Viridis,
+            min_identity: 70.0,
+            max_identity: 100.0,
+        }
+    }
+}
+
+impl IdentityGradient {
+    pub fn color(&self, identity: f32) -> [u8; 3] {
+        let span = self.max_identity - self.min_identity;
+        let t = if span > 0.0 {
+            (identity - self.min_identity) / span
+        } else {
+            f32::from(identity >= self.max_identity)
+        };
+        self.colormap.color(t)
+    }
+}
+
+/// Colours for everything the viewer colours by category
+#[derive(Debug, PartialEq)]
+pub struct Palette {
+    pub name: &'static str,
+    pub forward: [u8; 3],
+    pub reverse: [u8; 3],
+    pub colormap: Colormap,             // For identity
+    pub categories: &'static [[u8; 3]], // For query sequences, used in turn
+}
+
+pub const PALETTES: [Palette; 4] = [
+    Palette {
+        name: "Classic",
+        forward: [0, 255, 0], // Green/red like the C version
+        reverse: [255, 0, 0],
+        colormap: Colormap::Viridis,
+        categories: &KELLY,
+    },
+    Palette {
+        name: "Okabe-Ito",
+        forward: OKABE_ITO[1],
+        reverse: OKABE_ITO[0],
+        colormap: Colormap::TwoColor(OKABE_ITO[4], OKABE_ITO[3]),
+        categories: &OKABE_ITO,
+    },
+    // Neighbouring categories alternate between the ends of the map
+    Palette {
+        name: "Viridis",
+        forward: VIRIDIS[8],
+        reverse: VIRIDIS[4],
+        colormap: Colormap::Viridis,
+        categories: &[
+            VIRIDIS[8], VIRIDIS[3], VIRIDIS[6], VIRIDIS[2], VIRIDIS[7], VIRIDIS[4], VIRIDIS[5],
+        ],
+    },
+    Palette {
+        name: "Magma",
+        forward: MAGMA[7],
+        reverse: MAGMA[4],
+        colormap: Colormap::Magma,
+        categories: &[MAGMA[8], MAGMA[3], MAGMA[6], MAGMA[4], MAGMA[7], MAGMA[5]],
+    },
+];
+
+impl Palette {
+    /// Colour of category `idx`; colours repeat once all are used
+    pub fn category(&self, idx: usize) -> [u8; 3] {
+        self.categories[idx % self.categories.len()]
+    }
+}
+
+/// Linear interpolation between evenly spaced `stops`
+fn sample(stops: &[[u8; 3]], t: f32) -> [u8; 3] {
+    let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+    let low = (position as usize).min(stops.len() - 2);
+    let frac = position - low as f32;
+    let channel = |c: usize| {
+        let (from, to) = (stops[low][c] as f32, stops[low + 1][c] as f32);
+        (from + (to - from) * frac).round() as u8
+    };
+    [channel(0), channel(1), channel(2)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_gradient() {
+        let gradient = IdentityGradient::default();
+        assert_eq!(gradient.color(50.0), VIRIDIS[0]);
+        assert_eq!(gradient.color(100.0), VIRIDIS[8]);
+        assert_eq!(gradient.color(85.0), VIRIDIS[4]);
+
+        let custom = IdentityGradient {
+            colormap: Colormap::TwoColor([0, 0, 0], [200, 100, 0]),
+            min_identity: 90.0,
+            max_identity: 100.0,
+        };
+        assert_eq!(custom.color(95.0), [100, 50, 0]);
+        assert_eq!(
+            Colormap::from_name("custom", [0; 3], [1; 3])
+                .unwrap()
+                .name(),
+            "custom"
+        );
+        assert_eq!(
+            Colormap::from_name("magma", [0; 3], [0; 3])
+                .unwrap()
+                .color(1.0),
+            MAGMA[8]
+        );
+        assert!(Colormap::from_name("jet", [0; 3], [0; 3]).is_none());
+
+        // An empty range splits at its identity instead of dividing by zero
+        let step = IdentityGradient {
+            min_identity: 99.0,
+            max_identity: 99.0,
+            ..gradient
+        };
+        assert_eq!(step.color(98.0), VIRIDIS[0]);
+        assert_eq!(step.color(99.0), VIRIDIS[8]);
+    }
+
+    #[test]
+    fn test_palettes_tell_categories_apart() {
+        for palette in &PALETTES {
+            assert_ne!(palette.forward, palette.reverse, "{}", palette.name);
+            for (i, a) in palette.categories.iter().enumerate() {
+                assert!(!palette.categories[i + 1..].contains(a), "{}", palette.name);
+            }
+            let count = palette.categories.len();
+            assert_eq!(palette.category(count + 1), palette.category(1));
+        }
+    }
+}
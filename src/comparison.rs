@@ -0,0 +1,163 @@
+// Diff overlay of a second alignment file over the same genomes
+//
+// Segments of the second file are projected into the primary plot's coordinate
+// system and paired up by coordinate overlap, so each segment can be classed as
+// present in both files, only the first, or only the second.
+use crate::aln_reader::AlnFile;
+use crate::rust_plot::{AlignmentSegment, RustPlot};
+use anyhow::Result;
+use rstar::primitives::{GeomWithData, Rectangle};
+use rstar::{RTree, AABB};
+use std::path::Path;
+
+/// Minimum overlap (as a fraction of the shorter segment, on both axes) for two
+/// segments to count as the same alignment
+pub const DEFAULT_MIN_OVERLAP: f64 = 0.5;
+
+/// Where a segment is found when comparing two alignment files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Presence {
+    Both,
+    OnlyFirst,
+    OnlySecond,
+}
+
+/// A second alignment file laid over a primary plot
+pub struct Comparison {
+    /// Second file's segments, in the primary plot's genome-wide coordinates
+    pub segments: Vec<AlignmentSegment>,
+    /// Per primary segment: also present in the second file
+    pub first_shared: Vec<bool>,
+    /// Per comparison segment: also present in the primary file
+    pub second_shared: Vec<bool>,
+    /// Records of the second file whose sequences are not in the primary plot
+    pub skipped_records: usize,
+}
+
+impl Comparison {
+    /// Load a second .1aln file and compare it against `primary`
+    pub fn load<P: AsRef<Path>>(path: P, primary: &RustPlot, min_overlap: f64) -> Result<Self> {
+        let mut aln_file = AlnFile::open(path)?;
+        let records = aln_file.read_all_records()?;
+        let (segments, skipped_records) = primary.project_records(&records);
+        let (first_shared, second_shared) =
+            match_segments(&primary.segments, &segments, min_overlap);
+
+        Ok(Self {
+            segments,
+            first_shared,
+            second_shared,
+            skipped_records,
+        })
+    }
+
+    /// Presence class of a primary segment
+    pub fn first_presence(&self, idx: usize) -> Presence {
+        if self.first_shared.get(idx).copied().unwrap_or(false) {
+            Presence::Both
+        } else {
+            Presence::OnlyFirst
+        }
+    }
+
+    /// Count segments in each class: (both, only first, only second)
+    ///
+    /// "Both" counts primary segments, so it is comparable with "only first".
+    pub fn counts(&self) -> (usize, usize, usize) {
+        let both = self.first_shared.iter().filter(|&&s| s).count();
+        let only_first = self.first_shared.len() - both;
+        let only_second = self.second_shared.iter().filter(|&&s| !s).count();
+        (both, only_first, only_second)
+    }
+}
+
+/// Pair up segments of two sets that overlap on both axes with the same strand
+///
+/// Returns, for each segment of `first` and of `second`, whether it has a partner
+/// in the other set.
+pub fn match_segments(
+    first: &[AlignmentSegment],
+    second: &[AlignmentSegment],
+    min_overlap: f64,
+) -> (Vec<bool>, Vec<bool>) {
+    let mut first_shared = vec![false; first.len()];
+    let mut second_shared = vec![false; second.len()];
+
+    let tree = RTree::bulk_load(
+        second
+            .iter()
+            .enumerate()
+            .map(|(idx, seg)| GeomWithData::new(Rectangle::from_aabb(bbox(seg)), idx))
+            .collect(),
+    );
+
+    for (idx, seg) in first.iter().enumerate() {
+        for candidate in tree.locate_in_envelope_intersecting(&bbox(seg)) {
+            let other = &second[candidate.data];
+            if other.reverse == seg.reverse && overlaps(seg, other, min_overlap) {
+                first_shared[idx] = true;
+                second_shared[candidate.data] = true;
+            }
+        }
+    }
+
+    (first_shared, second_shared)
+}
+
+fn bbox(seg: &AlignmentSegment) -> AABB<[f64; 2]> {
+    AABB::from_corners(
+        [seg.abeg.min(seg.aend) as f64, seg.bbeg.min(seg.bend) as f64],
+        [seg.abeg.max(seg.aend) as f64, seg.bbeg.max(seg.bend) as f64],
+    )
+}
+
+/// Overlap on each axis must cover `min_overlap` of the shorter interval
+fn overlaps(a: &AlignmentSegment, b: &AlignmentSegment, min_overlap: f64) -> bool {
+    let axis = |a0: i64, a1: i64, b0: i64, b1: i64| {
+        let (alo, ahi) = (a0.min(a1), a0.max(a1));
+        let (blo, bhi) = (b0.min(b1), b0.max(b1));
+        let shared = (ahi.min(bhi) - alo.max(blo)).max(0) as f64;
+        let shorter = (ahi - alo).min(bhi - blo).max(1) as f64;
+        shared / shorter >= min_overlap
+    };
+    axis(a.abeg, a.aend, b.abeg, b.aend) && axis(a.bbeg, a.bend, b.bbeg, b.bend)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(abeg: i64, aend: i64, bbeg: i64, bend: i64, reverse: bool) -> AlignmentSegment {
+        AlignmentSegment {
+            abeg,
+            aend,
+            bbeg,
+            bend,
+            reverse,
+            identity: 100.0,
+        }
+    }
+
+    #[test]
+    fn test_match_segments_classifies_by_overlap() {
+        let first = vec![seg(0, 100, 0, 100, false), seg(500, 600, 500, 600, false)];
+        let second = vec![
+            seg(10, 110, 10, 110, false),
+            seg(900, 1000, 900, 1000, false),
+        ];
+
+        let (first_shared, second_shared) = match_segments(&first, &second, DEFAULT_MIN_OVERLAP);
+        assert_eq!(first_shared, vec![true, false]);
+        assert_eq!(second_shared, vec![true, false]);
+    }
+
+    #[test]
+    fn test_match_segments_requires_same_strand_and_enough_overlap() {
+        let first = vec![seg(0, 100, 0, 100, false), seg(0, 100, 200, 300, false)];
+        let second = vec![seg(0, 100, 100, 0, true), seg(80, 180, 280, 380, false)];
+
+        let (first_shared, second_shared) = match_segments(&first, &second, DEFAULT_MIN_OVERLAP);
+        assert_eq!(first_shared, vec![false, false]);
+        assert_eq!(second_shared, vec![false, false]);
+    }
+}
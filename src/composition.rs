@@ -0,0 +1,117 @@
+// Sequence composition tracks
+//
+// GC content of each FASTA sequence in fixed-size windows, looked up by
+// sequence name and local position so it can be drawn along a dotplot axis
+// in whatever order and orientation the sequences are laid out. Windows
+// with no A/C/G/T bases (all N, say) have no value.
+use crate::kmer_plot::{read_fasta, FastaRecord};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Default window size for GC tracks
+pub const DEFAULT_GC_WINDOW: usize = 1000;
+
+pub struct GcTrack {
+    pub window: usize,
+    windows: HashMap<String, Vec<Option<f32>>>, // Keyed by the first word of the FASTA header
+}
+
+impl GcTrack {
+    pub fn load<P: AsRef<Path>>(path: P, window: usize) -> Result<Self> {
+        Ok(Self::from_records(&read_fasta(path)?, window))
+    }
+
+    pub fn from_records(records: &[FastaRecord], window: usize) -> Self {
+        let window = window.max(1);
+        let windows = records
+            .iter()
+            .map(|rec| {
+                (
+                    first_word(&rec.name).to_string(),
+                    gc_windows(&rec.seq, window),
+                )
+            })
+            .collect();
+        Self { window, windows }
+    }
+
+    /// GC fraction (0-1) of the window holding `pos` on sequence `name`, if
+    /// the sequence is known and the window has any called bases
+    pub fn gc_at(&self, name: &str, pos: i64) -> Option<f32> {
+        if pos < 0 {
+            return None;
+        }
+        let windows = self.windows.get(first_word(name))?;
+        windows.get(pos as usize / self.window).copied().flatten()
+    }
+
+    /// GC fraction of the window `fraction` (0-1) of the way along sequence
+    /// `name`, for plots that draw every sequence at the same width
+    pub fn gc_at_fraction(&self, name: &str, fraction: f64) -> Option<f32> {
+        if !(0.0..1.0).contains(&fraction) {
+            return None;
+        }
+        let windows = self.windows.get(first_word(name))?;
+        windows
+            .get((fraction * windows.len() as f64) as usize)
+            .copied()
+            .flatten()
+    }
+
+    /// Number of sequences in the track
+    pub fn sequences(&self) -> usize {
+        self.windows.len()
+    }
+}
+
+/// GC fraction of each window of a sequence, ignoring bases other than A/C/G/T
+pub fn gc_windows(seq: &[u8], window: usize) -> Vec<Option<f32>> {
+    seq.chunks(window.max(1))
+        .map(|chunk| {
+            let (mut gc, mut called) = (0usize, 0usize);
+            for &base in chunk {
+                match base.to_ascii_uppercase() {
+                    b'G' | b'C' => {
+                        gc += 1;
+                        called += 1;
+                    }
+                    b'A' | b'T' => called += 1,
+                    _ => {}
+                }
+            }
+            (called > 0).then(|| gc as f32 / called as f32)
+        })
+        .collect()
+}
+
+/// .1aln and PAF files usually name sequences by the header's first word
+fn first_word(name: &str) -> &str {
+    name.split_whitespace().next().unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gc_windows() {
+        let windows = gc_windows(b"GGCCATATNNNNgcat", 4);
+        assert_eq!(windows, vec![Some(1.0), Some(0.0), None, Some(0.5)]);
+    }
+
+    #[test]
+    fn test_lookup_by_first_word() {
+        let records = vec![FastaRecord {
+            name: "chr1 some description".to_string(),
+            seq: b"AAAAGGGG".to_vec(),
+        }];
+        let track = GcTrack::from_records(&records, 4);
+        assert_eq!(track.gc_at("chr1", 5), Some(1.0));
+        assert_eq!(track.gc_at("chr1 other text", 0), Some(0.0));
+        assert_eq!(track.gc_at("chr1", 8), None);
+        assert_eq!(track.gc_at("chr2", 0), None);
+        assert_eq!(track.gc_at_fraction("chr1", 0.75), Some(1.0));
+        assert_eq!(track.gc_at_fraction("chr1", 1.0), None);
+    }
+}
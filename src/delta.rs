@@ -0,0 +1,221 @@
+// MUMmer delta input
+//
+// nucmer and promer write .delta files: a line naming the reference and
+// query FASTA files, the program name, then for each sequence pair a header
+// `>REF QRY REF_LEN QRY_LEN` and its alignments. Each alignment is a line
+// `S1 E1 S2 E2 ERRORS SIMILARITY_ERRORS STOPS` (1-based and inclusive, start
+// after end on the reverse strand) followed by its indel offsets, ended by 0.
+// The delta's query goes along x and its reference up y, as for PAF.
+use crate::aln_reader::{AlnRecord, ReadReport};
+use crate::input;
+use crate::paf::SequenceIds;
+use crate::rust_plot::RustPlot;
+use anyhow::{Context, Result};
+use std::io::BufRead;
+use std::path::Path;
+
+/// Whether `path` names a delta file (gzipped or not), by its extension
+pub fn is_delta_path(path: &Path) -> bool {
+    input::uncompressed_name(path)
+        .extension()
+        .is_some_and(|ext| ext == "delta")
+}
+
+/// A whole .delta file: sequences in order of appearance and every readable
+/// alignment
+pub struct DeltaFile {
+    pub query_sequences: Vec<String>,
+    pub target_sequences: Vec<String>,
+    pub query_lengths: Vec<i64>,
+    pub target_lengths: Vec<i64>,
+    pub records: Vec<AlnRecord>,
+    /// Alignment lines skipped for bad numbers or coordinates outside their
+    /// sequences
+    pub report: ReadReport,
+}
+
+impl DeltaFile {
+    pub fn open(path: &Path) -> Result<Self> {
+        let (reader, _) = input::open(path)?;
+        Self::parse(reader)
+            .with_context(|| format!("Failed to read .delta file: {}", path.display()))
+    }
+
+    pub fn to_plot(&self) -> RustPlot {
+        RustPlot::from_records(
+            &self.records,
+            self.query_sequences.clone(),
+            self.target_sequences.clone(),
+            self.query_lengths.clone(),
+            self.target_lengths.clone(),
+        )
+    }
+
+    /// Parse delta text; fails only if the header isn't a delta header
+    pub fn parse<R: BufRead>(reader: R) -> Result<Self> {
+        let mut lines = reader.lines();
+        lines.next().context("empty file")??; // FASTA paths
+        let program = lines.next().context("missing program line")??;
+        if !matches!(program.trim(), "NUCMER" | "PROMER") {
+            anyhow::bail!("not a MUMmer delta file (expected NUCMER or PROMER on line 2)");
+        }
+
+        let (mut query, mut target) = (SequenceIds::default(), SequenceIds::default());
+        let mut records = Vec::new();
+        let mut report = ReadReport::default();
+        let mut pair = None; // (query id, target id) of the current header
+        for line in lines {
+            let line = line?;
+            if let Some(header) = line.strip_prefix('>') {
+                let fields: Vec<&str> = header.split_whitespace().collect();
+                pair = match fields[..] {
+                    [reference, sequence, reference_len, sequence_len] => {
+                        match (reference_len.parse::<i64>(), sequence_len.parse::<i64>()) {
+                            (Ok(reference_len), Ok(sequence_len)) => Some((
+                                query.id(sequence, sequence_len),
+                                target.id(reference, reference_len),
+                            )),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                if pair.is_none() {
+                    report.skipped += 1;
+                }
+                continue;
+            }
+
+            // Alignment lines have seven numbers; indel offsets have one
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 7 {
+                continue;
+            }
+            let record = pair.and_then(|(query_id, target_id)| {
+                alignment(
+                    &fields,
+                    query_id,
+                    target_id,
+                    &query.names[query_id],
+                    query.lengths[query_id],
+                    &target.names[target_id],
+                    target.lengths[target_id],
+                )
+            });
+            match record {
+                Some(record) => records.push(record),
+                None => report.skipped += 1,
+            }
+        }
+
+        Ok(Self {
+            query_sequences: query.names,
+            target_sequences: target.names,
+            query_lengths: query.lengths,
+            target_lengths: target.lengths,
+            records,
+            report,
+        })
+    }
+}
+
+/// Load a .delta file as a plot, skipping alignments that can't be read
+pub fn from_file(path: &Path) -> Result<(RustPlot, ReadReport)> {
+    let delta = DeltaFile::open(path)?;
+    Ok((delta.to_plot(), delta.report))
+}
+
+/// One alignment line as a record, or None if it doesn't fit its sequences
+fn alignment(
+    fields: &[&str],
+    query_id: usize,
+    target_id: usize,
+    query_name: &str,
+    query_len: i64,
+    target_name: &str,
+    target_len: i64,
+) -> Option<AlnRecord> {
+    let numbers: Vec<i64> = fields
+        .iter()
+        .map(|field| field.parse().ok())
+        .collect::<Option<_>>()?;
+    let (reference_start, reference_end) = (numbers[0], numbers[1]);
+    let (query_start, query_end) = (numbers[2], numbers[3]);
+
+    // Half-open forward intervals; promer can reverse either side
+    let forward = |start: i64, end: i64| (start.min(end) - 1, start.max(end));
+    let reverse = (reference_start > reference_end) != (query_start > query_end);
+    let (query_start, query_end) = forward(query_start, query_end);
+    let (target_start, target_end) = forward(reference_start, reference_end);
+    let within = |start: i64, end: i64, len: i64| 0 <= start && start < end && end <= len;
+    if !within(query_start, query_end, query_len) || !within(target_start, target_end, target_len) {
+        return None;
+    }
+
+    // Reverse alignments count target coordinates from the end of the
+    // sequence, as in .1aln records
+    let (target_start, target_end) = if reverse {
+        (target_len - target_end, target_len - target_start)
+    } else {
+        (target_start, target_end)
+    };
+
+    Some(AlnRecord {
+        query_id: query_id as i64,
+        target_id: target_id as i64,
+        query_name: query_name.to_string(),
+        target_name: target_name.to_string(),
+        query_len,
+        target_len,
+        query_start,
+        query_end,
+        target_start,
+        target_end,
+        reverse: i32::from(reverse),
+        diffs: numbers[4].clamp(0, i32::MAX as i64) as i32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_nucmer_delta() {
+        let delta = "/data/ref.fa /data/qry.fa
+NUCMER
+>chr1 contig1 1000 500
+1 100 1 100 2 2 0
+-10
+0
+201 300 400 301 0 0 0
+0
+>chr1 contig2 1000 200
+1 50 1 bad 0 0 0
+0
+";
+        let delta = DeltaFile::parse(delta.as_bytes()).unwrap();
+        assert_eq!(delta.report.skipped, 1);
+        let plot = delta.to_plot();
+        assert_eq!(plot.query_sequences, vec!["contig1", "contig2"]);
+        assert_eq!(plot.query_lengths, vec![500, 200]);
+        assert_eq!(plot.target_sequences, vec!["chr1"]);
+        assert_eq!(plot.segments.len(), 2);
+
+        let seg = &plot.segments[0];
+        assert!(!seg.reverse);
+        assert_eq!((seg.abeg, seg.aend, seg.bbeg, seg.bend), (0, 100, 0, 100));
+        assert!((seg.identity - 98.0).abs() < 1e-3);
+        // Query 300..400 on the reverse strand of chr1 200..300
+        let seg = &plot.segments[1];
+        assert!(seg.reverse);
+        assert_eq!(
+            (seg.abeg, seg.aend, seg.bbeg, seg.bend),
+            (300, 400, 300, 200)
+        );
+
+        assert!(DeltaFile::parse("a b\nSOMETHING\n".as_bytes()).is_err());
+        assert!(is_delta_path(Path::new("out.delta")));
+        assert!(is_delta_path(Path::new("out.delta.gz")));
+    }
+}
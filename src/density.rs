@@ -0,0 +1,165 @@
+// Alignment density grids
+//
+// For the density heatmap: aligned bases per cell of a grid laid over the
+// viewport, so millions of alignments draw as one image rather than a line
+// each. Every alignment is clipped to the grid and walked cell by cell, its
+// query length shared among the cells it crosses. The walk runs here on the
+// CPU, or as a compute shader with the `gpu` feature (gpu_density.rs); both
+// take the same clipped lines.
+use crate::rust_plot::RustPlot;
+
+/// The genome rectangle a grid covers: `columns` x `rows` cells of
+/// `bp_per_cell` from (x, y) up and to the right
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DensityView {
+    pub x: f64,
+    pub y: f64,
+    pub bp_per_cell: f64,
+    pub columns: usize,
+    pub rows: usize,
+}
+
+/// An alignment clipped to the grid, in cell units, with the query bases of
+/// the clipped part
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CellLine {
+    pub from: [f32; 2],
+    pub to: [f32; 2],
+    pub weight: f32,
+}
+
+/// Aligned bases per cell, row 0 at the bottom (lowest target coordinate)
+#[derive(Clone, Debug, PartialEq)]
+pub struct DensityGrid {
+    pub columns: usize,
+    pub rows: usize,
+    pub cells: Vec<f32>,
+}
+
+impl DensityGrid {
+    pub fn max(&self) -> f32 {
+        self.cells.iter().copied().fold(0.0, f32::max)
+    }
+}
+
+/// The plot's passing alignments clipped to `view`
+pub fn cell_lines(plot: &RustPlot, view: &DensityView) -> Vec<CellLine> {
+    let (width, height) = (view.columns as f64, view.rows as f64);
+    plot.segments
+        .iter()
+        .filter(|seg| plot.passes(seg))
+        .filter_map(|seg| {
+            let from = (
+                (seg.abeg as f64 - view.x) / view.bp_per_cell,
+                (seg.bbeg as f64 - view.y) / view.bp_per_cell,
+            );
+            let to = (
+                (seg.aend as f64 - view.x) / view.bp_per_cell,
+                (seg.bend as f64 - view.y) / view.bp_per_cell,
+            );
+            let (t0, t1) = clip(from, to, width, height)?;
+            let at = |t: f64| {
+                [
+                    (from.0 + t * (to.0 - from.0)) as f32,
+                    (from.1 + t * (to.1 - from.1)) as f32,
+                ]
+            };
+            Some(CellLine {
+                from: at(t0),
+                to: at(t1),
+                weight: ((seg.aend - seg.abeg) as f64 * (t1 - t0)) as f32,
+            })
+        })
+        .collect()
+}
+
+/// Bin clipped lines into a `columns` x `rows` grid
+pub fn bin_lines(lines: &[CellLine], columns: usize, rows: usize) -> DensityGrid {
+    let mut cells = vec![0.0f32; columns * rows];
+    for line in lines {
+        let (dx, dy) = (line.to[0] - line.from[0], line.to[1] - line.from[1]);
+        // One sample per cell crossed, at the middle of its stretch
+        let steps = dx.abs().max(dy.abs()).ceil().max(1.0);
+        let weight = line.weight / steps;
+        for step in 0..steps as usize {
+            let t = (step as f32 + 0.5) / steps;
+            let (cx, cy) = (
+                (line.from[0] + t * dx).floor(),
+                (line.from[1] + t * dy).floor(),
+            );
+            if cx >= 0.0 && cy >= 0.0 && (cx as usize) < columns && (cy as usize) < rows {
+                cells[cy as usize * columns + cx as usize] += weight;
+            }
+        }
+    }
+    DensityGrid {
+        columns,
+        rows,
+        cells,
+    }
+}
+
+/// Parameter range [t0, t1] of the line from `from` to `to` inside
+/// [0, width] x [0, height] (Liang-Barsky), or None if it misses
+fn clip(from: (f64, f64), to: (f64, f64), width: f64, height: f64) -> Option<(f64, f64)> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let (mut t0, mut t1) = (0.0f64, 1.0f64);
+    for (p, q) in [
+        (-dx, from.0),
+        (dx, width - from.0),
+        (-dy, from.1),
+        (dy, height - from.1),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+        }
+    }
+    (t0 <= t1).then_some((t0, t1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aln_reader::test_record;
+
+    #[test]
+    fn test_bins_clipped_diagonal() {
+        let records = vec![test_record(0, 0, 1000, 0, 0, 1000)];
+        let plot = RustPlot::from_records(
+            &records,
+            vec!["q".to_string()],
+            vec!["t".to_string()],
+            vec![1000],
+            vec![1000],
+        );
+
+        // The lower-left half of the plot, 5 x 5 cells of 100 bp
+        let view = DensityView {
+            x: 0.0,
+            y: 0.0,
+            bp_per_cell: 100.0,
+            columns: 5,
+            rows: 5,
+        };
+        let lines = cell_lines(&plot, &view);
+        assert_eq!(lines.len(), 1);
+        assert_eq!((lines[0].from, lines[0].to), ([0.0, 0.0], [5.0, 5.0]));
+        assert!((lines[0].weight - 500.0).abs() < 1e-3);
+
+        let grid = bin_lines(&lines, view.columns, view.rows);
+        for i in 0..5 {
+            assert!((grid.cells[i * 5 + i] - 100.0).abs() < 1e-3);
+        }
+        assert_eq!(grid.cells[1], 0.0); // Off the diagonal
+        assert!((grid.max() - 100.0).abs() < 1e-3);
+    }
+}
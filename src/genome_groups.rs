@@ -0,0 +1,205 @@
+// All-vs-all plots over several genomes
+//
+// A set of pairwise alignment files covering N genomes is merged into one
+// super-matrix: the sequences of every genome on both axes, grouped by genome,
+// so each pair of genomes gets its own block. Sequences are assigned to genomes
+// by their PanSN prefix (sample#haplotype#contig -> sample#haplotype); names
+// without one belong to the file and side they first appear on.
+use crate::aln_reader::{AlnFile, AlnRecord};
+use crate::rust_plot::RustPlot;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Which genome each sequence of an all-vs-all plot belongs to
+#[derive(Clone, Debug, Default)]
+pub struct GenomeGroups {
+    pub names: Vec<String>,
+    genome_of: HashMap<String, usize>,
+}
+
+impl GenomeGroups {
+    pub fn genome(&self, sequence: &str) -> Option<usize> {
+        self.genome_of.get(sequence).copied()
+    }
+
+    /// Runs of consecutive sequences from the same genome along an axis, as
+    /// (genome, start, end) in that axis's coordinates
+    ///
+    /// Works on whatever order the sequences are currently laid out in, so a
+    /// genome split up by reordering shows as several runs.
+    pub fn runs(&self, sequences: &[String], boundaries: &[i64]) -> Vec<(usize, i64, i64)> {
+        let mut runs: Vec<(usize, i64, i64)> = Vec::new();
+        for (idx, name) in sequences.iter().enumerate() {
+            let Some(genome) = self.genome(name) else {
+                continue;
+            };
+            let (start, end) = (boundaries[idx], boundaries[idx + 1]);
+            match runs.last_mut() {
+                Some(last) if last.0 == genome && last.2 == start => last.2 = end,
+                _ => runs.push((genome, start, end)),
+            }
+        }
+        runs
+    }
+
+    /// (query genome, target genome) of every segment of `plot`, or None for
+    /// segments on sequences of no known genome
+    pub fn segment_genomes(&self, plot: &RustPlot) -> Vec<Option<(usize, usize)>> {
+        let by_sequence = |names: &[String]| -> Vec<Option<usize>> {
+            names.iter().map(|name| self.genome(name)).collect()
+        };
+        let (query, target) = (
+            by_sequence(&plot.query_sequences),
+            by_sequence(&plot.target_sequences),
+        );
+        plot.segments
+            .iter()
+            .map(|seg| {
+                let (q, t) = plot.segment_sequences(seg);
+                Some((query[q]?, target[t]?))
+            })
+            .collect()
+    }
+}
+
+/// PanSN genome prefix of a sequence name: everything before the last '#'
+pub fn pansn_genome(sequence: &str) -> Option<&str> {
+    sequence
+        .rsplit_once('#')
+        .map(|(genome, _)| genome)
+        .filter(|genome| !genome.is_empty())
+}
+
+/// Merge the records of several pairwise files, each given with a label for
+/// sequences that have no PanSN prefix, into one plot with every sequence on
+/// both axes, grouped by genome
+pub fn combine(files: &[(String, Vec<AlnRecord>)]) -> (RustPlot, GenomeGroups) {
+    let mut groups = GenomeGroups::default();
+    let mut members: Vec<Vec<String>> = Vec::new(); // Per genome, in order of appearance
+    let mut lengths: HashMap<String, i64> = HashMap::new();
+
+    let mut add = |name: &str, len: i64, fallback: String| {
+        let known = lengths.entry(name.to_string()).or_insert(0);
+        *known = (*known).max(len);
+        if groups.genome_of.contains_key(name) {
+            return;
+        }
+        let genome_name = pansn_genome(name).map(str::to_string).unwrap_or(fallback);
+        let genome = match groups.names.iter().position(|g| *g == genome_name) {
+            Some(genome) => genome,
+            None => {
+                groups.names.push(genome_name);
+                members.push(Vec::new());
+                groups.names.len() - 1
+            }
+        };
+        groups.genome_of.insert(name.to_string(), genome);
+        members[genome].push(name.to_string());
+    };
+    for (label, records) in files {
+        for rec in records {
+            add(&rec.query_name, rec.query_len, format!("{label} query"));
+            add(&rec.target_name, rec.target_len, format!("{label} target"));
+        }
+    }
+
+    let sequences: Vec<String> = members.into_iter().flatten().collect();
+    let index: HashMap<&str, usize> = sequences
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.as_str(), i))
+        .collect();
+    let records: Vec<AlnRecord> = files
+        .iter()
+        .flat_map(|(_, records)| records)
+        .map(|rec| AlnRecord {
+            query_id: index[rec.query_name.as_str()] as i64,
+            target_id: index[rec.target_name.as_str()] as i64,
+            ..rec.clone()
+        })
+        .collect();
+    let sequence_lengths: Vec<i64> = sequences.iter().map(|name| lengths[name]).collect();
+
+    let plot = RustPlot::from_records(
+        &records,
+        sequences.clone(),
+        sequences,
+        sequence_lengths.clone(),
+        sequence_lengths,
+    );
+    (plot, groups)
+}
+
+/// Read a set of pairwise .1aln files and merge them with `combine`
+pub fn load_all_vs_all(paths: &[PathBuf]) -> Result<(RustPlot, GenomeGroups)> {
+    let mut files = Vec::new();
+    for path in paths {
+        let mut aln_file = AlnFile::open(path)?;
+        let records = aln_file
+            .read_all_records()
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let label = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        files.push((label, records));
+    }
+    Ok(combine(&files))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aln_reader::test_record;
+
+    fn record(query: &str, target: &str, len: i64) -> AlnRecord {
+        AlnRecord {
+            query_name: query.to_string(),
+            target_name: target.to_string(),
+            query_len: len,
+            target_len: len,
+            ..test_record(0, 0, len / 2, 0, 0, len / 2)
+        }
+    }
+
+    #[test]
+    fn test_combine_groups_sequences_by_genome() {
+        let files = vec![
+            (
+                "a-vs-b".to_string(),
+                vec![
+                    record("A#1#chr1", "B#1#chr1", 100),
+                    record("A#1#chr2", "B#1#chr1", 50),
+                ],
+            ),
+            ("b-vs-c".to_string(), vec![record("B#1#chr1", "ctg7", 80)]),
+        ];
+        let (plot, groups) = combine(&files);
+
+        assert_eq!(groups.names, vec!["A#1", "B#1", "b-vs-c target"]);
+        assert_eq!(
+            plot.query_sequences,
+            vec!["A#1#chr1", "A#1#chr2", "B#1#chr1", "ctg7"]
+        );
+        assert_eq!(plot.query_sequences, plot.target_sequences);
+        assert_eq!(plot.query_lengths, vec![100, 50, 100, 80]);
+        // The B-vs-C alignment lands in the (B, C) block
+        let seg = &plot.segments[2];
+        assert_eq!((seg.abeg, seg.bbeg), (150, 250));
+
+        let runs = groups.runs(&plot.query_sequences, &plot.query_boundaries);
+        assert_eq!(runs, vec![(0, 0, 150), (1, 150, 250), (2, 250, 330)]);
+        assert_eq!(
+            groups.segment_genomes(&plot),
+            vec![Some((0, 1)), Some((0, 1)), Some((1, 2))]
+        );
+    }
+
+    #[test]
+    fn test_pansn_genome() {
+        assert_eq!(pansn_genome("HG002#1#chr6"), Some("HG002#1"));
+        assert_eq!(pansn_genome("chr6"), None);
+        assert_eq!(pansn_genome("#chr6"), None);
+    }
+}
@@ -0,0 +1,217 @@
+// Density binning on the GPU (`gpu` feature)
+//
+// The same walk as `density::bin_lines`, one compute invocation per clipped
+// alignment, adding whole bases per cell with atomics. Lines are uploaded in
+// batches that fit the smallest storage-buffer limit, and the grid is read
+// back once at the end. `GpuBinner::new` returns None without a usable
+// adapter, and callers fall back to the CPU.
+use crate::density::{CellLine, DensityGrid};
+use wgpu::util::DeviceExt;
+
+/// Lines per upload: 16 bytes of ends each, inside the 128 MB binding limit
+const BATCH_LINES: usize = 1 << 22;
+
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Workgroups per dispatch row (the per-dimension limit)
+const MAX_GROUPS_X: u32 = 65_535;
+
+const SHADER: &str = r#"
+struct Params {
+    columns: u32,
+    rows: u32,
+    count: u32,
+    stride: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> ends: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read> weights: array<f32>;
+@group(0) @binding(3) var<storage, read_write> bins: array<atomic<u32>>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x + id.y * params.stride;
+    if (i >= params.count) {
+        return;
+    }
+    let line = ends[i];
+    let d = line.zw - line.xy;
+    // One sample per cell crossed, at the middle of its stretch
+    let steps = max(ceil(max(abs(d.x), abs(d.y))), 1.0);
+    let weight = u32(max(round(weights[i] / steps), 1.0));
+    for (var step = 0u; step < u32(steps); step = step + 1u) {
+        let p = floor(line.xy + d * ((f32(step) + 0.5) / steps));
+        if (p.x >= 0.0 && p.y >= 0.0 && p.x < f32(params.columns) && p.y < f32(params.rows)) {
+            atomicAdd(&bins[u32(p.y) * params.columns + u32(p.x)], weight);
+        }
+    }
+}
+"#;
+
+pub struct GpuBinner {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    pub adapter_name: String,
+}
+
+impl GpuBinner {
+    /// Set up the compute pipeline on the best available adapter
+    pub fn new() -> Option<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }))?;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("density"),
+                required_features: wgpu::Features::empty(),
+                required_limits: wgpu::Limits::downlevel_defaults(),
+            },
+            None,
+        ))
+        .ok()?;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("density"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("density"),
+            layout: None,
+            module: &module,
+            entry_point: "main",
+            compilation_options: Default::default(),
+        });
+        Some(Self {
+            device,
+            queue,
+            pipeline,
+            adapter_name: adapter.get_info().name,
+        })
+    }
+
+    /// Bin clipped lines into a `columns` x `rows` grid; None if the GPU
+    /// fails partway
+    pub fn bin(&self, lines: &[CellLine], columns: usize, rows: usize) -> Option<DensityGrid> {
+        let cells = columns * rows;
+        let bins_size = (cells.max(1) * 4) as u64;
+        let bins = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("density bins"),
+            size: bins_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("density readback"),
+            size: bins_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        for batch in lines.chunks(BATCH_LINES) {
+            let mut ends = Vec::with_capacity(batch.len() * 16);
+            let mut weights = Vec::with_capacity(batch.len() * 4);
+            for line in batch {
+                for value in [line.from[0], line.from[1], line.to[0], line.to[1]] {
+                    ends.extend_from_slice(&value.to_le_bytes());
+                }
+                weights.extend_from_slice(&line.weight.to_le_bytes());
+            }
+            let groups = (batch.len() as u32).div_ceil(WORKGROUP_SIZE);
+            let (groups_x, groups_y) = (
+                groups.clamp(1, MAX_GROUPS_X),
+                groups.div_ceil(MAX_GROUPS_X).max(1),
+            );
+            let mut params = Vec::with_capacity(16);
+            for value in [
+                columns as u32,
+                rows as u32,
+                batch.len() as u32,
+                groups_x * WORKGROUP_SIZE,
+            ] {
+                params.extend_from_slice(&value.to_le_bytes());
+            }
+
+            let buffer = |label, contents: &[u8], usage| {
+                self.device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some(label),
+                        contents,
+                        usage,
+                    })
+            };
+            let params = buffer("density params", &params, wgpu::BufferUsages::UNIFORM);
+            let ends = buffer("density ends", &ends, wgpu::BufferUsages::STORAGE);
+            let weights = buffer("density weights", &weights, wgpu::BufferUsages::STORAGE);
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("density"),
+                layout: &self.pipeline.get_bind_group_layout(0),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: ends.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: weights.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: bins.as_entire_binding(),
+                    },
+                ],
+            });
+
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("density"),
+                });
+            {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("density"),
+                    timestamp_writes: None,
+                });
+                pass.set_pipeline(&self.pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.dispatch_workgroups(groups_x, groups_y, 1);
+            }
+            self.queue.submit(Some(encoder.finish()));
+        }
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("density readback"),
+            });
+        encoder.copy_buffer_to_buffer(&bins, 0, &readback, 0, bins_size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = readback.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        rx.recv().ok()?.ok()?;
+        let cells: Vec<f32> = slice
+            .get_mapped_range()
+            .chunks_exact(4)
+            .take(cells)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as f32)
+            .collect();
+        readback.unmap();
+
+        Some(DensityGrid {
+            columns,
+            rows,
+            cells,
+        })
+    }
+}
@@ -0,0 +1,73 @@
+// Plain or gzip-compressed text input
+//
+// PAF and delta files are often kept gzipped, or bgzipped so they can be
+// indexed; both are read here as they are, without unpacking to disk first.
+// Compression is recognised by the gzip magic bytes rather than the file
+// name, and bgzip's run of gzip members is read through as one stream.
+use anyhow::{Context, Result};
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Open `path` for reading, decompressing it if it is gzipped; also says
+/// whether it was
+pub fn open(path: &Path) -> Result<(Box<dyn BufRead + Send>, bool)> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let head = reader
+        .fill_buf()
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    if head.starts_with(&GZIP_MAGIC) {
+        Ok((Box::new(BufReader::new(MultiGzDecoder::new(reader))), true))
+    } else {
+        Ok((Box::new(reader), false))
+    }
+}
+
+/// `path` without a .gz or .bgz suffix, to tell formats apart by extension
+pub fn uncompressed_name(path: &Path) -> PathBuf {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("gz" | "bgz") => path.with_extension(""),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn test_reads_concatenated_gzip_members() {
+        // bgzip writes a series of gzip members; all must be read
+        let mut bytes = Vec::new();
+        for part in ["first line\n", "second line\n"] {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(part.as_bytes()).unwrap();
+            bytes.extend(encoder.finish().unwrap());
+        }
+        let path = std::env::temp_dir().join(format!("alnview-input-{}.gz", std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+
+        let (mut reader, compressed) = open(&path).unwrap();
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(compressed);
+        assert_eq!(text, "first line\nsecond line\n");
+
+        assert_eq!(
+            uncompressed_name(Path::new("run/out.delta.gz")),
+            Path::new("run/out.delta")
+        );
+        assert_eq!(
+            uncompressed_name(Path::new("out.paf")),
+            Path::new("out.paf")
+        );
+    }
+}
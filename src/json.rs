@@ -0,0 +1,41 @@
+// JSON output helpers
+//
+// The web viewer and `--stats --format json` write their JSON by hand rather
+// than through a serialisation library; these build the pieces they share.
+
+/// A string as a JSON string literal, quoted and escaped
+pub fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Integers as a JSON array
+pub fn json_numbers(values: &[i64]) -> String {
+    let values: Vec<String> = values.iter().map(i64::to_string).collect();
+    format!("[{}]", values.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_escaping() {
+        assert_eq!(json_string("chr1 \"alt\"\t\\"), r#""chr1 \"alt\"\t\\""#);
+        assert_eq!(json_string("a\u{1}"), r#""a\u0001""#);
+        assert_eq!(json_numbers(&[0, -5, 12]), "[0,-5,12]");
+        assert_eq!(json_numbers(&[]), "[]");
+    }
+}
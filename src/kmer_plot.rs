@@ -0,0 +1,311 @@
+// Quick k-mer dotplot built directly from two FASTA files
+//
+// Shared k-mers that occur exactly once in each genome are chained along their
+// diagonal into segments, then fed through the same RustPlot pipeline as .1aln
+// alignments. Useful for a first look before the aligner has finished.
+use crate::aln_reader::AlnRecord;
+use crate::rust_plot::RustPlot;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Default k-mer size for quick dotplots
+pub const DEFAULT_KMER_SIZE: usize = 21;
+
+/// Largest k that fits in a 2-bit packed u64
+pub const MAX_KMER_SIZE: usize = 32;
+
+/// A FASTA record: header (without '>') and upper-cased sequence
+pub struct FastaRecord {
+    pub name: String,
+    pub seq: Vec<u8>,
+}
+
+/// Read all records from a FASTA file
+pub fn read_fasta<P: AsRef<Path>>(path: P) -> Result<Vec<FastaRecord>> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open FASTA file: {}", path.display()))?;
+
+    let mut records = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let line = line.trim_end();
+        if let Some(header) = line.strip_prefix('>') {
+            records.push(FastaRecord {
+                name: header.trim().to_string(),
+                seq: Vec::new(),
+            });
+        } else if let Some(rec) = records.last_mut() {
+            rec.seq.extend(line.bytes().map(|b| b.to_ascii_uppercase()));
+        } else if !line.is_empty() {
+            anyhow::bail!(
+                "{} does not look like FASTA (no '>' header)",
+                path.display()
+            );
+        }
+    }
+
+    Ok(records)
+}
+
+/// Build a k-mer dotplot from a query and a target FASTA file
+pub fn kmer_dotplot<P: AsRef<Path>, Q: AsRef<Path>>(
+    query_fasta: P,
+    target_fasta: Q,
+    k: usize,
+) -> Result<RustPlot> {
+    let query = read_fasta(query_fasta)?;
+    let target = read_fasta(target_fasta)?;
+    let records = kmer_records(&query, &target, k)?;
+
+    Ok(RustPlot::from_records(
+        &records,
+        query.iter().map(|r| r.name.clone()).collect(),
+        target.iter().map(|r| r.name.clone()).collect(),
+        query.iter().map(|r| r.seq.len() as i64).collect(),
+        target.iter().map(|r| r.seq.len() as i64).collect(),
+    ))
+}
+
+/// Where a canonical k-mer was seen, and how often
+#[derive(Clone, Copy)]
+struct KmerSite {
+    count: u32,
+    seq: u32,
+    pos: u32,
+    forward: bool,
+}
+
+/// A single shared-k-mer hit between the two genomes
+#[derive(Clone, Copy)]
+struct Hit {
+    qid: u32,
+    tid: u32,
+    reverse: bool,
+    diagonal: i64,
+    qpos: i64,
+    tpos: i64,
+}
+
+/// Find unique shared k-mers and chain them into alignment-like records
+///
+/// Reverse-strand records use target coordinates on the reverse-complemented
+/// target sequence, matching the .1aln convention RustPlot expects.
+pub fn kmer_records(
+    query: &[FastaRecord],
+    target: &[FastaRecord],
+    k: usize,
+) -> Result<Vec<AlnRecord>> {
+    if k == 0 || k > MAX_KMER_SIZE {
+        anyhow::bail!("k-mer size must be between 1 and {MAX_KMER_SIZE}, got {k}");
+    }
+
+    let target_sites = index_unique_kmers(target, k);
+    let query_sites = index_unique_kmers(query, k);
+
+    // Collect hits for k-mers unique in both genomes
+    let mut hits = Vec::new();
+    for (kmer, qsite) in &query_sites {
+        if qsite.count != 1 {
+            continue;
+        }
+        let Some(tsite) = target_sites.get(kmer) else {
+            continue;
+        };
+        if tsite.count != 1 {
+            continue;
+        }
+
+        let reverse = qsite.forward != tsite.forward;
+        let qpos = qsite.pos as i64;
+        let tpos = tsite.pos as i64;
+        hits.push(Hit {
+            qid: qsite.seq,
+            tid: tsite.seq,
+            reverse,
+            diagonal: if reverse { tpos + qpos } else { tpos - qpos },
+            qpos,
+            tpos,
+        });
+    }
+
+    hits.sort_unstable_by_key(|h| (h.qid, h.tid, h.reverse, h.diagonal, h.qpos));
+
+    // Chain hits on the same diagonal whose k-mers touch or overlap
+    let k = k as i64;
+    let mut records = Vec::new();
+    let mut i = 0;
+    while i < hits.len() {
+        let first = hits[i];
+        let mut last = first;
+        let mut j = i + 1;
+        while j < hits.len() {
+            let h = hits[j];
+            if h.qid != first.qid
+                || h.tid != first.tid
+                || h.reverse != first.reverse
+                || h.diagonal != first.diagonal
+                || h.qpos - last.qpos > k
+            {
+                break;
+            }
+            last = h;
+            j += 1;
+        }
+
+        let target_len = target[first.tid as usize].seq.len() as i64;
+        let (target_start, target_end) = if first.reverse {
+            // On the reverse strand target positions fall as query positions rise
+            (target_len - first.tpos - k, target_len - last.tpos)
+        } else {
+            (first.tpos, last.tpos + k)
+        };
+
+        records.push(AlnRecord {
+            query_id: first.qid as i64,
+            target_id: first.tid as i64,
+            query_name: query[first.qid as usize].name.clone(),
+            target_name: target[first.tid as usize].name.clone(),
+            query_len: query[first.qid as usize].seq.len() as i64,
+            target_len,
+            query_start: first.qpos,
+            query_end: last.qpos + k,
+            target_start,
+            target_end,
+            reverse: first.reverse as i32,
+            diffs: 0,
+        });
+
+        i = j;
+    }
+
+    Ok(records)
+}
+
+/// Count canonical k-mers across all sequences, remembering the first site of each
+fn index_unique_kmers(records: &[FastaRecord], k: usize) -> HashMap<u64, KmerSite> {
+    let mut sites: HashMap<u64, KmerSite> = HashMap::new();
+    for (seq_idx, rec) in records.iter().enumerate() {
+        for (pos, kmer, forward) in canonical_kmers(&rec.seq, k) {
+            sites
+                .entry(kmer)
+                .and_modify(|site| site.count += 1)
+                .or_insert(KmerSite {
+                    count: 1,
+                    seq: seq_idx as u32,
+                    pos: pos as u32,
+                    forward,
+                });
+        }
+    }
+    sites
+}
+
+/// Iterate (position, canonical 2-bit k-mer, is_forward) over a sequence,
+/// skipping any window that contains a non-ACGT base
+fn canonical_kmers(seq: &[u8], k: usize) -> impl Iterator<Item = (usize, u64, bool)> + '_ {
+    let mask = if k == 32 {
+        u64::MAX
+    } else {
+        (1u64 << (2 * k)) - 1
+    };
+    let shift = 2 * (k as u64 - 1);
+    let mut fwd = 0u64;
+    let mut rev = 0u64;
+    let mut valid = 0usize;
+
+    seq.iter().enumerate().filter_map(move |(i, &base)| {
+        let code = match base {
+            b'A' => 0u64,
+            b'C' => 1,
+            b'G' => 2,
+            b'T' => 3,
+            _ => {
+                valid = 0;
+                return None;
+            }
+        };
+        fwd = ((fwd << 2) | code) & mask;
+        rev = (rev >> 2) | ((3 - code) << shift);
+        valid += 1;
+        if valid < k {
+            return None;
+        }
+        let pos = i + 1 - k;
+        if fwd <= rev {
+            Some((pos, fwd, true))
+        } else {
+            Some((pos, rev, false))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rec(name: &str, seq: &str) -> FastaRecord {
+        FastaRecord {
+            name: name.to_string(),
+            seq: seq.as_bytes().to_vec(),
+        }
+    }
+
+    fn revcomp(seq: &str) -> String {
+        seq.bytes()
+            .rev()
+            .map(|b| match b {
+                b'A' => 'T',
+                b'C' => 'G',
+                b'G' => 'C',
+                _ => 'A',
+            })
+            .collect()
+    }
+
+    const SEQ: &str = "ACGTTGCAAGGCTTACCGATGGCATTCAGGACTTAGCCATAGGCTAAC";
+
+    #[test]
+    fn test_canonical_kmer_matches_revcomp() {
+        let fwd: Vec<_> = canonical_kmers(b"ACGTTGC", 5).collect();
+        let rc = revcomp("ACGTTGC");
+        let rev: Vec<_> = canonical_kmers(rc.as_bytes(), 5).collect();
+        assert_eq!(fwd.len(), 3);
+        assert_eq!(fwd[0].1, rev[2].1);
+        assert_ne!(fwd[0].2, rev[2].2);
+    }
+
+    #[test]
+    fn test_identical_sequences_chain_into_one_forward_record() {
+        let records = kmer_records(&[rec("q", SEQ)], &[rec("t", SEQ)], 11).unwrap();
+        assert_eq!(records.len(), 1);
+        let r = &records[0];
+        assert_eq!(r.reverse, 0);
+        assert_eq!((r.query_start, r.query_end), (0, SEQ.len() as i64));
+        assert_eq!((r.target_start, r.target_end), (0, SEQ.len() as i64));
+    }
+
+    #[test]
+    fn test_reverse_complement_gives_reverse_record() {
+        let rc = revcomp(SEQ);
+        let records = kmer_records(&[rec("q", SEQ)], &[rec("t", &rc)], 11).unwrap();
+        assert_eq!(records.len(), 1);
+        let r = &records[0];
+        assert_eq!(r.reverse, 1);
+        // Full-length match on the reverse-complemented target
+        assert_eq!((r.target_start, r.target_end), (0, SEQ.len() as i64));
+    }
+
+    #[test]
+    fn test_n_bases_break_kmers() {
+        assert_eq!(canonical_kmers(b"ACGNACG", 3).count(), 2);
+    }
+
+    #[test]
+    fn test_rejects_oversized_k() {
+        assert!(kmer_records(&[rec("q", SEQ)], &[rec("t", SEQ)], 33).is_err());
+    }
+}
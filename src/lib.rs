@@ -1,5 +1,35 @@
 // Library interface for ALNVIEW
+pub mod agp;
 pub mod aln_reader;
+pub mod aln_writer;
+pub mod ascii_plot;
+pub mod bindings;
+pub mod colormap;
+pub mod comparison;
+pub mod composition;
+pub mod delta;
+pub mod density;
 pub mod ffi;
+pub mod genome_groups;
+#[cfg(feature = "gpu")]
+pub mod gpu_density;
+pub mod input;
+pub mod json;
+pub mod kmer_plot;
+pub mod measure;
+pub mod paf;
+pub mod pdf;
+pub mod presets;
+pub mod raster;
+pub mod regions;
 pub mod rust_plot;
+pub mod segment_store;
+pub mod selection;
 pub mod sequence_filter;
+pub mod sequence_layout;
+pub mod session;
+pub mod stats;
+pub mod sv_calls;
+pub mod tags;
+pub mod timings;
+pub mod web_server;
@@ -1,25 +1,84 @@
+mod agp;
 mod aln_reader;
+mod aln_writer;
+mod ascii_plot;
+mod bindings;
+mod colormap;
+mod comparison;
+mod composition;
+mod delta;
+mod density;
+mod genome_groups;
+#[cfg(feature = "gpu")]
+mod gpu_density;
+mod input;
+mod json;
+mod kmer_plot;
+mod measure;
+mod notifications;
+mod paf;
+mod pdf;
+mod presets;
+mod raster;
+mod regions;
 mod rust_plot;
+mod segment_store;
+mod selection;
 mod sequence_filter;
-
-use clap::Parser;
+mod sequence_layout;
+mod session;
+mod stats;
+mod sv_calls;
+mod synteny_view;
+mod tags;
+mod timings;
+mod web_server;
+mod wrapped_view;
+
+use agp::{Agp, ContigBoundaries};
+use bindings::{Action, Bindings, Gesture};
+use clap::{Parser, Subcommand};
+use colormap::{Colormap, IdentityGradient, Palette, PALETTES};
+use comparison::{Comparison, Presence};
+use composition::GcTrack;
+use density::{CellLine, DensityView};
 use eframe::egui;
-use rust_plot::RustPlot;
+use genome_groups::GenomeGroups;
+use notifications::Notifications;
+use paf::FollowEvent;
+use presets::{Preset, PresetLayer, PresetStore};
+use regions::Region;
+use rust_plot::{
+    format_bp, scale_bar, AlignmentSegment, Cluster, PartnerInterval, RustPlot, SegmentFilter,
+    SequenceReport, StrandFilter, SweepFilter, WidthScale,
+};
+use segment_store::{SegmentStore, STORE_TILES};
 use sequence_filter::SequenceFilter;
-use std::path::PathBuf;
-use std::sync::mpsc::{channel, Receiver};
+use sequence_layout::{LayoutEntry, SequenceLayout};
+use session::{Annotation, Session, SessionView};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
+use tags::{Tag, TagTarget};
+use timings::Timings;
 
 /// ALNview - Alignment viewer for FASTGA .1aln files
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     /// Path to .1aln file to load (if not provided, opens GUI)
     #[clap(value_name = "FILE")]
     file: Option<PathBuf>,
 
-    /// Create and save plot as PNG (requires file argument)
+    /// Create and save plot as PNG, or as a vector PDF with a legend and axis
+    /// titles if OUTPUT ends in .pdf (requires file argument)
     #[clap(long, value_name = "OUTPUT")]
     plot: Option<PathBuf>,
 
@@ -27,12 +86,23 @@ struct Args {
     #[clap(long)]
     stats: bool,
 
-    /// Filter query sequences by name/prefix (comma-separated)
-    #[clap(long, value_name = "NAMES")]
+    /// How --stats prints: text for people, or json / tsv (stat, sequence,
+    /// value rows) on stdout for pipelines, with progress going to stderr
+    #[clap(
+        long,
+        value_enum,
+        default_value = "text",
+        requires = "stats",
+        conflicts_with_all = ["plot", "ascii", "sv_vcf", "export_paf", "export_1aln", "grid_pages", "kmer", "all_vs_all"]
+    )]
+    format: StatsFormat,
+
+    /// Filter query sequences by name/prefix (comma-separated) or a /regex/
+    #[clap(long, visible_alias = "query-seqs", value_name = "NAMES")]
     query_filter: Option<String>,
 
-    /// Filter target sequences by name/prefix (comma-separated)
-    #[clap(long, value_name = "NAMES")]
+    /// Filter target sequences by name/prefix (comma-separated) or a /regex/
+    #[clap(long, visible_alias = "target-seqs", value_name = "NAMES")]
     target_filter: Option<String>,
 
     /// Filter query sequences by range (e.g., "0-5")
@@ -42,46 +112,433 @@ struct Args {
     /// Filter target sequences by range (e.g., "0-5")
     #[clap(long, value_name = "RANGE")]
     target_range: Option<String>,
+
+    /// Apply a sequence layout file (order and visibility, as exported from the GUI)
+    #[clap(long, value_name = "LAYOUT")]
+    layout: Option<PathBuf>,
+
+    /// Order query sequences as listed in a file: one name per line, followed by ` -` to flip
+    /// it; unlisted sequences follow in file order
+    #[clap(long, value_name = "FILE")]
+    order: Option<PathBuf>,
+
+    /// Order target sequences as listed in a file, as for --order
+    #[clap(long, value_name = "FILE")]
+    order_target: Option<PathBuf>,
+
+    /// Join contigs into the scaffolds of an AGP file and draw the plot in scaffold
+    /// coordinates, with contig boundaries dotted (--plot and the GUI)
+    #[clap(long, value_name = "AGP")]
+    agp: Option<PathBuf>,
+
+    /// Downsample plots with more alignments than this, keeping longer ones preferentially
+    #[clap(long, value_name = "N", default_value_t = rust_plot::DEFAULT_MAX_SEGMENTS)]
+    max_segments: usize,
+
+    /// Never downsample, however many alignments the file has
+    #[clap(long)]
+    all_segments: bool,
+
+    /// Keep only the best alignments at each position, as QUERY:TARGET counts
+    /// (e.g. 1:1 for one-to-one, 1:N for the best per query position); a
+    /// plane sweep like sweepga's, scored by matching bases
+    #[clap(long, value_name = "SPEC")]
+    filter: Option<String>,
+
+    /// Open a saved session (file, view and annotations); with --plot, its
+    /// annotations are drawn on the PNG
+    #[clap(long, value_name = "SESSION")]
+    session: Option<PathBuf>,
+
+    /// Title drawn above the --plot image (overrides the session's)
+    #[clap(long, value_name = "TEXT")]
+    title: Option<String>,
+
+    /// Subtitle drawn under the title (overrides the session's)
+    #[clap(long, value_name = "TEXT")]
+    subtitle: Option<String>,
+
+    /// Draw a scale bar in the corner of the --plot image
+    #[clap(long)]
+    scale_bar: bool,
+
+    /// Centre the genome rectangle in the --plot image, padding the shorter
+    /// axis, so 1 bp is the same length on both axes
+    #[clap(long)]
+    equal_aspect: bool,
+
+    /// Sort alignments by position before filtering, sampling and drawing, so
+    /// the same alignments give a byte-identical --plot image (or --ascii
+    /// text) whatever order the file lists them in
+    #[clap(long)]
+    deterministic: bool,
+
+    /// Build a quick k-mer dotplot from two FASTA files instead of reading a .1aln
+    #[clap(long, num_args = 2, value_names = ["QUERY_FASTA", "TARGET_FASTA"])]
+    kmer: Option<Vec<PathBuf>>,
+
+    /// Merge pairwise .1aln files covering several genomes into one
+    /// all-vs-all plot, grouping sequences by genome (PanSN prefix)
+    #[clap(long, num_args = 1.., value_name = "FILES", conflicts_with = "kmer")]
+    all_vs_all: Option<Vec<PathBuf>>,
+
+    /// Read FILE as PAF while an aligner writes it (a growing file, a pipe,
+    /// or - for stdin), adding alignments to the plot as they arrive
+    #[clap(long)]
+    follow: bool,
+
+    /// Draw with a preset saved from the GUI (layer colours and filters,
+    /// cutoffs, sweep filter, equal-width mode); --filter overrides its sweep
+    #[clap(long, value_name = "NAME")]
+    preset: Option<String>,
+
+    /// Hide alignments below this percent identity (the plot-wide cutoff,
+    /// overriding a preset's)
+    #[clap(long, value_name = "PCT")]
+    min_identity: Option<f32>,
+
+    /// Write candidate inversions and translocations found in the alignments
+    /// left after sequence filters, layout, cutoffs and --filter to a VCF
+    /// (target coordinates, supporting alignments in INFO)
+    #[clap(long, value_name = "VCF", conflicts_with_all = ["kmer", "all_vs_all"])]
+    sv_vcf: Option<PathBuf>,
+
+    /// Write the alignments left after sequence filters, layout, preset
+    /// cutoffs and --filter to a PAF file, never downsampled
+    #[clap(long, value_name = "PAF", conflicts_with_all = ["kmer", "all_vs_all"])]
+    export_paf: Option<PathBuf>,
+
+    /// Copy the alignments left after sequence filters, layout, preset
+    /// cutoffs and --filter from a .1aln input to a new .1aln, whole (trace
+    /// points and all), never downsampled
+    #[clap(long = "export-1aln", value_name = "1ALN", conflicts_with_all = ["kmer", "all_vs_all"])]
+    export_1aln: Option<PathBuf>,
+
+    /// Draw one panel per query x target sequence pair that has alignments,
+    /// as pages of a PDF if OUTPUT ends in .pdf, else as PNGs in the
+    /// directory OUTPUT
+    #[clap(long, value_name = "OUTPUT", conflicts_with_all = ["kmer", "all_vs_all"])]
+    grid_pages: Option<PathBuf>,
+
+    /// Print the dot plot to stdout in Unicode braille, WIDTH characters wide
+    /// (default 100), for a quick look from a terminal or job log
+    #[clap(
+        long,
+        value_name = "WIDTH",
+        num_args = 0..=1,
+        default_missing_value = "100",
+        conflicts_with_all = ["kmer", "all_vs_all"]
+    )]
+    ascii: Option<usize>,
+
+    /// Report wall time and peak memory of each stage (read, convert, arrange,
+    /// render); the GUI prints them to the console
+    #[clap(long)]
+    timings: bool,
+
+    /// Keep FILE's alignments in a memory-mapped store at PATH with only an
+    /// overview in memory, reading full detail for the viewport when zoomed
+    /// in; the store is built on first use and reused while newer than FILE
+    #[clap(long, value_name = "PATH")]
+    segment_store: Option<PathBuf>,
+
+    /// K-mer size for --kmer mode (1-32)
+    #[clap(long, value_name = "K", default_value_t = kmer_plot::DEFAULT_KMER_SIZE)]
+    kmer_size: usize,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum StatsFormat {
+    Text,
+    Json,
+    Tsv,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Serve an interactive viewer of FILE over HTTP, to explore alignments on
+    /// a remote machine from a browser
+    Serve {
+        /// Path to .1aln file to serve
+        #[clap(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Port to listen on
+        #[clap(long, default_value_t = web_server::DEFAULT_PORT)]
+        port: u16,
+
+        /// Address to listen on; 0.0.0.0 exposes the viewer to the network
+        #[clap(long, default_value = "127.0.0.1")]
+        bind: String,
+    },
+
+    /// Build a reusable index of FILE (spatial tiles and a level-of-detail
+    /// overview, memory-mapped) that the GUI and CLI open instead of FILE
+    /// while it is newer
+    Index {
+        /// Path to .1aln file to index
+        #[clap(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Where to write the index (default: FILE.alnidx, where it is looked for)
+        #[clap(long, short, value_name = "PATH")]
+        output: Option<PathBuf>,
+
+        /// Tiles along each axis of the spatial index
+        #[clap(long, value_name = "N", default_value_t = STORE_TILES)]
+        tiles: usize,
+    },
+
+    /// Convert the alignments of FILE to another format, written to stdout
+    Convert {
+        /// Path to .1aln file to convert
+        #[clap(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Write PAF, with differences as NM and divergence as dv tags
+        #[clap(long, required = true)]
+        paf: bool,
+    },
 }
 
 fn main() -> Result<(), eframe::Error> {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
     let args = Args::parse();
+    if let Some(command) = args.command {
+        let result = match command {
+            Command::Serve { file, port, bind } => {
+                let mut timings = Timings::default();
+                read_plot(&file, &mut timings)
+                    .and_then(|plot| web_server::serve(plot, &format!("{bind}:{port}")))
+            }
+            Command::Index {
+                file,
+                output,
+                tiles,
+            } => build_index(&file, output, tiles),
+            Command::Convert { file, .. } => convert_to_paf(&file),
+        };
+        if let Err(e) = result {
+            eprintln!("Error: {e:#}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    let max_segments = (!args.all_segments).then_some(args.max_segments);
+    let preset = args.preset.as_deref().map(|name| {
+        let store = PresetStore::load_default().unwrap_or_else(|e| {
+            eprintln!("Error loading presets: {e:#}");
+            std::process::exit(1);
+        });
+        store.get(name).cloned().unwrap_or_else(|| {
+            let names: Vec<&str> = store.presets.iter().map(|p| p.name.as_str()).collect();
+            eprintln!(
+                "Error: no preset named '{name}' (saved presets: {})",
+                if names.is_empty() {
+                    "none".to_string()
+                } else {
+                    names.join(", ")
+                }
+            );
+            std::process::exit(1);
+        })
+    });
+    let sweep = match args.filter.as_deref().map(SweepFilter::parse).transpose() {
+        Ok(sweep) => sweep.or(preset.as_ref().and_then(|p| p.sweep)),
+        Err(e) => {
+            eprintln!("Error parsing --filter: {e}");
+            std::process::exit(1);
+        }
+    };
 
-    // CLI mode: if file is provided with --stats or --plot
-    if let Some(ref file) = args.file {
-        if args.stats || args.plot.is_some() {
-            // Parse filters
-            let query_filter =
-                match parse_filters(args.query_filter.as_deref(), args.query_range.as_deref()) {
-                    Ok(f) => f,
-                    Err(e) => {
-                        eprintln!("Error parsing query filter: {e}");
-                        std::process::exit(1);
-                    }
-                };
-            let target_filter =
-                match parse_filters(args.target_filter.as_deref(), args.target_range.as_deref()) {
-                    Ok(f) => f,
-                    Err(e) => {
-                        eprintln!("Error parsing target filter: {e}");
-                        std::process::exit(1);
-                    }
-                };
+    let mut cutoff = preset.as_ref().map(|p| p.cutoff).unwrap_or_default();
+    if let Some(min_identity) = args.min_identity {
+        if !(0.0..=100.0).contains(&min_identity) {
+            eprintln!("Error: --min-identity must be between 0 and 100, got {min_identity}");
+            std::process::exit(1);
+        }
+        cutoff.min_identity = min_identity;
+    }
 
-            match run_cli_mode(
-                file,
-                args.plot.as_ref(),
-                args.stats,
-                &query_filter,
-                &target_filter,
-            ) {
-                Ok(_) => return Ok(()),
+    let layout = match args.layout.as_ref().map(SequenceLayout::load).transpose() {
+        Ok(layout) => layout,
+        Err(e) => {
+            eprintln!("Error loading layout: {e:#}");
+            std::process::exit(1);
+        }
+    };
+    let order_entries = |path: Option<&PathBuf>| match path.map(SequenceLayout::load_order) {
+        Some(Ok(entries)) => entries,
+        Some(Err(e)) => {
+            eprintln!("Error loading sequence order: {e:#}");
+            std::process::exit(1);
+        }
+        None => Vec::new(),
+    };
+    let order = (args.order.is_some() || args.order_target.is_some()).then(|| SequenceLayout {
+        query: order_entries(args.order.as_ref()),
+        target: order_entries(args.order_target.as_ref()),
+    });
+    let agp = match args.agp.as_ref().map(Agp::load).transpose() {
+        Ok(agp) => agp,
+        Err(e) => {
+            eprintln!("Error loading AGP file: {e:#}");
+            std::process::exit(1);
+        }
+    };
+
+    let mut session = match args.session.as_ref().map(Session::load).transpose() {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("Error loading session: {e:#}");
+            std::process::exit(1);
+        }
+    };
+    if args.title.is_some() || args.subtitle.is_some() {
+        let session = session.get_or_insert_with(Session::default);
+        if let Some(ref title) = args.title {
+            session.title = title.clone();
+        }
+        if let Some(ref subtitle) = args.subtitle {
+            session.subtitle = subtitle.clone();
+        }
+    }
+    // A session's file stands in for the FILE argument
+    let file = args
+        .file
+        .clone()
+        .or_else(|| session.as_ref().and_then(|s| s.file.clone()));
+
+    // CLI mode: if a file (or FASTA pair) is provided with --stats or --plot
+    if (file.is_some() || args.kmer.is_some() || args.all_vs_all.is_some())
+        && (args.stats
+            || args.plot.is_some()
+            || args.sv_vcf.is_some()
+            || args.export_paf.is_some()
+            || args.export_1aln.is_some()
+            || args.grid_pages.is_some()
+            || args.ascii.is_some())
+    {
+        // Parse filters
+        let query_filter =
+            match parse_filters(args.query_filter.as_deref(), args.query_range.as_deref()) {
+                Ok(f) => f,
                 Err(e) => {
-                    eprintln!("Error: {e}");
+                    eprintln!("Error parsing query filter: {e}");
                     std::process::exit(1);
                 }
+            };
+        let target_filter =
+            match parse_filters(args.target_filter.as_deref(), args.target_range.as_deref()) {
+                Ok(f) => f,
+                Err(e) => {
+                    eprintln!("Error parsing target filter: {e}");
+                    std::process::exit(1);
+                }
+            };
+
+        let selection = SequenceSelection {
+            query_filter,
+            target_filter,
+            layout,
+            order,
+            agp: agp.clone(),
+            max_segments,
+            sweep,
+            cutoff,
+            normalized: preset.as_ref().is_some_and(|p| p.equal_width),
+            deterministic: args.deterministic,
+        };
+        let mut render = RenderOptions {
+            scale_bar: args.scale_bar,
+            equal_aspect: args.equal_aspect,
+            ..RenderOptions::from_session(session.as_ref())
+        };
+        if let Some(ref preset) = preset {
+            if !preset.layers.is_empty() {
+                render.layers = preset
+                    .layers
+                    .iter()
+                    .filter(|layer| layer.visible)
+                    .map(preset_layer)
+                    .collect();
+            }
+            // Bp scale means nothing once sequences are stretched to equal width
+            render.scale_bar &= !preset.equal_width;
+            if preset.mode != "dotplot" && args.plot.is_some() {
+                eprintln!(
+                    "⚠️  Preset '{}' uses the {} view; the PNG is drawn as a dot plot",
+                    preset.name, preset.mode
+                );
+            }
+        }
+
+        let mut timings = Timings::default();
+        let result = match (&args.kmer, &file) {
+            _ if args.all_vs_all.is_some() => run_all_vs_all_cli_mode(
+                args.all_vs_all.as_deref().unwrap_or_default(),
+                args.plot.as_ref(),
+                args.stats,
+                &selection,
+                render.clone(),
+                &mut timings,
+            ),
+            (Some(fastas), _) => run_kmer_cli_mode(
+                (&fastas[0], &fastas[1]),
+                args.kmer_size,
+                args.plot.as_ref(),
+                args.stats,
+                &selection,
+                &render,
+                &mut timings,
+            ),
+            (None, Some(file)) => run_cli_mode(
+                file,
+                args.plot.as_ref(),
+                args.stats.then_some(args.format),
+                &selection,
+                &render,
+                &mut timings,
+            ),
+            (None, None) => unreachable!(),
+        };
+        let result = match (&args.sv_vcf, &file) {
+            (Some(vcf), Some(file)) => result.and_then(|_| export_sv_vcf(file, vcf, &selection)),
+            _ => result,
+        };
+        let result = match (&args.export_paf, &file) {
+            (Some(paf), Some(file)) => {
+                result.and_then(|_| export_filtered_paf(file, paf, &selection, &mut timings))
+            }
+            _ => result,
+        };
+        let result = match (&args.export_1aln, &file) {
+            (Some(dest), Some(file)) => {
+                result.and_then(|_| export_filtered_1aln(file, dest, &selection, &mut timings))
+            }
+            _ => result,
+        };
+        let result = match (&args.grid_pages, &file) {
+            (Some(output), Some(file)) => result
+                .and_then(|_| export_grid_pages(file, output, &selection, &render, &mut timings)),
+            _ => result,
+        };
+        let result = match (args.ascii, &file) {
+            (Some(width), Some(file)) => {
+                result.and_then(|_| print_ascii_plot(file, width, &selection, &mut timings))
+            }
+            _ => result,
+        };
+        if args.timings {
+            eprint!("{}", timings.report());
+        }
+
+        match result {
+            Ok(_) => return Ok(()),
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
             }
         }
     }
@@ -94,17 +551,120 @@ fn main() -> Result<(), eframe::Error> {
         ..Default::default()
     };
 
-    let mut app = AlnViewApp::default();
+    let presets = PresetStore::load_default().unwrap_or_else(|e| {
+        eprintln!("⚠️  Ignoring saved presets: {e:#}");
+        PresetStore::default()
+    });
+    let bindings = Bindings::load_default().unwrap_or_else(|e| {
+        eprintln!("⚠️  Ignoring saved mouse bindings: {e:#}");
+        Bindings::default()
+    });
+    let mut app = AlnViewApp {
+        presets,
+        bindings,
+        pending_layout: layout,
+        pending_order: order,
+        pending_agp: agp,
+        max_segments,
+        sweep_filter: sweep,
+        pending_preset: preset.map(|preset| Preset { sweep, ..preset }), // --filter wins
+        pending_cutoff: args.min_identity.is_some().then_some(cutoff),
+        timings: args.timings,
+        ..Default::default()
+    };
 
     // If file was provided, load it on startup
-    if let Some(file) = args.file {
+    if let Some(files) = args.all_vs_all {
+        app.load_all_vs_all_async(files);
+    } else if let Some(fastas) = args.kmer {
+        app.kmer_size = args.kmer_size;
+        app.load_kmer_async(fastas[0].clone(), fastas[1].clone(), args.kmer_size);
+    } else if let (true, Some(file)) = (args.follow, file.as_ref()) {
+        app.follow_paf(file.clone());
+    } else if let (Some(store), Some(file)) = (args.segment_store, file.as_ref()) {
+        app.pending_session = session;
+        app.load_file_via_store_async(file.clone(), store);
+    } else if let Some(file) = file {
         app.current_file = Some(file.clone());
+        app.pending_session = session;
         app.load_file_async(file);
     }
 
     eframe::run_native("ALNview", options, Box::new(move |_cc| Ok(Box::new(app))))
 }
 
+/// Which sequences a CLI render shows, in what order, and how many segments at most
+struct SequenceSelection {
+    query_filter: SequenceFilter,
+    target_filter: SequenceFilter,
+    layout: Option<SequenceLayout>,
+    order: Option<SequenceLayout>, // From --order/--order-target; reorders without hiding
+    agp: Option<Agp>,              // Scaffolds to draw --plot in
+    max_segments: Option<usize>,
+    sweep: Option<SweepFilter>,
+    cutoff: SegmentFilter, // Plot-wide cutoff from a preset
+    normalized: bool,      // Stretch every sequence to equal width (preset)
+    deterministic: bool,   // Canonical segment order, independent of the file's
+}
+
+impl SequenceSelection {
+    /// Records the plane sweep, the cutoff and both sequence filters keep,
+    /// with the plot of them, so --stats covers what --plot would draw; as
+    /// there, the sweep runs over every alignment before the rest hide any
+    fn select_records(
+        &self,
+        records: Vec<aln_reader::AlnRecord>,
+        query_sequences: Vec<String>,
+        target_sequences: Vec<String>,
+        (query_lengths, target_lengths): (Vec<i64>, Vec<i64>),
+    ) -> (Vec<aln_reader::AlnRecord>, RustPlot) {
+        let mut plot = RustPlot::from_records(
+            &records,
+            query_sequences,
+            target_sequences,
+            query_lengths,
+            target_lengths,
+        );
+        plot.cutoff = self.cutoff;
+        let swept = self.sweep.map(|sweep| plot.swept(sweep));
+        let keeps = |filter: &SequenceFilter, names: &[String], id: i64| {
+            names
+                .get(id as usize)
+                .is_none_or(|name| filter.matches(id as usize, name))
+        };
+        let records: Vec<_> = records
+            .into_iter()
+            .enumerate()
+            .filter(|(idx, rec)| {
+                swept.as_ref().is_none_or(|kept| kept[*idx])
+                    && plot.passes(&plot.segments[*idx])
+                    && keeps(&self.query_filter, &plot.query_sequences, rec.query_id)
+                    && keeps(&self.target_filter, &plot.target_sequences, rec.target_id)
+            })
+            .map(|(_, rec)| rec)
+            .collect();
+        let mut selected = RustPlot::from_records(
+            &records,
+            plot.query_sequences,
+            plot.target_sequences,
+            plot.query_lengths,
+            plot.target_lengths,
+        );
+        selected.cutoff = self.cutoff;
+        (records, selected)
+    }
+}
+
+/// Radius (pixels) and brightness (0-1) of a length-scaled dot: both grow with
+/// the log of the alignment length, from 100 bp up to 1 Mb
+fn length_dot(len: i64) -> (f32, f32) {
+    let t = (((len.abs().max(1) as f32).log10() - 2.0) / 4.0).clamp(0.0, 1.0);
+    (
+        MIN_DOT_RADIUS_PX + (LENGTH_DOT_MAX_RADIUS_PX - MIN_DOT_RADIUS_PX) * t,
+        0.3 + 0.7 * t,
+    )
+}
+
 /// Parse filters from CLI arguments
 fn parse_filters(
     names_opt: Option<&str>,
@@ -113,7 +673,7 @@ fn parse_filters(
     let mut filter = SequenceFilter::new();
 
     if let Some(names) = names_opt {
-        filter = SequenceFilter::from_names(names);
+        filter = SequenceFilter::from_text(names)?;
     }
 
     if let Some(range) = range_opt {
@@ -129,22 +689,78 @@ fn parse_filters(
 fn run_cli_mode(
     file: &PathBuf,
     output_plot: Option<&PathBuf>,
-    print_stats: bool,
-    query_filter: &SequenceFilter,
-    target_filter: &SequenceFilter,
+    stats_format: Option<StatsFormat>,
+    selection: &SequenceSelection,
+    render: &RenderOptions,
+    timings: &mut Timings,
 ) -> anyhow::Result<()> {
-    use aln_reader::AlnFile;
+    use aln_reader::{AlnFile, AlnRecord, ReadReport};
 
-    println!("Reading .1aln file: {}", file.display());
+    // Structured statistics own stdout
+    let structured = stats_format.is_some_and(|format| format != StatsFormat::Text);
+    let progress = |text: String| {
+        if structured {
+            eprintln!("{text}");
+        } else {
+            println!("{text}");
+        }
+    };
 
-    let mut aln_file = AlnFile::open(file)?;
+    // Names up front and records on demand from a .1aln; a .delta is read whole
+    type ReadRecords = Box<dyn FnOnce() -> (Vec<AlnRecord>, ReadReport)>;
+    let (query_sequences, target_sequences, lengths, read_records): (_, _, _, ReadRecords) =
+        if delta::is_delta_path(file) {
+            progress(format!("Reading .delta file: {}", file.display()));
+            let delta = delta::DeltaFile::open(file)?;
+            (
+                delta.query_sequences.clone(),
+                delta.target_sequences.clone(),
+                (delta.query_lengths.clone(), delta.target_lengths.clone()),
+                Box::new(move || (delta.records, delta.report)),
+            )
+        } else {
+            progress(format!("Reading .1aln file: {}", file.display()));
+            let mut aln_file = AlnFile::open(file)?;
+            (
+                aln_file.query_sequences.clone(),
+                aln_file.target_sequences.clone(),
+                (Vec::new(), Vec::new()),
+                Box::new(move || aln_file.read_records_lenient()),
+            )
+        };
 
-    println!("Query sequences: {}", aln_file.query_sequences.len());
-    println!("Target sequences: {}", aln_file.target_sequences.len());
+    progress(format!("Query sequences: {}", query_sequences.len()));
+    progress(format!("Target sequences: {}", target_sequences.len()));
 
-    if print_stats {
+    if structured {
+        let (records, report) = timings.time("read", read_records);
+        if !report.is_clean() {
+            eprintln!("⚠️  {}", report.summary(records.len()));
+        }
+        let (records, plot) =
+            selection.select_records(records, query_sequences, target_sequences, lengths);
+        let mut stats = stats::AlignmentStats::new(&records, &plot);
+        let matching = |seqs: Vec<stats::SequenceStats>, filter: &SequenceFilter| {
+            seqs.into_iter()
+                .enumerate()
+                .filter(|(i, seq)| filter.matches(*i, &seq.name))
+                .map(|(_, seq)| seq)
+                .collect()
+        };
+        stats.query = matching(stats.query, &selection.query_filter);
+        stats.target = matching(stats.target, &selection.target_filter);
+        match stats_format {
+            Some(StatsFormat::Json) => print!("{}", stats.to_json()),
+            _ => print!("{}", stats.to_tsv()),
+        }
+    } else if stats_format.is_some() {
         println!("\nReading alignment records...");
-        let records = aln_file.read_all_records()?;
+        let (records, report) = timings.time("read", read_records);
+        if !report.is_clean() {
+            eprintln!("⚠️  {}", report.summary(records.len()));
+        }
+        let (records, plot) =
+            selection.select_records(records, query_sequences, target_sequences, lengths);
         println!("Total alignments: {}", records.len());
 
         if !records.is_empty() {
@@ -177,30 +793,482 @@ fn run_cli_mode(
             println!("  Forward alignments: {forward_count}");
             println!("  Reverse alignments: {reverse_count}");
             println!("  Total aligned bases: {total_length}");
+
+            print_ani(&plot);
+        }
+    }
+
+    if let Some(output_path) = output_plot {
+        println!("\nRendering plot to: {}", output_path.display());
+        let plot = read_plot(file, timings)?;
+        save_filtered_plot(plot, output_path, selection, render, timings)?;
+    }
+
+    Ok(())
+}
+
+/// All of FILE's alignments, from its index (`alnview index`) when that is
+/// newer than FILE
+fn read_plot(file: &Path, timings: &mut Timings) -> anyhow::Result<RustPlot> {
+    let index = segment_store::sidecar_path(file);
+    if segment_store::is_fresh(&index, file) {
+        println!("Reading index: {}", index.display());
+        let store = SegmentStore::open(&index)?;
+        return Ok(timings.time("read", || store.overview(store.len())));
+    }
+    let (plot, report) = RustPlot::from_file_timed(file, timings)?;
+    if !report.is_clean() {
+        eprintln!("⚠️  {}", report.summary(plot.segments.len()));
+    }
+    Ok(plot)
+}
+
+/// Write FILE's index for `alnview index`
+fn build_index(file: &Path, output: Option<PathBuf>, tiles: usize) -> anyhow::Result<()> {
+    let output = output.unwrap_or_else(|| segment_store::sidecar_path(file));
+    println!("Reading .1aln file: {}", file.display());
+    let (plot, report) = RustPlot::from_file_with_report(file)?;
+    if !report.is_clean() {
+        eprintln!("⚠️  {}", report.summary(plot.segments.len()));
+    }
+    SegmentStore::write(&output, &plot, tiles)?;
+    println!(
+        "✅ Indexed {} alignments to {}",
+        plot.segments.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+/// Stream the records of a .1aln file to stdout as PAF lines, for
+/// `alnview convert --paf`
+fn convert_to_paf(file: &Path) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    let mut aln = aln_reader::AlnFile::open(file)?;
+    let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+    let written = (|| -> anyhow::Result<()> {
+        while let Some(rec) = aln.read_record()? {
+            writeln!(out, "{}", paf::paf_line(&rec))?;
+        }
+        out.flush()?;
+        Ok(())
+    })();
+    match written {
+        // The reader went away (e.g. `| head`); that is not a failure
+        Err(e)
+            if e.downcast_ref::<std::io::Error>()
+                .is_some_and(|e| e.kind() == std::io::ErrorKind::BrokenPipe) =>
+        {
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// Write the file's alignments that survive the CLI selection (everything
+/// but downsampling and equal widths) as PAF, for --export-paf
+fn export_filtered_paf(
+    file: &Path,
+    paf_path: &Path,
+    selection: &SequenceSelection,
+    timings: &mut Timings,
+) -> anyhow::Result<()> {
+    let plot = read_plot(file, timings)?;
+    let plot = select_alignments(plot, selection)?;
+    let written = paf::write_plot(&plot, paf_path)?;
+    println!(
+        "✅ Wrote {written} filtered alignments to {}",
+        paf_path.display()
+    );
+    Ok(())
+}
+
+/// Copy the file's alignments that survive the CLI selection (everything but
+/// downsampling and equal widths) to a new .1aln, for --export-1aln
+fn export_filtered_1aln(
+    file: &Path,
+    dest: &Path,
+    selection: &SequenceSelection,
+    timings: &mut Timings,
+) -> anyhow::Result<()> {
+    if !aln_writer::is_1aln_path(file) {
+        anyhow::bail!(
+            "--export-1aln copies alignments out of a .1aln file; use --export-paf for {}",
+            file.display()
+        );
+    }
+    let plot = read_plot(file, timings)?;
+    let plot = select_alignments(plot, selection)?;
+    let written = aln_writer::write_plot(file, &plot, dest)?;
+    println!(
+        "✅ Wrote {written} filtered alignments to {}",
+        dest.display()
+    );
+    Ok(())
+}
+
+/// Draw a panel per query x target sequence pair with passing alignments
+/// (after the CLI selection), into one PDF or a directory of PNGs, for
+/// --grid-pages
+fn export_grid_pages(
+    file: &Path,
+    output: &Path,
+    selection: &SequenceSelection,
+    render: &RenderOptions,
+    timings: &mut Timings,
+) -> anyhow::Result<()> {
+    let plot = read_plot(file, timings)?;
+    let plot = select_alignments(plot, selection)?;
+    let mut pairs: Vec<(usize, usize)> = plot.pair_identity().into_keys().collect();
+    if pairs.is_empty() {
+        anyhow::bail!("No sequence pair has alignments to draw");
+    }
+    pairs.sort_unstable();
+
+    let as_pdf = output
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+    if !as_pdf {
+        std::fs::create_dir_all(output)?;
+    }
+    println!("Drawing {} sequence pairs...", pairs.len());
+    let mut pages = Vec::new();
+    timings.time("render", || -> anyhow::Result<()> {
+        for (page, &(query, target)) in pairs.iter().enumerate() {
+            let mut panel = plot.with_order(&[query], &[target]);
+            if let Some(max) = selection.max_segments {
+                if panel.passing_segments() > max {
+                    panel = panel.downsampled(max);
+                }
+            }
+            let (query, target) = (&plot.query_sequences[query], &plot.target_sequences[target]);
+            // Regions and annotations are placed on the whole plot, so they stay off
+            let options = RenderOptions {
+                title: format!("{query} vs {target}"),
+                subtitle: render.title.clone(),
+                annotations: Vec::new(),
+                regions: Vec::new(),
+                genome_groups: None,
+                viewport: None,
+                ..render.clone()
+            };
+            if as_pdf {
+                pages.push(pdf_page(&panel, &options)?);
+            } else {
+                let name = format!(
+                    "{:03}_{}_vs_{}.png",
+                    page + 1,
+                    file_name_part(query),
+                    file_name_part(target)
+                );
+                render_plot_to_png(&panel, &output.join(name), 1200, 1200, &options)?;
+            }
+        }
+        if as_pdf {
+            std::fs::write(output, pdf::finish_document(pages)?)?;
+        }
+        Ok(())
+    })?;
+    println!("✅ Wrote {} panels to {}", pairs.len(), output.display());
+    Ok(())
+}
+
+/// A sequence name made safe for a file name
+fn file_name_part(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Find candidate structural variants in the file's alignments that survive
+/// the CLI selection and write them as VCF, for --sv-vcf
+fn export_sv_vcf(
+    file: &Path,
+    vcf_path: &PathBuf,
+    selection: &SequenceSelection,
+) -> anyhow::Result<()> {
+    let plot = read_plot(file, &mut Timings::default())?;
+    let plot = select_alignments(plot, selection)?;
+    let calls = sv_calls::find_sv_candidates(&plot, sv_calls::DEFAULT_SV_MIN_LENGTH);
+    std::fs::write(vcf_path, sv_calls::to_vcf(&calls, &plot))?;
+    println!(
+        "✅ Wrote {} candidate structural variants to {}",
+        calls.len(),
+        vcf_path.display()
+    );
+    Ok(())
+}
+
+/// Print the arranged plot as braille text, for --ascii
+fn print_ascii_plot(
+    file: &Path,
+    width: usize,
+    selection: &SequenceSelection,
+    timings: &mut Timings,
+) -> anyhow::Result<()> {
+    let plot = read_plot(file, timings)?;
+    let plot = arrange_plot(plot, selection, timings)?;
+    if plot.query_genome_len == 0 || plot.target_genome_len == 0 {
+        anyhow::bail!("no sequences left to draw");
+    }
+    println!();
+    print!("{}", ascii_plot::to_braille(&plot, width));
+    Ok(())
+}
+
+/// Print whole-genome and per-query-sequence ANI for --stats
+fn print_ani(plot: &RustPlot) {
+    let summary = plot.ani();
+    let Some(ani) = summary.ani else {
+        return;
+    };
+    println!(
+        "\nANI (best alignment per query base): {ani:.2}% over {} ({:.1}% of query)",
+        format_bp(summary.covered_bp),
+        percent_of(summary.covered_bp, summary.query_bp)
+    );
+    for seq in &summary.per_sequence {
+        println!(
+            "  {}\t{:.2}%\t{} covered ({:.1}%)",
+            plot.query_sequences[seq.sequence],
+            seq.ani,
+            format_bp(seq.covered_bp),
+            percent_of(seq.covered_bp, seq.length)
+        );
+    }
+}
+
+fn percent_of(part: i64, whole: i64) -> f64 {
+    if whole > 0 {
+        100.0 * part as f64 / whole as f64
+    } else {
+        0.0
+    }
+}
+
+/// Run k-mer dotplot mode: build the plot from two FASTA files, then print stats or render it
+fn run_kmer_cli_mode(
+    (query_fasta, target_fasta): (&PathBuf, &PathBuf),
+    k: usize,
+    output_plot: Option<&PathBuf>,
+    print_stats: bool,
+    selection: &SequenceSelection,
+    render: &RenderOptions,
+    timings: &mut Timings,
+) -> anyhow::Result<()> {
+    println!(
+        "Building {k}-mer dotplot: {} vs {}",
+        query_fasta.display(),
+        target_fasta.display()
+    );
+
+    let plot = timings.time("k-mers", || {
+        kmer_plot::kmer_dotplot(query_fasta, target_fasta, k)
+    })?;
+
+    println!("Query sequences: {}", plot.query_sequences.len());
+    println!("Target sequences: {}", plot.target_sequences.len());
+
+    if print_stats {
+        let forward_count = plot.segments.iter().filter(|s| !s.reverse).count();
+        println!("\nK-mer match statistics:");
+        println!("  Matched segments: {}", plot.segments.len());
+        println!("  Forward segments: {forward_count}");
+        println!(
+            "  Reverse segments: {}",
+            plot.segments.len() - forward_count
+        );
+    }
+
+    if let Some(output_path) = output_plot {
+        println!("\nRendering plot to: {}", output_path.display());
+        save_filtered_plot(plot, output_path, selection, render, timings)?;
+    }
+
+    Ok(())
+}
+
+/// Run all-vs-all mode: merge pairwise files into one plot grouped by genome,
+/// then print stats or render it with the genome blocks outlined
+fn run_all_vs_all_cli_mode(
+    files: &[PathBuf],
+    output_plot: Option<&PathBuf>,
+    print_stats: bool,
+    selection: &SequenceSelection,
+    render: RenderOptions,
+    timings: &mut Timings,
+) -> anyhow::Result<()> {
+    println!("Merging {} alignment files", files.len());
+    let (plot, groups) = timings.time("read", || genome_groups::load_all_vs_all(files))?;
+
+    if print_stats {
+        println!("Genomes: {}", groups.names.len());
+        for name in &groups.names {
+            println!("  {name}");
         }
+        println!("Sequences: {}", plot.query_sequences.len());
+        println!("Total alignments: {}", plot.segments.len());
     }
 
     if let Some(output_path) = output_plot {
         println!("\nRendering plot to: {}", output_path.display());
-        let mut plot = RustPlot::from_file(file)?;
+        let render = RenderOptions {
+            genome_groups: Some(groups),
+            ..render
+        };
+        save_filtered_plot(plot, output_path, selection, &render, timings)?;
+    }
+
+    Ok(())
+}
 
-        // Apply filters if specified
-        if !query_filter.is_empty() || !target_filter.is_empty() {
-            println!("Applying filters...");
-            plot = plot.with_filters(query_filter, target_filter)?;
+/// Apply the sequence layout and filters (if any) and render the plot to a
+/// PNG, with the title, regions and annotations drawn on top
+fn save_filtered_plot(
+    plot: RustPlot,
+    output_path: &PathBuf,
+    selection: &SequenceSelection,
+    render: &RenderOptions,
+    timings: &mut Timings,
+) -> anyhow::Result<()> {
+    let (plot, render) = match selection.agp.as_ref().map(|agp| agp.scaffold(&plot)) {
+        Some(Some((scaffolded, contigs))) => {
             println!(
-                "  Filtered to {} query x {} target sequences",
-                plot.query_sequences.len(),
-                plot.target_sequences.len()
+                "Scaffolded with AGP: {} query x {} target sequences",
+                scaffolded.query_sequences.len(),
+                scaffolded.target_sequences.len()
+            );
+            let render = RenderOptions {
+                contig_boundaries: Some(contigs),
+                ..render.clone()
+            };
+            (scaffolded, render)
+        }
+        Some(None) => {
+            eprintln!("⚠️  No sequences of this plot are in the AGP file; drawing contigs");
+            (plot, render.clone())
+        }
+        None => (plot, render.clone()),
+    };
+    let plot = arrange_plot(plot, selection, timings)?;
+    timings.time("render", || {
+        if output_path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+        {
+            render_plot_to_pdf(&plot, output_path, &render)
+        } else {
+            render_plot_to_png(&plot, output_path, 1200, 1200, &render)
+        }
+    })?;
+    println!("✅ Plot saved successfully!");
+    Ok(())
+}
+
+/// Apply the cutoffs, sweep, sequence layout, filters and downsampling of a
+/// CLI selection
+fn arrange_plot(
+    mut plot: RustPlot,
+    selection: &SequenceSelection,
+    timings: &mut Timings,
+) -> anyhow::Result<RustPlot> {
+    let arrange_start = Instant::now();
+    plot = select_alignments(plot, selection)?;
+
+    if let Some(max) = selection.max_segments {
+        if plot.passing_segments() > max {
+            eprintln!(
+                "⚠️  {} alignments exceed --max-segments; drawing a length-weighted sample of {max} (use --all-segments to draw everything)",
+                plot.passing_segments()
             );
-            println!("  {} segments remain", plot.segments.len());
+            plot = plot.downsampled(max);
+        }
+    }
+    if selection.normalized {
+        plot = plot.normalized();
+    }
+    timings.record("arrange", arrange_start.elapsed());
+    Ok(plot)
+}
+
+/// Apply the cutoffs, sweep, sequence layout and filters of a CLI selection
+fn select_alignments(
+    mut plot: RustPlot,
+    selection: &SequenceSelection,
+) -> anyhow::Result<RustPlot> {
+    plot.cutoff = selection.cutoff;
+    if selection.deterministic {
+        plot = plot.sorted();
+    }
+    if let Some(sweep) = selection.sweep {
+        let before = plot.segments.len();
+        plot = plot.plane_sweep(sweep);
+        println!(
+            "Plane sweep {sweep}: kept {} of {before} alignments",
+            plot.segments.len()
+        );
+    }
+    if let Some(ref layout) = selection.layout {
+        println!("Applying sequence layout...");
+        let (arranged, missing) = layout.apply(&plot);
+        if missing > 0 {
+            eprintln!("⚠️  {missing} sequences in the layout are not in this plot");
+        }
+        plot = arranged;
+    }
+    if let Some(ref order) = selection.order {
+        println!("Applying sequence order...");
+        let (order, missing) = order.completed(&plot);
+        if missing > 0 {
+            eprintln!("⚠️  {missing} sequences in the order files are not in this plot");
         }
+        plot = order.apply(&plot).0;
+    }
 
-        render_plot_to_png(&plot, output_path, 1200, 1200)?;
-        println!("✅ Plot saved successfully!");
+    // Apply filters if specified
+    let (query_filter, target_filter) = (&selection.query_filter, &selection.target_filter);
+    if !query_filter.is_empty() || !target_filter.is_empty() {
+        println!("Applying filters...");
+        plot = plot.with_filters(query_filter, target_filter)?;
+        println!(
+            "  Filtered to {} query x {} target sequences",
+            plot.query_sequences.len(),
+            plot.target_sequences.len()
+        );
+        println!("  {} segments remain", plot.segments.len());
     }
+    Ok(plot)
+}
 
-    Ok(())
+/// Font for text in rendered images, so output doesn't depend on the fonts
+/// installed
+const EMBEDDED_FONT: &[u8] = include_bytes!("../fonts/DejaVuSans.ttf");
+
+/// Left and bottom margins of a PNG (pixels), for target and query labels
+const PNG_MARGIN_LEFT: u32 = 10;
+const PNG_MARGIN_BOTTOM: u32 = 100;
+
+/// Line height of PNG sequence labels; a sequence narrower than this on the
+/// plot goes unlabelled
+const PNG_LABEL_LINE_PX: i32 = 11;
+
+/// Height of the band above a PNG's plot for its title and subtitle
+fn png_title_band(options: &RenderOptions) -> u32 {
+    match (
+        options.title.trim().is_empty(),
+        options.subtitle.trim().is_empty(),
+    ) {
+        (true, true) => 0,
+        (false, false) => 52,
+        _ => 32,
+    }
 }
 
 /// Render a plot to a PNG file for testing/golden file generation
@@ -209,16 +1277,23 @@ fn render_plot_to_png(
     output_path: &PathBuf,
     width: u32,
     height: u32,
+    options: &RenderOptions,
 ) -> anyhow::Result<()> {
     use ab_glyph::{FontRef, PxScale};
     use image::{Rgba, RgbaImage};
     use imageproc::drawing::draw_text_mut;
 
-    // Add margin for labels (10px left padding, 100px bottom for x-axis labels)
-    let margin_left = 10;
-    let margin_bottom = 100;
+    let title = options.title.trim();
+    let subtitle = options.subtitle.trim();
+
+    // Add margin for labels (10px left padding, 100px bottom for x-axis labels),
+    // plus a band at the top for the title and subtitle if there are any
+    let margin_left = PNG_MARGIN_LEFT;
+    let margin_bottom = PNG_MARGIN_BOTTOM;
+    let margin_top = png_title_band(options);
     let plot_width = width - margin_left;
-    let plot_height = height - margin_bottom;
+    let plot_height = height - margin_bottom - margin_top;
+    let plot_bottom = margin_top + plot_height;
 
     let mut img = RgbaImage::new(width, height);
 
@@ -228,134 +1303,610 @@ fn render_plot_to_png(
     }
 
     // Load font (using embedded DejaVu Sans)
-    let font_data = include_bytes!("../fonts/DejaVuSans.ttf");
-    let font = FontRef::try_from_slice(font_data)
+    let font = FontRef::try_from_slice(EMBEDDED_FONT)
         .map_err(|e| anyhow::anyhow!("Failed to load font: {}", e))?;
 
     let small_text_scale = PxScale::from(10.0);
 
-    let alen = plot.get_alen() as f64;
-    let blen = plot.get_blen() as f64;
+    // The whole plot, or just the viewport of a view export
+    let (view_x, view_y, alen, blen) = match options.viewport {
+        Some([x, y, width, height]) => (x, y, width, height),
+        None => (0.0, 0.0, plot.get_alen() as f64, plot.get_blen() as f64),
+    };
 
     // Calculate scale to fit entire genome in the plot area (excluding margins)
     let scale_x = alen / plot_width as f64;
     let scale_y = blen / plot_height as f64;
     let scale = scale_x.max(scale_y);
 
-    // Genome to pixel mapping (accounting for margins)
+    // With equal aspect the genome rectangle is centred and the spare room
+    // along the shorter axis is left as padding
+    let (pad_x, pad_y) = if options.equal_aspect {
+        (
+            ((plot_width as f64 - alen / scale) / 2.0).max(0.0) as u32,
+            ((plot_height as f64 - blen / scale) / 2.0).max(0.0) as u32,
+        )
+    } else {
+        (0, 0)
+    };
+    let (genome_left, genome_right) = (margin_left + pad_x, width - pad_x);
+    let (genome_top, genome_bottom) = (margin_top + pad_y, plot_bottom - pad_y);
+
+    // Genome to pixel mapping (accounting for margins)
     let genome_to_pixel = |gx: f64, gy: f64| -> (i32, i32) {
-        let px = margin_left as i32 + (gx / scale) as i32;
-        let py = (plot_height as i32) - (gy / scale) as i32 - 1; // Flip Y
+        let px = genome_left as i32 + ((gx - view_x) / scale) as i32;
+        let py = (genome_bottom as i32) - ((gy - view_y) / scale) as i32 - 1; // Flip Y
         (px, py)
     };
 
-    // Draw query sequence boundaries (vertical lines) and labels
+    if options.equal_aspect {
+        let outline = Rgba([60, 60, 60, 255]);
+        let (left, top) = (genome_left as i32, genome_top as i32);
+        let (right, bottom) = (genome_right as i32 - 1, genome_bottom as i32 - 1);
+        draw_line(&mut img, left, top, right, top, outline);
+        draw_line(&mut img, right, top, right, bottom, outline);
+        draw_line(&mut img, right, bottom, left, bottom, outline);
+        draw_line(&mut img, left, bottom, left, top, outline);
+    }
+
+    // Contig boundaries inside AGP scaffolds, dotted under the scaffold lines
+    if let Some(ref contigs) = options.contig_boundaries {
+        let dim = Rgba([60, 60, 60, 255]);
+        let (query, target) = contigs.for_plot(plot);
+        for pos in query {
+            let (px, _) = genome_to_pixel(pos as f64, 0.0);
+            if px < margin_left as i32 || px >= width as i32 {
+                continue;
+            }
+            for y in (genome_top..genome_bottom).step_by(2) {
+                if let Some(pixel) = img.get_pixel_mut_checked(px as u32, y) {
+                    *pixel = dim;
+                }
+            }
+        }
+        for pos in target {
+            let (_, py) = genome_to_pixel(0.0, pos as f64);
+            if py < margin_top as i32 || py >= plot_bottom as i32 {
+                continue;
+            }
+            for x in (genome_left..genome_right).step_by(2) {
+                if let Some(pixel) = img.get_pixel_mut_checked(x, py as u32) {
+                    *pixel = dim;
+                }
+            }
+        }
+    }
+
+    // Draw query sequence boundaries (vertical lines)
     let query_boundaries = plot.get_scaffold_boundaries(0);
-    for (idx, &pos) in query_boundaries.iter().enumerate() {
+    for &pos in &query_boundaries {
         let (px, _) = genome_to_pixel(pos as f64, 0.0);
-
-        // Draw vertical boundary line
         if px >= margin_left as i32 && px < width as i32 {
-            for y in 0..plot_height {
+            for y in genome_top..genome_bottom {
                 if let Some(pixel) = img.get_pixel_mut_checked(px as u32, y) {
                     *pixel = Rgba([100, 100, 100, 255]); // Gray
                 }
             }
         }
+    }
 
-        // Draw sequence name label (rotated 90 degrees on X-axis)
-        // We'll draw text rotated by drawing it vertically in the bottom margin
-        if idx < plot.query_sequences.len() {
-            let name = &plot.query_sequences[idx];
-            // Extract meaningful part of name for display
-            let display_name = extract_display_name(name, 20);
-
-            // Position: draw vertically starting at the boundary line
-            let label_x = px + 5;
-            let label_y = (plot_height + 5) as i32;
-
-            // Draw rotated text by creating a temporary image and rotating it
-            // For simplicity, we'll just draw it vertically character by character
-            if label_x >= margin_left as i32 && label_x < (width - 20) as i32 {
-                for (i, ch) in display_name.chars().enumerate() {
-                    let char_y = label_y + (i as i32 * 11);
-                    if char_y < height as i32 - 5 {
-                        draw_text_mut(
-                            &mut img,
-                            Rgba([200, 200, 200, 255]),
-                            label_x,
-                            char_y,
-                            small_text_scale,
-                            &font,
-                            &ch.to_string(),
-                        );
-                    }
-                }
-            }
+    // Query names down the bottom margin, one character per line, centred
+    // under each sequence wide enough to hold a column of text; names too
+    // long for the margin are cut short
+    let label_top = (plot_bottom + 5) as i32;
+    let max_chars = ((height as i32 - 5 - label_top) / PNG_LABEL_LINE_PX).max(0) as usize;
+    for (idx, name) in plot.query_sequences.iter().enumerate() {
+        let (beg, _) = genome_to_pixel(query_boundaries[idx] as f64, 0.0);
+        let (end, _) = genome_to_pixel(query_boundaries[idx + 1] as f64, 0.0);
+        let label_x = (beg + end) / 2 - PNG_LABEL_LINE_PX / 2;
+        if end - beg < PNG_LABEL_LINE_PX
+            || label_x < margin_left as i32
+            || label_x >= (width - 20) as i32
+        {
+            continue;
+        }
+        let display_name = extract_display_name(name, max_chars.min(20));
+        for (i, ch) in display_name.chars().enumerate() {
+            draw_text_mut(
+                &mut img,
+                Rgba([200, 200, 200, 255]),
+                label_x,
+                label_top + i as i32 * PNG_LABEL_LINE_PX,
+                small_text_scale,
+                &font,
+                &ch.to_string(),
+            );
         }
     }
 
-    // Draw target sequence boundaries (horizontal lines) and labels
+    // Draw target sequence boundaries (horizontal lines)
     let target_boundaries = plot.get_scaffold_boundaries(1);
-    for (idx, &pos) in target_boundaries.iter().enumerate() {
+    for &pos in &target_boundaries {
         let (_, py) = genome_to_pixel(0.0, pos as f64);
-
-        // Draw horizontal boundary line
-        if py >= 0 && py < plot_height as i32 {
-            for x in margin_left..width {
+        if py >= margin_top as i32 && py < plot_bottom as i32 {
+            for x in genome_left..genome_right {
                 if let Some(pixel) = img.get_pixel_mut_checked(x, py as u32) {
                     *pixel = Rgba([100, 100, 100, 255]); // Gray
                 }
             }
         }
+    }
+
+    // Target names at the left edge, at the middle of each sequence tall
+    // enough for a line of text
+    for (idx, name) in plot.target_sequences.iter().enumerate() {
+        let (_, bottom) = genome_to_pixel(0.0, target_boundaries[idx] as f64);
+        let (_, top) = genome_to_pixel(0.0, target_boundaries[idx + 1] as f64);
+        let label_y = (top + bottom) / 2 - PNG_LABEL_LINE_PX / 2;
+        if bottom - top < PNG_LABEL_LINE_PX
+            || label_y < margin_top as i32
+            || label_y >= plot_bottom as i32 - 10
+        {
+            continue;
+        }
+        draw_text_mut(
+            &mut img,
+            Rgba([200, 200, 200, 255]),
+            (genome_left + 5) as i32,
+            label_y,
+            small_text_scale,
+            &font,
+            &extract_display_name(name, 25),
+        );
+    }
 
-        // Draw sequence name label horizontally at the bottom of the boundary line
-        // This keeps it visible as you scan across the plot
-        if idx < plot.target_sequences.len() {
-            let name = &plot.target_sequences[idx];
-            // Extract meaningful part of name for display
-            let display_name = extract_display_name(name, 25);
+    // Thicker separators between the genome blocks of an all-vs-all plot
+    if let Some(ref groups) = options.genome_groups {
+        let separator = Rgba([170, 170, 200, 255]);
+        let (left, right) = (genome_left as i32, genome_right as i32 - 1);
+        let (top, bottom) = (genome_top as i32, genome_bottom as i32 - 1);
+        for (_, start, _) in groups.runs(&plot.query_sequences, &plot.query_boundaries) {
+            if start > 0 {
+                let (px, _) = genome_to_pixel(start as f64, 0.0);
+                draw_thick_line(&mut img, px, top, px, bottom, 3.0, separator);
+            }
+        }
+        for (_, start, _) in groups.runs(&plot.target_sequences, &plot.target_boundaries) {
+            if start > 0 {
+                let (_, py) = genome_to_pixel(0.0, start as f64);
+                draw_thick_line(&mut img, left, py, right, py, 3.0, separator);
+            }
+        }
+    }
 
-            // Position at left edge, just below the boundary line
-            let label_x = (margin_left + 5) as i32;
-            let label_y = py + 2; // Just below the line
+    // Draw the segments of each layer with its colours and filters,
+    // anti-aliased and accumulated so dense stretches come out brighter; each
+    // layer is laid over the ones before at its own opacity
+    let genome_to_point = |gx: f64, gy: f64| -> (f32, f32) {
+        (
+            genome_left as f32 + ((gx - view_x) / scale) as f32,
+            genome_bottom as f32 - ((gy - view_y) / scale) as f32,
+        )
+    };
+    let segments = plot.query_segments_in_region(0, view_x, view_y, alen, blen);
+    let mut accumulator = raster::Accumulator::new(img.width(), img.height());
+    for layer in &options.layers {
+        accumulator.clear();
+        for seg in &segments {
+            if !layer.shows_strand(seg.reverse)
+                || (layer.filter.is_active() && !layer.filter.matches(seg))
+            {
+                continue;
+            }
+            let [r, g, b, _] = layer.segment_color(seg, false).to_srgba_unmultiplied();
+            accumulator.line(
+                genome_to_point(seg.abeg as f64, seg.bbeg as f64),
+                genome_to_point(seg.aend as f64, seg.bend as f64),
+                layer.segment_width(seg),
+                [r, g, b],
+            );
+        }
+        accumulator.composite(&mut img, layer.opacity.clamp(0.0, 1.0));
+    }
 
-            if label_y >= 0 && label_y < plot_height as i32 - 10 {
-                draw_text_mut(
-                    &mut img,
-                    Rgba([200, 200, 200, 255]),
-                    label_x,
-                    label_y,
-                    small_text_scale,
-                    &font,
-                    &display_name,
-                );
+    // Regions of interest: a light wash, an outline and the name
+    for region in &options.regions {
+        let [r, g, b] = region.color;
+        let color = Rgba([r, g, b, 255]);
+        let (x0, y1) = genome_to_pixel(region.x_min as f64, region.y_min as f64);
+        let (x1, y0) = genome_to_pixel(region.x_max as f64, region.y_max as f64);
+        let x_range = x0.max(margin_left as i32)..=x1.min(width as i32 - 1);
+        let y_range = y0.max(margin_top as i32)..=y1.min(plot_bottom as i32 - 1);
+        for y in y_range.clone() {
+            for x in x_range.clone() {
+                let pixel = img.get_pixel_mut(x as u32, y as u32);
+                for c in 0..3 {
+                    let blended = pixel[c] as f32 * 0.85 + color[c] as f32 * 0.15;
+                    pixel[c] = blended as u8;
+                }
             }
         }
+        draw_line(&mut img, x0, y0, x1, y0, color);
+        draw_line(&mut img, x1, y0, x1, y1, color);
+        draw_line(&mut img, x1, y1, x0, y1, color);
+        draw_line(&mut img, x0, y1, x0, y0, color);
+        draw_text_mut(
+            &mut img,
+            color,
+            x0 + 3,
+            y0 + 2,
+            PxScale::from(11.0),
+            &font,
+            &region.name,
+        );
     }
 
-    // Draw all segments for layer 0
-    let segments = plot.query_segments_in_region(0, 0.0, 0.0, alen, blen);
+    // Scale bar in the bottom-right corner of the plot area
+    if options.scale_bar {
+        let (len, label) = scale_bar(scale, SCALE_BAR_MAX_PX);
+        let color = Rgba([255, 255, 255, 255]);
+        let right = width as i32 - 16;
+        let left = right - (len as f64 / scale) as i32;
+        let y = plot_bottom as i32 - 16;
+        draw_line(&mut img, left, y, right, y, color);
+        draw_line(&mut img, left, y + 1, right, y + 1, color);
+        for x in [left, right] {
+            draw_line(&mut img, x, y - 4, x, y + 4, color);
+        }
+        let (text_width, _) = imageproc::drawing::text_size(PxScale::from(12.0), &font, &label);
+        draw_text_mut(
+            &mut img,
+            color,
+            right - text_width as i32,
+            y - 20,
+            PxScale::from(12.0),
+            &font,
+            &label,
+        );
+    }
 
-    for seg in segments {
-        let (x1, y1) = genome_to_pixel(seg.abeg as f64, seg.bbeg as f64);
-        let (x2, y2) = genome_to_pixel(seg.aend as f64, seg.bend as f64);
+    // Title and subtitle in the top band
+    let mut text_y = 6;
+    if !title.is_empty() {
+        draw_text_mut(
+            &mut img,
+            Rgba([255, 255, 255, 255]),
+            margin_left as i32 + 5,
+            text_y,
+            PxScale::from(20.0),
+            &font,
+            title,
+        );
+        text_y += 26;
+    }
+    if !subtitle.is_empty() {
+        draw_text_mut(
+            &mut img,
+            Rgba([180, 180, 180, 255]),
+            margin_left as i32 + 5,
+            text_y,
+            PxScale::from(14.0),
+            &font,
+            subtitle,
+        );
+    }
 
-        // Color: green for forward, red for reverse
-        let color = if seg.reverse {
-            Rgba([255, 0, 0, 255]) // Red
+    // Annotations on top, labels offset up and right when they have an arrow
+    let annotation_color = Rgba([255, 255, 255, 255]);
+    for annotation in &options.annotations {
+        let (px, py) = genome_to_pixel(annotation.x, annotation.y);
+        let (lx, ly) = if annotation.arrow {
+            let (lx, ly) = (
+                px + ANNOTATION_ARROW_PX as i32,
+                py - ANNOTATION_ARROW_PX as i32,
+            );
+            draw_line(&mut img, lx, ly, px, py, annotation_color);
+            draw_line(&mut img, px, py, px + 6, py - 1, annotation_color);
+            draw_line(&mut img, px, py, px + 1, py - 6, annotation_color);
+            (lx, ly - 14)
         } else {
-            Rgba([0, 255, 0, 255]) // Green
+            (px, py - 7)
         };
-
-        // Draw line using Bresenham's algorithm
-        draw_line(&mut img, x1, y1, x2, y2, color);
+        for (i, line) in annotation.text.lines().enumerate() {
+            draw_text_mut(
+                &mut img,
+                annotation_color,
+                lx,
+                ly + i as i32 * 14,
+                PxScale::from(13.0),
+                &font,
+                line,
+            );
+        }
     }
 
     img.save(output_path)?;
     Ok(())
 }
 
+/// Size of a --plot PDF page in points (10 inches square)
+const PDF_PAGE_PT: f32 = 720.0;
+
+/// Render a plot to a vector PDF for manuscripts
+fn render_plot_to_pdf(
+    plot: &RustPlot,
+    output_path: &Path,
+    options: &RenderOptions,
+) -> anyhow::Result<()> {
+    std::fs::write(output_path, pdf_page(plot, options)?.finish()?)?;
+    Ok(())
+}
+
+/// A plot as a PDF page: the PNG's overlays, with a scale bar, axis titles
+/// and a strand colour legend always drawn, and text in the embedded DejaVu
+/// Sans
+fn pdf_page(plot: &RustPlot, options: &RenderOptions) -> anyhow::Result<pdf::PdfPage<'static>> {
+    let mut page = pdf::PdfPage::new(PDF_PAGE_PT, PDF_PAGE_PT, EMBEDDED_FONT)?;
+    let (white, gray, label_gray) = ([255, 255, 255], [100, 100, 100], [200, 200, 200]);
+    page.fill_rect(0.0, 0.0, PDF_PAGE_PT, PDF_PAGE_PT, [0, 0, 0], 1.0);
+
+    // Title and subtitle, then the legend row, above the plot; sequence names
+    // and axis titles go in the left and bottom margins
+    let (left, right) = (72.0, PDF_PAGE_PT - 16.0);
+    let mut top = 14.0;
+    let title = options.title.trim();
+    if !title.is_empty() {
+        page.text(left, top, 16.0, white, title);
+        top += 22.0;
+    }
+    let subtitle = options.subtitle.trim();
+    if !subtitle.is_empty() {
+        page.text(left, top, 11.0, [180, 180, 180], subtitle);
+        top += 16.0;
+    }
+
+    // Legend: a swatch and name per drawn strand of each layer, or the
+    // identity gradient of a layer coloured by identity
+    let mut legend_x = left;
+    for layer in &options.layers {
+        if layer.color_by_identity {
+            let gradient = &layer.gradient;
+            let label = format!("{} {:.0}%", layer.name, gradient.min_identity);
+            page.text(legend_x, top, 9.0, label_gray, &label);
+            legend_x += 4.0 + page.text_width(9.0, &label);
+            for step in 0..24 {
+                let t = step as f32 / 23.0;
+                page.fill_rect(
+                    legend_x + step as f32 * 2.0,
+                    top + 1.0,
+                    2.0,
+                    8.0,
+                    gradient.colormap.color(t),
+                    1.0,
+                );
+            }
+            let label = format!("{:.0}%", gradient.max_identity);
+            page.text(legend_x + 52.0, top, 9.0, label_gray, &label);
+            legend_x += 64.0 + page.text_width(9.0, &label);
+            continue;
+        }
+        for (reverse, strand) in [(false, "forward"), (true, "reverse")] {
+            if !layer.shows_strand(reverse) {
+                continue;
+            }
+            let [r, g, b, _] = layer.strand_color(reverse, false).to_array();
+            page.line(
+                (legend_x, top + 5.0),
+                (legend_x + 14.0, top + 5.0),
+                2.0,
+                [r, g, b],
+            );
+            let label = format!("{} {strand}", layer.name);
+            page.text(legend_x + 18.0, top, 9.0, label_gray, &label);
+            legend_x += 30.0 + page.text_width(9.0, &label);
+        }
+    }
+    top += 20.0;
+
+    let (plot_top, plot_bottom) = (top, PDF_PAGE_PT - 90.0);
+    let (plot_width, plot_height) = (right - left, plot_bottom - plot_top);
+    let (alen, blen) = (plot.get_alen() as f64, plot.get_blen() as f64);
+    let scale = (alen / plot_width as f64).max(blen / plot_height as f64); // bp per point
+    let (pad_x, pad_y) = if options.equal_aspect {
+        (
+            ((plot_width as f64 - alen / scale) / 2.0).max(0.0) as f32,
+            ((plot_height as f64 - blen / scale) / 2.0).max(0.0) as f32,
+        )
+    } else {
+        (0.0, 0.0)
+    };
+    let (genome_left, genome_right) = (left + pad_x, right - pad_x);
+    let (genome_top, genome_bottom) = (plot_top + pad_y, plot_bottom - pad_y);
+    let to_page = |gx: f64, gy: f64| {
+        (
+            genome_left + (gx / scale) as f32,
+            genome_bottom - (gy / scale) as f32,
+        )
+    };
+    page.stroke_rect(
+        genome_left,
+        genome_top,
+        genome_right - genome_left,
+        genome_bottom - genome_top,
+        0.5,
+        [60, 60, 60],
+    );
+
+    // Sequence boundaries, with query names down the bottom margin and target
+    // names right-aligned in the left margin, each centred on its sequence;
+    // sequences too narrow for a line of text go unlabelled
+    let label_size = 6.0;
+    if let Some(ref contigs) = options.contig_boundaries {
+        let (query, target) = contigs.for_plot(plot);
+        for pos in query {
+            let (x, _) = to_page(pos as f64, 0.0);
+            page.line((x, genome_top), (x, genome_bottom), 0.1, [70, 70, 70]);
+        }
+        for pos in target {
+            let (_, y) = to_page(0.0, pos as f64);
+            page.line((genome_left, y), (genome_right, y), 0.1, [70, 70, 70]);
+        }
+    }
+    for &pos in &plot.query_boundaries {
+        let (x, _) = to_page(pos as f64, 0.0);
+        page.line((x, genome_top), (x, genome_bottom), 0.3, gray);
+    }
+    for (idx, name) in plot.query_sequences.iter().enumerate() {
+        let (beg, _) = to_page(plot.query_boundaries[idx] as f64, 0.0);
+        let (end, _) = to_page(plot.query_boundaries[idx + 1] as f64, 0.0);
+        if end - beg >= label_size + 1.0 {
+            page.text_down(
+                (beg + end - label_size) / 2.0,
+                plot_bottom + 4.0,
+                label_size,
+                label_gray,
+                &extract_display_name(name, 20),
+            );
+        }
+    }
+    for &pos in &plot.target_boundaries {
+        let (_, y) = to_page(0.0, pos as f64);
+        page.line((genome_left, y), (genome_right, y), 0.3, gray);
+    }
+    for (idx, name) in plot.target_sequences.iter().enumerate() {
+        let (_, bottom) = to_page(0.0, plot.target_boundaries[idx] as f64);
+        let (_, top) = to_page(0.0, plot.target_boundaries[idx + 1] as f64);
+        if bottom - top >= label_size + 1.0 {
+            let name = extract_display_name(name, 12);
+            let width = page.text_width(label_size, &name);
+            page.text(
+                genome_left - 3.0 - width,
+                (top + bottom - label_size) / 2.0,
+                label_size,
+                label_gray,
+                &name,
+            );
+        }
+    }
+
+    // Axis titles
+    let axis_title = |axis: &str, count: usize, len: i64| {
+        format!("{axis} ({count} sequences, {})", format_bp(len))
+    };
+    let query_title = axis_title("Query", plot.query_sequences.len(), plot.query_genome_len);
+    let width = page.text_width(10.0, &query_title);
+    page.text(
+        (genome_left + genome_right - width) / 2.0,
+        PDF_PAGE_PT - 16.0,
+        10.0,
+        white,
+        &query_title,
+    );
+    let target_title = axis_title(
+        "Target",
+        plot.target_sequences.len(),
+        plot.target_genome_len,
+    );
+    let width = page.text_width(10.0, &target_title);
+    page.text_up(
+        4.0,
+        (genome_top + genome_bottom + width) / 2.0,
+        10.0,
+        white,
+        &target_title,
+    );
+
+    // Thicker separators between the genome blocks of an all-vs-all plot
+    if let Some(ref groups) = options.genome_groups {
+        let separator = [170, 170, 200];
+        for (_, start, _) in groups.runs(&plot.query_sequences, &plot.query_boundaries) {
+            if start > 0 {
+                let (x, _) = to_page(start as f64, 0.0);
+                page.line((x, genome_top), (x, genome_bottom), 1.5, separator);
+            }
+        }
+        for (_, start, _) in groups.runs(&plot.target_sequences, &plot.target_boundaries) {
+            if start > 0 {
+                let (_, y) = to_page(0.0, start as f64);
+                page.line((genome_left, y), (genome_right, y), 1.5, separator);
+            }
+        }
+    }
+
+    // Segments of each layer, half a point per pixel of line width
+    let segments = plot.query_segments_in_region(0, 0.0, 0.0, alen, blen);
+    for layer in &options.layers {
+        page.set_line_opacity(layer.opacity.clamp(0.0, 1.0));
+        for seg in &segments {
+            if !layer.shows_strand(seg.reverse)
+                || (layer.filter.is_active() && !layer.filter.matches(seg))
+            {
+                continue;
+            }
+            let [r, g, b, _] = layer.segment_color(seg, false).to_array();
+            page.line(
+                to_page(seg.abeg as f64, seg.bbeg as f64),
+                to_page(seg.aend as f64, seg.bend as f64),
+                layer.segment_width(seg) * 0.5,
+                [r, g, b],
+            );
+        }
+    }
+    page.set_line_opacity(1.0);
+
+    // Regions of interest: a light wash, an outline and the name
+    for region in &options.regions {
+        let (x0, y1) = to_page(region.x_min as f64, region.y_min as f64);
+        let (x1, y0) = to_page(region.x_max as f64, region.y_max as f64);
+        page.fill_rect(x0, y0, x1 - x0, y1 - y0, region.color, 0.15);
+        page.stroke_rect(x0, y0, x1 - x0, y1 - y0, 0.75, region.color);
+        page.text(x0 + 2.0, y0 + 2.0, 7.0, region.color, &region.name);
+    }
+
+    // Scale bar in the bottom-right corner of the plot area
+    let (len, label) = scale_bar(scale, SCALE_BAR_MAX_PX * 0.5);
+    let bar_right = genome_right - 10.0;
+    let bar_left = bar_right - (len as f64 / scale) as f32;
+    let bar_y = genome_bottom - 10.0;
+    page.line((bar_left, bar_y), (bar_right, bar_y), 1.0, white);
+    for x in [bar_left, bar_right] {
+        page.line((x, bar_y - 3.0), (x, bar_y + 3.0), 1.0, white);
+    }
+    let width = page.text_width(8.0, &label);
+    page.text(bar_right - width, bar_y - 14.0, 8.0, white, &label);
+
+    // Annotations on top, labels offset up and right when they have an arrow
+    let arrow = ANNOTATION_ARROW_PX * 0.5;
+    for annotation in &options.annotations {
+        let (x, y) = to_page(annotation.x, annotation.y);
+        let (label_x, label_y) = if annotation.arrow {
+            let (lx, ly) = (x + arrow, y - arrow);
+            page.line((lx, ly), (x, y), 0.75, white);
+            page.line((x, y), (x + 3.0, y - 0.5), 0.75, white);
+            page.line((x, y), (x + 0.5, y - 3.0), 0.75, white);
+            (lx, ly - 8.0)
+        } else {
+            (x, y - 4.0)
+        };
+        for (i, line) in annotation.text.lines().enumerate() {
+            page.text(label_x, label_y + i as f32 * 8.0, 7.0, white, line);
+        }
+    }
+
+    Ok(page)
+}
+
+/// Draw a line `thickness` pixels wide by stamping one-pixel lines over a
+/// disc; a width of one (or less) is a plain Bresenham line
+fn draw_thick_line(
+    img: &mut image::RgbaImage,
+    x0: i32,
+    y0: i32,
+    x1: i32,
+    y1: i32,
+    thickness: f32,
+    color: image::Rgba<u8>,
+) {
+    let radius = ((thickness - 1.0) / 2.0).round().max(0.0) as i32;
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            if dx * dx + dy * dy <= radius * radius {
+                draw_line(img, x0 + dx, y0 + dy, x1 + dx, y1 + dy, color);
+            }
+        }
+    }
+}
+
 /// Draw a line using Bresenham's algorithm
 fn draw_line(
     img: &mut image::RgbaImage,
@@ -402,14 +1953,223 @@ fn draw_line(
 // Application State
 // ============================================================================
 
+/// Default deepest zoom-in, in bp per pixel (100 px per base)
+const DEFAULT_MIN_SCALE: f64 = 0.01;
+
+/// The per-base grid is drawn once each base is at least this many pixels wide
+const BP_GRID_MAX_SCALE: f64 = 0.2;
+
+/// Depth (in screen pixels) of the coverage tracks along the canvas edges
+const COVERAGE_TRACK_PX: f32 = 40.0;
+
+/// Rough distance between major ruler ticks
+const AXIS_TICK_SPACING_PX: f64 = 120.0;
+
+/// Depth (in screen pixels) of the GC% strips along the canvas edges
+const GC_TRACK_PX: f32 = 8.0;
+
+/// Radius (in screen pixels) of the dot drawn for an alignment shorter than a pixel
+const MIN_DOT_RADIUS_PX: f32 = 0.75;
+
+/// Width (in screen pixels) of the grab area and handle of the swipe divider
+const SWIPE_HANDLE_PX: f32 = 12.0;
+
+/// With length-scaled dots on, alignments shorter than this on screen (in
+/// pixels) are drawn as dots sized by their length in bp
+const LENGTH_DOT_MAX_PX: f32 = 4.0;
+
+/// Largest radius (in screen pixels) of a length-scaled dot
+const LENGTH_DOT_MAX_RADIUS_PX: f32 = 3.5;
+
+/// Size (in screen pixels) of a density heatmap cell
+const DENSITY_CELL_PX: f32 = 2.0;
+
+/// Alignments in view above which the canvas switches to the density heatmap
+/// by default, keeping frame times bounded at pangenome scale
+const DEFAULT_DENSITY_THRESHOLD: usize = 250_000;
+
+/// Most sequence-pair cells shaded at once; zoomed out past this the
+/// checkerboard is left off
+const MAX_SHADED_CELLS: usize = 40_000;
+
+/// Dash and gap lengths (screen pixels) of dashed boundary lines
+const DASH_PX: f32 = 6.0;
+const DASH_GAP_PX: f32 = 4.0;
+
+/// Most boundary lines per axis drawn dashed or dotted; past this they are so
+/// close together that solid lines look the same and draw far faster
+const MAX_DASHED_LINES: usize = 400;
+
+/// Longer side (in screen pixels) of the overview minimap, and its inset
+/// from the canvas corner
+const MINIMAP_PX: f32 = 160.0;
+const MINIMAP_INSET_PX: f32 = 10.0;
+
+/// How long the view must hold still to count as a step in the history, how
+/// many steps are kept, and how many the History menu lists
+const VIEW_SETTLE_TIME: Duration = Duration::from_millis(400);
+const VIEW_HISTORY_LIMIT: usize = 200;
+const HISTORY_MENU_VIEWS: usize = 15;
+
+/// Screen offset (along both axes) between an arrow annotation's label and its point
+const ANNOTATION_ARROW_PX: f32 = 40.0;
+
+/// How close (in screen pixels) a right-click must be to an annotation to edit it
+const ANNOTATION_PICK_PX: f32 = 12.0;
+
+/// Depth (in screen pixels) of the strip along each axis where a click selects
+/// the sequence there
+const SEQUENCE_BAND_PX: f32 = 36.0;
+
+/// Longest a scale bar may be, in pixels, on the canvas and in exports
+const SCALE_BAR_MAX_PX: f64 = 150.0;
+
+/// How close (in screen pixels) the cursor must be to a segment to hover it
+const HOVER_TOLERANCE_PX: f64 = 4.0;
+
+/// Kinetic panning decay rate (1/s) and the speed (points/s) at which it stops
+const KINETIC_FRICTION: f32 = 4.0;
+const KINETIC_MIN_SPEED: f32 = 20.0;
+
+/// Share of the view moved by one arrow-key press, unless changed in the
+/// View menu
+const KEY_PAN_FRACTION: f64 = 0.25;
+
+/// Menu accelerators (Cmd on macOS, Ctrl elsewhere)
+const OPEN_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::O);
+const SAVE_SESSION_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::S);
+const EXPORT_PNG_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::E);
+const QUIT_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Q);
+const ZOOM_IN_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Plus);
+const ZOOM_OUT_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Minus);
+const RESET_VIEW_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Num0);
+const FIND_SEQUENCE_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::F);
+const GO_TO_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::G);
+// Ctrl+Tab on every platform, as browsers and editors use it
+const NEXT_TAB_SHORTCUT: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::CTRL, egui::Key::Tab);
+const PREVIOUS_TAB_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(
+    egui::Modifiers::CTRL.plus(egui::Modifiers::SHIFT),
+    egui::Key::Tab,
+);
+
+/// Matches listed in the Find sequence window
+const SEQUENCE_SEARCH_RESULTS: usize = 12;
+
+/// Forward and reverse colours of the high-contrast palette, told apart
+/// without red-green vision
+const HIGH_CONTRAST_FORWARD: egui::Color32 = egui::Color32::from_rgb(255, 230, 0);
+const HIGH_CONTRAST_REVERSE: egui::Color32 = egui::Color32::from_rgb(0, 220, 255);
+
 struct AlnViewApp {
     // Data
     plot: Option<RustPlot>,
+    full_plot: Option<RustPlot>, // Unfiltered plot while sequences are hidden
+    sweep_filter: Option<SweepFilter>, // Best-N plane sweep applied to loaded plots
+    unswept_plot: Option<RustPlot>, // File-order plot before the sweep, while it is on
+    hidden_query: HashSet<String>,
+    hidden_target: HashSet<String>,
+    flipped_query: HashSet<String>, // Sequences shown reverse-complemented
+    flipped_target: HashSet<String>,
+    query_order: Vec<usize>, // Display order of query sequences (indices into the file)
+    target_order: Vec<usize>,
+    pending_layout: Option<SequenceLayout>, // From --layout, applied once the plot loads
+    pending_order: Option<SequenceLayout>,  // From --order/--order-target, applied after it
+    pending_agp: Option<Agp>,               // From --agp, scaffolds the plot being installed
+    contig_boundaries: Option<ContigBoundaries>, // Contigs inside the scaffolds of an AGP file
+    pending_session: Option<Session>,       // Opened session, applied once its file loads
+    annotations: Vec<Annotation>,           // Text labels pinned to genome positions
+    annotation_edit: Option<(Option<usize>, Annotation)>, // Open editor: index (None = new), draft
+    context_pos: Option<(f64, f64)>,        // Genome position of the last right-click
+    context_segment: Option<usize>,         // Segment under the last right-click
+    context_sequence: Option<(Axis, String)>, // Sequence label or band right-clicked
+    label_drag: Option<(Axis, String)>,     // Sequence whose name is being dragged elsewhere
+    regions: Vec<Region>,                   // Saved regions of interest
+    tags: Vec<Tag>,                         // Curation tags on segments and regions
+    tag_edit: Option<(Option<usize>, Tag)>, // Open tag editor: index (None = new), draft
+    plot_title: String,                     // Figure title and subtitle, saved with the session
+    plot_subtitle: String,
+    show_title_dialog: bool,
+    show_view_export: bool,
+    view_export_width: u32, // Pixels across an exported view
+    show_sequence_search: bool,
+    sequence_search: String, // Name typed in the Find sequence window
+    show_go_to: bool,
+    go_to_text: String,          // Loci typed in the Go to window
+    go_to_error: Option<String>, // Why the last Go to failed
+    show_region_panel: bool,
+    show_sequence_filter: bool,
+    sequence_filters: [FilterDraft; 2], // Query and target, as typed in the filter panel
 
     // View state
     view: ViewState,
-    view_history: Vec<ViewState>, // For 'z' key to go back
+    view_mode: ViewMode,
+    wrap_rows: usize, // Rows in the wrapped view; 0 picks them from the genome lengths
+    show_coverage: bool, // Coverage depth margin tracks
+    show_scale_bar: bool, // Round-length scale bar in the corner
+    export_equal_aspect: bool, // Centre exported plots at 1:1 bp
+    repeat_display: RepeatDisplay, // How segments in high-depth query regions are drawn
+    repeat_depth: u32, // Query depth at which a segment counts as repetitive
+    repetitive: Option<(u32, Vec<bool>)>, // Cached per-segment flags and the depth they used
+    clusters: Option<Vec<Cluster>>, // Off-diagonal clusters, found on first use
+    cluster_index: Option<usize>, // Cluster the view last stepped to
+    show_cluster_hulls: bool, // Outline groups of nearby alignments
+    hull_clusters: Option<Vec<Cluster>>, // Clusters of all alignments, found on first use
+    segment_coloring: SegmentColoring,
+    chain_gap: i64,                         // Largest gap within a chain, bp
+    chain_ids: Option<(Vec<usize>, usize)>, // Chain of each segment and the count, found on first use
+    sequence_report: Option<(Axis, usize, SequenceReport)>, // Sequence whose axis band was clicked
+    partner_query: Option<PartnerQuery>,    // "Where does this region go?" results
+    box_summary: Option<BoxSummary>,        // Alignments in a box summed up with Ctrl+drag
+    show_identity_cells: bool,              // Tint sequence-pair cells by mean identity
+    shade_pair_cells: bool,                 // Checkerboard of sequence-pair cells
+    boundary_styles: BoundaryStyles,
+    pair_identity: Option<HashMap<(usize, usize), f32>>, // Mean identity per cell, found on first use
+    show_density: bool, // Draw aligned bases per cell instead of each alignment
+    auto_density: bool, // Switch to the heatmap while more alignments than this are in view
+    density_threshold: usize,
+    visible_count: Option<(DensityView, usize)>, // Passing alignments in the last viewport counted
+    density_active: bool, // Heatmap drawn in the last frame, chosen or switched to
+    density: Option<(DensityView, egui::TextureHandle)>, // Heatmap of the last viewport binned
+    show_minimap: bool,   // Whole-genome overview in the corner
+    show_crosshair: bool, // Lines through the pointer across the canvas
+    measuring: bool,      // Clicks place the ruler's ends instead of selecting
+    ruler: Vec<(f64, f64)>, // Ruler ends placed so far, in genome coordinates
+    minimap: Option<egui::TextureHandle>, // The overview, binned once per plot
+    #[cfg(feature = "gpu")]
+    gpu_density: bool, // Bin the heatmap with a compute shader
+    #[cfg(feature = "gpu")]
+    gpu_binner: Option<Option<gpu_density::GpuBinner>>, // Set up on first use; None inside if no GPU
+    show_perf: bool,              // Memory/performance readout in the status bar
+    show_sequence_panel: bool,    // Sequences panel for reordering/hiding
+    last_draw_time: Duration,     // Time spent drawing the last canvas frame
+    last_visible_segments: usize, // Segments drawn in the last frame
+    view_history: ViewHistory,    // Z goes back, Shift+Z forward
     needs_initial_fit: bool,      // Flag to fit view on first render
+    fit_whole: bool,              // Fit shows the whole plot (vs. filling the smaller side)
+    fit_margin: f32,              // Padding around the plot, as a fraction of each genome
+    min_scale: f64,               // Deepest zoom-in, in bp per pixel
+    show_bp_grid: bool,           // Per-base grid at deep zoom
+    dot_tiny_segments: bool,      // Draw sub-pixel alignments as dots so they stay visible
+    length_scaled_dots: bool,     // At overview zoom, dots sized and brightened by alignment length
+    sticky_labels: bool,          // Pin names of sequences scrolled partly out of view
+    normalized: bool,             // Every sequence drawn at equal width, positions in %
+    axes_swapped: bool,           // Target along x and query along y
+    scroll_to_pan: bool,          // Wheel pans (Ctrl/Cmd+wheel zooms) instead of zooming
+    high_contrast: bool,          // Bright widgets and a yellow/cyan strand palette
+    palette: &'static Palette,    // Strand, identity and query sequence colours
+    kinetic_pan: bool,            // Keep gliding after a flick
+    key_pan_fraction: f64,        // Share of the view one arrow-key press moves
+    pan_velocity: egui::Vec2,     // Current glide velocity, screen points/second
     last_canvas_size: (f32, f32), // Last canvas dimensions for zoom limits
 
     // Layer settings
@@ -418,16 +2178,68 @@ struct AlnViewApp {
 
     // UI state
     current_file: Option<PathBuf>,
+    tabs: Vec<Tab>,    // Every open file in tab order, empty while only one is open
+    active_tab: usize, // Tab whose document is in the fields above (its slot holds a placeholder)
     show_about: bool,
+    notifications: Notifications,
+
+    // K-mer dotplot dialog state
+    show_kmer_dialog: bool,
+    kmer_query_fasta: Option<PathBuf>,
+    kmer_target_fasta: Option<PathBuf>,
+    kmer_size: usize,
 
     // Loading state
     loading: Arc<Mutex<LoadingState>>,
-    plot_receiver: Option<Receiver<Result<RustPlot, String>>>,
+    loading_source: Option<PathBuf>, // File the current/last load was reading
+    load_started: Option<Instant>,
+    load_summary: Option<LoadSummary>, // Shown once a plot finishes loading
+    show_load_summary: bool,
+    plot_receiver: Option<Receiver<Result<LoadedPlot, String>>>,
+    max_segments: Option<usize>, // Offer to downsample above this many segments
+    timings: bool,               // --timings: log stage times and memory to the console
+    time_next_frame: bool,       // Log the first frame drawn after a load
+    pending_huge_plot: Option<RustPlot>, // Loaded plot waiting on the downsampling prompt
+    genome_groups: Option<GenomeGroups>, // Genome of each sequence in an all-vs-all plot
+    genome_coloring: GenomeColoring,
+    segment_genomes: Option<Vec<Option<(usize, usize)>>>, // Per segment, for genome colouring
+    loaded_genome_groups: Option<GenomeGroups>,           // Arrived with the plot being installed
+    segment_store: Option<SegmentStore>, // Full-resolution segments on disk behind an overview plot
+    loaded_segment_store: Option<PathBuf>, // Store to open when the loading plot is installed
+    last_load_time: Option<Duration>,
+
+    // Comparison overlay (second file over the same genomes)
+    comparison: Option<Comparison>,
+    comparison_file: Option<PathBuf>,
+    comparison_receiver: Option<Receiver<Result<Comparison, String>>>,
+    follow_receiver: Option<Receiver<FollowEvent>>, // Snapshots of a PAF being written
+    follow_stop: Option<Arc<AtomicBool>>,           // Set to stop the follower thread
+    load_cancel: Option<Arc<AtomicBool>>,           // Set to abandon the plot being loaded
+
+    // GC composition tracks, read from FASTA in the background
+    show_gc_tracks: bool,
+    query_gc: Option<GcTrack>,
+    target_gc: Option<GcTrack>,
+    gc_channel: (Sender<LoadedGc>, Receiver<LoadedGc>),
+    color_by_presence: bool,
+    presets: PresetStore,
+    pending_preset: Option<Preset>, // --preset, applied once the first plot is installed
+    pending_cutoff: Option<SegmentFilter>, // --min-identity, applied after the preset
+    preset_name_edit: Option<String>, // Name being typed for "Save preset..."
+    bindings: Bindings,             // What each mouse gesture does on the canvas
+    bindings_edit: Option<Bindings>, // Draft in the mouse bindings editor
+    swipe: bool,         // Split the canvas: first file left of the divider, second right
+    swipe_fraction: f32, // Divider position, as a fraction of the canvas width
+    side_by_side: bool,  // Second file in a pane of its own, right of the first
+    link_views: bool,    // Both panes pan and zoom together
+    second_view: ViewState, // The second pane's own view while unlinked
 
     // Interaction state
-    box_zoom_start: Option<egui::Pos2>, // Shift+drag box zoom
-    #[allow(dead_code)]
-    selected_segment: Option<usize>, // For x/X key selection (future feature)
+    hovered_segment: Option<usize>, // Segment under the cursor, if any
+    box_zoom_start: Option<egui::Pos2>, // Corner where a box drag started
+    drag_action: Option<Action>,    // What the drag under way does, by the bindings
+    selected_segments: Vec<usize>,  // Alignments picked with Shift+Ctrl+drag
+    selected_segment: Option<usize>, // Alignment clicked for the info panel
 
     // Cursor position info (for display in layers panel)
     cursor_query_name: String,
@@ -436,17 +2248,196 @@ struct AlnViewApp {
     cursor_target_pos: i64,
     cursor_genome_x: f64,
     cursor_genome_y: f64,
+    cursor_on_canvas: bool, // Whether the cursor fields describe the pointer right now
 }
 
+/// A plot from the background loader, with a warning if the file was damaged
+/// A plot built in the background, with a warning to show and, for
+/// all-vs-all plots, the genome of each sequence
+type LoadedPlot = (RustPlot, Option<String>, Option<GenomeGroups>);
+
+/// A GC track from the background reader, for one axis
+type LoadedGc = (Axis, Result<GcTrack, String>);
+
 #[derive(Clone)]
 enum LoadingState {
     Idle,
-    Loading(String), // file path
+    Loading(String, Option<f32>), // File path, and the share read once it can be estimated
     Success(String),
     Failed(String),
 }
 
+/// A background load's way back to the UI: where it reports progress, and
+/// the flag Cancel sets
 #[derive(Clone)]
+struct LoadProgress {
+    loading: Arc<Mutex<LoadingState>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl LoadProgress {
+    fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// Show `fraction` of the load done; false once it has been cancelled
+    fn update(&self, fraction: f32) -> bool {
+        // Checked under the lock, so a cancelled load can't overwrite the
+        // state of the next one
+        let mut state = self.loading.lock().unwrap();
+        if self.is_cancelled() {
+            return false;
+        }
+        if let LoadingState::Loading(_, progress) = &mut *state {
+            // The total is only estimated, so never claim to be done
+            *progress = Some(fraction.min(0.99));
+        }
+        true
+    }
+
+    /// Progress callback for reading the records of `path`, out of a total
+    /// estimated from its size
+    fn records(&self, path: &Path) -> impl FnMut(usize) -> bool + '_ {
+        let estimated = std::fs::metadata(path)
+            .map_or(0, |meta| meta.len() / aln_reader::ESTIMATED_RECORD_BYTES)
+            .max(1);
+        move |read| self.update(read as f32 / estimated as f32)
+    }
+}
+
+/// Which genome axis a sequence belongs to
+#[derive(Clone, Copy, PartialEq)]
+enum Axis {
+    Query,
+    Target,
+}
+
+/// One axis of the sequence filter panel, until applied
+#[derive(Default)]
+struct FilterDraft {
+    text: String,               // Names, prefixes or a /regex/
+    range: String,              // Index range, like 0-9
+    unchecked: HashSet<String>, // Sequences unticked in the list
+}
+
+impl FilterDraft {
+    /// Whether a sequence matches the text and range, ignoring the ticks
+    fn filter(&self) -> anyhow::Result<SequenceFilter> {
+        let mut filter = SequenceFilter::from_text(&self.text)?;
+        if !self.range.trim().is_empty() {
+            filter.range = SequenceFilter::from_range(&self.range)?.range;
+        }
+        Ok(filter)
+    }
+}
+
+/// A clickable sequence name drawn along a dotplot axis
+struct SequenceLabel {
+    axis: Axis,
+    name: String,
+    text: String,
+    rect: egui::Rect,
+}
+
+/// An interval of one axis and where it aligns on the other, for the
+/// partners window
+struct PartnerQuery {
+    axis: Axis, // Axis the interval is on; partners are on the other one
+    label: String,
+    partners: Vec<PartnerInterval>,
+}
+
+/// Alignments in a box and where it is, for the box stats window
+struct BoxSummary {
+    stats: selection::BoxStats,
+    locus: Option<String>, // As "Go to" reads it
+}
+
+/// What was just loaded, for the post-load summary dialog
+struct LoadSummary {
+    source: String,
+    query_sequences: usize,
+    target_sequences: usize,
+    query_len: i64,
+    target_len: i64,
+    alignments: usize,
+    downsampled_from: Option<usize>, // Alignments in the file, if only a sample was kept
+    aligned_bp: i64,
+    ani: rust_plot::AniSummary,
+    ani_names: Vec<String>, // Query sequence of each per-sequence ANI
+    load_time: Option<Duration>,
+    memory_bytes: usize,
+}
+
+impl LoadSummary {
+    fn new(
+        plot: &RustPlot,
+        source: Option<&std::path::Path>,
+        load_time: Option<Duration>,
+        downsampled_from: Option<usize>,
+    ) -> Self {
+        let ani = plot.ani();
+        Self {
+            source: source.map_or_else(|| "Plot".to_string(), |p| p.display().to_string()),
+            query_sequences: plot.query_sequences.len(),
+            target_sequences: plot.target_sequences.len(),
+            query_len: plot.get_alen(),
+            target_len: plot.get_blen(),
+            alignments: plot.segments.len(),
+            downsampled_from,
+            aligned_bp: plot.segments.iter().map(|s| s.aend - s.abeg).sum(),
+            ani_names: ani
+                .per_sequence
+                .iter()
+                .map(|seq| plot.query_sequences[seq.sequence].clone())
+                .collect(),
+            ani,
+            load_time,
+            memory_bytes: plot.estimated_memory_bytes(),
+        }
+    }
+}
+
+/// Drag payload for reordering rows of the sequences panel
+struct SequenceDrag {
+    axis: Axis,
+    from: usize,
+}
+
+/// How repetitive (multi-mapping) segments are drawn
+#[derive(Clone, Copy, PartialEq)]
+enum RepeatDisplay {
+    Normal,
+    Dim,
+    Recolor,
+}
+
+/// Colouring of an all-vs-all plot's segments by where they come from
+#[derive(Clone, Copy, PartialEq)]
+enum GenomeColoring {
+    Strand,
+    QueryGenome,
+    TargetGenome,
+    GenomePair, // One hue per (query, target) block, i.e. per pairwise file
+}
+
+/// Whether segments take their layer's colours or a colour of their own group
+#[derive(Clone, Copy, PartialEq)]
+enum SegmentColoring {
+    Layers,        // By strand, or by identity where the layer says so
+    Chain,         // One hue per chain of collinear alignments
+    QuerySequence, // One palette colour per query sequence
+}
+
+/// How the loaded alignments are drawn on the canvas
+#[derive(Clone, Copy, PartialEq)]
+enum ViewMode {
+    DotPlot,
+    Synteny,
+    Wrapped, // Whole plot with the longer genome split across stacked rows
+}
+
+#[derive(Clone, PartialEq)]
 struct ViewState {
     x: f64,     // Genome x coordinate at left edge
     y: f64,     // Genome y coordinate at bottom edge
@@ -457,112 +2448,760 @@ struct ViewState {
     max_y: f64,
 }
 
-#[derive(Clone)]
-struct LayerSettings {
-    visible: bool,
-    name: String,
-    color_forward: egui::Color32,
-    color_reverse: egui::Color32,
-    thickness: f32,
+impl Default for ViewState {
+    fn default() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            scale: 1000.0, // 1000 bp per pixel initially
+            max_x: 1_000_000.0,
+            max_y: 1_000_000.0,
+        }
+    }
 }
 
-impl Default for AlnViewApp {
+/// Views visited, for undo and redo. Every pan, zoom or jump becomes one
+/// step once the view has held still for a moment, so a drag or a run of
+/// wheel clicks is a single step.
+#[derive(Default)]
+struct ViewHistory {
+    back: Vec<ViewState>,
+    forward: Vec<ViewState>,
+    settled: Option<ViewState>, // The view as of the last step
+    last_seen: Option<ViewState>,
+    moved_at: Option<Instant>,
+}
+
+impl ViewHistory {
+    /// Note the current view; returns true while a change is waiting to settle
+    fn track(&mut self, view: &ViewState, pointer_down: bool) -> bool {
+        if self.last_seen.as_ref() != Some(view) {
+            self.last_seen = Some(view.clone());
+            self.moved_at = Some(Instant::now());
+        }
+        let Some(ref settled) = self.settled else {
+            self.settled = Some(view.clone());
+            return false;
+        };
+        if settled == view {
+            return false;
+        }
+        let still = self
+            .moved_at
+            .is_none_or(|moved| moved.elapsed() >= VIEW_SETTLE_TIME);
+        if !still || pointer_down {
+            return true;
+        }
+        self.back.push(settled.clone());
+        if self.back.len() > VIEW_HISTORY_LIMIT {
+            self.back.remove(0);
+        }
+        self.forward.clear();
+        self.settled = Some(view.clone());
+        false
+    }
+
+    /// Step `steps` views back (or forward), returning the view to show
+    fn step(&mut self, current: &ViewState, steps: usize, forward: bool) -> Option<ViewState> {
+        let (from, to) = if forward {
+            (&mut self.forward, &mut self.back)
+        } else {
+            (&mut self.back, &mut self.forward)
+        };
+        if steps == 0 || steps > from.len() {
+            return None;
+        }
+        to.push(current.clone());
+        for _ in 1..steps {
+            let skipped = from.pop()?;
+            to.push(skipped);
+        }
+        let view = from.pop()?;
+        self.settled = Some(view.clone());
+        self.last_seen = Some(view.clone());
+        Some(view)
+    }
+
+    /// Forget every step, e.g. once the coordinates change meaning
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// An open file's own state: the plot, its layout and view, layers and
+/// annotations. The active tab's lives in the app's fields; the others wait
+/// here and are swapped in when their tab is picked.
+struct Tab {
+    plot: Option<RustPlot>,
+    full_plot: Option<RustPlot>,
+    unswept_plot: Option<RustPlot>,
+    sweep_filter: Option<SweepFilter>,
+    hidden_query: HashSet<String>,
+    hidden_target: HashSet<String>,
+    flipped_query: HashSet<String>,
+    flipped_target: HashSet<String>,
+    query_order: Vec<usize>,
+    target_order: Vec<usize>,
+    annotations: Vec<Annotation>,
+    regions: Vec<Region>,
+    tags: Vec<Tag>,
+    plot_title: String,
+    plot_subtitle: String,
+    view: ViewState,
+    view_history: ViewHistory,
+    needs_initial_fit: bool,
+    normalized: bool,
+    axes_swapped: bool,
+    layers: Vec<LayerSettings>,
+    num_layers: usize,
+    current_file: Option<PathBuf>,
+    genome_groups: Option<GenomeGroups>,
+    contig_boundaries: Option<ContigBoundaries>,
+    segment_store: Option<SegmentStore>,
+    comparison: Option<Comparison>,
+    comparison_file: Option<PathBuf>,
+    query_gc: Option<GcTrack>,
+    target_gc: Option<GcTrack>,
+}
+
+impl Default for Tab {
     fn default() -> Self {
         Self {
             plot: None,
-            view: ViewState {
-                x: 0.0,
-                y: 0.0,
-                scale: 1000.0, // 1000 bp per pixel initially
-                max_x: 1_000_000.0,
-                max_y: 1_000_000.0,
-            },
-            view_history: Vec::new(),
+            full_plot: None,
+            unswept_plot: None,
+            sweep_filter: None,
+            hidden_query: HashSet::new(),
+            hidden_target: HashSet::new(),
+            flipped_query: HashSet::new(),
+            flipped_target: HashSet::new(),
+            query_order: Vec::new(),
+            target_order: Vec::new(),
+            annotations: Vec::new(),
+            regions: Vec::new(),
+            tags: Vec::new(),
+            plot_title: String::new(),
+            plot_subtitle: String::new(),
+            view: ViewState::default(),
+            view_history: ViewHistory::default(),
             needs_initial_fit: false,
-            last_canvas_size: (800.0, 600.0),
+            normalized: false,
+            axes_swapped: false,
             layers: vec![LayerSettings::default()],
             num_layers: 0,
             current_file: None,
-            show_about: false,
-            loading: Arc::new(Mutex::new(LoadingState::Idle)),
-            plot_receiver: None,
-            box_zoom_start: None,
-            selected_segment: None,
-            cursor_query_name: String::new(),
-            cursor_query_pos: 0,
-            cursor_target_name: String::new(),
-            cursor_target_pos: 0,
-            cursor_genome_x: 0.0,
-            cursor_genome_y: 0.0,
+            genome_groups: None,
+            contig_boundaries: None,
+            segment_store: None,
+            comparison: None,
+            comparison_file: None,
+            query_gc: None,
+            target_gc: None,
         }
     }
 }
 
-impl Default for LayerSettings {
-    fn default() -> Self {
-        Self {
-            visible: true,
-            name: "Layer 0".to_string(),
-            color_forward: egui::Color32::from_rgb(0, 100, 200),
-            color_reverse: egui::Color32::from_rgb(200, 100, 0),
-            thickness: 2.0,
+/// How a boundary line is broken up
+#[derive(Clone, Copy, PartialEq)]
+enum LineDash {
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl LineDash {
+    const ALL: [LineDash; 3] = [LineDash::Solid, LineDash::Dashed, LineDash::Dotted];
+
+    fn label(self) -> &'static str {
+        match self {
+            LineDash::Solid => "Solid",
+            LineDash::Dashed => "Dashed",
+            LineDash::Dotted => "Dotted",
         }
     }
 }
 
-// ============================================================================
-// Main App Implementation
-// ============================================================================
+/// Style of one kind of canvas line that is not an alignment
+#[derive(Clone, Copy)]
+struct LineStyle {
+    dash: LineDash,
+    width: f32,
+    color: egui::Color32,
+}
 
-impl eframe::App for AlnViewApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Check if plot loaded from background thread
-        if let Some(ref receiver) = self.plot_receiver {
-            if let Ok(result) = receiver.try_recv() {
-                match result {
-                    Ok(rust_plot) => {
-                        // Extract real genome lengths
-                        let alen = rust_plot.get_alen() as f64;
-                        let blen = rust_plot.get_blen() as f64;
-                        println!("✅ Plot loaded successfully! Genome lengths: {alen} x {blen}");
-
-                        // Update view with actual genome dimensions
-                        self.view.max_x = alen;
-                        self.view.max_y = blen;
-                        self.view.x = 0.0;
-                        self.view.y = 0.0;
-                        // Will fit to canvas on first render
-                        self.needs_initial_fit = true;
-
-                        // Get actual number of layers from plot
-                        let nlays = rust_plot.get_nlays() as usize;
-                        println!("  Plot has {nlays} layers");
-
-                        self.num_layers = nlays;
-
-                        // Create layer settings for all layers
-                        self.layers = (0..nlays)
-                            .map(|i| LayerSettings {
-                                visible: true,
-                                name: format!("Layer {i}"),
-                                ..Default::default()
-                            })
-                            .collect();
+impl LineStyle {
+    const fn new(dash: LineDash, width: f32, color: egui::Color32) -> Self {
+        Self { dash, width, color }
+    }
 
-                        self.plot = Some(rust_plot);
-                        *self.loading.lock().unwrap() =
-                            LoadingState::Success("Loaded successfully".to_string());
-                    }
-                    Err(e) => {
-                        *self.loading.lock().unwrap() = LoadingState::Failed(e);
-                    }
-                }
-                self.plot_receiver = None;
+    /// Draw a straight line from `from` to `to`, broken into dashes or dots
+    /// counted from `from`
+    fn draw(&self, painter: &egui::Painter, from: egui::Pos2, to: egui::Pos2) {
+        let stroke = egui::Stroke::new(self.width, self.color);
+        let length = from.distance(to);
+        if self.dash == LineDash::Solid || length < 1.0 {
+            painter.line_segment([from, to], stroke);
+            return;
+        }
+        let step = (to - from) / length;
+        if self.dash == LineDash::Dotted {
+            let spacing = (self.width * 3.0).max(3.0);
+            let mut at = 0.0;
+            while at <= length {
+                painter.circle_filled(from + step * at, self.width / 2.0, self.color);
+                at += spacing;
+            }
+        } else {
+            let mut start = 0.0;
+            while start < length {
+                let end = (start + DASH_PX).min(length);
+                painter.line_segment([from + step * start, from + step * end], stroke);
+                start += DASH_PX + DASH_GAP_PX;
             }
         }
+    }
 
-        // Check loading state
-        let loading_state = self.loading.lock().unwrap().clone();
+    fn vline(&self, painter: &egui::Painter, x: f32, rect: egui::Rect) {
+        self.draw(
+            painter,
+            egui::pos2(x, rect.min.y),
+            egui::pos2(x, rect.max.y),
+        );
+    }
+
+    fn hline(&self, painter: &egui::Painter, y: f32, rect: egui::Rect) {
+        self.draw(
+            painter,
+            egui::pos2(rect.min.x, y),
+            egui::pos2(rect.max.x, y),
+        );
+    }
+
+    /// This style drawn solid
+    fn solid(self) -> Self {
+        Self {
+            dash: LineDash::Solid,
+            ..self
+        }
+    }
+}
+
+/// Styles of the sequence boundaries, genome ends and origin axes, kept
+/// distinct from one another and from alignments
+#[derive(Clone, Copy)]
+struct BoundaryStyles {
+    scaffold: LineStyle,
+    query_end: LineStyle,
+    target_end: LineStyle,
+    origin: LineStyle,
+}
+
+impl Default for BoundaryStyles {
+    fn default() -> Self {
+        Self {
+            scaffold: LineStyle::new(LineDash::Dashed, 1.0, egui::Color32::from_gray(100)),
+            query_end: LineStyle::new(LineDash::Solid, 2.0, egui::Color32::DARK_RED),
+            target_end: LineStyle::new(LineDash::Solid, 2.0, egui::Color32::DARK_BLUE),
+            origin: LineStyle::new(LineDash::Dotted, 1.0, egui::Color32::GRAY),
+        }
+    }
+}
+
+impl BoundaryStyles {
+    /// Dash, width and colour pickers for each kind of line
+    fn edit(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("boundary_styles").show(ui, |ui| {
+            for (label, style) in [
+                ("Sequence boundaries", &mut self.scaffold),
+                ("Query end", &mut self.query_end),
+                ("Target end", &mut self.target_end),
+                ("Origin axes", &mut self.origin),
+            ] {
+                ui.label(label);
+                egui::ComboBox::from_id_source(("line_dash", label))
+                    .selected_text(style.dash.label())
+                    .show_ui(ui, |ui| {
+                        for dash in LineDash::ALL {
+                            ui.selectable_value(&mut style.dash, dash, dash.label());
+                        }
+                    });
+                ui.add(
+                    egui::DragValue::new(&mut style.width)
+                        .range(0.5..=5.0)
+                        .speed(0.1)
+                        .suffix(" px"),
+                );
+                ui.color_edit_button_srgba(&mut style.color);
+                ui.end_row();
+            }
+        });
+        if ui.button("Reset").clicked() {
+            *self = Self::default();
+        }
+    }
+}
+
+#[derive(Clone)]
+struct LayerSettings {
+    visible: bool,
+    name: String,
+    color_forward: egui::Color32,
+    color_reverse: egui::Color32,
+    thickness: f32,
+    width_scale: WidthScale, // Line width follows each alignment's length or identity
+    opacity: f32,            // 0 (invisible) to 1 (opaque), so overlapping layers both show
+    show_forward: bool,
+    show_reverse: bool,
+    filter: SegmentFilter,   // Cutoffs for this layer only
+    color_by_identity: bool, // Instead of by strand
+    gradient: IdentityGradient,
+}
+
+impl LayerSettings {
+    /// Whether segments on this strand are shown
+    fn shows_strand(&self, reverse: bool) -> bool {
+        if reverse {
+            self.show_reverse
+        } else {
+            self.show_forward
+        }
+    }
+
+    /// Colour of segments on this strand; the high-contrast palette replaces
+    /// the layer's own
+    fn strand_color(&self, reverse: bool, high_contrast: bool) -> egui::Color32 {
+        match (reverse, high_contrast) {
+            (false, false) => self.color_forward,
+            (true, false) => self.color_reverse,
+            (false, true) => HIGH_CONTRAST_FORWARD,
+            (true, true) => HIGH_CONTRAST_REVERSE,
+        }
+    }
+
+    /// The layer in `palette`'s strand colours and identity colour map
+    fn with_palette(self, palette: &Palette) -> Self {
+        Self {
+            color_forward: rgb(palette.forward),
+            color_reverse: rgb(palette.reverse),
+            gradient: IdentityGradient {
+                colormap: palette.colormap,
+                ..self.gradient
+            },
+            ..self
+        }
+    }
+
+    /// Line width of `seg`, in pixels
+    fn segment_width(&self, seg: &AlignmentSegment) -> f32 {
+        self.thickness * self.width_scale.factor(seg)
+    }
+
+    /// Colour of `seg`: by identity on the layer's gradient, else by strand
+    fn segment_color(&self, seg: &AlignmentSegment, high_contrast: bool) -> egui::Color32 {
+        if self.color_by_identity {
+            let [r, g, b] = self.gradient.color(seg.identity);
+            egui::Color32::from_rgb(r, g, b)
+        } else {
+            self.strand_color(seg.reverse, high_contrast)
+        }
+    }
+}
+
+impl Default for AlnViewApp {
+    fn default() -> Self {
+        Self {
+            plot: None,
+            full_plot: None,
+            sweep_filter: None,
+            unswept_plot: None,
+            hidden_query: HashSet::new(),
+            hidden_target: HashSet::new(),
+            flipped_query: HashSet::new(),
+            flipped_target: HashSet::new(),
+            query_order: Vec::new(),
+            target_order: Vec::new(),
+            pending_layout: None,
+            pending_order: None,
+            pending_agp: None,
+            contig_boundaries: None,
+            pending_session: None,
+            annotations: Vec::new(),
+            annotation_edit: None,
+            context_pos: None,
+            context_segment: None,
+            context_sequence: None,
+            label_drag: None,
+            regions: Vec::new(),
+            tags: Vec::new(),
+            tag_edit: None,
+            plot_title: String::new(),
+            plot_subtitle: String::new(),
+            show_title_dialog: false,
+            show_view_export: false,
+            view_export_width: 2400,
+            show_sequence_search: false,
+            sequence_search: String::new(),
+            show_go_to: false,
+            go_to_text: String::new(),
+            go_to_error: None,
+            show_region_panel: false,
+            show_sequence_filter: false,
+            sequence_filters: Default::default(),
+            view: ViewState::default(),
+            view_mode: ViewMode::DotPlot,
+            wrap_rows: 0,
+            show_coverage: false,
+            show_scale_bar: true,
+            export_equal_aspect: false,
+            repeat_display: RepeatDisplay::Normal,
+            repeat_depth: rust_plot::DEFAULT_REPEAT_DEPTH,
+            repetitive: None,
+            clusters: None,
+            cluster_index: None,
+            show_cluster_hulls: false,
+            hull_clusters: None,
+            segment_coloring: SegmentColoring::Layers,
+            chain_gap: rust_plot::DEFAULT_CHAIN_GAP,
+            chain_ids: None,
+            sequence_report: None,
+            partner_query: None,
+            box_summary: None,
+            show_identity_cells: false,
+            shade_pair_cells: false,
+            boundary_styles: BoundaryStyles::default(),
+            show_density: false,
+            auto_density: true,
+            density_threshold: DEFAULT_DENSITY_THRESHOLD,
+            visible_count: None,
+            density_active: false,
+            density: None,
+            show_minimap: true,
+            show_crosshair: true,
+            measuring: false,
+            ruler: Vec::new(),
+            minimap: None,
+            #[cfg(feature = "gpu")]
+            gpu_density: true,
+            #[cfg(feature = "gpu")]
+            gpu_binner: None,
+            pair_identity: None,
+            show_perf: false,
+            show_sequence_panel: false,
+            last_draw_time: Duration::ZERO,
+            last_visible_segments: 0,
+            view_history: ViewHistory::default(),
+            needs_initial_fit: false,
+            fit_whole: true,
+            fit_margin: 0.05,
+            min_scale: DEFAULT_MIN_SCALE,
+            show_bp_grid: true,
+            dot_tiny_segments: true,
+            length_scaled_dots: false,
+            sticky_labels: true,
+            normalized: false,
+            axes_swapped: false,
+            scroll_to_pan: false,
+            high_contrast: false,
+            palette: &PALETTES[0],
+            kinetic_pan: true,
+            key_pan_fraction: KEY_PAN_FRACTION,
+            pan_velocity: egui::Vec2::ZERO,
+            last_canvas_size: (800.0, 600.0),
+            layers: vec![LayerSettings::default()],
+            num_layers: 0,
+            current_file: None,
+            tabs: Vec::new(),
+            active_tab: 0,
+            show_about: false,
+            notifications: Notifications::default(),
+            show_kmer_dialog: false,
+            kmer_query_fasta: None,
+            kmer_target_fasta: None,
+            kmer_size: kmer_plot::DEFAULT_KMER_SIZE,
+            loading: Arc::new(Mutex::new(LoadingState::Idle)),
+            loading_source: None,
+            load_started: None,
+            load_summary: None,
+            show_load_summary: true,
+            plot_receiver: None,
+            max_segments: Some(rust_plot::DEFAULT_MAX_SEGMENTS),
+            timings: false,
+            time_next_frame: false,
+            pending_huge_plot: None,
+            genome_groups: None,
+            genome_coloring: GenomeColoring::Strand,
+            segment_genomes: None,
+            loaded_genome_groups: None,
+            segment_store: None,
+            loaded_segment_store: None,
+            last_load_time: None,
+            comparison: None,
+            comparison_file: None,
+            comparison_receiver: None,
+            follow_receiver: None,
+            follow_stop: None,
+            load_cancel: None,
+            show_gc_tracks: false,
+            query_gc: None,
+            target_gc: None,
+            gc_channel: channel(),
+            color_by_presence: true,
+            presets: PresetStore::default(),
+            pending_preset: None,
+            pending_cutoff: None,
+            preset_name_edit: None,
+            bindings: Bindings::default(),
+            bindings_edit: None,
+            swipe: false,
+            swipe_fraction: 0.5,
+            side_by_side: false,
+            link_views: true,
+            second_view: ViewState::default(),
+            hovered_segment: None,
+            box_zoom_start: None,
+            drag_action: None,
+            selected_segments: Vec::new(),
+            selected_segment: None,
+            cursor_query_name: String::new(),
+            cursor_query_pos: 0,
+            cursor_target_name: String::new(),
+            cursor_target_pos: 0,
+            cursor_genome_x: 0.0,
+            cursor_genome_y: 0.0,
+            cursor_on_canvas: false,
+        }
+    }
+}
+
+/// A preset's layer as the viewer draws it
+fn preset_layer(layer: &PresetLayer) -> LayerSettings {
+    LayerSettings {
+        visible: layer.visible,
+        name: layer.name.clone(),
+        color_forward: rgb(layer.color_forward),
+        color_reverse: rgb(layer.color_reverse),
+        thickness: layer.thickness,
+        width_scale: layer.width_scale,
+        opacity: layer.opacity,
+        show_forward: layer.show_forward,
+        show_reverse: layer.show_reverse,
+        filter: layer.filter,
+        color_by_identity: layer.color_by_identity,
+        gradient: layer.gradient,
+    }
+}
+
+impl Default for LayerSettings {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            name: "Layer 0".to_string(),
+            color_forward: egui::Color32::from_rgb(0, 255, 0), // Green/red like the C version
+            color_reverse: egui::Color32::from_rgb(255, 0, 0),
+            thickness: 1.0,
+            width_scale: WidthScale::Fixed,
+            opacity: 1.0,
+            show_forward: true,
+            show_reverse: true,
+            filter: SegmentFilter::default(),
+            color_by_identity: false,
+            gradient: IdentityGradient::default(),
+        }
+    }
+}
+
+/// What an export needs to draw the plot the way the viewer shows it: the
+/// visible layers with their colours and filters, and the figure's text and
+/// overlays
+#[derive(Clone)]
+struct RenderOptions {
+    layers: Vec<LayerSettings>, // Visible layers only, in drawing order
+    title: String,
+    subtitle: String,
+    annotations: Vec<Annotation>,
+    regions: Vec<Region>,
+    scale_bar: bool,
+    equal_aspect: bool, // Centre the genome rectangle, padding the shorter axis
+    genome_groups: Option<GenomeGroups>, // Outline genome blocks of an all-vs-all plot
+    contig_boundaries: Option<ContigBoundaries>, // Contigs inside AGP scaffolds
+    viewport: Option<[f64; 4]>, // Genome x, y, width, height to draw instead of everything (PNG)
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            layers: vec![LayerSettings::default()],
+            title: String::new(),
+            subtitle: String::new(),
+            annotations: Vec::new(),
+            regions: Vec::new(),
+            scale_bar: false,
+            equal_aspect: false,
+            genome_groups: None,
+            contig_boundaries: None,
+            viewport: None,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Default layer styling with the session's title and overlays, for the CLI
+    fn from_session(session: Option<&Session>) -> Self {
+        let Some(session) = session else {
+            return Self::default();
+        };
+        Self {
+            title: session.title.clone(),
+            subtitle: session.subtitle.clone(),
+            annotations: session.annotations.clone(),
+            regions: session.regions.clone(),
+            ..Self::default()
+        }
+    }
+}
+
+// ============================================================================
+// Main App Implementation
+// ============================================================================
+
+impl eframe::App for AlnViewApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Check if plot loaded from background thread
+        let received = self
+            .plot_receiver
+            .as_ref()
+            .and_then(|receiver| receiver.try_recv().ok());
+        if let Some(result) = received {
+            match result {
+                Ok((mut rust_plot, warning, groups)) => {
+                    self.last_load_time = self.load_started.map(|t| t.elapsed());
+                    self.loaded_genome_groups = groups;
+                    if let Some(warning) = warning {
+                        self.notifications.warning(
+                            "File partially loaded",
+                            warning,
+                            self.loading_source.as_deref(),
+                        );
+                    }
+                    // The cutoff install_plot will apply, so the prompt
+                    // counts and samples only the alignments it shows
+                    if let Some(cutoff) = self
+                        .pending_cutoff
+                        .or_else(|| self.pending_preset.as_ref().map(|preset| preset.cutoff))
+                    {
+                        rust_plot.cutoff = cutoff;
+                    }
+                    match self.max_segments {
+                        // Ask before drawing millions of segments
+                        Some(max) if rust_plot.passing_segments() > max => {
+                            self.pending_huge_plot = Some(rust_plot);
+                        }
+                        _ => self.install_plot(rust_plot, None),
+                    }
+                    *self.loading.lock().unwrap() =
+                        LoadingState::Success("Loaded successfully".to_string());
+                }
+                Err(e) => {
+                    *self.loading.lock().unwrap() = LoadingState::Failed(e);
+                    // Drop the empty tab the failed file was to fill
+                    if self.plot.is_none() && !self.tabs.is_empty() {
+                        self.close_tab(self.active_tab);
+                    }
+                }
+            }
+            self.plot_receiver = None;
+        }
+
+        // Check if comparison finished loading
+        if let Some(ref receiver) = self.comparison_receiver {
+            if let Ok(result) = receiver.try_recv() {
+                match result {
+                    Ok(comparison) => {
+                        if comparison.skipped_records > 0 {
+                            self.notifications.warning(
+                                "Sequences not in current plot",
+                                format!(
+                                    "{} comparison records refer to sequences the current plot does not have and were skipped.",
+                                    comparison.skipped_records
+                                ),
+                                self.comparison_file.as_deref(),
+                            );
+                        }
+                        let (both, only_first, only_second) = comparison.counts();
+                        self.notifications.info(
+                            "Comparison loaded",
+                            format!(
+                                "{both} shared, {only_first} only in first, {only_second} only in second"
+                            ),
+                        );
+                        self.comparison = Some(comparison);
+                        *self.loading.lock().unwrap() =
+                            LoadingState::Success("Comparison loaded".to_string());
+                    }
+                    Err(e) => {
+                        self.comparison_file = None;
+                        *self.loading.lock().unwrap() = LoadingState::Failed(e);
+                    }
+                }
+                self.comparison_receiver = None;
+            }
+        }
+
+        // New alignments from a followed PAF
+        let mut finished = false;
+        while let Some(event) = self
+            .follow_receiver
+            .as_ref()
+            .and_then(|receiver| receiver.try_recv().ok())
+        {
+            match event {
+                FollowEvent::Snapshot(plot) => self.update_streamed_plot(*plot),
+                FollowEvent::Finished(alignments, skipped) => {
+                    let mut message = format!("Read {alignments} alignments");
+                    if skipped > 0 {
+                        message.push_str(&format!("; skipped {skipped} malformed lines"));
+                    }
+                    self.notifications.info("PAF input finished", message);
+                    finished = true;
+                }
+                FollowEvent::Failed(e) => {
+                    self.notifications.error(
+                        "Could not follow PAF",
+                        e,
+                        self.loading_source.as_deref(),
+                    );
+                    finished = true;
+                }
+            }
+        }
+        if finished {
+            self.follow_receiver = None;
+            self.follow_stop = None;
+        }
+
+        // Check if a GC track finished loading
+        while let Ok((axis, result)) = self.gc_channel.1.try_recv() {
+            match result {
+                Ok(track) => {
+                    self.notifications.info(
+                        "GC track loaded",
+                        format!(
+                            "GC% in {} bp windows for {} sequences",
+                            track.window,
+                            track.sequences()
+                        ),
+                    );
+                    match axis {
+                        Axis::Query => self.query_gc = Some(track),
+                        Axis::Target => self.target_gc = Some(track),
+                    }
+                }
+                Err(e) => self.notifications.error("Could not load GC track", e, None),
+            }
+        }
+
+        // Check loading state
+        let loading_state = self.loading.lock().unwrap().clone();
         match loading_state {
             LoadingState::Success(msg) => {
                 println!("✅ {msg}");
@@ -570,40 +3209,525 @@ impl eframe::App for AlnViewApp {
             }
             LoadingState::Failed(msg) => {
                 eprintln!("❌ {msg}");
+                self.notifications
+                    .error("Loading failed", msg, self.loading_source.as_deref());
                 *self.loading.lock().unwrap() = LoadingState::Idle;
             }
             _ => {}
         }
 
+        self.handle_shortcuts(ctx);
+
+        // Settle the view into a history step once it holds still
+        let pointer_down = ctx.input(|i| i.pointer.any_down());
+        if !self.needs_initial_fit && self.view_history.track(&self.view, pointer_down) {
+            ctx.request_repaint_after(VIEW_SETTLE_TIME);
+        }
+
         // Menu bar
         egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
-                    if ui.button("📁 Open .1aln file...").clicked() {
+                    if ui
+                        .add(
+                            egui::Button::new("📁 Open .1aln file...")
+                                .shortcut_text(ctx.format_shortcut(&OPEN_SHORTCUT)),
+                        )
+                        .clicked()
+                    {
                         self.open_file_dialog();
                         ui.close_menu();
                     }
+                    if ui
+                        .add_enabled(
+                            self.plot.is_some(),
+                            egui::Button::new("🔀 Open as comparison..."),
+                        )
+                        .clicked()
+                    {
+                        self.open_comparison_dialog();
+                        ui.close_menu();
+                    }
+                    if self.follow_stop.is_some() {
+                        if ui.button("⏹ Stop following PAF").clicked() {
+                            self.stop_following();
+                            ui.close_menu();
+                        }
+                    } else if ui
+                        .button("📡 Follow PAF...")
+                        .on_hover_text("Watch a PAF file grow as an aligner writes it")
+                        .clicked()
+                    {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .set_title("PAF file or pipe to follow")
+                            .add_filter("PAF", &["paf", "gz"])
+                            .pick_file()
+                        {
+                            self.follow_paf(path);
+                        }
+                        ui.close_menu();
+                    }
+                    if ui
+                        .button("🧩 Open all-vs-all set...")
+                        .on_hover_text("Pairwise .1aln files covering several genomes, shown as one matrix")
+                        .clicked()
+                    {
+                        if let Some(paths) = rfd::FileDialog::new()
+                            .set_title("Pairwise alignment files")
+                            .add_filter("Alignment Files", &["1aln", "delta", "gz"])
+                            .pick_files()
+                        {
+                            self.load_all_vs_all_async(paths);
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("🧬 Quick k-mer dotplot...").clicked() {
+                        self.show_kmer_dialog = true;
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("📂 Open session...").clicked() {
+                        self.open_session_dialog();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            self.plot.is_some(),
+                            egui::Button::new("💾 Save session...")
+                                .shortcut_text(ctx.format_shortcut(&SAVE_SESSION_SHORTCUT)),
+                        )
+                        .clicked()
+                    {
+                        self.save_session_dialog();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            self.plot.is_some(),
+                            egui::Button::new("🏷 Title and subtitle..."),
+                        )
+                        .clicked()
+                    {
+                        self.show_title_dialog = true;
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui
+                        .add_enabled(
+                            self.plot.is_some(),
+                            egui::Button::new("📥 Import sequence layout..."),
+                        )
+                        .clicked()
+                    {
+                        self.import_layout_dialog();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            self.plot.is_some() && self.follow_receiver.is_none(),
+                            egui::Button::new("🧩 Scaffold with AGP..."),
+                        )
+                        .on_hover_text(
+                            "Open this plot in a new tab with contigs joined into the scaffolds of an AGP file",
+                        )
+                        .clicked()
+                    {
+                        self.scaffold_with_agp_dialog();
+                        ui.close_menu();
+                    }
+                    ui.add_enabled_ui(self.plot.is_some(), |ui| {
+                        ui.menu_button("📥 Import sequence order", |ui| {
+                            for (axis, label) in
+                                [(Axis::Query, "Query..."), (Axis::Target, "Target...")]
+                            {
+                                if ui.button(label).clicked() {
+                                    self.import_order_dialog(axis);
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    });
+                    if ui
+                        .add_enabled(
+                            self.plot.is_some(),
+                            egui::Button::new("📤 Export sequence layout..."),
+                        )
+                        .clicked()
+                    {
+                        self.export_layout_dialog();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            self.plot.is_some(),
+                            egui::Button::new("🖼 Export PNG...")
+                                .shortcut_text(ctx.format_shortcut(&EXPORT_PNG_SHORTCUT)),
+                        )
+                        .clicked()
+                    {
+                        self.export_png_dialog();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            self.plot.is_some(),
+                            egui::Button::new("🔭 Export view as image..."),
+                        )
+                        .on_hover_text("Just what is on screen, at a resolution you choose")
+                        .clicked()
+                    {
+                        self.show_view_export = true;
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            self.plot.is_some() && !self.normalized,
+                            egui::Button::new("🧬 Export SV candidates (VCF)..."),
+                        )
+                        .on_hover_text("Inversions and translocations found in the passing alignments of visible sequences, on target coordinates")
+                        .on_disabled_hover_text("Not available while sequences are drawn equal width")
+                        .clicked()
+                    {
+                        self.export_sv_vcf_dialog();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            !self.selected_segments.is_empty() && !self.normalized,
+                            egui::Button::new(format!(
+                                "📋 Export selection ({})...",
+                                self.selected_segments.len()
+                            )),
+                        )
+                        .on_hover_text("The alignments picked with Shift+Ctrl+drag, as CSV or TSV")
+                        .on_disabled_hover_text("Shift+Ctrl+drag on the plot to select alignments")
+                        .clicked()
+                    {
+                        self.export_selection_dialog();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            self.plot.is_some() && !self.normalized,
+                            egui::Button::new("📤 Export filtered alignments..."),
+                        )
+                        .on_hover_text("The alignments of visible sequences that pass the cutoffs and sweep filter, as PAF or (from a .1aln file) a new .1aln")
+                        .on_disabled_hover_text("Not available while sequences are drawn equal width")
+                        .clicked()
+                    {
+                        self.export_alignments_dialog();
+                        ui.close_menu();
+                    }
+                    ui.checkbox(&mut self.export_equal_aspect, "1:1 bp aspect on export");
                     ui.separator();
-                    if ui.button("❌ Quit").clicked() {
+                    if ui
+                        .add(
+                            egui::Button::new("❌ Quit")
+                                .shortcut_text(ctx.format_shortcut(&QUIT_SHORTCUT)),
+                        )
+                        .clicked()
+                    {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                     }
                 });
 
                 ui.menu_button("View", |ui| {
-                    if ui.button("🔍 Zoom In").clicked() {
+                    if ui
+                        .add(
+                            egui::Button::new("🔍 Zoom In")
+                                .shortcut_text(ctx.format_shortcut(&ZOOM_IN_SHORTCUT)),
+                        )
+                        .clicked()
+                    {
                         self.zoom(2.0);
                         ui.close_menu();
                     }
-                    if ui.button("🔍 Zoom Out").clicked() {
+                    if ui
+                        .add(
+                            egui::Button::new("🔍 Zoom Out")
+                                .shortcut_text(ctx.format_shortcut(&ZOOM_OUT_SHORTCUT)),
+                        )
+                        .clicked()
+                    {
                         self.zoom(0.5);
                         ui.close_menu();
                     }
-                    if ui.button("🏠 Reset View").clicked() {
+                    if ui
+                        .add(
+                            egui::Button::new("🏠 Reset View")
+                                .shortcut_text(ctx.format_shortcut(&RESET_VIEW_SHORTCUT)),
+                        )
+                        .clicked()
+                    {
+                        self.reset_view();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            self.plot.is_some(),
+                            egui::Button::new("🔎 Find sequence...")
+                                .shortcut_text(ctx.format_shortcut(&FIND_SEQUENCE_SHORTCUT)),
+                        )
+                        .clicked()
+                    {
+                        self.show_sequence_search = true;
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            self.plot.is_some(),
+                            egui::Button::new("📍 Go to...")
+                                .shortcut_text(ctx.format_shortcut(&GO_TO_SHORTCUT)),
+                        )
+                        .clicked()
+                    {
+                        self.show_go_to = true;
+                        ui.close_menu();
+                    }
+                    if ui
+                        .checkbox(&mut self.fit_whole, "Fit whole plot")
+                        .on_hover_text("Off: fill the window with the shorter genome")
+                        .changed()
+                    {
                         self.reset_view();
+                    }
+                    ui.add(
+                        egui::Slider::new(&mut self.fit_margin, 0.0..=0.25)
+                            .text("Margin")
+                            .custom_formatter(|v, _| format!("{:.0}%", v * 100.0)),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut self.key_pan_fraction, 0.05..=1.0)
+                            .text("Arrow-key pan")
+                            .custom_formatter(|v, _| format!("{:.0}%", v * 100.0)),
+                    )
+                    .on_hover_text("Share of the view one arrow-key press moves");
+                    ui.add(
+                        egui::Slider::new(&mut self.min_scale, 0.001..=1.0)
+                            .logarithmic(true)
+                            .text("Max zoom (bp/px)"),
+                    );
+                    ui.checkbox(&mut self.show_bp_grid, "Base grid at deep zoom");
+                    if ui
+                        .checkbox(&mut self.normalized, "Equal-width sequences (%)")
+                        .on_hover_text("Stretch every sequence to the same width; positions read as % of the sequence")
+                        .changed()
+                    {
+                        self.apply_sequence_layout();
+                    }
+                    ui.checkbox(&mut self.sticky_labels, "Sticky sequence names")
+                        .on_hover_text("Keep the names of the sequences at the top and left edges in view");
+                    ui.checkbox(&mut self.dot_tiny_segments, "Show tiny alignments as dots")
+                        .on_hover_text("Alignments shorter than a pixel are drawn as dots instead of vanishing");
+                    ui.checkbox(&mut self.length_scaled_dots, "Length-scaled dots when zoomed out")
+                        .on_hover_text("Alignments only a few pixels long are drawn as dots whose size and brightness grow with their length, instead of overplotted hairlines");
+                    ui.checkbox(&mut self.scroll_to_pan, "Scroll to pan")
+                        .on_hover_text("Wheel pans; Ctrl/Cmd+wheel zooms");
+                    if ui.button("🖱 Mouse bindings...").clicked() {
+                        self.bindings_edit = Some(self.bindings.clone());
+                        ui.close_menu();
+                    }
+                    if ui
+                        .checkbox(&mut self.high_contrast, "High contrast")
+                        .on_hover_text(
+                            "Brighter text and outlines, and yellow/cyan alignments in place of the layer colours",
+                        )
+                        .changed()
+                    {
+                        ctx.set_visuals(if self.high_contrast {
+                            high_contrast_visuals()
+                        } else {
+                            egui::Visuals::dark()
+                        });
+                    }
+                    ui.menu_button("Palette", |ui| {
+                        for palette in &PALETTES {
+                            if ui
+                                .radio(std::ptr::eq(self.palette, palette), palette.name)
+                                .on_hover_text(
+                                    "Strand colours of every layer, identity colours and \
+                                     query sequence colours",
+                                )
+                                .clicked()
+                            {
+                                self.apply_palette(palette);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    ui.checkbox(&mut self.kinetic_pan, "Kinetic panning");
+                    ui.separator();
+                    ui.radio_value(&mut self.view_mode, ViewMode::DotPlot, "Dot plot");
+                    ui.radio_value(&mut self.view_mode, ViewMode::Synteny, "Synteny ribbons");
+                    ui.radio_value(&mut self.view_mode, ViewMode::Wrapped, "Wrapped rows");
+                    if self.view_mode == ViewMode::Wrapped {
+                        ui.add(
+                            egui::Slider::new(&mut self.wrap_rows, 0..=32)
+                                .text("Rows")
+                                .custom_formatter(|v, _| {
+                                    if v == 0.0 {
+                                        "auto".to_string()
+                                    } else {
+                                        format!("{v:.0}")
+                                    }
+                                }),
+                        );
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.show_coverage, "Coverage tracks");
+                    if ui
+                        .checkbox(&mut self.show_gc_tracks, "GC% tracks")
+                        .on_hover_text("GC content along each axis, from the FASTA files")
+                        .changed()
+                        && self.show_gc_tracks
+                    {
+                        // K-mer plots already know their FASTA files
+                        for (axis, fasta) in [
+                            (Axis::Query, self.kmer_query_fasta.clone()),
+                            (Axis::Target, self.kmer_target_fasta.clone()),
+                        ] {
+                            let loaded = match axis {
+                                Axis::Query => self.query_gc.is_some(),
+                                Axis::Target => self.target_gc.is_some(),
+                            };
+                            if let (false, Some(path)) = (loaded, fasta) {
+                                self.load_gc_async(axis, path);
+                            }
+                        }
+                    }
+                    ui.menu_button("GC% from FASTA", |ui| {
+                        for (axis, label) in [(Axis::Query, "Query FASTA..."), (Axis::Target, "Target FASTA...")] {
+                            if ui.button(label).clicked() {
+                                if let Some(path) = pick_fasta(label.trim_end_matches('.')) {
+                                    self.show_gc_tracks = true;
+                                    self.load_gc_async(axis, path);
+                                }
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    ui.checkbox(&mut self.show_scale_bar, "Scale bar");
+                    ui.checkbox(&mut self.show_crosshair, "Crosshair");
+                    if ui
+                        .checkbox(&mut self.measuring, "📏 Measure")
+                        .on_hover_text("Click two points for their distance along each axis")
+                        .changed()
+                    {
+                        self.ruler.clear();
+                    }
+                    ui.checkbox(&mut self.show_minimap, "Minimap")
+                        .on_hover_text("Whole-genome overview; click or drag in it to move the view");
+                    ui.checkbox(&mut self.show_cluster_hulls, "Cluster hulls")
+                        .on_hover_text("Outline groups of nearby alignments, with their counts");
+                    ui.menu_button("Color alignments", |ui| self.segment_coloring_menu(ui));
+                    ui.checkbox(&mut self.shade_pair_cells, "Shade sequence pairs")
+                        .on_hover_text(
+                            "Alternate light and dark backgrounds for each query x target sequence pair",
+                        );
+                    ui.menu_button("Boundary lines", |ui| self.boundary_styles.edit(ui));
+                    ui.checkbox(&mut self.show_identity_cells, "Identity heatmap")
+                        .on_hover_text(
+                            "Tint each sequence pair by the mean identity of its alignments",
+                        );
+                    ui.checkbox(&mut self.show_density, "Density heatmap").on_hover_text(
+                        "Draw aligned bases per screen cell instead of each alignment, \
+                         for files with millions of alignments",
+                    );
+                    ui.add_enabled_ui(!self.show_density, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut self.auto_density, "Density above")
+                                .on_hover_text(
+                                    "Switch to the density heatmap while more alignments than \
+                                     this are in view",
+                                );
+                            ui.add_enabled(
+                                self.auto_density,
+                                egui::DragValue::new(&mut self.density_threshold)
+                                    .range(1_000..=100_000_000)
+                                    .speed(1_000),
+                            );
+                        });
+                    });
+                    #[cfg(feature = "gpu")]
+                    if ui
+                        .add_enabled(
+                            self.show_density || self.auto_density,
+                            egui::Checkbox::new(&mut self.gpu_density, "Bin density on the GPU"),
+                        )
+                        .changed()
+                    {
+                        self.density = None;
+                    }
+                    if ui
+                        .add_enabled(
+                            self.plot.is_some(),
+                            egui::Button::new("➡ Next off-diagonal cluster (N)"),
+                        )
+                        .clicked()
+                    {
+                        self.step_cluster(true);
+                        ui.close_menu();
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Repeats:");
+                        ui.radio_value(&mut self.repeat_display, RepeatDisplay::Normal, "Normal");
+                        ui.radio_value(&mut self.repeat_display, RepeatDisplay::Dim, "Dim");
+                        ui.radio_value(&mut self.repeat_display, RepeatDisplay::Recolor, "Recolor");
+                    });
+                    ui.add(
+                        egui::Slider::new(&mut self.repeat_depth, 2..=20)
+                            .text("Repeat depth")
+                            .clamp_to_range(true),
+                    )
+                    .on_hover_text(
+                        "Alignments stacked on the same query bases to count as a repeat",
+                    );
+                    ui.checkbox(&mut self.show_perf, "Performance readout");
+                    ui.checkbox(&mut self.show_sequence_panel, "Sequences panel");
+                    if ui
+                        .add_enabled(
+                            self.segment_store.is_none() && self.follow_receiver.is_none(),
+                            egui::Button::new("⇄ Swap query/target"),
+                        )
+                        .on_hover_text("Put the target along x and the query along y")
+                        .on_disabled_hover_text(
+                            "Not while reading from an index or following a growing PAF",
+                        )
+                        .clicked()
+                    {
+                        self.swap_axes();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .button("🪄 Auto-arrange")
+                        .on_hover_text(
+                            "Order and orient the query sequences by where they align best on the target, to bring the alignments onto the diagonal",
+                        )
+                        .clicked()
+                    {
+                        self.auto_arrange();
+                        ui.close_menu();
+                    }
+                    if ui.button("🔽 Filter sequences...").clicked() {
+                        self.open_sequence_filter();
+                        ui.close_menu();
+                    }
+                    ui.checkbox(&mut self.show_region_panel, "Regions panel");
+                    ui.separator();
+                    let any_hidden =
+                        !self.hidden_query.is_empty() || !self.hidden_target.is_empty();
+                    if ui
+                        .add_enabled(any_hidden, egui::Button::new("👁 Show all sequences"))
+                        .clicked()
+                    {
+                        self.show_all_sequences();
                         ui.close_menu();
                     }
                 });
 
+                ui.menu_button("History", |ui| self.history_menu(ui));
+                ui.menu_button("Presets", |ui| self.presets_menu(ui));
+
                 ui.menu_button("Help", |ui| {
                     if ui.button("ℹ About").clicked() {
                         self.show_about = true;
@@ -614,18 +3738,28 @@ impl eframe::App for AlnViewApp {
                 ui.separator();
 
                 // Quick zoom buttons
-                if ui.button("🔍+").clicked() {
+                if labeled(ui.button("🔍+"), "Zoom in").clicked() {
                     self.zoom(2.0);
                 }
-                if ui.button("🔍-").clicked() {
+                if labeled(ui.button("🔍-"), "Zoom out").clicked() {
                     self.zoom(0.5);
                 }
-                if ui.button("🏠").clicked() {
+                if labeled(ui.button("🏠"), "Reset view").clicked() {
                     self.reset_view();
                 }
+
+                ui.separator();
+                ui.selectable_value(&mut self.view_mode, ViewMode::DotPlot, "Dot plot");
+                ui.selectable_value(&mut self.view_mode, ViewMode::Synteny, "Synteny");
+                ui.selectable_value(&mut self.view_mode, ViewMode::Wrapped, "Wrapped");
             });
         });
 
+        // One tab per open file, once there is more than one
+        if !self.tabs.is_empty() {
+            self.tab_bar(ctx);
+        }
+
         // Side panel for layer controls
         egui::SidePanel::left("layers_panel")
             .default_width(250.0)
@@ -636,6 +3770,8 @@ impl eframe::App for AlnViewApp {
                 if self.num_layers == 0 {
                     ui.label("No layers loaded");
                 } else {
+                    self.cutoff_control(ui);
+                    ui.separator();
                     for i in 0..self.num_layers {
                         if i < self.layers.len() {
                             self.layer_control(ui, i);
@@ -644,37 +3780,114 @@ impl eframe::App for AlnViewApp {
                     }
                 }
 
-                ui.separator();
-                ui.label(format!("Scale: {:.1} bp/px", self.view.scale));
+                if self.comparison.is_some() {
+                    ui.separator();
+                    self.comparison_control(ui);
+                }
 
-                ui.separator();
-                ui.heading("Cursor Position");
-                ui.separator();
+                if self.genome_groups.is_some() {
+                    ui.separator();
+                    self.genome_control(ui);
+                }
+
+                ui.separator();
+                ui.label(format!("Scale: {}", format_scale(self.view.scale)));
+                let hidden = self.hidden_query.len() + self.hidden_target.len();
+                if hidden > 0 {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{hidden} sequences hidden"));
+                        if ui.small_button("Show all").clicked() {
+                            self.show_all_sequences();
+                        }
+                    });
+                }
+
+                ui.separator();
+                ui.heading("Cursor Position");
+                ui.separator();
 
                 // Display cursor information
                 if !self.cursor_query_name.is_empty() {
                     ui.label(egui::RichText::new("Query:").strong());
-                    ui.label(format!("  {}", extract_display_name(&self.cursor_query_name, 35)));
-                    ui.label(format!("  Position: {} bp (local)", self.cursor_query_pos));
+                    ui.label(format!(
+                        "  {}",
+                        extract_display_name(&self.cursor_query_name, 35)
+                    ));
+                    ui.label(format!(
+                        "  Position: {} (local)",
+                        format_extent(self.cursor_query_pos, self.normalized)
+                    ));
                     ui.label(format!("  Genome: {:.0} bp", self.cursor_genome_x));
                     ui.add_space(5.0);
                     ui.label(egui::RichText::new("Target:").strong());
-                    ui.label(format!("  {}", extract_display_name(&self.cursor_target_name, 35)));
-                    ui.label(format!("  Position: {} bp (local)", self.cursor_target_pos));
+                    ui.label(format!(
+                        "  {}",
+                        extract_display_name(&self.cursor_target_name, 35)
+                    ));
+                    ui.label(format!(
+                        "  Position: {} (local)",
+                        format_extent(self.cursor_target_pos, self.normalized)
+                    ));
                     ui.label(format!("  Genome: {:.0} bp", self.cursor_genome_y));
                 } else {
                     ui.label("Move cursor over plot");
                 }
             });
 
+        // Side panel for sequence order and visibility
+        if self.show_sequence_panel && self.plot.is_some() {
+            egui::SidePanel::right("sequences_panel")
+                .default_width(220.0)
+                .show(ctx, |ui| {
+                    ui.heading("Sequences");
+                    ui.label(
+                        egui::RichText::new("Drag ☰ to reorder, untick to hide, ⇄ to flip")
+                            .weak()
+                            .small(),
+                    );
+                    ui.separator();
+                    self.sequence_list(ui, Axis::Query);
+                    ui.separator();
+                    self.sequence_list(ui, Axis::Target);
+                });
+        }
+
+        // Side panel with the colour of each query sequence
+        if self.segment_coloring == SegmentColoring::QuerySequence && self.plot.is_some() {
+            egui::SidePanel::right("query_colors_panel")
+                .default_width(200.0)
+                .show(ctx, |ui| self.query_color_legend(ui));
+        }
+
+        // Side panel with every field of the clicked alignment
+        if self.selected_segment.is_some() && self.plot.is_some() {
+            egui::SidePanel::right("segment_panel")
+                .default_width(240.0)
+                .show(ctx, |ui| self.segment_info(ui));
+        }
+
+        // Side panel listing regions of interest
+        if self.show_region_panel && self.plot.is_some() {
+            egui::SidePanel::right("regions_panel")
+                .default_width(220.0)
+                .show(ctx, |ui| {
+                    self.tag_list(ui);
+                    ui.separator();
+                    self.region_list(ui);
+                });
+        }
+
         // Status bar
+        let mut cancel_load = false;
         egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 // Show loading state
-                match &*self.loading.lock().unwrap() {
-                    LoadingState::Loading(path) => {
-                        ui.spinner();
+                let loading_state = self.loading.lock().unwrap().clone();
+                match loading_state {
+                    LoadingState::Loading(path, progress) => {
+                        loading_progress(ui, progress);
                         ui.label(format!("Loading: {path}"));
+                        cancel_load = ui.small_button("✖ Cancel").clicked();
                     }
                     _ => {
                         if let Some(ref path) = self.current_file {
@@ -682,36 +3895,143 @@ impl eframe::App for AlnViewApp {
                         } else {
                             ui.label("No file loaded");
                         }
+
+                        // Off-diagonal cluster the view last stepped to
+                        if let (Some(ref clusters), Some(idx)) =
+                            (&self.clusters, self.cluster_index)
+                        {
+                            if let Some(cluster) = clusters.get(idx) {
+                                ui.separator();
+                                ui.label(format!(
+                                    "Cluster {}/{}: {} alignments, {}",
+                                    idx + 1,
+                                    clusters.len(),
+                                    cluster.segments,
+                                    format_extent(cluster.aligned_bp, self.normalized)
+                                ));
+                            }
+                        }
+
+                        // Segment under the cursor
+                        if let (Some(ref plot), Some(idx)) = (&self.plot, self.hovered_segment) {
+                            if idx < plot.segments.len() {
+                                ui.separator();
+                                ui.label(plot.describe_segment(idx));
+                            }
+                        }
                     }
                 }
 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    let at_limit = if self.view.scale <= self.min_scale {
+                        " (max zoom)"
+                    } else {
+                        ""
+                    };
                     ui.label(format!(
-                        "Pos: X={:.0} Y={:.0}  Scale: {:.1} bp/px",
-                        self.view.x, self.view.y, self.view.scale
+                        "Pos: X={:.0} Y={:.0}  Scale: {}{at_limit}",
+                        self.view.x,
+                        self.view.y,
+                        format_scale(self.view.scale)
                     ));
+
+                    // Sequence-local position under the pointer
+                    if self.cursor_on_canvas && !self.cursor_query_name.is_empty() {
+                        let position = |pos: i64| {
+                            if self.normalized {
+                                format_extent(pos, true)
+                            } else {
+                                pos.to_string()
+                            }
+                        };
+                        ui.separator();
+                        ui.label(
+                            egui::RichText::new(format!(
+                                "{}:{} × {}:{}",
+                                extract_display_name(&self.cursor_query_name, 35),
+                                position(self.cursor_query_pos),
+                                extract_display_name(&self.cursor_target_name, 35),
+                                position(self.cursor_target_pos)
+                            ))
+                            .monospace(),
+                        );
+                    }
+
+                    if self.density_active && !self.show_density {
+                        if let Some((_, count)) = self.visible_count {
+                            ui.separator();
+                            ui.label(format!("▦ Density view: {count} alignments in view"))
+                                .on_hover_text("Zoom in, or raise View → Density above, to draw each alignment");
+                        }
+                    }
+
+                    if self.show_perf {
+                        if let Some(ref plot) = self.plot {
+                            ui.separator();
+                            ui.label(format!(
+                                "Segments: {} ({} drawn)  Mem: {}  Draw: {:.1} ms",
+                                plot.segments.len(),
+                                self.last_visible_segments,
+                                format_bytes(plot.estimated_memory_bytes()),
+                                self.last_draw_time.as_secs_f64() * 1000.0
+                            ));
+                        }
+                    }
                 });
             });
         });
+        if cancel_load {
+            self.cancel_loading();
+        }
 
         // Main canvas
         egui::CentralPanel::default().show(ctx, |ui| {
             if self.plot.is_some() {
-                self.render_canvas(ui);
+                let draw_start = Instant::now();
+                let canvas = if self.side_by_side && self.comparison.is_some() {
+                    // The second file in the right half, after the first is drawn
+                    let (left, right) = ui
+                        .available_rect_before_wrap()
+                        .split_left_right_at_fraction(0.5);
+                    let canvas = ui
+                        .allocate_ui_at_rect(left.shrink2(egui::vec2(2.0, 0.0)), |ui| {
+                            self.render_canvas(ui)
+                        })
+                        .inner;
+                    ui.allocate_ui_at_rect(right.shrink2(egui::vec2(2.0, 0.0)), |ui| {
+                        self.render_second_pane(ui)
+                    });
+                    canvas
+                } else {
+                    self.render_canvas(ui)
+                };
+                self.last_draw_time = draw_start.elapsed();
+                if canvas.has_focus() {
+                    // Focus ring for keyboard users, drawn last so it stays on top
+                    ui.painter().rect_stroke(
+                        canvas.rect.shrink(1.0),
+                        0.0,
+                        ui.visuals().selection.stroke,
+                    );
+                }
+                if std::mem::take(&mut self.time_next_frame) {
+                    let mut timings = Timings::default();
+                    timings.record("render", self.last_draw_time);
+                    print!("{}", timings.report());
+                }
             } else {
                 ui.centered_and_justified(|ui| {
                     ui.vertical_centered(|ui| {
                         ui.heading("🦀 ALNview - Rust Edition");
                         ui.add_space(20.0);
 
-                        let is_loading =
-                            matches!(&*self.loading.lock().unwrap(), LoadingState::Loading(_));
-
-                        if is_loading {
-                            if let LoadingState::Loading(path) = &*self.loading.lock().unwrap() {
-                                ui.spinner();
-                                ui.label(format!("Loading: {path}..."));
-                                ui.label("This may take a while for large files");
+                        let loading_state = self.loading.lock().unwrap().clone();
+                        if let LoadingState::Loading(path, progress) = loading_state {
+                            loading_progress(ui, progress);
+                            ui.label(format!("Loading: {path}..."));
+                            ui.label("This may take a while for large files");
+                            if ui.button("✖ Cancel").clicked() {
+                                self.cancel_loading();
                             }
                         } else {
                             ui.label("Open a .1aln file to begin");
@@ -749,9 +4069,144 @@ impl eframe::App for AlnViewApp {
                 });
         }
 
+        // K-mer dotplot dialog
+        if self.show_kmer_dialog {
+            self.kmer_dialog(ctx);
+        }
+
+        // Downsampling prompt for very large files
+        if self.pending_huge_plot.is_some() {
+            self.downsample_dialog(ctx);
+        }
+
+        // Name prompt for saving a preset
+        if self.preset_name_edit.is_some() {
+            self.save_preset_dialog(ctx);
+        }
+
+        if self.bindings_edit.is_some() {
+            self.bindings_dialog(ctx);
+        }
+
+        // Annotation editor
+        if self.annotation_edit.is_some() {
+            self.annotation_dialog(ctx);
+        }
+
+        // Tag editor
+        if self.tag_edit.is_some() {
+            self.tag_dialog(ctx);
+        }
+
+        // Per-sequence report from an axis band click
+        if self.sequence_report.is_some() {
+            self.sequence_report_window(ctx);
+        }
+
+        // Where a region aligns on the other genome
+        if self.partner_query.is_some() {
+            self.partners_window(ctx);
+        }
+
+        // Alignments in a Ctrl+dragged box
+        if self.box_summary.is_some() {
+            self.box_summary_window(ctx);
+        }
+
+        // Distances between the ruler's ends
+        if self.measuring {
+            self.measure_window(ctx);
+        }
+
+        // Figure title editor
+        if self.show_title_dialog {
+            egui::Window::new("Title and subtitle")
+                .open(&mut self.show_title_dialog)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    egui::Grid::new("title_grid").num_columns(2).show(ui, |ui| {
+                        ui.label("Title");
+                        ui.text_edit_singleline(&mut self.plot_title);
+                        ui.end_row();
+                        ui.label("Subtitle");
+                        ui.text_edit_singleline(&mut self.plot_subtitle);
+                        ui.end_row();
+                    });
+                    ui.label(
+                        egui::RichText::new("Saved with the session and drawn on exported plots")
+                            .weak()
+                            .small(),
+                    );
+                });
+        }
+
+        // Size of a view export
+        if self.show_view_export {
+            let mut open = true;
+            let mut export = false;
+            let (canvas_width, canvas_height) = self.last_canvas_size;
+            egui::Window::new("Export view as image")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Width");
+                        ui.add(
+                            egui::DragValue::new(&mut self.view_export_width)
+                                .range(200..=20_000)
+                                .suffix(" px"),
+                        );
+                    });
+                    let plot_height = (self.view_export_width - PNG_MARGIN_LEFT) as f32
+                        * canvas_height
+                        / canvas_width.max(1.0);
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "Plot area {} x {:.0} px, plus margins for labels",
+                            self.view_export_width - PNG_MARGIN_LEFT,
+                            plot_height
+                        ))
+                        .weak()
+                        .small(),
+                    );
+                    export = ui.button("Export...").clicked();
+                });
+            if export {
+                open = false;
+                self.export_view_dialog(self.view_export_width);
+            }
+            self.show_view_export = open;
+        }
+
+        // Jump to a sequence by name
+        if self.show_sequence_search && self.plot.is_some() {
+            self.sequence_search_window(ctx);
+        }
+
+        // Jump to typed coordinates
+        if self.show_go_to && self.plot.is_some() {
+            self.go_to_window(ctx);
+        }
+
+        if self.show_sequence_filter && self.plot.is_some() {
+            self.sequence_filter_window(ctx);
+        }
+
+        // Summary of the file just loaded
+        if self.load_summary.is_some() {
+            self.load_summary_dialog(ctx);
+        }
+
+        // Error dialogs and warning toasts
+        self.notifications.show(ctx);
+
         // Request repaint if loading
-        if matches!(&*self.loading.lock().unwrap(), LoadingState::Loading(_)) {
+        if matches!(&*self.loading.lock().unwrap(), LoadingState::Loading(..)) {
             ctx.request_repaint();
+        } else if self.follow_receiver.is_some() {
+            ctx.request_repaint_after(paf::SNAPSHOT_INTERVAL / 4);
         }
     }
 }
@@ -761,472 +4216,6032 @@ impl eframe::App for AlnViewApp {
 // ============================================================================
 
 impl AlnViewApp {
+    /// Length/identity cutoffs for the whole plot, applied without reloading
+    fn cutoff_control(&mut self, ui: &mut egui::Ui) {
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        let mut cutoff = plot.cutoff;
+
+        ui.strong("Cutoffs (all layers)");
+        ui.horizontal(|ui| {
+            ui.label("Min length:");
+            ui.add(
+                egui::DragValue::new(&mut cutoff.min_length)
+                    .range(0..=i64::MAX)
+                    .speed(100)
+                    .suffix(" bp"),
+            );
+        });
+        ui.add(egui::Slider::new(&mut cutoff.min_identity, 0.0..=100.0).text("Min identity %"));
+        if cutoff.is_active() {
+            ui.label(
+                egui::RichText::new(format!(
+                    "{} of {} alignments pass",
+                    plot.passing_segments(),
+                    plot.segments.len()
+                ))
+                .weak()
+                .small(),
+            );
+        }
+
+        if cutoff != plot.cutoff {
+            self.set_cutoff(cutoff);
+        }
+
+        // Best-N plane sweep, which rebuilds the plot rather than hiding segments
+        let mut sweep = self.sweep_filter;
+        ui.horizontal(|ui| {
+            let mut enabled = sweep.is_some();
+            if ui
+                .checkbox(&mut enabled, "Best alignments only")
+                .on_hover_text("Keep only the best-scoring alignments at each query and target position (plane sweep, as in sweepga)")
+                .changed()
+            {
+                sweep = enabled.then_some(SweepFilter::ONE_TO_ONE);
+            }
+            if let Some(ref mut filter) = sweep {
+                egui::ComboBox::from_id_source("sweep_filter")
+                    .selected_text(filter.to_string())
+                    .show_ui(ui, |ui| {
+                        for preset in [
+                            SweepFilter::ONE_TO_ONE,
+                            SweepFilter::ONE_TO_MANY,
+                            SweepFilter::MANY_TO_ONE,
+                        ] {
+                            ui.selectable_value(filter, preset, preset.to_string());
+                        }
+                    });
+            }
+        });
+        if let (Some(_), Some(unswept)) = (sweep, self.unswept_plot.as_ref()) {
+            let kept = self
+                .full_plot
+                .as_ref()
+                .or(self.plot.as_ref())
+                .map_or(0, |plot| plot.segments.len());
+            ui.label(
+                egui::RichText::new(format!(
+                    "{kept} of {} alignments kept",
+                    unswept.segments.len()
+                ))
+                .weak()
+                .small(),
+            );
+        }
+        if sweep != self.sweep_filter {
+            self.apply_sweep_filter(sweep);
+            // Comparison flags belong to the old plot's segments; rebuild them
+            if self.comparison.take().is_some() {
+                if let Some(path) = self.comparison_file.clone() {
+                    self.load_comparison_async(path);
+                }
+            }
+        }
+    }
+
+    /// Change the plot-wide cutoff, dropping analyses that depended on it
+    fn set_cutoff(&mut self, cutoff: SegmentFilter) {
+        for plot in self
+            .plot
+            .iter_mut()
+            .chain(self.full_plot.iter_mut())
+            .chain(self.unswept_plot.iter_mut())
+        {
+            plot.cutoff = cutoff;
+        }
+        // Derived analyses only count passing segments
+        self.clear_plot_caches();
+    }
+
+    /// Rebuild the plot from the file's alignments with `sweep` applied (or
+    /// none), keeping the current sequence layout
+    fn apply_sweep_filter(&mut self, sweep: Option<SweepFilter>) {
+        self.sweep_filter = sweep;
+        let base = self
+            .unswept_plot
+            .take()
+            .or_else(|| self.full_plot.take())
+            .or_else(|| self.plot.take());
+        let Some(base) = base else {
+            return;
+        };
+        self.plot = None;
+        self.full_plot = Some(match sweep {
+            Some(filter) => {
+                let swept = base.plane_sweep(filter);
+                println!(
+                    "🧹 Plane sweep {filter}: kept {} of {} alignments",
+                    swept.segments.len(),
+                    base.segments.len()
+                );
+                self.unswept_plot = Some(base);
+                swept
+            }
+            None => base,
+        });
+        self.arrange_sequences();
+    }
+
     fn layer_control(&mut self, ui: &mut egui::Ui, idx: usize) {
         let layer = &mut self.layers[idx];
+        let mut duplicate = false;
 
         ui.group(|ui| {
             ui.horizontal(|ui| {
                 ui.checkbox(&mut layer.visible, "");
                 ui.strong(&layer.name);
+                if labeled(
+                    ui.small_button("⧉"),
+                    "Duplicate this layer, e.g. to compare filter settings",
+                )
+                .clicked()
+                {
+                    duplicate = true;
+                }
             });
 
             ui.horizontal(|ui| {
-                ui.label("Forward:");
+                ui.checkbox(&mut layer.show_forward, "Forward:")
+                    .on_hover_text("Show forward alignments (F toggles all layers)");
                 ui.color_edit_button_srgba(&mut layer.color_forward);
             });
 
             ui.horizontal(|ui| {
-                ui.label("Reverse:");
+                ui.checkbox(&mut layer.show_reverse, "Reverse:")
+                    .on_hover_text("Show reverse alignments (R toggles all layers)");
                 ui.color_edit_button_srgba(&mut layer.color_reverse);
             });
 
+            ui.checkbox(&mut layer.color_by_identity, "Color by identity")
+                .on_hover_text("Colour alignments by percent identity instead of by strand");
+            if layer.color_by_identity {
+                let gradient = &mut layer.gradient;
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source(("layer_colormap", idx))
+                        .selected_text(gradient.colormap.name())
+                        .show_ui(ui, |ui| {
+                            let custom = match gradient.colormap {
+                                Colormap::TwoColor(..) => gradient.colormap,
+                                _ => Colormap::TwoColor([40, 60, 200], [255, 220, 0]),
+                            };
+                            for colormap in [Colormap::Viridis, Colormap::Magma, custom] {
+                                let name = colormap.name();
+                                ui.selectable_value(&mut gradient.colormap, colormap, name);
+                            }
+                        });
+                    if let Colormap::TwoColor(ref mut low, ref mut high) = gradient.colormap {
+                        ui.color_edit_button_srgb(low).on_hover_text("Low identity");
+                        ui.color_edit_button_srgb(high)
+                            .on_hover_text("High identity");
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Identity:");
+                    ui.add(
+                        egui::DragValue::new(&mut gradient.min_identity)
+                            .range(0.0..=100.0)
+                            .speed(0.1)
+                            .suffix("%"),
+                    );
+                    ui.label("to");
+                    ui.add(
+                        egui::DragValue::new(&mut gradient.max_identity)
+                            .range(0.0..=100.0)
+                            .speed(0.1)
+                            .suffix("%"),
+                    );
+                });
+            }
+
             ui.horizontal(|ui| {
                 ui.label("Thickness:");
                 ui.add(egui::Slider::new(&mut layer.thickness, 0.5..=10.0));
             });
-        });
-    }
 
-    fn render_canvas(&mut self, ui: &mut egui::Ui) {
-        let (response, painter) =
-            ui.allocate_painter(ui.available_size(), egui::Sense::click_and_drag());
+            ui.horizontal(|ui| {
+                ui.label("Scale width:");
+                egui::ComboBox::from_id_source(("layer_width_scale", idx))
+                    .selected_text(layer.width_scale.label())
+                    .show_ui(ui, |ui| {
+                        for scale in WidthScale::ALL {
+                            ui.selectable_value(&mut layer.width_scale, scale, scale.label());
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "Widen long (1 kb to 1 Mb) or highly identical (70% to 100%) \
+                         alignments, from half to three times the thickness",
+                    );
+            });
 
-        let rect = response.rect;
+            ui.horizontal(|ui| {
+                ui.label("Opacity:");
+                ui.add(egui::Slider::new(&mut layer.opacity, 0.05..=1.0))
+                    .on_hover_text("See overlapping layers through each other");
+            });
 
-        // Track canvas size for zoom limits
-        self.last_canvas_size = (rect.width(), rect.height());
+            let title = if layer.filter.is_active() {
+                "Filters (active)"
+            } else {
+                "Filters"
+            };
+            egui::CollapsingHeader::new(title)
+                .id_source(("layer_filters", idx))
+                .show(ui, |ui| {
+                    let filter = &mut layer.filter;
+                    ui.horizontal(|ui| {
+                        ui.label("Min length:");
+                        ui.add(
+                            egui::DragValue::new(&mut filter.min_length)
+                                .range(0..=i64::MAX)
+                                .speed(100)
+                                .suffix(" bp"),
+                        );
+                    });
+                    ui.add(
+                        egui::Slider::new(&mut filter.min_identity, 0.0..=100.0)
+                            .text("Min identity %"),
+                    );
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut filter.strand, StrandFilter::Both, "Both");
+                        ui.radio_value(&mut filter.strand, StrandFilter::Forward, "Fwd");
+                        ui.radio_value(&mut filter.strand, StrandFilter::Reverse, "Rev");
+                    });
+                    if ui
+                        .add_enabled(filter.is_active(), egui::Button::new("Clear"))
+                        .clicked()
+                    {
+                        *filter = SegmentFilter::default();
+                    }
+                });
+        });
 
-        // Fit view to canvas on first render after loading
-        if self.needs_initial_fit && rect.width() > 0.0 && rect.height() > 0.0 {
-            self.fit_view_to_canvas(rect);
-            self.needs_initial_fit = false;
+        if duplicate {
+            let copy = LayerSettings {
+                name: format!("{} (copy)", self.layers[idx].name),
+                color_forward: egui::Color32::from_rgb(0, 200, 255),
+                color_reverse: egui::Color32::from_rgb(255, 0, 200),
+                ..self.layers[idx].clone()
+            };
+            self.layers.insert(idx + 1, copy);
+            self.num_layers += 1;
         }
+    }
 
-        // Handle interaction
-        self.handle_interaction(&response, rect);
-
-        // Genome to screen mapping using scale (bp/pixel)
-        let genome_to_screen = |gx: f64, gy: f64| -> egui::Pos2 {
-            let pixel_x = (gx - self.view.x) / self.view.scale;
-            let pixel_y = (gy - self.view.y) / self.view.scale;
-
-            egui::pos2(
-                rect.min.x + pixel_x as f32,
-                rect.max.y - pixel_y as f32, // Y is flipped
+    /// Fields of the clicked alignment, genome-wide and per sequence
+    fn segment_info(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("Alignment");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.small_button("✕").on_hover_text("Deselect").clicked() {
+                    self.selected_segment = None;
+                }
+            });
+        });
+        let (Some(plot), Some(idx)) = (self.plot.as_ref(), self.selected_segment) else {
+            return;
+        };
+        let Some(seg) = plot.segments.get(idx) else {
+            return;
+        };
+        let rec = plot.segment_record(seg);
+        // Target coordinates on the forward strand, as PAF has them
+        let (target_start, target_end) = if seg.reverse {
+            (
+                rec.target_len - rec.target_end,
+                rec.target_len - rec.target_start,
             )
+        } else {
+            (rec.target_start, rec.target_end)
+        };
+        let grid = |ui: &mut egui::Ui, id: &str, rows: &[(&str, String)]| {
+            egui::Grid::new(id).num_columns(2).show(ui, |ui| {
+                for (field, value) in rows {
+                    ui.label(egui::RichText::new(*field).weak());
+                    ui.label(value);
+                }
+            });
         };
 
-        // Background - black like ALNVIEW
-        painter.rect_filled(rect, 0.0, egui::Color32::BLACK);
+        ui.separator();
+        egui::CollapsingHeader::new("Query")
+            .default_open(true)
+            .show(ui, |ui| {
+                grid(
+                    ui,
+                    "segment_query",
+                    &[
+                        ("Name", rec.query_name.clone()),
+                        ("Length", format_bp(rec.query_len)),
+                        ("Start", rec.query_start.to_string()),
+                        ("End", rec.query_end.to_string()),
+                        ("Genome start", seg.abeg.to_string()),
+                        ("Genome end", seg.aend.to_string()),
+                    ],
+                )
+            });
+        egui::CollapsingHeader::new("Target")
+            .default_open(true)
+            .show(ui, |ui| {
+                grid(
+                    ui,
+                    "segment_target",
+                    &[
+                        ("Name", rec.target_name.clone()),
+                        ("Length", format_bp(rec.target_len)),
+                        ("Start", target_start.to_string()),
+                        ("End", target_end.to_string()),
+                        ("Genome start", seg.bbeg.to_string()),
+                        ("Genome end", seg.bend.to_string()),
+                    ],
+                )
+            });
+        egui::CollapsingHeader::new("Alignment")
+            .default_open(true)
+            .show(ui, |ui| {
+                grid(
+                    ui,
+                    "segment_alignment",
+                    &[
+                        ("Strand", if seg.reverse { "-" } else { "+" }.to_string()),
+                        ("Length", format_bp(seg.aend - seg.abeg)),
+                        ("Identity", format!("{:.2}%", seg.identity)),
+                        // Segments keep identity, not the diff count itself
+                        ("Diffs", format!("≈ {}", rec.diffs)),
+                        (
+                            "Passes filters",
+                            if plot.passes(seg) { "yes" } else { "no" }.to_string(),
+                        ),
+                    ],
+                )
+            });
+        ui.separator();
+        ui.label(
+            egui::RichText::new("Target coordinates are on the forward strand")
+                .weak()
+                .small(),
+        );
+    }
 
-        // Draw genome boundaries and scaffold lines
-        if let Some(ref plot) = self.plot {
-            let alen = plot.get_alen() as f64;
-            let blen = plot.get_blen() as f64;
+    fn region_list(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Regions");
+        ui.label(
+            egui::RichText::new("⇧ Shift+Alt+drag on the plot to add a region")
+                .weak()
+                .small(),
+        );
+        ui.horizontal(|ui| {
+            if ui.button("➕ Current view").clicked() {
+                let (width, height) = self.last_canvas_size;
+                let (x, y) = (self.view.x, self.view.y);
+                let (x2, y2) = (
+                    x + width as f64 * self.view.scale,
+                    y + height as f64 * self.view.scale,
+                );
+                self.add_region(x, y, x2, y2);
+            }
+            let any = !self.regions.is_empty();
+            if ui.add_enabled(any, egui::Button::new("BED")).clicked() {
+                self.export_regions_dialog(false);
+            }
+            if ui.add_enabled(any, egui::Button::new("TSV")).clicked() {
+                self.export_regions_dialog(true);
+            }
+        });
+        ui.separator();
+
+        let mut goto = None;
+        let mut partners = None;
+        let mut tag = None;
+        let mut remove = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (idx, region) in self.regions.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_srgb(&mut region.color);
+                    ui.add(egui::TextEdit::singleline(&mut region.name).desired_width(100.0));
+                    if labeled(ui.small_button("🔍"), "Zoom to region").clicked() {
+                        goto = Some(idx);
+                    }
+                    if labeled(
+                        ui.small_button("🔎"),
+                        "Where does its query interval align?",
+                    )
+                    .clicked()
+                    {
+                        partners = Some((region.x_min, region.x_max));
+                    }
+                    if labeled(ui.small_button("🏷"), "Tag region").clicked() {
+                        tag = Some(idx);
+                    }
+                    if labeled(ui.small_button("🗑"), "Delete region").clicked() {
+                        remove = Some(idx);
+                    }
+                });
+                ui.label(
+                    egui::RichText::new(format!(
+                        "{} x {}",
+                        format_extent(region.x_max - region.x_min, self.normalized),
+                        format_extent(region.y_max - region.y_min, self.normalized)
+                    ))
+                    .weak()
+                    .small(),
+                );
+            }
+        });
 
-            // Calculate visible genome region
-            let view_width = rect.width() as f64 * self.view.scale;
-            let view_height = rect.height() as f64 * self.view.scale;
+        if let Some((start, end)) = partners {
+            self.query_partners(Axis::Query, start, end);
+        }
+        if let Some(idx) = goto {
+            let region = &self.regions[idx];
+            let (min_x, min_y) = (region.x_min as f64, region.y_min as f64);
+            let (max_x, max_y) = (region.x_max as f64, region.y_max as f64);
+            let (width, height) = self.last_canvas_size;
+            let canvas = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width, height));
+            self.zoom_to_region(canvas, min_x, min_y, max_x, max_y);
+        }
+        if let Some(idx) = tag {
+            let region = &self.regions[idx];
+            let draft = Tag {
+                target: TagTarget::Region,
+                x_min: region.x_min,
+                x_max: region.x_max,
+                y_min: region.y_min,
+                y_max: region.y_max,
+                reverse: false,
+                tag: String::new(),
+                note: String::new(),
+            };
+            self.tag_edit = Some((None, draft));
+        }
+        if let Some(idx) = remove {
+            self.regions.remove(idx);
+        }
+    }
 
-            // Draw scaffold boundaries for genome A (vertical dashed gray lines)
-            let scaffolds_a = plot.get_scaffold_boundaries(0);
-            for &pos in &scaffolds_a {
-                let x = pos as f64;
-                if x >= self.view.x && x <= self.view.x + view_width {
-                    let x_pos = genome_to_screen(x, 0.0).x;
-                    // TODO: egui doesn't support dashed lines yet, using solid gray
-                    painter.vline(
-                        x_pos,
-                        rect.y_range(),
-                        (1.0, egui::Color32::from_rgb(100, 100, 100)),
-                    );
-                }
+    /// Curation tags, with their notes
+    fn tag_list(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Tags");
+        ui.horizontal(|ui| {
+            ui.label(
+                egui::RichText::new("Right-click an alignment to tag it")
+                    .weak()
+                    .small(),
+            );
+            if ui
+                .add_enabled(!self.tags.is_empty(), egui::Button::new("TSV"))
+                .clicked()
+            {
+                self.export_tags_dialog();
             }
+        });
 
-            // Draw scaffold boundaries for genome B (horizontal dashed gray lines)
-            let scaffolds_b = plot.get_scaffold_boundaries(1);
-            for &pos in &scaffolds_b {
-                let y = pos as f64;
-                if y >= self.view.y && y <= self.view.y + view_height {
-                    let y_pos = genome_to_screen(0.0, y).y;
-                    painter.hline(
-                        rect.x_range(),
-                        y_pos,
-                        (1.0, egui::Color32::from_rgb(100, 100, 100)),
-                    );
+        let mut goto = None;
+        let mut edit = None;
+        let mut remove = None;
+        egui::ScrollArea::vertical()
+            .id_source("tag_list")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                for (idx, tag) in self.tags.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        let kind = match tag.target {
+                            TagTarget::Segment => "╱",
+                            TagTarget::Region => "▭",
+                        };
+                        ui.label(format!("{kind} {}", tag.tag));
+                        if labeled(ui.small_button("🔍"), "Zoom to tag").clicked() {
+                            goto = Some(idx);
+                        }
+                        if labeled(ui.small_button("✏"), "Edit tag").clicked() {
+                            edit = Some(idx);
+                        }
+                        if labeled(ui.small_button("🗑"), "Delete tag").clicked() {
+                            remove = Some(idx);
+                        }
+                    });
+                    if !tag.note.is_empty() {
+                        ui.label(egui::RichText::new(&tag.note).weak().small());
+                    }
                 }
-            }
+            });
 
-            // Draw genome end boundaries (thicker)
-            if alen >= self.view.x && alen <= self.view.x + view_width {
-                let x_pos = genome_to_screen(alen, 0.0).x;
-                painter.vline(x_pos, rect.y_range(), (2.0, egui::Color32::DARK_RED));
-            }
+        if let Some(idx) = goto {
+            let tag = &self.tags[idx];
+            // Pad so a tagged alignment is not flush with the canvas edges
+            let pad = ((tag.x_max - tag.x_min).max(tag.y_max - tag.y_min) / 10) as f64;
+            let (min_x, min_y) = (tag.x_min as f64 - pad, tag.y_min as f64 - pad);
+            let (max_x, max_y) = (tag.x_max as f64 + pad, tag.y_max as f64 + pad);
+            let (width, height) = self.last_canvas_size;
+            let canvas = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width, height));
+            self.zoom_to_region(canvas, min_x, min_y, max_x, max_y);
+        }
+        if let Some(idx) = edit {
+            self.tag_edit = Some((Some(idx), self.tags[idx].clone()));
+        }
+        if let Some(idx) = remove {
+            self.tags.remove(idx);
+        }
+    }
 
-            if blen >= self.view.y && blen <= self.view.y + view_height {
-                let y_pos = genome_to_screen(0.0, blen).y;
-                painter.hline(rect.x_range(), y_pos, (2.0, egui::Color32::DARK_BLUE));
+    /// Reorderable, toggleable list of one axis' sequences
+    fn sequence_list(&mut self, ui: &mut egui::Ui, axis: Axis) {
+        let Some(plot) = self.full_plot.as_ref().or(self.plot.as_ref()) else {
+            return;
+        };
+        let (title, names, order, hidden, flipped) = match axis {
+            Axis::Query => (
+                "Query",
+                &plot.query_sequences,
+                &self.query_order,
+                &self.hidden_query,
+                &self.flipped_query,
+            ),
+            Axis::Target => (
+                "Target",
+                &plot.target_sequences,
+                &self.target_order,
+                &self.hidden_target,
+                &self.flipped_target,
+            ),
+        };
+
+        let mut moved = None;
+        let mut toggled = None;
+        let mut flip = None;
+        let mut reset = false;
+
+        ui.horizontal(|ui| {
+            ui.strong(title);
+            ui.label(format!("({})", names.len()));
+            if ui.small_button("Reset order").clicked() {
+                reset = true;
             }
+        });
 
-            // Draw axes at origin
-            if self.view.x <= 0.0 && self.view.x + view_width >= 0.0 {
-                let x_pos = genome_to_screen(0.0, 0.0).x;
-                painter.vline(x_pos, rect.y_range(), (1.0, egui::Color32::GRAY));
-            }
-            if self.view.y <= 0.0 && self.view.y + view_height >= 0.0 {
-                let y_pos = genome_to_screen(0.0, 0.0).y;
-                painter.hline(rect.x_range(), y_pos, (1.0, egui::Color32::GRAY));
-            }
+        let row_height = ui.spacing().interact_size.y;
+        egui::ScrollArea::vertical()
+            .id_source(title)
+            .max_height(ui.available_height() / 2.0 - 20.0)
+            .show_rows(ui, row_height, order.len(), |ui, rows| {
+                for pos in rows {
+                    let name = &names[order[pos]];
+                    let row = ui
+                        .horizontal(|ui| {
+                            ui.dnd_drag_source(
+                                egui::Id::new((title, "row", pos)),
+                                SequenceDrag { axis, from: pos },
+                                |ui| {
+                                    ui.label("☰");
+                                },
+                            );
+                            let mut visible = !hidden.contains(name);
+                            if ui.checkbox(&mut visible, "").changed() {
+                                toggled = Some(name.clone());
+                            }
+                            if ui
+                                .selectable_label(flipped.contains(name), "⇄")
+                                .on_hover_text("Reverse-complement this sequence")
+                                .clicked()
+                            {
+                                flip = Some(name.clone());
+                            }
+                            ui.label(extract_display_name(name, 28))
+                                .on_hover_text(name.as_str());
+                        })
+                        .response;
+
+                    // Drop marker above the row the dragged sequence will land on
+                    if let Some(drag) = row.dnd_hover_payload::<SequenceDrag>() {
+                        if drag.axis == axis {
+                            ui.painter().hline(
+                                row.rect.x_range(),
+                                row.rect.top(),
+                                egui::Stroke::new(2.0, ui.visuals().selection.bg_fill),
+                            );
+                        }
+                    }
+                    if let Some(drag) = row.dnd_release_payload::<SequenceDrag>() {
+                        if drag.axis == axis {
+                            moved = Some((drag.from, pos));
+                        }
+                    }
+                }
+            });
+
+        if reset {
+            self.reset_sequence_order(axis);
         }
+        if let Some((from, to)) = moved {
+            self.move_sequence(axis, from, to);
+        }
+        if let Some(name) = toggled {
+            self.toggle_sequence(axis, name, false);
+        }
+        if let Some(name) = flip {
+            self.flip_sequence(axis, name);
+        }
+    }
 
-        // Draw alignment segments for each visible layer
-        if let Some(ref plot) = self.plot {
-            for (layer_idx, layer_settings) in self.layers.iter().enumerate() {
-                if !layer_settings.visible || layer_idx >= self.num_layers {
-                    continue;
+    /// Make a freshly loaded plot the current one, resetting per-plot state
+    ///
+    /// `downsampled_from` is the original segment count if the plot was thinned.
+    fn install_plot(&mut self, rust_plot: RustPlot, downsampled_from: Option<usize>) {
+        self.time_next_frame = self.timings;
+        self.genome_groups = self.loaded_genome_groups.take();
+        self.segment_store = None;
+        if let Some(path) = self.loaded_segment_store.take() {
+            match SegmentStore::open(&path) {
+                Ok(store) => self.segment_store = Some(store),
+                Err(e) => self.notifications.error(
+                    "Segment store unavailable",
+                    format!("{e:#}"),
+                    Some(&path),
+                ),
+            }
+        }
+        self.contig_boundaries = None;
+        let rust_plot = match self.pending_agp.take() {
+            Some(agp) => match agp.scaffold(&rust_plot) {
+                Some((scaffolded, contigs)) => {
+                    // The index holds the contigs' coordinates
+                    self.segment_store = None;
+                    self.contig_boundaries = Some(contigs);
+                    scaffolded
+                }
+                None => {
+                    self.notifications.warning(
+                        "AGP not applied",
+                        "No sequences of this plot are in the AGP file.",
+                        None,
+                    );
+                    rust_plot
                 }
+            },
+            None => rust_plot,
+        };
+        // Extract real genome lengths
+        let alen = rust_plot.get_alen() as f64;
+        let blen = rust_plot.get_blen() as f64;
 
-                // Calculate visible genome region based on canvas size and scale
-                let view_width = rect.width() as f64 * self.view.scale;
-                let view_height = rect.height() as f64 * self.view.scale;
+        // Update view with actual genome dimensions
+        self.view.max_x = alen;
+        self.view.max_y = blen;
+        self.view.x = 0.0;
+        self.view.y = 0.0;
+        // Will fit to canvas on first render
+        self.needs_initial_fit = true;
+        self.view_history.clear();
 
-                // Query R*-tree for segments in visible region
-                let visible_segs = plot.query_segments_in_region(
-                    layer_idx as i32,
-                    self.view.x,
-                    self.view.y,
-                    view_width,
-                    view_height,
-                );
+        // Get actual number of layers from plot
+        let nlays = rust_plot.get_nlays() as usize;
 
-                // Draw visible segments
-                for seg in visible_segs {
-                    // Draw the segment as a line
-                    let p1 = genome_to_screen(seg.abeg as f64, seg.bbeg as f64);
-                    let p2 = genome_to_screen(seg.aend as f64, seg.bend as f64);
+        self.num_layers = nlays;
+
+        // Create layer settings for all layers
+        self.layers = (0..nlays)
+            .map(|i| {
+                LayerSettings {
+                    visible: true,
+                    name: format!("Layer {i}"),
+                    ..Default::default()
+                }
+                .with_palette(self.palette)
+            })
+            .collect();
+
+        if self.show_load_summary {
+            self.load_summary = Some(LoadSummary::new(
+                &rust_plot,
+                self.loading_source.as_deref(),
+                self.last_load_time,
+                downsampled_from,
+            ));
+        }
 
-                    // Forward = same direction (both increasing or both decreasing)
-                    // Reverse = opposite direction
-                    let is_forward = !seg.reverse;
+        if rust_plot.segments.is_empty() {
+            self.notifications.warning(
+                "No alignments",
+                "The file loaded but contains no alignment records.",
+                self.loading_source.as_deref(),
+            );
+        }
 
-                    // Use green for forward, red for reverse (like C version)
-                    let color = if is_forward {
-                        egui::Color32::from_rgb(0, 255, 0) // Green for forward
-                    } else {
-                        egui::Color32::from_rgb(255, 0, 0) // Red for reverse complement
-                    };
+        self.query_order = (0..rust_plot.query_sequences.len()).collect();
+        self.target_order = (0..rust_plot.target_sequences.len()).collect();
+        self.plot = Some(rust_plot);
+        self.clear_plot_caches();
+        self.full_plot = None;
+        self.unswept_plot = None;
+        self.hidden_query.clear();
+        self.hidden_target.clear();
+        self.flipped_query.clear();
+        self.flipped_target.clear();
+        self.axes_swapped = false;
+        if self.sweep_filter.is_some() {
+            self.apply_sweep_filter(self.sweep_filter);
+        }
+        if let Some(layout) = self.pending_layout.take() {
+            self.apply_layout(&layout);
+        } else if self.normalized {
+            self.apply_sequence_layout();
+        }
+        if let Some(order) = self.pending_order.take() {
+            self.apply_order(&order);
+        }
+        // Annotations, regions, tags and titles belong to the plot they were placed on
+        self.annotations.clear();
+        self.regions.clear();
+        self.tags.clear();
+        self.plot_title.clear();
+        self.plot_subtitle.clear();
+        if let Some(session) = self.pending_session.take() {
+            self.apply_session(session);
+        }
+        // A comparison only makes sense against the plot it was built for
+        self.comparison = None;
+        self.comparison_file = None;
+        if let Some(preset) = self.pending_preset.take() {
+            self.apply_preset(&preset);
+        }
+        if let Some(cutoff) = self.pending_cutoff.take() {
+            self.set_cutoff(cutoff);
+        }
+    }
+
+    /// Offer to downsample a plot with more segments than the threshold
+    fn downsample_dialog(&mut self, ctx: &egui::Context) {
+        let Some(total) = self
+            .pending_huge_plot
+            .as_ref()
+            .map(|p| p.passing_segments())
+        else {
+            return;
+        };
+        let mut max = self.max_segments.unwrap_or(rust_plot::DEFAULT_MAX_SEGMENTS);
+        let mut choice = None;
+
+        egui::Window::new("Large alignment file")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "This file has {total} alignments. Drawing them all may make the viewer slow to respond."
+                ));
+                ui.add_space(5.0);
+                ui.label(
+                    "Downsampling keeps a length-weighted sample, so long alignments are kept and short ones thinned out.",
+                );
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    ui.label("Keep at most");
+                    ui.add(
+                        egui::DragValue::new(&mut max)
+                            .range(1000..=total)
+                            .speed(10_000),
+                    );
+                    ui.label("alignments");
+                });
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button(format!("Downsample to {max}")).clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("Load everything").clicked() {
+                        choice = Some(false);
+                    }
+                });
+            });
 
-                    painter.line_segment([p1, p2], egui::Stroke::new(1.0, color));
+        self.max_segments = Some(max);
+        if let Some(downsample) = choice {
+            if let Some(plot) = self.pending_huge_plot.take() {
+                if downsample {
+                    self.install_plot(plot.downsampled(max), Some(total));
+                } else {
+                    self.install_plot(plot, None);
                 }
             }
         }
+    }
 
-        // Draw border
-        painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui::Color32::GRAY));
+    fn load_summary_dialog(&mut self, ctx: &egui::Context) {
+        let Some(ref summary) = self.load_summary else {
+            return;
+        };
+        let mut open = true;
+        let mut close = false;
+
+        egui::Window::new("Loaded")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.strong(&summary.source);
+                ui.add_space(5.0);
+                egui::Grid::new("load_summary_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.label("Query sequences");
+                        ui.label(format!(
+                            "{} ({})",
+                            summary.query_sequences,
+                            format_bp(summary.query_len)
+                        ));
+                        ui.end_row();
+                        ui.label("Target sequences");
+                        ui.label(format!(
+                            "{} ({})",
+                            summary.target_sequences,
+                            format_bp(summary.target_len)
+                        ));
+                        ui.end_row();
+                        ui.label("Alignments");
+                        match summary.downsampled_from {
+                            Some(total) => {
+                                ui.label(format!("{} (sampled from {total})", summary.alignments))
+                            }
+                            None => ui.label(summary.alignments.to_string()),
+                        };
+                        ui.end_row();
+                        ui.label("Aligned bases (query)");
+                        ui.label(format_bp(summary.aligned_bp));
+                        ui.end_row();
+                        if let Some(ani) = summary.ani.ani {
+                            ui.label("ANI").on_hover_text(
+                                "Identity of the best alignment at each covered query base",
+                            );
+                            ui.label(format!(
+                                "{ani:.2}% over {:.1}% of query",
+                                percent_of(summary.ani.covered_bp, summary.ani.query_bp)
+                            ));
+                            ui.end_row();
+                        }
+                        if let Some(load_time) = summary.load_time {
+                            ui.label("Load time");
+                            ui.label(format!("{:.2} s", load_time.as_secs_f64()));
+                            ui.end_row();
+                        }
+                        ui.label("Memory (plot data)");
+                        ui.label(format_bytes(summary.memory_bytes));
+                        ui.end_row();
+                    });
 
-        // Draw scale/axes
-        self.draw_axes(ui, &painter, rect);
+                if summary.ani.per_sequence.len() > 1 {
+                    ui.collapsing("ANI per query sequence", |ui| {
+                        egui::ScrollArea::vertical()
+                            .max_height(200.0)
+                            .show(ui, |ui| {
+                                egui::Grid::new("load_summary_ani_grid")
+                                    .num_columns(3)
+                                    .striped(true)
+                                    .show(ui, |ui| {
+                                        for (seq, name) in
+                                            summary.ani.per_sequence.iter().zip(&summary.ani_names)
+                                        {
+                                            ui.label(extract_display_name(name, 30));
+                                            ui.label(format!("{:.2}%", seq.ani));
+                                            ui.label(format!(
+                                                "{:.1}% covered",
+                                                percent_of(seq.covered_bp, seq.length)
+                                            ));
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                    });
+                }
 
-        // Update cursor position info (displayed in layers panel)
-        if let Some(ref plot) = self.plot {
-            if let Some(hover_pos) = response.hover_pos() {
-                // Convert screen position to genome coordinates
-                let pixel_x = (hover_pos.x - rect.min.x) as f64;
-                let pixel_y = (rect.max.y - hover_pos.y) as f64;
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("OK").clicked() {
+                        close = true;
+                    }
+                    ui.checkbox(&mut self.show_load_summary, "Show after every load");
+                });
+            });
 
-                let genome_x = self.view.x + pixel_x * self.view.scale;
-                let genome_y = self.view.y + pixel_y * self.view.scale;
+        if !open || close {
+            self.load_summary = None;
+        }
+    }
 
-                // Get sequence info
-                let (_query_idx, query_name, query_local) =
-                    plot.query_coord_to_sequence(genome_x as i64);
-                let (_target_idx, target_name, target_local) =
-                    plot.target_coord_to_sequence(genome_y as i64);
+    fn comparison_control(&mut self, ui: &mut egui::Ui) {
+        let Some(ref comparison) = self.comparison else {
+            return;
+        };
+        let (both, only_first, only_second) = comparison.counts();
+        let skipped = comparison.skipped_records;
 
-                // Update cursor info fields (displayed in layers panel)
-                self.cursor_query_name = query_name;
-                self.cursor_query_pos = query_local;
-                self.cursor_target_name = target_name;
-                self.cursor_target_pos = target_local;
-                self.cursor_genome_x = genome_x;
-                self.cursor_genome_y = genome_y;
+        ui.heading("Comparison");
+        if let Some(ref path) = self.comparison_file {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+            ui.label(format!("vs {}", truncate_name(name, 30)));
+        }
+
+        ui.checkbox(&mut self.color_by_presence, "Color by presence");
+        if ui
+            .checkbox(&mut self.swipe, "Swipe between files")
+            .on_hover_text("First file left of a draggable divider, second file right of it")
+            .changed()
+        {
+            self.side_by_side &= !self.swipe;
+        }
+        if ui
+            .checkbox(&mut self.side_by_side, "Side by side")
+            .on_hover_text("Second file in a pane of its own, right of the first")
+            .changed()
+        {
+            self.swipe &= !self.side_by_side;
+            self.second_view = self.view.clone();
+        }
+        if ui
+            .add_enabled(
+                self.side_by_side,
+                egui::Checkbox::new(&mut self.link_views, "Link pan and zoom"),
+            )
+            .changed()
+        {
+            // Unlinking starts the second pane where the first one is
+            self.second_view = self.view.clone();
+        }
+        for (presence, label, count) in [
+            (Presence::Both, "In both", both),
+            (Presence::OnlyFirst, "Only first", only_first),
+            (Presence::OnlySecond, "Only second", only_second),
+        ] {
+            ui.horizontal(|ui| {
+                let (swatch, _) =
+                    ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                ui.painter()
+                    .rect_filled(swatch, 2.0, presence_color(presence));
+                ui.label(format!("{label}: {count}"));
+            });
+        }
+        if skipped > 0 {
+            ui.label(format!("{skipped} records on unknown sequences skipped"));
+        }
+
+        if ui.button("Close comparison").clicked() {
+            self.comparison = None;
+            self.comparison_file = None;
+        }
+    }
+
+    /// The comparison file on a canvas of its own, in the same genome
+    /// coordinates as the first; it pans and zooms the shared view while
+    /// linked, otherwise its own
+    fn render_second_pane(&mut self, ui: &mut egui::Ui) {
+        let (response, painter) =
+            ui.allocate_painter(ui.available_size(), egui::Sense::click_and_drag());
+        let rect = response.rect;
+        if !self.link_views {
+            std::mem::swap(&mut self.view, &mut self.second_view);
+        }
+        // Drag pans, the wheel or a pinch zooms at the pointer
+        if response.dragged() {
+            let delta = response.drag_delta();
+            self.view.x -= delta.x as f64 * self.view.scale;
+            self.view.y += delta.y as f64 * self.view.scale;
+            self.clamp_view_position(rect);
+        }
+        if response.hovered() {
+            let (raw_scroll, zoom_delta) = ui.input(|i| (i.raw_scroll_delta, i.zoom_delta()));
+            if zoom_delta != 1.0 {
+                self.zoom_with_mouse(zoom_delta as f64, &response, rect);
+            } else if raw_scroll.y != 0.0 {
+                let factor = if raw_scroll.y > 0.0 { 1.2 } else { 0.8 };
+                self.zoom_with_mouse(factor, &response, rect);
             }
         }
+        if let (Some(plot), Some(comparison)) = (self.plot.as_ref(), self.comparison.as_ref()) {
+            self.draw_second_pane(&painter, rect, plot, comparison);
+        }
+        if !self.link_views {
+            std::mem::swap(&mut self.view, &mut self.second_view);
+        }
     }
 
-    fn draw_axes(&self, _ui: &mut egui::Ui, painter: &egui::Painter, rect: egui::Rect) {
+    /// Sequence boundaries and the comparison file's segments under the
+    /// current view, with the file's name along the top
+    fn draw_second_pane(
+        &self,
+        painter: &egui::Painter,
+        rect: egui::Rect,
+        plot: &RustPlot,
+        comparison: &Comparison,
+    ) {
+        let painter = painter.with_clip_rect(rect);
+        painter.rect_filled(rect, 0.0, egui::Color32::BLACK);
+        let to_screen = |gx: f64, gy: f64| {
+            egui::pos2(
+                rect.min.x + ((gx - self.view.x) / self.view.scale) as f32,
+                rect.max.y - ((gy - self.view.y) / self.view.scale) as f32,
+            )
+        };
+        let grid = egui::Stroke::new(1.0, egui::Color32::from_rgb(100, 100, 100));
+        for &pos in &plot.query_boundaries {
+            painter.vline(to_screen(pos as f64, 0.0).x, rect.y_range(), grid);
+        }
+        for &pos in &plot.target_boundaries {
+            painter.hline(rect.x_range(), to_screen(0.0, pos as f64).y, grid);
+        }
+
+        let layer = self.layers.first().cloned().unwrap_or_default();
         let view_width = rect.width() as f64 * self.view.scale;
         let view_height = rect.height() as f64 * self.view.scale;
+        let (x_min, x_max) = (self.view.x as i64, (self.view.x + view_width) as i64);
+        let (y_min, y_max) = (self.view.y as i64, (self.view.y + view_height) as i64);
+        for seg in comparison.segments.iter() {
+            if !seg.intersects(x_min, x_max, y_min, y_max) || !plot.passes(seg) {
+                continue;
+            }
+            let color = layer
+                .segment_color(seg, self.high_contrast)
+                .gamma_multiply(layer.opacity);
+            let p1 = to_screen(seg.abeg as f64, seg.bbeg as f64);
+            let p2 = to_screen(seg.aend as f64, seg.bend as f64);
+            if self.dot_tiny_segments && p1.distance(p2) < 1.0 {
+                let radius = (layer.segment_width(seg) / 2.0).max(MIN_DOT_RADIUS_PX);
+                painter.circle_filled(p1.lerp(p2, 0.5), radius, color);
+            } else {
+                painter.line_segment([p1, p2], egui::Stroke::new(layer.segment_width(seg), color));
+            }
+        }
 
-        // X axis label
-        let x_text = format!("{:.0} - {:.0} bp", self.view.x, self.view.x + view_width);
+        let name = self
+            .comparison_file
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .map_or("file".to_string(), |n| {
+                truncate_name(&n.to_string_lossy(), 30)
+            });
+        let link = if self.link_views { "🔗 " } else { "" };
         painter.text(
-            egui::pos2(rect.center().x, rect.max.y - 5.0),
-            egui::Align2::CENTER_BOTTOM,
-            x_text,
-            egui::FontId::proportional(10.0),
-            egui::Color32::DARK_GRAY,
+            rect.center_top() + egui::vec2(0.0, 8.0),
+            egui::Align2::CENTER_TOP,
+            format!("{link}{name}"),
+            egui::FontId::proportional(12.0),
+            egui::Color32::WHITE,
+        );
+        painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui::Color32::GRAY));
+    }
+
+    /// Screen x of the swipe divider while swiping between the two files of a
+    /// comparison, dragged with the handle along it
+    fn swipe_divider(&mut self, ui: &mut egui::Ui, rect: egui::Rect) -> Option<f32> {
+        if !self.swipe || self.comparison.is_none() || rect.width() <= 0.0 {
+            return None;
+        }
+        let x = rect.min.x + self.swipe_fraction * rect.width();
+        let handle = egui::Rect::from_center_size(
+            egui::pos2(x, rect.center().y),
+            egui::vec2(SWIPE_HANDLE_PX, rect.height()),
         );
+        let response = ui
+            .interact(handle, ui.id().with("swipe_divider"), egui::Sense::drag())
+            .on_hover_cursor(egui::CursorIcon::ResizeHorizontal);
+        if let (true, Some(pos)) = (response.dragged(), response.interact_pointer_pos()) {
+            self.swipe_fraction = ((pos.x - rect.min.x) / rect.width()).clamp(0.0, 1.0);
+        }
+        Some(rect.min.x + self.swipe_fraction * rect.width())
+    }
 
-        // Y axis label (rotated would be nice, but keeping simple for now)
-        let y_text = format!("{:.0} - {:.0} bp", self.view.y, self.view.y + view_height);
+    /// Draw the comparison file's segments right of the divider at `x`, in the
+    /// first layer's strand colours, then the divider and file names
+    fn draw_swipe_second(
+        &self,
+        painter: &egui::Painter,
+        rect: egui::Rect,
+        x: f32,
+        comparison: &Comparison,
+        genome_to_screen: impl Fn(f64, f64) -> egui::Pos2,
+    ) {
+        let right =
+            painter.with_clip_rect(egui::Rect::from_x_y_ranges(x..=rect.max.x, rect.y_range()));
+        let layer = self.layers.first().cloned().unwrap_or_default();
+        let view_width = rect.width() as f64 * self.view.scale;
+        let view_height = rect.height() as f64 * self.view.scale;
+        let (x_min, x_max) = (self.view.x as i64, (self.view.x + view_width) as i64);
+        let (y_min, y_max) = (self.view.y as i64, (self.view.y + view_height) as i64);
+        let passes = |seg: &AlignmentSegment| self.plot.as_ref().is_none_or(|p| p.passes(seg));
+        for seg in comparison.segments.iter() {
+            if !seg.intersects(x_min, x_max, y_min, y_max) || !passes(seg) {
+                continue;
+            }
+            let color = layer
+                .segment_color(seg, self.high_contrast)
+                .gamma_multiply(layer.opacity);
+            let p1 = genome_to_screen(seg.abeg as f64, seg.bbeg as f64);
+            let p2 = genome_to_screen(seg.aend as f64, seg.bend as f64);
+            if self.dot_tiny_segments && p1.distance(p2) < 1.0 {
+                let radius = (layer.segment_width(seg) / 2.0).max(MIN_DOT_RADIUS_PX);
+                right.circle_filled(p1.lerp(p2, 0.5), radius, color);
+            } else {
+                right.line_segment([p1, p2], egui::Stroke::new(layer.segment_width(seg), color));
+            }
+        }
+
+        painter.vline(
+            x,
+            rect.y_range(),
+            egui::Stroke::new(2.0, egui::Color32::WHITE),
+        );
+        painter.circle_filled(
+            egui::pos2(x, rect.center().y),
+            SWIPE_HANDLE_PX / 2.0,
+            egui::Color32::WHITE,
+        );
+        let name = |path: Option<&PathBuf>| {
+            path.and_then(|p| p.file_name())
+                .map_or("file".to_string(), |n| {
+                    truncate_name(&n.to_string_lossy(), 30)
+                })
+        };
+        let font = egui::FontId::proportional(12.0);
         painter.text(
-            egui::pos2(rect.min.x + 5.0, rect.center().y),
-            egui::Align2::LEFT_CENTER,
-            y_text,
-            egui::FontId::proportional(10.0),
-            egui::Color32::DARK_GRAY,
+            egui::pos2(x - 8.0, rect.min.y + 8.0),
+            egui::Align2::RIGHT_TOP,
+            format!("◀ {}", name(self.current_file.as_ref())),
+            font.clone(),
+            egui::Color32::WHITE,
+        );
+        painter.text(
+            egui::pos2(x + 8.0, rect.min.y + 8.0),
+            egui::Align2::LEFT_TOP,
+            format!("{} ▶", name(self.comparison_file.as_ref())),
+            font,
+            egui::Color32::WHITE,
         );
     }
 
-    fn handle_interaction(&mut self, response: &egui::Response, rect: egui::Rect) {
-        // Z key - go back in zoom history
-        response.ctx.input(|i| {
-            if i.key_pressed(egui::Key::Z) {
-                if let Some(prev_view) = self.view_history.pop() {
-                    self.view = prev_view;
+    /// Save the current look as a preset, or apply or delete a saved one
+    fn presets_menu(&mut self, ui: &mut egui::Ui) {
+        if ui
+            .button("💾 Save preset...")
+            .on_hover_text("Layer colours and filters, cutoffs, sweep filter and rendering mode")
+            .clicked()
+        {
+            self.preset_name_edit = Some(String::new());
+            ui.close_menu();
+        }
+        if self.presets.presets.is_empty() {
+            ui.label(egui::RichText::new("No saved presets").weak());
+            return;
+        }
+        ui.separator();
+        let mut apply = None;
+        let mut delete = None;
+        for preset in &self.presets.presets {
+            ui.horizontal(|ui| {
+                if ui.button(&preset.name).clicked() {
+                    apply = Some(preset.clone());
+                    ui.close_menu();
+                }
+                if labeled(ui.small_button("🗑"), "Delete preset").clicked() {
+                    delete = Some(preset.name.clone());
+                }
+            });
+        }
+        if let Some(preset) = apply {
+            self.apply_preset(&preset);
+        }
+        if let Some(name) = delete {
+            self.presets.remove(&name);
+            self.save_presets();
+        }
+    }
+
+    fn save_preset_dialog(&mut self, ctx: &egui::Context) {
+        let Some(mut name) = self.preset_name_edit.take() else {
+            return;
+        };
+        let mut open = true;
+        let mut save = false;
+        let mut cancel = false;
+        egui::Window::new("Save preset")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    let edit = ui.text_edit_singleline(&mut name);
+                    edit.request_focus();
+                    save = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                });
+                if self.presets.get(name.trim()).is_some() {
+                    ui.label(egui::RichText::new("Replaces the preset of this name").weak());
+                }
+                ui.horizontal(|ui| {
+                    save |= ui
+                        .add_enabled(!name.trim().is_empty(), egui::Button::new("Save"))
+                        .clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        if save && !name.trim().is_empty() {
+            let preset = self.current_preset(name.trim().to_string());
+            self.presets.insert(preset);
+            self.save_presets();
+        } else if open && !cancel {
+            self.preset_name_edit = Some(name);
+        }
+    }
+
+    /// Editor for the mouse bindings, saved to the config directory
+    fn bindings_dialog(&mut self, ctx: &egui::Context) {
+        let Some(mut draft) = self.bindings_edit.take() else {
+            return;
+        };
+        let mut open = true;
+        let mut save = false;
+        let mut cancel = false;
+        let problem = draft.validate().err();
+        egui::Window::new("Mouse bindings")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("bindings_grid")
+                    .num_columns(5)
+                    .spacing([12.0, 4.0])
+                    .show(ui, |ui| {
+                        for action in Action::ALL {
+                            let mut binding = draft.get(action);
+                            ui.label(action.label());
+                            ui.checkbox(&mut binding.modifiers.shift, "Shift");
+                            ui.checkbox(&mut binding.modifiers.ctrl, "Ctrl/Cmd");
+                            ui.checkbox(&mut binding.modifiers.alt, "Alt");
+                            egui::ComboBox::from_id_source(("binding", action.name()))
+                                .selected_text(binding.gesture.name())
+                                .show_ui(ui, |ui| {
+                                    for gesture in Gesture::ALL.into_iter().filter(|&gesture| {
+                                        (action == Action::Zoom) == (gesture == Gesture::Wheel)
+                                    }) {
+                                        ui.selectable_value(
+                                            &mut binding.gesture,
+                                            gesture,
+                                            gesture.name(),
+                                        );
+                                    }
+                                });
+                            draft.set(action, binding);
+                            ui.end_row();
+                        }
+                    });
+                if let Some(problem) = &problem {
+                    ui.colored_label(egui::Color32::from_rgb(255, 120, 120), problem.to_string());
                 }
+                ui.horizontal(|ui| {
+                    save = ui
+                        .add_enabled(problem.is_none(), egui::Button::new("Save"))
+                        .clicked();
+                    if ui.button("Defaults").clicked() {
+                        draft = Bindings::default();
+                    }
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        if save {
+            self.bindings = draft;
+            if let Err(e) = self.bindings.save_default() {
+                self.notifications.error(
+                    "Could not save mouse bindings",
+                    format!("{e:#}"),
+                    Bindings::default_path().as_deref(),
+                );
             }
-        });
+        } else if open && !cancel {
+            self.bindings_edit = Some(draft);
+        }
+    }
 
-        // Shift+drag for box zoom
-        if response.hovered() {
-            let shift_held = response.ctx.input(|i| i.modifiers.shift);
+    fn save_presets(&mut self) {
+        if let Err(e) = self.presets.save_default() {
+            self.notifications.error(
+                "Could not save presets",
+                format!("{e:#}"),
+                PresetStore::default_path().as_deref(),
+            );
+        }
+    }
 
-            if shift_held && response.drag_started() {
-                self.box_zoom_start = response.hover_pos();
+    /// The current drawing settings as a preset called `name`
+    fn current_preset(&self, name: String) -> Preset {
+        let rgb = |color: egui::Color32| [color.r(), color.g(), color.b()];
+        Preset {
+            name,
+            layers: self
+                .layers
+                .iter()
+                .map(|layer| PresetLayer {
+                    name: layer.name.clone(),
+                    visible: layer.visible,
+                    color_forward: rgb(layer.color_forward),
+                    color_reverse: rgb(layer.color_reverse),
+                    thickness: layer.thickness,
+                    width_scale: layer.width_scale,
+                    opacity: layer.opacity,
+                    show_forward: layer.show_forward,
+                    show_reverse: layer.show_reverse,
+                    filter: layer.filter,
+                    color_by_identity: layer.color_by_identity,
+                    gradient: layer.gradient,
+                })
+                .collect(),
+            cutoff: self
+                .plot
+                .as_ref()
+                .map(|plot| plot.cutoff)
+                .unwrap_or_default(),
+            sweep: self.sweep_filter,
+            mode: match self.view_mode {
+                ViewMode::DotPlot => "dotplot",
+                ViewMode::Synteny => "synteny",
+                ViewMode::Wrapped => "wrapped",
             }
+            .to_string(),
+            tiny_dots: self.dot_tiny_segments,
+            length_dots: self.length_scaled_dots,
+            equal_width: self.normalized,
+        }
+    }
 
-            if let Some(start) = self.box_zoom_start {
-                if response.dragged() {
-                    // Draw box while dragging
-                    if let Some(current) = response.hover_pos() {
-                        let painter = response.ctx.debug_painter();
-                        let box_rect = egui::Rect::from_two_pos(start, current);
-                        painter.rect_stroke(
-                            box_rect,
-                            0.0,
-                            egui::Stroke::new(2.0, egui::Color32::from_rgb(255, 100, 100)),
-                        );
+    /// Draw the loaded plot with a saved preset's settings
+    fn apply_preset(&mut self, preset: &Preset) {
+        if !preset.layers.is_empty() {
+            self.layers = preset.layers.iter().map(preset_layer).collect();
+            while self.layers.len() < self.num_layers {
+                let name = format!("Layer {}", self.layers.len());
+                self.layers.push(
+                    LayerSettings {
+                        name,
+                        ..Default::default()
                     }
-                }
+                    .with_palette(self.palette),
+                );
+            }
+        }
+        if self.plot.as_ref().map(|plot| plot.cutoff) != Some(preset.cutoff) {
+            self.set_cutoff(preset.cutoff);
+        }
+        if preset.sweep != self.sweep_filter {
+            self.apply_sweep_filter(preset.sweep);
+        }
+        self.view_mode = match preset.mode.as_str() {
+            "synteny" => ViewMode::Synteny,
+            "wrapped" => ViewMode::Wrapped,
+            _ => ViewMode::DotPlot,
+        };
+        self.dot_tiny_segments = preset.tiny_dots;
+        self.length_scaled_dots = preset.length_dots;
+        if preset.equal_width != self.normalized {
+            self.normalized = preset.equal_width;
+            self.apply_sequence_layout();
+        }
+        self.notifications.info(
+            "Preset applied",
+            format!("Applied preset '{}'", preset.name),
+        );
+    }
 
-                if response.drag_stopped() {
-                    // Zoom to box
-                    if let Some(end) = response.hover_pos() {
-                        self.zoom_to_box(rect, start, end);
-                    }
-                    self.box_zoom_start = None;
+    fn genome_control(&mut self, ui: &mut egui::Ui) {
+        let Some(ref groups) = self.genome_groups else {
+            return;
+        };
+        let count = groups.names.len();
+
+        ui.heading("Genomes");
+        ui.radio_value(
+            &mut self.genome_coloring,
+            GenomeColoring::Strand,
+            "Color by strand",
+        );
+        ui.radio_value(
+            &mut self.genome_coloring,
+            GenomeColoring::QueryGenome,
+            "Color by query genome",
+        );
+        ui.radio_value(
+            &mut self.genome_coloring,
+            GenomeColoring::TargetGenome,
+            "Color by target genome",
+        );
+        ui.radio_value(
+            &mut self.genome_coloring,
+            GenomeColoring::GenomePair,
+            "Color by genome pair (file)",
+        );
+        let show_swatches = matches!(
+            self.genome_coloring,
+            GenomeColoring::QueryGenome | GenomeColoring::TargetGenome
+        );
+        for (idx, name) in groups.names.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if show_swatches {
+                    let (swatch, _) =
+                        ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                    ui.painter()
+                        .rect_filled(swatch, 2.0, genome_color(idx, count));
                 }
+                ui.label(truncate_name(name, 30));
+            });
+        }
+    }
+
+    /// Colour of segment `idx` under the chosen genome colouring, if any
+    fn segment_genome_color(&self, idx: usize) -> Option<egui::Color32> {
+        let count = self.genome_groups.as_ref()?.names.len();
+        let (query, target) = (*self.segment_genomes.as_ref()?.get(idx)?)?;
+        match self.genome_coloring {
+            GenomeColoring::Strand => None,
+            GenomeColoring::QueryGenome => Some(genome_color(query, count)),
+            GenomeColoring::TargetGenome => Some(genome_color(target, count)),
+            GenomeColoring::GenomePair => Some(genome_color(query * count + target, count * count)),
+        }
+    }
+
+    /// Colour of segment `idx` when colouring by chain
+    fn segment_chain_color(&self, idx: usize) -> Option<egui::Color32> {
+        if self.segment_coloring != SegmentColoring::Chain {
+            return None;
+        }
+        let (ids, _) = self.chain_ids.as_ref()?;
+        Some(chain_color(*ids.get(idx)?))
+    }
+
+    /// Layer colours or chain colours, and how far apart chained alignments may be
+    fn segment_coloring_menu(&mut self, ui: &mut egui::Ui) {
+        ui.radio_value(
+            &mut self.segment_coloring,
+            SegmentColoring::Layers,
+            "By layer (strand or identity)",
+        );
+        ui.radio_value(
+            &mut self.segment_coloring,
+            SegmentColoring::Chain,
+            "By chain",
+        )
+        .on_hover_text(
+            "One colour per chain of collinear alignments, so the pieces of a \
+                 syntenic block match",
+        );
+        ui.add_enabled_ui(self.segment_coloring == SegmentColoring::Chain, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Max gap:");
+                let gap = egui::DragValue::new(&mut self.chain_gap)
+                    .range(0..=100_000_000)
+                    .speed(1_000)
+                    .suffix(" bp");
+                if ui.add(gap).changed() {
+                    self.chain_ids = None;
+                }
+            });
+            if let Some((_, count)) = self.chain_ids {
+                ui.label(format!("{count} chains"));
+            }
+        });
+        ui.radio_value(
+            &mut self.segment_coloring,
+            SegmentColoring::QuerySequence,
+            "By query sequence",
+        )
+        .on_hover_text(
+            "One colour per query sequence, listed in a side panel, so alignments \
+             translocated to another target sequence stand out",
+        );
+    }
+
+    /// Recolour every layer and the query sequences from `palette`
+    fn apply_palette(&mut self, palette: &'static Palette) {
+        self.palette = palette;
+        self.layers = std::mem::take(&mut self.layers)
+            .into_iter()
+            .map(|layer| layer.with_palette(palette))
+            .collect();
+    }
+
+    /// Colour of each query sequence, while alignments are coloured by them
+    fn query_color_legend(&mut self, ui: &mut egui::Ui) {
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.heading("Query colors");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui
+                    .small_button("✕")
+                    .on_hover_text("Color by layer")
+                    .clicked()
+                {
+                    self.segment_coloring = SegmentColoring::Layers;
+                }
+            });
+        });
+        let colors = self.palette.categories.len();
+        if plot.query_sequences.len() > colors {
+            ui.label(
+                egui::RichText::new(format!("Colours repeat every {colors} sequences"))
+                    .weak()
+                    .small(),
+            );
+        }
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (idx, name) in plot.query_sequences.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let (swatch, _) =
+                        ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                    ui.painter()
+                        .rect_filled(swatch, 2.0, rgb(self.palette.category(idx)));
+                    ui.label(truncate_name(name, 30)).on_hover_text(name);
+                });
+            }
+        });
+    }
+
+    fn kmer_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+
+        egui::Window::new("Quick k-mer dotplot")
+            .collapsible(false)
+            .resizable(false)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label("Plot k-mers that are unique and shared between two FASTA files");
+                ui.add_space(5.0);
+
+                egui::Grid::new("kmer_inputs")
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        ui.label("Query:");
+                        ui.label(fasta_label(self.kmer_query_fasta.as_ref()));
+                        if ui.button("Browse...").clicked() {
+                            if let Some(path) = pick_fasta("Select query FASTA") {
+                                self.kmer_query_fasta = Some(path);
+                            }
+                        }
+                        ui.end_row();
+
+                        ui.label("Target:");
+                        ui.label(fasta_label(self.kmer_target_fasta.as_ref()));
+                        if ui.button("Browse...").clicked() {
+                            if let Some(path) = pick_fasta("Select target FASTA") {
+                                self.kmer_target_fasta = Some(path);
+                            }
+                        }
+                        ui.end_row();
+                    });
+
+                ui.horizontal(|ui| {
+                    ui.label("K-mer size:");
+                    ui.add(egui::Slider::new(
+                        &mut self.kmer_size,
+                        8..=kmer_plot::MAX_KMER_SIZE,
+                    ));
+                });
+
+                ui.add_space(5.0);
+                let ready = self.kmer_query_fasta.is_some() && self.kmer_target_fasta.is_some();
+                if ui
+                    .add_enabled(ready, egui::Button::new("Build dotplot"))
+                    .clicked()
+                {
+                    if let (Some(query), Some(target)) = (
+                        self.kmer_query_fasta.clone(),
+                        self.kmer_target_fasta.clone(),
+                    ) {
+                        self.load_kmer_async(query, target, self.kmer_size);
+                        self.show_kmer_dialog = false;
+                    }
+                }
+            });
+
+        if !open {
+            self.show_kmer_dialog = false;
+        }
+    }
+
+    fn render_canvas(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        let (response, painter) =
+            ui.allocate_painter(ui.available_size(), egui::Sense::click_and_drag());
+        response.widget_info(|| {
+            egui::WidgetInfo::labeled(
+                egui::WidgetType::Other,
+                ui.is_enabled(),
+                "Alignment plot: arrow keys pan, Ctrl+plus and Ctrl+minus zoom",
+            )
+        });
+
+        let rect = response.rect;
+
+        // Track canvas size for zoom limits
+        self.last_canvas_size = (rect.width(), rect.height());
+
+        // Fit view to canvas on first render after loading
+        if self.needs_initial_fit && rect.width() > 0.0 && rect.height() > 0.0 {
+            self.fit_view_to_canvas(rect);
+            self.needs_initial_fit = false;
+        }
+
+        // Handle interaction
+        let pan_origin = (self.view.x, self.view.y);
+        self.handle_interaction(&response, rect);
+        let swipe_x = self.swipe_divider(ui, rect);
+        let minimap_rect = self.minimap_interaction(ui, rect);
+
+        if self.view_mode == ViewMode::Synteny {
+            self.hovered_segment = None;
+            self.render_synteny(&painter, rect);
+            return response;
+        }
+        if self.view_mode == ViewMode::Wrapped {
+            self.hovered_segment = None;
+            self.render_wrapped(&painter, rect);
+            return response;
+        }
+        if self.repeat_display != RepeatDisplay::Normal {
+            self.update_repeat_flags();
+        }
+        if self.show_cluster_hulls && self.hull_clusters.is_none() {
+            self.hull_clusters = self
+                .plot
+                .as_ref()
+                .map(|plot| plot.segment_clusters(rust_plot::DEFAULT_CLUSTER_MIN_SEGMENTS));
+        }
+        if self.segment_coloring == SegmentColoring::Chain && self.chain_ids.is_none() {
+            self.chain_ids = self
+                .plot
+                .as_ref()
+                .map(|plot| plot.chain_ids(self.chain_gap));
+        }
+        if self.show_identity_cells && self.pair_identity.is_none() {
+            self.pair_identity = self.plot.as_ref().map(|plot| plot.pair_identity());
+        }
+        self.density_active = self.show_density
+            || (self.auto_density && self.visible_segment_count(rect) > self.density_threshold);
+        if self.density_active {
+            self.update_density(ui.ctx(), rect);
+        }
+
+        // Genome to screen mapping using scale (bp/pixel)
+        let genome_to_screen = |gx: f64, gy: f64| -> egui::Pos2 {
+            let mut pixel_x = (gx - self.view.x) / self.view.scale;
+            let mut pixel_y = (gy - self.view.y) / self.view.scale;
+
+            // Below 1 bp/px, land every base on a whole pixel so lines don't shimmer
+            if self.view.scale < 1.0 {
+                pixel_x = pixel_x.round();
+                pixel_y = pixel_y.round();
+            }
+
+            egui::pos2(
+                rect.min.x + pixel_x as f32,
+                rect.max.y - pixel_y as f32, // Y is flipped
+            )
+        };
+
+        // Background - black like ALNVIEW
+        painter.rect_filled(rect, 0.0, egui::Color32::BLACK);
+
+        // Checkerboard of sequence pairs, so each cluster's contig pair is plain
+        if let (true, Some(plot)) = (self.shade_pair_cells, self.plot.as_ref()) {
+            let (x0, y0) = (self.view.x, self.view.y);
+            let x1 = x0 + rect.width() as f64 * self.view.scale;
+            let y1 = y0 + rect.height() as f64 * self.view.scale;
+            // Sequences i..j of `boundaries` overlapping [from, to]
+            let visible = |boundaries: &[i64], from: f64, to: f64| {
+                let count = boundaries.len().saturating_sub(1);
+                let first = boundaries[1..].partition_point(|&b| (b as f64) <= from);
+                let last = boundaries.partition_point(|&b| (b as f64) < to);
+                first.min(count)..last.min(count)
+            };
+            let queries = visible(&plot.query_boundaries, x0, x1);
+            let targets = visible(&plot.target_boundaries, y0, y1);
+            if queries.len() * targets.len() <= MAX_SHADED_CELLS {
+                let shade = egui::Color32::from_gray(28);
+                for q in queries {
+                    for t in targets.clone().filter(|t| (q + t) % 2 == 1) {
+                        let cell = egui::Rect::from_two_pos(
+                            genome_to_screen(
+                                plot.query_boundaries[q] as f64,
+                                plot.target_boundaries[t] as f64,
+                            ),
+                            genome_to_screen(
+                                plot.query_boundaries[q + 1] as f64,
+                                plot.target_boundaries[t + 1] as f64,
+                            ),
+                        );
+                        painter.rect_filled(cell.intersect(rect), 0.0, shade);
+                    }
+                }
+            }
+        }
+
+        // Sequence-pair cells tinted by mean identity, under everything else
+        if let (true, Some(plot), Some(identity)) = (
+            self.show_identity_cells,
+            self.plot.as_ref(),
+            self.pair_identity.as_ref(),
+        ) {
+            for (&(q, t), &id) in identity {
+                let cell = egui::Rect::from_two_pos(
+                    genome_to_screen(
+                        plot.query_boundaries[q] as f64,
+                        plot.target_boundaries[t] as f64,
+                    ),
+                    genome_to_screen(
+                        plot.query_boundaries[q + 1] as f64,
+                        plot.target_boundaries[t + 1] as f64,
+                    ),
+                );
+                if rect.intersects(cell) {
+                    painter.rect_filled(cell.intersect(rect), 0.0, identity_color(id));
+                }
+            }
+        }
+
+        // Draw genome boundaries and scaffold lines
+        if let Some(ref plot) = self.plot {
+            let alen = plot.get_alen() as f64;
+            let blen = plot.get_blen() as f64;
+
+            // Calculate visible genome region
+            let view_width = rect.width() as f64 * self.view.scale;
+            let view_height = rect.height() as f64 * self.view.scale;
+
+            // Contig boundaries inside AGP scaffolds, thinner and dimmer
+            if let Some(ref contigs) = self.contig_boundaries {
+                let stroke = egui::Stroke::new(0.5, egui::Color32::from_gray(70));
+                let (query, target) = contigs.for_plot(plot);
+                for x in query.into_iter().map(|pos| pos as f64) {
+                    if x >= self.view.x && x <= self.view.x + view_width {
+                        painter.vline(genome_to_screen(x, 0.0).x, rect.y_range(), stroke);
+                    }
+                }
+                for y in target.into_iter().map(|pos| pos as f64) {
+                    if y >= self.view.y && y <= self.view.y + view_height {
+                        painter.hline(rect.x_range(), genome_to_screen(0.0, y).y, stroke);
+                    }
+                }
+            }
+
+            // Sequence boundaries, solid once too many are in view to tell apart
+            let styles = self.boundary_styles;
+            let visible = |boundaries: Vec<i64>, from: f64, to: f64| -> Vec<f64> {
+                boundaries
+                    .into_iter()
+                    .map(|pos| pos as f64)
+                    .filter(|&pos| pos >= from && pos <= to)
+                    .collect()
+            };
+            let xs = visible(
+                plot.get_scaffold_boundaries(0),
+                self.view.x,
+                self.view.x + view_width,
+            );
+            let ys = visible(
+                plot.get_scaffold_boundaries(1),
+                self.view.y,
+                self.view.y + view_height,
+            );
+            let style = |count: usize| {
+                if count > MAX_DASHED_LINES {
+                    styles.scaffold.solid()
+                } else {
+                    styles.scaffold
+                }
+            };
+            let (x_style, y_style) = (style(xs.len()), style(ys.len()));
+            for x in xs {
+                x_style.vline(&painter, genome_to_screen(x, 0.0).x, rect);
+            }
+            for y in ys {
+                y_style.hline(&painter, genome_to_screen(0.0, y).y, rect);
+            }
+
+            // Genome blocks of an all-vs-all plot, with thicker separators
+            if let Some(ref groups) = self.genome_groups {
+                let stroke = egui::Stroke::new(3.0, egui::Color32::from_rgb(170, 170, 200));
+                let font = egui::FontId::proportional(13.0);
+                let label_color = egui::Color32::from_rgb(200, 200, 230);
+                for (genome, start, end) in
+                    groups.runs(&plot.query_sequences, &plot.query_boundaries)
+                {
+                    let (x0, x1) = (
+                        genome_to_screen(start as f64, 0.0).x,
+                        genome_to_screen(end as f64, 0.0).x,
+                    );
+                    if x1 < rect.min.x || x0 > rect.max.x {
+                        continue;
+                    }
+                    if start > 0 {
+                        painter.vline(x0, rect.y_range(), stroke);
+                    }
+                    let x = ((x0.max(rect.min.x) + x1.min(rect.max.x)) / 2.0)
+                        .clamp(rect.min.x, rect.max.x);
+                    painter.text(
+                        egui::pos2(x, rect.min.y + 4.0),
+                        egui::Align2::CENTER_TOP,
+                        &groups.names[genome],
+                        font.clone(),
+                        label_color,
+                    );
+                }
+                for (genome, start, end) in
+                    groups.runs(&plot.target_sequences, &plot.target_boundaries)
+                {
+                    let (y0, y1) = (
+                        genome_to_screen(0.0, start as f64).y,
+                        genome_to_screen(0.0, end as f64).y,
+                    );
+                    if y0 < rect.min.y || y1 > rect.max.y {
+                        continue;
+                    }
+                    if start > 0 {
+                        painter.hline(rect.x_range(), y0, stroke);
+                    }
+                    let y = ((y1.max(rect.min.y) + y0.min(rect.max.y)) / 2.0)
+                        .clamp(rect.min.y, rect.max.y);
+                    painter.text(
+                        egui::pos2(rect.min.x + 4.0, y),
+                        egui::Align2::LEFT_CENTER,
+                        &groups.names[genome],
+                        font.clone(),
+                        label_color,
+                    );
+                }
+            }
+
+            // Draw genome end boundaries (thicker)
+            if alen >= self.view.x && alen <= self.view.x + view_width {
+                let x_pos = genome_to_screen(alen, 0.0).x;
+                styles.query_end.vline(&painter, x_pos, rect);
+            }
+
+            if blen >= self.view.y && blen <= self.view.y + view_height {
+                let y_pos = genome_to_screen(0.0, blen).y;
+                styles.target_end.hline(&painter, y_pos, rect);
+            }
+
+            // Draw axes at origin
+            if self.view.x <= 0.0 && self.view.x + view_width >= 0.0 {
+                let x_pos = genome_to_screen(0.0, 0.0).x;
+                styles.origin.vline(&painter, x_pos, rect);
+            }
+            if self.view.y <= 0.0 && self.view.y + view_height >= 0.0 {
+                let y_pos = genome_to_screen(0.0, 0.0).y;
+                styles.origin.hline(&painter, y_pos, rect);
+            }
+        }
+
+        // Per-base grid once bases are several pixels wide
+        if self.show_bp_grid && self.view.scale <= BP_GRID_MAX_SCALE {
+            let grid_stroke = egui::Stroke::new(1.0, egui::Color32::from_gray(35));
+            let first_x = self.view.x.ceil();
+            let last_x = self.view.x + rect.width() as f64 * self.view.scale;
+            let mut gx = first_x;
+            while gx <= last_x {
+                painter.vline(genome_to_screen(gx, 0.0).x, rect.y_range(), grid_stroke);
+                gx += 1.0;
+            }
+            let first_y = self.view.y.ceil();
+            let last_y = self.view.y + rect.height() as f64 * self.view.scale;
+            let mut gy = first_y;
+            while gy <= last_y {
+                painter.hline(rect.x_range(), genome_to_screen(0.0, gy).y, grid_stroke);
+                gy += 1.0;
+            }
+        }
+
+        // Genome of every segment, for colouring an all-vs-all plot
+        if self.genome_coloring != GenomeColoring::Strand && self.segment_genomes.is_none() {
+            if let (Some(plot), Some(groups)) = (&self.plot, &self.genome_groups) {
+                self.segment_genomes = Some(groups.segment_genomes(plot));
+            }
+        }
+
+        // Draw alignment segments for each visible layer
+        self.last_visible_segments = 0;
+        let repeats = self
+            .repetitive
+            .as_ref()
+            .filter(|_| self.repeat_display != RepeatDisplay::Normal)
+            .map(|(_, flags)| flags);
+        let presence_overlay = self
+            .comparison
+            .as_ref()
+            .filter(|_| self.color_by_presence && swipe_x.is_none());
+        // In swipe mode the first file is drawn left of the divider only
+        let first_painter = match swipe_x {
+            Some(x) => {
+                painter.with_clip_rect(egui::Rect::from_x_y_ranges(rect.min.x..=x, rect.y_range()))
+            }
+            None => painter.clone(),
+        };
+        let store_detail = self.store_viewport_segments(
+            rect.width() as f64 * self.view.scale,
+            rect.height() as f64 * self.view.scale,
+        );
+        if self.density_active {
+            self.draw_density(&first_painter, rect);
+        } else if let Some(ref plot) = self.plot {
+            for (layer_idx, layer_settings) in self.layers.iter().enumerate() {
+                if !layer_settings.visible || layer_idx >= self.num_layers {
+                    continue;
+                }
+
+                // Calculate visible genome region based on canvas size and scale
+                let view_width = rect.width() as f64 * self.view.scale;
+                let view_height = rect.height() as f64 * self.view.scale;
+
+                // Query R*-tree for segments in visible region, or read them
+                // from the segment store (no index into the plot, so no
+                // per-segment overlays) when zoomed in on a store-backed plot
+                let visible_segs: Vec<(Option<usize>, &AlignmentSegment)> = match store_detail {
+                    Some(ref segments) => segments.iter().map(|seg| (None, seg)).collect(),
+                    None => plot
+                        .query_segment_indices_in_region(
+                            self.view.x,
+                            self.view.y,
+                            view_width,
+                            view_height,
+                        )
+                        .into_iter()
+                        .map(|idx| (Some(idx), &plot.segments[idx]))
+                        .collect(),
+                };
+
+                // Draw visible segments that pass this layer's filters
+                let filter = layer_settings.filter;
+                self.last_visible_segments += visible_segs.len();
+                for (idx, seg) in visible_segs {
+                    if !layer_settings.shows_strand(seg.reverse)
+                        || (filter.is_active() && !filter.matches(seg))
+                    {
+                        continue;
+                    }
+
+                    // Draw the segment as a line
+                    let p1 = genome_to_screen(seg.abeg as f64, seg.bbeg as f64);
+                    let p2 = genome_to_screen(seg.aend as f64, seg.bend as f64);
+
+                    // Green for forward, red for reverse (like the C version)
+                    // unless the layer colours by identity
+                    let color = if let (Some(comparison), Some(idx)) = (presence_overlay, idx) {
+                        presence_color(comparison.first_presence(idx))
+                    } else if let Some(color) = idx.and_then(|idx| self.segment_genome_color(idx)) {
+                        color
+                    } else if let Some(color) = idx.and_then(|idx| self.segment_chain_color(idx)) {
+                        color
+                    } else if self.segment_coloring == SegmentColoring::QuerySequence {
+                        rgb(self.palette.category(plot.segment_sequences(seg).0))
+                    } else {
+                        layer_settings.segment_color(seg, self.high_contrast)
+                    };
+                    let color = match (repeats, idx) {
+                        (Some(flags), Some(idx)) if flags.get(idx).copied().unwrap_or(false) => {
+                            repeat_color(color, self.repeat_display)
+                        }
+                        _ => color,
+                    }
+                    .gamma_multiply(layer_settings.opacity);
+                    let width = layer_settings.segment_width(seg);
+
+                    if self.length_scaled_dots && p1.distance(p2) < LENGTH_DOT_MAX_PX {
+                        let (radius, brightness) = length_dot(seg.aend - seg.abeg);
+                        first_painter.circle_filled(
+                            p1.lerp(p2, 0.5),
+                            radius.max(width / 2.0),
+                            color.gamma_multiply(brightness),
+                        );
+                    } else if self.dot_tiny_segments && p1.distance(p2) < 1.0 {
+                        let radius = (width / 2.0).max(MIN_DOT_RADIUS_PX);
+                        first_painter.circle_filled(p1.lerp(p2, 0.5), radius, color);
+                    } else {
+                        first_painter.line_segment([p1, p2], egui::Stroke::new(width, color));
+                    }
+                }
+            }
+
+            // Segments only the comparison file has
+            if let Some(comparison) = presence_overlay {
+                let view_width = rect.width() as f64 * self.view.scale;
+                let view_height = rect.height() as f64 * self.view.scale;
+                let (x_min, x_max) = (self.view.x as i64, (self.view.x + view_width) as i64);
+                let (y_min, y_max) = (self.view.y as i64, (self.view.y + view_height) as i64);
+                let color = presence_color(Presence::OnlySecond);
+
+                for (seg, _) in comparison
+                    .segments
+                    .iter()
+                    .zip(&comparison.second_shared)
+                    .filter(|(_, &shared)| !shared)
+                {
+                    if seg.intersects(x_min, x_max, y_min, y_max) {
+                        let p1 = genome_to_screen(seg.abeg as f64, seg.bbeg as f64);
+                        let p2 = genome_to_screen(seg.aend as f64, seg.bend as f64);
+                        painter.line_segment([p1, p2], egui::Stroke::new(1.0, color));
+                    }
+                }
+            }
+        }
+
+        // The second file right of the swipe divider
+        if let (Some(x), Some(comparison)) = (swipe_x, self.comparison.as_ref()) {
+            self.draw_swipe_second(&painter, rect, x, comparison, genome_to_screen);
+        }
+
+        // Alignments picked with Shift+Ctrl+drag
+        if let Some(ref plot) = self.plot {
+            let stroke = egui::Stroke::new(2.5, egui::Color32::from_rgb(255, 120, 255));
+            for seg in self
+                .selected_segments
+                .iter()
+                .filter_map(|&i| plot.segments.get(i))
+            {
+                painter.line_segment(
+                    [
+                        genome_to_screen(seg.abeg as f64, seg.bbeg as f64),
+                        genome_to_screen(seg.aend as f64, seg.bend as f64),
+                    ],
+                    stroke,
+                );
+            }
+        }
+
+        // Highlight the clicked segment, then the one under the cursor
+        if let (Some(ref plot), Some(idx)) = (&self.plot, self.selected_segment) {
+            if let Some(seg) = plot.segments.get(idx) {
+                painter.line_segment(
+                    [
+                        genome_to_screen(seg.abeg as f64, seg.bbeg as f64),
+                        genome_to_screen(seg.aend as f64, seg.bend as f64),
+                    ],
+                    egui::Stroke::new(3.0, egui::Color32::from_rgb(0, 220, 255)),
+                );
+            }
+        }
+        if let (Some(ref plot), Some(idx)) = (&self.plot, self.hovered_segment) {
+            if let Some(seg) = plot.segments.get(idx) {
+                painter.line_segment(
+                    [
+                        genome_to_screen(seg.abeg as f64, seg.bbeg as f64),
+                        genome_to_screen(seg.aend as f64, seg.bend as f64),
+                    ],
+                    egui::Stroke::new(3.0, egui::Color32::YELLOW),
+                );
+            }
+        }
+
+        // Translucent hulls around groups of nearby alignments
+        if let (true, Some(clusters)) = (self.show_cluster_hulls, self.hull_clusters.as_ref()) {
+            draw_cluster_hulls(&painter, clusters, genome_to_screen);
+        }
+
+        // Alignments of the sequence picked from an axis band
+        if let (Some(ref plot), Some((_, _, ref report))) = (&self.plot, &self.sequence_report) {
+            let view_width = rect.width() as f64 * self.view.scale;
+            let view_height = rect.height() as f64 * self.view.scale;
+            let (x_min, x_max) = (self.view.x as i64, (self.view.x + view_width) as i64);
+            let (y_min, y_max) = (self.view.y as i64, (self.view.y + view_height) as i64);
+            let stroke = egui::Stroke::new(2.0, egui::Color32::from_rgb(255, 215, 0));
+            for seg in report.segments.iter().filter_map(|&i| plot.segments.get(i)) {
+                if seg.intersects(x_min, x_max, y_min, y_max) {
+                    painter.line_segment(
+                        [
+                            genome_to_screen(seg.abeg as f64, seg.bbeg as f64),
+                            genome_to_screen(seg.aend as f64, seg.bend as f64),
+                        ],
+                        stroke,
+                    );
+                }
+            }
+        }
+
+        draw_regions(&painter, &self.regions, genome_to_screen);
+        draw_tags(&painter, &self.tags, genome_to_screen);
+        draw_annotations(&painter, &self.annotations, genome_to_screen);
+        if self.measuring {
+            draw_ruler(&painter, &self.ruler, genome_to_screen);
+        }
+
+        // Figure title, as it will appear on exports
+        let mut pos = rect.left_top() + egui::vec2(8.0, 6.0);
+        if !self.plot_title.is_empty() || !self.plot_subtitle.is_empty() {
+            for (text, size, color) in [
+                (&self.plot_title, 18.0, egui::Color32::WHITE),
+                (&self.plot_subtitle, 13.0, egui::Color32::LIGHT_GRAY),
+            ] {
+                if text.is_empty() {
+                    continue;
+                }
+                let galley =
+                    painter.layout_no_wrap(text.clone(), egui::FontId::proportional(size), color);
+                let height = galley.size().y;
+                painter.rect_filled(
+                    egui::Rect::from_min_size(pos, galley.size()).expand(3.0),
+                    3.0,
+                    egui::Color32::from_black_alpha(160),
+                );
+                painter.galley(pos, galley, color);
+                pos.y += height + 6.0;
+            }
+        }
+
+        if self.show_scale_bar && !self.normalized {
+            draw_scale_bar(&painter, rect, self.view.scale);
+        }
+
+        let gradients: Vec<&LayerSettings> = self
+            .layers
+            .iter()
+            .take(self.num_layers)
+            .filter(|layer| layer.visible && layer.color_by_identity)
+            .collect();
+        if !gradients.is_empty() && self.view_mode == ViewMode::DotPlot {
+            draw_identity_legend(&painter, rect, &gradients);
+        }
+
+        // Coverage depth along both axes
+        if self.show_coverage {
+            self.draw_coverage_tracks(&painter, rect);
+        }
+
+        // GC content strips, inside the coverage tracks if those are shown
+        if self.show_gc_tracks {
+            self.draw_gc_tracks(&painter, rect);
+        }
+
+        // Names of sequences scrolled partly out of view, under the title
+        if self.sticky_labels {
+            self.draw_sticky_labels(&painter, rect, pos.y);
+        }
+
+        // Draw border
+        painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui::Color32::GRAY));
+
+        // Draw scale/axes
+        self.draw_axes(ui, &painter, rect);
+        if let Some(minimap_rect) = minimap_rect {
+            self.draw_minimap(&painter, rect, minimap_rect);
+        }
+
+        // Update cursor position info (displayed in layers panel)
+        self.hovered_segment = None;
+        self.cursor_on_canvas = false;
+        if let Some(ref plot) = self.plot {
+            if let Some(hover_pos) = response.hover_pos() {
+                self.cursor_on_canvas = true;
+                if self.show_crosshair {
+                    let stroke = egui::Stroke::new(1.0, egui::Color32::from_white_alpha(90));
+                    painter.hline(rect.x_range(), hover_pos.y, stroke);
+                    painter.vline(hover_pos.x, rect.y_range(), stroke);
+                }
+
+                // Convert screen position to genome coordinates
+                let pixel_x = (hover_pos.x - rect.min.x) as f64;
+                let pixel_y = (rect.max.y - hover_pos.y) as f64;
+
+                let genome_x = self.view.x + pixel_x * self.view.scale;
+                let genome_y = self.view.y + pixel_y * self.view.scale;
+
+                // Get sequence info
+                let (_query_idx, query_name, query_local) =
+                    plot.query_coord_to_sequence(genome_x as i64);
+                let (_target_idx, target_name, target_local) =
+                    plot.target_coord_to_sequence(genome_y as i64);
+
+                // Update cursor info fields (displayed in layers panel)
+                self.cursor_query_name = query_name;
+                self.cursor_query_pos = query_local;
+                self.cursor_target_name = target_name;
+                self.cursor_target_pos = target_local;
+                self.cursor_genome_x = genome_x;
+                self.cursor_genome_y = genome_y;
+
+                // Segment under the cursor (summarised in the status bar)
+                self.hovered_segment =
+                    plot.segment_at(genome_x, genome_y, HOVER_TOLERANCE_PX * self.view.scale);
+            }
+        }
+
+        // Sequence labels: click hides a sequence, Shift-click isolates it
+        let labels = self.sequence_labels(&painter, rect);
+        let hovered = response
+            .hover_pos()
+            .and_then(|pos| labels.iter().position(|label| label.rect.contains(pos)));
+        draw_sequence_labels(&painter, &labels, hovered);
+
+        // Dragging a name moves its sequence along the axis, not the view
+        if response.drag_started() {
+            let press = ui.input(|i| i.pointer.press_origin());
+            if let Some(label) = press.and_then(|pos| labels.iter().find(|l| l.rect.contains(pos)))
+            {
+                (self.view.x, self.view.y) = pan_origin;
+                self.drag_action = None;
+                self.box_zoom_start = None;
+                self.label_drag = Some((label.axis, label.name.clone()));
+            }
+        }
+        if let Some((axis, name)) = self.label_drag.clone() {
+            let drop = response
+                .hover_pos()
+                .and_then(|pos| self.sequence_drop(rect, axis, pos));
+            if let Some((_, edge, _)) = drop {
+                let stroke = egui::Stroke::new(3.0, ui.visuals().selection.bg_fill);
+                match axis {
+                    Axis::Query => painter.vline(edge, rect.y_range(), stroke),
+                    Axis::Target => painter.hline(rect.x_range(), edge, stroke),
+                };
+            }
+            if let Some(pos) = response.hover_pos() {
+                painter.text(
+                    pos + egui::vec2(12.0, -12.0),
+                    egui::Align2::LEFT_BOTTOM,
+                    extract_display_name(&name, 28),
+                    egui::FontId::proportional(13.0),
+                    egui::Color32::WHITE,
+                );
+            }
+            if response.drag_stopped() {
+                self.label_drag = None;
+                if let Some((target, _, after)) = drop {
+                    self.drop_sequence(axis, &name, &target, after);
+                }
+            }
+        }
+        if self.measuring && self.view_mode == ViewMode::DotPlot {
+            // A third click starts a new measurement
+            if response.hovered() {
+                ui.ctx().set_cursor_icon(egui::CursorIcon::Crosshair);
+            }
+            if let (true, Some(pos)) = (response.clicked(), response.interact_pointer_pos()) {
+                if self.ruler.len() == 2 {
+                    self.ruler.clear();
+                }
+                self.ruler.push(self.screen_to_genome(rect, pos));
+            }
+        } else if let Some(idx) = hovered {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+            if response.clicked() {
+                let isolate = ui.input(|i| i.modifiers.shift);
+                let label = &labels[idx];
+                self.toggle_sequence(label.axis, label.name.clone(), isolate);
+            }
+        } else if response.clicked() && self.hovered_segment.is_some() {
+            self.selected_segment = self.hovered_segment;
+        } else if response.clicked() {
+            self.selected_segment = None;
+            // Clicking an axis band away from any segment reports that sequence
+            if let (Some(pos), Some(ref plot)) = (response.interact_pointer_pos(), &self.plot) {
+                let (x, y) = self.screen_to_genome(rect, pos);
+                let band = if pos.y >= rect.max.y - SEQUENCE_BAND_PX {
+                    Some((Axis::Query, plot.query_coord_to_sequence(x as i64).0))
+                } else if pos.x <= rect.min.x + SEQUENCE_BAND_PX {
+                    Some((Axis::Target, plot.target_coord_to_sequence(y as i64).0))
+                } else {
+                    None
+                };
+                self.sequence_report = band.map(|(axis, idx)| {
+                    let genome = if axis == Axis::Query { 0 } else { 1 };
+                    (axis, idx, plot.sequence_report(genome, idx))
+                });
+            }
+        }
+
+        // Right-click menu for placing and editing annotations
+        if response.secondary_clicked() {
+            self.context_pos = response
+                .interact_pointer_pos()
+                .map(|pos| self.screen_to_genome(rect, pos));
+            self.context_segment = self.hovered_segment;
+            // A sequence's name label or its band along the axis
+            self.context_sequence = match (hovered, response.interact_pointer_pos(), &self.plot) {
+                (Some(idx), _, _) => Some((labels[idx].axis, labels[idx].name.clone())),
+                (None, Some(pos), Some(plot)) => {
+                    let (x, y) = self.screen_to_genome(rect, pos);
+                    if pos.y >= rect.max.y - SEQUENCE_BAND_PX {
+                        Some((Axis::Query, plot.query_coord_to_sequence(x as i64).1))
+                    } else if pos.x <= rect.min.x + SEQUENCE_BAND_PX {
+                        Some((Axis::Target, plot.target_coord_to_sequence(y as i64).1))
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            };
+        }
+        response.context_menu(|ui| self.canvas_context_menu(ui));
+        response
+    }
+
+    fn canvas_context_menu(&mut self, ui: &mut egui::Ui) {
+        let Some((x, y)) = self.context_pos else {
+            ui.close_menu();
+            return;
+        };
+        let pick = ANNOTATION_PICK_PX as f64 * self.view.scale;
+        let near = self
+            .annotations
+            .iter()
+            .position(|a| (a.x - x).abs() <= pick && (a.y - y).abs() <= pick);
+
+        if let Some((axis, name)) = self.context_sequence.clone() {
+            let flipped = match axis {
+                Axis::Query => &self.flipped_query,
+                Axis::Target => &self.flipped_target,
+            };
+            let label = if flipped.contains(&name) {
+                format!(
+                    "⇄ Restore orientation of {}",
+                    extract_display_name(&name, 28)
+                )
+            } else {
+                format!("⇄ Flip orientation of {}", extract_display_name(&name, 28))
+            };
+            if ui
+                .button(label)
+                .on_hover_text(
+                    "Reverse-complement this sequence, so its alignments run the other way",
+                )
+                .clicked()
+            {
+                self.flip_sequence(axis, name);
+                ui.close_menu();
+            }
+            ui.separator();
+        }
+        if ui.button("📝 Add annotation here").clicked() {
+            self.annotation_edit = Some((
+                None,
+                Annotation {
+                    x,
+                    y,
+                    text: String::new(),
+                    arrow: true,
+                },
+            ));
+            ui.close_menu();
+        }
+        ui.separator();
+        let (width, height) = self.last_canvas_size;
+        let (view_x, view_y) = (self.view.x, self.view.y);
+        if ui
+            .button("🔎 Where does the visible query range align?")
+            .clicked()
+        {
+            let end = view_x + width as f64 * self.view.scale;
+            self.query_partners(Axis::Query, view_x as i64, end as i64);
+            ui.close_menu();
+        }
+        if ui
+            .button("🔎 Where does the visible target range align?")
+            .clicked()
+        {
+            let end = view_y + height as f64 * self.view.scale;
+            self.query_partners(Axis::Target, view_y as i64, end as i64);
+            ui.close_menu();
+        }
+        if let (Some(ref plot), Some(idx)) = (&self.plot, self.context_segment) {
+            if let Some(seg) = plot.segments.get(idx) {
+                ui.separator();
+                let tagged = self.tags.iter().position(|tag| tag.is_on(seg));
+                let label = if tagged.is_some() {
+                    "🏷 Edit alignment tag"
+                } else {
+                    "🏷 Tag this alignment..."
+                };
+                if ui.button(label).clicked() {
+                    self.tag_edit = Some(match tagged {
+                        Some(idx) => (Some(idx), self.tags[idx].clone()),
+                        None => (None, Tag::on_segment(seg)),
+                    });
+                    ui.close_menu();
+                }
+            }
+        }
+        if let Some(idx) = near {
+            ui.separator();
+            if ui.button("✏ Edit annotation").clicked() {
+                self.annotation_edit = Some((Some(idx), self.annotations[idx].clone()));
+                ui.close_menu();
+            }
+            if ui.button("🗑 Delete annotation").clicked() {
+                self.annotations.remove(idx);
+                ui.close_menu();
+            }
+        }
+    }
+
+    fn annotation_dialog(&mut self, ctx: &egui::Context) {
+        let Some((index, ref mut draft)) = self.annotation_edit else {
+            return;
+        };
+        let mut open = true;
+        let mut done = None;
+
+        egui::Window::new(if index.is_some() {
+            "Edit annotation"
+        } else {
+            "Add annotation"
+        })
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label(format!("At x = {:.0}, y = {:.0}", draft.x, draft.y));
+            let text = ui.add(
+                egui::TextEdit::multiline(&mut draft.text)
+                    .desired_rows(2)
+                    .hint_text("Label text"),
+            );
+            if index.is_none() && !text.has_focus() && draft.text.is_empty() {
+                text.request_focus();
+            }
+            ui.checkbox(&mut draft.arrow, "Arrow pointing at the position");
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!draft.text.trim().is_empty(), egui::Button::new("OK"))
+                    .clicked()
+                {
+                    done = Some(true);
+                }
+                if ui.button("Cancel").clicked() {
+                    done = Some(false);
+                }
+            });
+        });
+
+        if !open || done == Some(false) {
+            self.annotation_edit = None;
+        } else if done == Some(true) {
+            if let Some((index, draft)) = self.annotation_edit.take() {
+                match index {
+                    Some(idx) if idx < self.annotations.len() => self.annotations[idx] = draft,
+                    _ => self.annotations.push(draft),
+                }
+            }
+        }
+    }
+
+    fn tag_dialog(&mut self, ctx: &egui::Context) {
+        let Some((index, ref mut draft)) = self.tag_edit else {
+            return;
+        };
+        let mut open = true;
+        let mut done = None;
+
+        let title = match (index, draft.target) {
+            (Some(_), _) => "Edit tag",
+            (None, TagTarget::Segment) => "Tag alignment",
+            (None, TagTarget::Region) => "Tag region",
+        };
+        egui::Window::new(title)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if let Some(ref plot) = self.plot {
+                    let describe = |genome, start, end| {
+                        let names = if genome == 0 {
+                            &plot.query_sequences
+                        } else {
+                            &plot.target_sequences
+                        };
+                        match plot.sequence_intervals(genome, start, end)[..] {
+                            [(idx, lo, hi)] => format!("{}:{lo}-{hi}", names[idx]),
+                            ref pieces => format!("{} sequences", pieces.len()),
+                        }
+                    };
+                    ui.label(format!(
+                        "{} vs {}",
+                        describe(0, draft.x_min, draft.x_max),
+                        describe(1, draft.y_min, draft.y_max)
+                    ));
+                }
+                ui.horizontal(|ui| {
+                    let text = ui.add(
+                        egui::TextEdit::singleline(&mut draft.tag)
+                            .desired_width(180.0)
+                            .hint_text("Tag"),
+                    );
+                    if index.is_none() && !text.has_focus() && draft.tag.is_empty() {
+                        text.request_focus();
+                    }
+                    egui::ComboBox::from_id_source("tag_suggestions")
+                        .selected_text("")
+                        .width(20.0)
+                        .show_ui(ui, |ui| {
+                            for suggestion in tags::TAG_SUGGESTIONS {
+                                if ui
+                                    .selectable_label(draft.tag == suggestion, suggestion)
+                                    .clicked()
+                                {
+                                    draft.tag = suggestion.to_string();
+                                }
+                            }
+                        });
+                });
+                ui.add(
+                    egui::TextEdit::multiline(&mut draft.note)
+                        .desired_rows(3)
+                        .hint_text("Notes"),
+                );
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!draft.tag.trim().is_empty(), egui::Button::new("OK"))
+                        .clicked()
+                    {
+                        done = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        done = Some(false);
+                    }
+                });
+            });
+
+        if !open || done == Some(false) {
+            self.tag_edit = None;
+        } else if done == Some(true) {
+            if let Some((index, draft)) = self.tag_edit.take() {
+                match index {
+                    Some(idx) if idx < self.tags.len() => self.tags[idx] = draft,
+                    _ => self.tags.push(draft),
+                }
+                self.show_region_panel = true;
+            }
+        }
+    }
+
+    /// List where [start, end) of one axis (genome coordinates) aligns on the other
+    fn query_partners(&mut self, axis: Axis, start: i64, end: i64) {
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        let (genome, names) = match axis {
+            Axis::Query => (0, &plot.query_sequences),
+            Axis::Target => (1, &plot.target_sequences),
+        };
+        let pieces = plot.sequence_intervals(genome, start, end);
+        let label = match pieces[..] {
+            [] => return,
+            [(idx, lo, hi)] => format!("{}:{lo}-{hi}", names[idx]),
+            [(first, ..), .., (last, ..)] => format!(
+                "{} .. {} ({} sequences)",
+                names[first],
+                names[last],
+                pieces.len()
+            ),
+        };
+        self.partner_query = Some(PartnerQuery {
+            axis,
+            label,
+            partners: plot.partner_intervals(
+                genome,
+                start,
+                end,
+                rust_plot::DEFAULT_PARTNER_MERGE_GAP,
+            ),
+        });
+    }
+
+    /// Table of the partner intervals found by `query_partners`
+    fn partners_window(&mut self, ctx: &egui::Context) {
+        let (Some(plot), Some(query)) = (self.plot.as_ref(), self.partner_query.as_ref()) else {
+            return;
+        };
+        let partner_names = match query.axis {
+            Axis::Query => &plot.target_sequences,
+            Axis::Target => &plot.query_sequences,
+        };
+        let normalized = self.normalized;
+        let mut open = true;
+
+        egui::Window::new("Where does this region align?")
+            .open(&mut open)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} aligns to {} intervals",
+                    query.label,
+                    query.partners.len()
+                ));
+                if query.partners.is_empty() {
+                    return;
+                }
+                if ui.button("📋 Copy as TSV").clicked() {
+                    let mut text =
+                        String::from("sequence\tstart\tend\taligned_bp\tidentity\talignments\n");
+                    for p in &query.partners {
+                        text.push_str(&format!(
+                            "{}\t{}\t{}\t{}\t{:.2}\t{}\n",
+                            partner_names[p.seq],
+                            p.start,
+                            p.end,
+                            p.aligned_bp,
+                            p.identity,
+                            p.alignments
+                        ));
+                    }
+                    ui.ctx().copy_text(text);
+                }
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("partners_grid")
+                            .num_columns(5)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.strong("Sequence");
+                                ui.strong("Interval");
+                                ui.strong("Aligned");
+                                ui.strong("Identity");
+                                ui.strong("Alignments");
+                                ui.end_row();
+                                for p in &query.partners {
+                                    ui.label(extract_display_name(&partner_names[p.seq], 30));
+                                    ui.label(format!(
+                                        "{}-{}",
+                                        format_extent(p.start, normalized),
+                                        format_extent(p.end, normalized)
+                                    ));
+                                    ui.label(format_extent(p.aligned_bp, normalized));
+                                    ui.label(format!("{:.1}%", p.identity));
+                                    ui.label(p.alignments.to_string());
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+
+        if !open {
+            self.partner_query = None;
+        }
+    }
+
+    /// Summary of the alignments involving the sequence picked from an axis band
+    fn sequence_report_window(&mut self, ctx: &egui::Context) {
+        let (Some(plot), Some(&(axis, idx, ref report))) =
+            (self.plot.as_ref(), self.sequence_report.as_ref())
+        else {
+            return;
+        };
+        let (names, lengths, partner_names) = match axis {
+            Axis::Query => (
+                &plot.query_sequences,
+                &plot.query_lengths,
+                &plot.target_sequences,
+            ),
+            Axis::Target => (
+                &plot.target_sequences,
+                &plot.target_lengths,
+                &plot.query_sequences,
+            ),
+        };
+        let normalized = self.normalized;
+        let mut open = true;
+
+        egui::Window::new("Sequence report")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                ui.heading(&names[idx]);
+                egui::Grid::new("sequence_report_grid")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Length");
+                        ui.label(format_extent(lengths[idx], normalized));
+                        ui.end_row();
+                        ui.label("Alignments");
+                        ui.label(report.segments.len().to_string());
+                        ui.end_row();
+                        ui.label("Aligned");
+                        ui.label(format_extent(report.aligned_bp, normalized));
+                        ui.end_row();
+                        ui.label("Partners");
+                        ui.label(report.partners.len().to_string());
+                        ui.end_row();
+                    });
+
+                if !report.partners.is_empty() {
+                    ui.separator();
+                    egui::ScrollArea::vertical()
+                        .max_height(240.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("sequence_partners_grid")
+                                .num_columns(3)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    ui.strong("Partner");
+                                    ui.strong("Alignments");
+                                    ui.strong("Aligned");
+                                    ui.end_row();
+                                    for &(partner, count, bp) in &report.partners {
+                                        ui.label(extract_display_name(&partner_names[partner], 30));
+                                        ui.label(count.to_string());
+                                        ui.label(format_extent(bp, normalized));
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                }
+            });
+
+        if !open {
+            self.sequence_report = None;
+        }
+    }
+
+    /// Lay out the names of sequences wide enough on screen to be labelled
+    fn sequence_labels(&self, painter: &egui::Painter, rect: egui::Rect) -> Vec<SequenceLabel> {
+        let Some(ref plot) = self.plot else {
+            return Vec::new();
+        };
+        let font = egui::FontId::proportional(10.0);
+        let mut labels = Vec::new();
+
+        // Query names along the bottom edge, centred on each sequence
+        for (idx, name) in plot.query_sequences.iter().enumerate() {
+            let beg = (plot.query_boundaries[idx] as f64 - self.view.x) / self.view.scale;
+            let end = (plot.query_boundaries[idx + 1] as f64 - self.view.x) / self.view.scale;
+            let x0 = rect.min.x + (beg as f32).max(0.0);
+            let x1 = rect.min.x + (end as f32).min(rect.width());
+            let max_chars = ((x1 - x0) / 6.0) as usize;
+            if max_chars < 4 {
+                continue;
+            }
+            let mut text = extract_display_name(name, max_chars.min(30));
+            if plot.query_reversed[idx] {
+                text.insert_str(0, "⇄ ");
+            }
+            let size = painter
+                .layout_no_wrap(text.clone(), font.clone(), egui::Color32::WHITE)
+                .size();
+            labels.push(SequenceLabel {
+                axis: Axis::Query,
+                name: name.clone(),
+                text,
+                rect: egui::Rect::from_center_size(
+                    egui::pos2((x0 + x1) / 2.0, rect.max.y - 24.0),
+                    size + egui::vec2(6.0, 2.0),
+                ),
+            });
+        }
+
+        // Target names along the left edge, at the middle of each sequence
+        for (idx, name) in plot.target_sequences.iter().enumerate() {
+            let beg = (plot.target_boundaries[idx] as f64 - self.view.y) / self.view.scale;
+            let end = (plot.target_boundaries[idx + 1] as f64 - self.view.y) / self.view.scale;
+            let y0 = rect.max.y - (end as f32).min(rect.height());
+            let y1 = rect.max.y - (beg as f32).max(0.0);
+            if y1 - y0 < 14.0 {
+                continue;
+            }
+            let mut text = extract_display_name(name, 20);
+            if plot.target_reversed[idx] {
+                text.insert_str(0, "⇄ ");
+            }
+            let size = painter
+                .layout_no_wrap(text.clone(), font.clone(), egui::Color32::WHITE)
+                .size();
+            labels.push(SequenceLabel {
+                axis: Axis::Target,
+                name: name.clone(),
+                text,
+                rect: egui::Rect::from_min_size(
+                    egui::pos2(rect.min.x + 4.0, (y0 + y1 - size.y) / 2.0),
+                    size + egui::vec2(6.0, 2.0),
+                ),
+            });
+        }
+
+        labels
+    }
+
+    /// Draw the linear synteny view for the region visible in the dotplot
+    fn render_synteny(&self, painter: &egui::Painter, rect: egui::Rect) {
+        painter.rect_filled(rect, 0.0, egui::Color32::BLACK);
+
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+
+        let view_width = rect.width() as f64 * self.view.scale;
+        let view_height = rect.height() as f64 * self.view.scale;
+        let query_window = synteny_view::AxisWindow {
+            start: self.view.x,
+            end: self.view.x + view_width,
+        };
+        let target_window = synteny_view::AxisWindow {
+            start: self.view.y,
+            end: self.view.y + view_height,
+        };
+
+        let mut segments = Vec::new();
+        for (layer_idx, layer_settings) in self.layers.iter().enumerate() {
+            if !layer_settings.visible || layer_idx >= self.num_layers {
+                continue;
+            }
+            let filter = layer_settings.filter;
+            segments.extend(
+                plot.query_segments_in_region(
+                    layer_idx as i32,
+                    self.view.x,
+                    self.view.y,
+                    view_width,
+                    view_height,
+                )
+                .into_iter()
+                .filter(|seg| layer_settings.shows_strand(seg.reverse) && filter.matches(seg)),
+            );
+        }
+
+        synteny_view::draw_synteny(painter, rect, plot, query_window, target_window, &segments);
+
+        painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui::Color32::GRAY));
+    }
+
+    /// Draw the whole plot with the longer genome wrapped across rows, in
+    /// each visible layer's colours and filters
+    fn render_wrapped(&self, painter: &egui::Painter, rect: egui::Rect) {
+        let Some(ref plot) = self.plot else {
+            painter.rect_filled(rect, 0.0, egui::Color32::BLACK);
+            return;
+        };
+
+        let mut segments = Vec::new();
+        for (layer_idx, layer) in self.layers.iter().enumerate() {
+            if !layer.visible || layer_idx >= self.num_layers {
+                continue;
+            }
+            segments.extend(
+                plot.query_segments_in_region(
+                    layer_idx as i32,
+                    0.0,
+                    0.0,
+                    plot.get_alen() as f64,
+                    plot.get_blen() as f64,
+                )
+                .into_iter()
+                .filter(|seg| layer.shows_strand(seg.reverse) && layer.filter.matches(seg))
+                .map(|seg| {
+                    let color = layer
+                        .segment_color(&seg, self.high_contrast)
+                        .gamma_multiply(layer.opacity);
+                    (seg, color)
+                }),
+            );
+        }
+
+        wrapped_view::draw_wrapped(painter, rect, plot, self.wrap_rows, &segments);
+    }
+
+    /// Draw alignment depth along the bottom (query) and left (target) canvas edges
+    ///
+    /// Depth 1 is drawn blue, collapsed duplications (depth > 1) orange, and unaligned
+    /// stretches get a red baseline so gaps are visible next to the dotplot.
+    fn draw_coverage_tracks(&self, painter: &egui::Painter, rect: egui::Rect) {
+        const TRACK_SIZE: f32 = COVERAGE_TRACK_PX;
+        const MAX_DEPTH: f64 = 4.0;
+
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+
+        let view_width = rect.width() as f64 * self.view.scale;
+        let view_height = rect.height() as f64 * self.view.scale;
+        let query_depth = plot.coverage_depth(
+            0,
+            self.view.x,
+            self.view.x + view_width,
+            rect.width().max(1.0) as usize,
+        );
+        let target_depth = plot.coverage_depth(
+            1,
+            self.view.y,
+            self.view.y + view_height,
+            rect.height().max(1.0) as usize,
+        );
+
+        let backdrop = egui::Color32::from_rgba_unmultiplied(20, 20, 20, 200);
+        let depth_color = |depth: f64| {
+            if depth > 1.05 {
+                egui::Color32::from_rgb(255, 150, 0)
+            } else {
+                egui::Color32::from_rgb(80, 140, 255)
+            }
+        };
+        let gap_color = egui::Color32::from_rgb(200, 40, 40);
+
+        // Query track along the bottom edge; bins run left to right
+        let track =
+            egui::Rect::from_min_max(egui::pos2(rect.min.x, rect.max.y - TRACK_SIZE), rect.max);
+        painter.rect_filled(track, 0.0, backdrop);
+        for (i, &depth) in query_depth.iter().enumerate() {
+            let x = rect.min.x + i as f32;
+            if depth <= 0.0 {
+                painter.vline(x, (track.max.y - 2.0)..=track.max.y, (1.0, gap_color));
+                continue;
+            }
+            let height = (depth.min(MAX_DEPTH) / MAX_DEPTH) as f32 * TRACK_SIZE;
+            painter.vline(
+                x,
+                (track.max.y - height)..=track.max.y,
+                (1.0, depth_color(depth)),
+            );
+        }
+
+        // Target track along the left edge; bins run bottom to top
+        let track =
+            egui::Rect::from_min_max(rect.min, egui::pos2(rect.min.x + TRACK_SIZE, rect.max.y));
+        painter.rect_filled(track, 0.0, backdrop);
+        for (i, &depth) in target_depth.iter().enumerate() {
+            let y = rect.max.y - i as f32;
+            if depth <= 0.0 {
+                painter.hline(track.min.x..=(track.min.x + 2.0), y, (1.0, gap_color));
+                continue;
+            }
+            let width = (depth.min(MAX_DEPTH) / MAX_DEPTH) as f32 * TRACK_SIZE;
+            painter.hline(
+                track.min.x..=(track.min.x + width),
+                y,
+                (1.0, depth_color(depth)),
+            );
+        }
+    }
+
+    /// Pin the name of the query sequence under the left edge and the target
+    /// sequence under the top edge when their starts are scrolled out of view,
+    /// with the position at that edge; the next sequence pushes its label out
+    fn draw_sticky_labels(&self, painter: &egui::Painter, rect: egui::Rect, mut top: f32) {
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        let font = egui::FontId::proportional(11.0);
+        let color = egui::Color32::from_rgb(255, 230, 150);
+        let backdrop = egui::Color32::from_black_alpha(160);
+        let to_screen_x =
+            |gx: i64| rect.min.x + ((gx as f64 - self.view.x) / self.view.scale) as f32;
+        let to_screen_y =
+            |gy: i64| rect.max.y - ((gy as f64 - self.view.y) / self.view.scale) as f32;
+
+        let left = self.view.x;
+        if left > 0.0 && left < plot.get_alen() as f64 {
+            let (idx, name, local) = plot.query_coord_to_sequence(left as i64);
+            if (plot.query_boundaries[idx] as f64) < left {
+                let text = format!(
+                    "{} @ {}",
+                    extract_display_name(&name, 40),
+                    format_extent(local, self.normalized)
+                );
+                let galley = painter.layout_no_wrap(text, font.clone(), color);
+                let end = to_screen_x(plot.query_boundaries[idx + 1]);
+                let x = (rect.min.x + 8.0).min(end - galley.size().x - 6.0);
+                let pos = egui::pos2(x, top);
+                top += galley.size().y + 6.0;
+                painter.rect_filled(
+                    egui::Rect::from_min_size(pos, galley.size()).expand(3.0),
+                    3.0,
+                    backdrop,
+                );
+                painter.galley(pos, galley, color);
+            }
+        }
+
+        let upper = self.view.y + rect.height() as f64 * self.view.scale;
+        if upper > 0.0 && upper < plot.get_blen() as f64 {
+            let (idx, name, local) = plot.target_coord_to_sequence(upper as i64);
+            if (plot.target_boundaries[idx + 1] as f64) > upper {
+                let text = format!(
+                    "{} @ {}",
+                    extract_display_name(&name, 40),
+                    format_extent(local, self.normalized)
+                );
+                let galley = painter.layout_no_wrap(text, font, color);
+                let bottom = to_screen_y(plot.target_boundaries[idx]);
+                let y = top.min(bottom - galley.size().y - 6.0);
+                let pos = egui::pos2(rect.min.x + 8.0, y);
+                painter.rect_filled(
+                    egui::Rect::from_min_size(pos, galley.size()).expand(3.0),
+                    3.0,
+                    backdrop,
+                );
+                painter.galley(pos, galley, color);
+            }
+        }
+    }
+
+    /// Draw GC content as thin heat strips along the bottom (query) and left
+    /// (target) canvas edges, one sample per pixel
+    fn draw_gc_tracks(&self, painter: &egui::Painter, rect: egui::Rect) {
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        let inset = if self.show_coverage {
+            COVERAGE_TRACK_PX
+        } else {
+            0.0
+        };
+
+        if let Some(ref track) = self.query_gc {
+            let bottom = rect.max.y - inset;
+            let alen = plot.get_alen() as f64;
+            for i in 0..rect.width().max(0.0) as usize {
+                let x = self.view.x + (i as f64 + 0.5) * self.view.scale;
+                if x < 0.0 || x >= alen {
+                    continue;
+                }
+                let (_, name, local) = plot.query_coord_to_sequence(x as i64);
+                if let Some(gc) = self.gc_lookup(track, &name, local) {
+                    painter.vline(
+                        rect.min.x + i as f32,
+                        (bottom - GC_TRACK_PX)..=bottom,
+                        (1.0, gc_color(gc)),
+                    );
+                }
+            }
+        }
+
+        if let Some(ref track) = self.target_gc {
+            let left = rect.min.x + inset;
+            let blen = plot.get_blen() as f64;
+            for i in 0..rect.height().max(0.0) as usize {
+                let y = self.view.y + (i as f64 + 0.5) * self.view.scale;
+                if y < 0.0 || y >= blen {
+                    continue;
+                }
+                let (_, name, local) = plot.target_coord_to_sequence(y as i64);
+                if let Some(gc) = self.gc_lookup(track, &name, local) {
+                    painter.hline(
+                        left..=(left + GC_TRACK_PX),
+                        rect.max.y - i as f32,
+                        (1.0, gc_color(gc)),
+                    );
+                }
+            }
+        }
+    }
+
+    /// GC of the window at local position `local`, which is in band units
+    /// when sequences are drawn at equal width
+    fn gc_lookup(&self, track: &GcTrack, name: &str, local: i64) -> Option<f32> {
+        if self.normalized {
+            track.gc_at_fraction(name, local as f64 / rust_plot::NORMALIZED_BAND as f64)
+        } else {
+            track.gc_at(name, local)
+        }
+    }
+
+    fn load_gc_async(&mut self, axis: Axis, path: PathBuf) {
+        println!("🔍 Computing GC% track: {}", path.display());
+        let tx = self.gc_channel.0.clone();
+        thread::spawn(move || {
+            let result = GcTrack::load(&path, composition::DEFAULT_GC_WINDOW)
+                .map_err(|e| format!("Failed to read {}: {e:#}", path.display()));
+            let _ = tx.send((axis, result));
+        });
+    }
+
+    /// Rulers along the bottom and left edges: round major ticks with labels
+    /// and minor ticks between, in a sequence's own coordinates when the view
+    /// lies inside it
+    fn draw_axes(&self, _ui: &mut egui::Ui, painter: &egui::Painter, rect: egui::Rect) {
+        let view_width = rect.width() as f64 * self.view.scale;
+        let view_height = rect.height() as f64 * self.view.scale;
+        let axis_color = if self.high_contrast {
+            egui::Color32::WHITE
+        } else {
+            egui::Color32::DARK_GRAY
+        };
+        let font = egui::FontId::proportional(10.0);
+
+        let Some(plot) = self.plot.as_ref().filter(|_| !self.normalized) else {
+            // Band positions have no base coordinates to rule
+            let band = rust_plot::NORMALIZED_BAND as f64;
+            let range = |start: f64, len: f64| {
+                format!(
+                    "{:.2} - {:.2} sequences",
+                    start / band,
+                    (start + len) / band
+                )
+            };
+            painter.text(
+                egui::pos2(rect.center().x, rect.max.y - 5.0),
+                egui::Align2::CENTER_BOTTOM,
+                range(self.view.x, view_width),
+                font.clone(),
+                axis_color,
+            );
+            painter.text(
+                egui::pos2(rect.min.x + 5.0, rect.center().y),
+                egui::Align2::LEFT_CENTER,
+                range(self.view.y, view_height),
+                font,
+                axis_color,
+            );
+            return;
+        };
+
+        let (major, _) = scale_bar(self.view.scale, AXIS_TICK_SPACING_PX);
+        let x_ticks = axis_ticks_in_view(
+            self.view.x,
+            view_width,
+            &plot.query_boundaries,
+            &plot.query_lengths,
+            &plot.query_reversed,
+            &plot.query_sequences,
+            major,
+        );
+        let y_ticks = axis_ticks_in_view(
+            self.view.y,
+            view_height,
+            &plot.target_boundaries,
+            &plot.target_lengths,
+            &plot.target_reversed,
+            &plot.target_sequences,
+            major,
+        );
+        let stroke = egui::Stroke::new(1.0, axis_color);
+        let tick_len = |tick: &rust_plot::AxisTick| if tick.major { 6.0 } else { 3.0 };
+
+        for tick in &x_ticks.0 {
+            let x = rect.min.x + ((tick.coord - self.view.x) / self.view.scale) as f32;
+            painter.line_segment(
+                [
+                    egui::pos2(x, rect.max.y),
+                    egui::pos2(x, rect.max.y - tick_len(tick)),
+                ],
+                stroke,
+            );
+            if tick.major {
+                painter.text(
+                    egui::pos2(x, rect.max.y - 8.0),
+                    egui::Align2::CENTER_BOTTOM,
+                    rust_plot::tick_label(tick.local, major),
+                    font.clone(),
+                    axis_color,
+                );
+            }
+        }
+        for tick in &y_ticks.0 {
+            let y = rect.max.y - ((tick.coord - self.view.y) / self.view.scale) as f32;
+            painter.line_segment(
+                [
+                    egui::pos2(rect.min.x, y),
+                    egui::pos2(rect.min.x + tick_len(tick), y),
+                ],
+                stroke,
+            );
+            if tick.major {
+                // Reading upwards, centred on the tick
+                let galley = painter.layout_no_wrap(
+                    rust_plot::tick_label(tick.local, major),
+                    font.clone(),
+                    axis_color,
+                );
+                let pos = egui::pos2(rect.min.x + 8.0, y + galley.size().x / 2.0);
+                painter.add(
+                    egui::epaint::TextShape::new(pos, galley, axis_color)
+                        .with_angle(-std::f32::consts::FRAC_PI_2),
+                );
+            }
+        }
+
+        // Name the sequence a ruler counts along
+        if let Some(name) = x_ticks.1 {
+            painter.text(
+                egui::pos2(rect.center().x, rect.max.y - 22.0),
+                egui::Align2::CENTER_BOTTOM,
+                name,
+                font.clone(),
+                axis_color,
+            );
+        }
+        if let Some(name) = y_ticks.1 {
+            let galley = painter.layout_no_wrap(name.to_string(), font, axis_color);
+            let pos = egui::pos2(rect.min.x + 22.0, rect.center().y + galley.size().x / 2.0);
+            painter.add(
+                egui::epaint::TextShape::new(pos, galley, axis_color)
+                    .with_angle(-std::f32::consts::FRAC_PI_2),
+            );
+        }
+    }
+
+    /// Menu accelerators, so every menu action has a keyboard route
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        let pressed =
+            |shortcut: egui::KeyboardShortcut| ctx.input_mut(|i| i.consume_shortcut(&shortcut));
+        if pressed(OPEN_SHORTCUT) {
+            self.open_file_dialog();
+        }
+        if self.plot.is_some() && pressed(SAVE_SESSION_SHORTCUT) {
+            self.save_session_dialog();
+        }
+        if self.plot.is_some() && pressed(EXPORT_PNG_SHORTCUT) {
+            self.export_png_dialog();
+        }
+        if !self.tabs.is_empty() && !self.is_loading() {
+            if pressed(PREVIOUS_TAB_SHORTCUT) {
+                self.switch_tab((self.active_tab + self.tabs.len() - 1) % self.tabs.len());
+            } else if pressed(NEXT_TAB_SHORTCUT) {
+                self.switch_tab((self.active_tab + 1) % self.tabs.len());
+            }
+        }
+        if pressed(QUIT_SHORTCUT) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+        // Cmd+= too, as plus needs Shift on most layouts
+        let equals = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Equals);
+        if pressed(ZOOM_IN_SHORTCUT) || pressed(equals) {
+            self.zoom(2.0);
+        }
+        if pressed(ZOOM_OUT_SHORTCUT) {
+            self.zoom(0.5);
+        }
+        if pressed(RESET_VIEW_SHORTCUT) {
+            self.reset_view();
+        }
+        if self.plot.is_some() && pressed(FIND_SEQUENCE_SHORTCUT) {
+            self.show_sequence_search = true;
+        }
+        if self.plot.is_some() && pressed(GO_TO_SHORTCUT) {
+            self.show_go_to = true;
+        }
+    }
+
+    fn handle_interaction(&mut self, response: &egui::Response, rect: egui::Rect) {
+        // Arrow keys pan when the canvas has keyboard focus (Tab to it), or
+        // when nothing else does
+        let ctx = &response.ctx;
+        if response.has_focus() {
+            // Keep the arrows for panning rather than moving focus
+            ctx.memory_mut(|m| {
+                m.set_focus_lock_filter(
+                    response.id,
+                    egui::EventFilter {
+                        horizontal_arrows: true,
+                        vertical_arrows: true,
+                        ..Default::default()
+                    },
+                )
+            });
+        }
+        if response.has_focus() || ctx.memory(|m| m.focused().is_none()) {
+            let (dx, dy) = ctx.input(|i| {
+                let axis = |back, forward| {
+                    f64::from(i.key_pressed(forward) as u8) - f64::from(i.key_pressed(back) as u8)
+                };
+                (
+                    axis(egui::Key::ArrowLeft, egui::Key::ArrowRight),
+                    axis(egui::Key::ArrowDown, egui::Key::ArrowUp),
+                )
+            });
+            if dx != 0.0 || dy != 0.0 {
+                let fraction = self.key_pan_fraction;
+                self.view.x += dx * fraction * rect.width() as f64 * self.view.scale;
+                self.view.y += dy * fraction * rect.height() as f64 * self.view.scale;
+                self.clamp_view_position(rect);
+            }
+
+            // +/- zoom about the centre, Home resets, Page Up/Down step along
+            // the diagonal (Ctrl/Cmd+plus/minus are menu shortcuts, consumed
+            // before this)
+            let (zoom_in, zoom_out, home, page) = ctx.input(|i| {
+                (
+                    i.key_pressed(egui::Key::Plus) || i.key_pressed(egui::Key::Equals),
+                    i.key_pressed(egui::Key::Minus),
+                    i.key_pressed(egui::Key::Home),
+                    f64::from(i.key_pressed(egui::Key::PageDown) as u8)
+                        - f64::from(i.key_pressed(egui::Key::PageUp) as u8),
+                )
+            });
+            if !ctx.wants_keyboard_input() {
+                if zoom_in {
+                    self.zoom_at_point(2.0, rect.center(), rect);
+                }
+                if zoom_out {
+                    self.zoom_at_point(0.5, rect.center(), rect);
+                }
+                if home {
+                    self.reset_view();
+                }
+                if page != 0.0 {
+                    self.step_along_diagonal(page, rect);
+                }
+            }
+        }
+
+        // Z / Shift+Z - back and forward through the view history
+        let (history_key, forward) = response
+            .ctx
+            .input(|i| (i.key_pressed(egui::Key::Z), i.modifiers.shift));
+        if history_key && !response.ctx.wants_keyboard_input() {
+            self.step_view_history(1, forward);
+        }
+
+        // F / R - show or hide forward / reverse alignments on every layer
+        let (toggle_forward, toggle_reverse) = response
+            .ctx
+            .input(|i| (i.key_pressed(egui::Key::F), i.key_pressed(egui::Key::R)));
+        if !response.ctx.wants_keyboard_input() {
+            for (toggle, reverse) in [(toggle_forward, false), (toggle_reverse, true)] {
+                if toggle {
+                    self.toggle_strand(reverse);
+                }
+            }
+        }
+
+        // N / Shift+N - step through off-diagonal clusters
+        let (next_cluster, backwards) = response
+            .ctx
+            .input(|i| (i.key_pressed(egui::Key::N), i.modifiers.shift));
+        if next_cluster {
+            self.step_cluster(!backwards);
+        }
+
+        // A drag does whatever the mouse bindings give its button and the
+        // modifiers held when it starts: pan, or a box (Shift+drag by default)
+        let held = response.ctx.input(|i| bindings::Modifiers {
+            shift: i.modifiers.shift,
+            ctrl: i.modifiers.command,
+            alt: i.modifiers.alt,
+        });
+        if response.drag_started() {
+            self.drag_action = [
+                (egui::PointerButton::Primary, Gesture::Drag),
+                (egui::PointerButton::Secondary, Gesture::RightDrag),
+                (egui::PointerButton::Middle, Gesture::MiddleDrag),
+            ]
+            .into_iter()
+            .find(|&(button, _)| response.drag_started_by(button))
+            .and_then(|(_, gesture)| self.bindings.action(held, gesture));
+        }
+
+        if response.hovered() {
+            // Boxes need the 2D dotplot mapping
+            if response.drag_started()
+                && matches!(
+                    self.drag_action,
+                    Some(Action::BoxZoom | Action::Select | Action::Region | Action::Stats)
+                )
+                && self.view_mode == ViewMode::DotPlot
+            {
+                self.box_zoom_start = response.hover_pos();
+            }
+
+            if let Some(start) = self.box_zoom_start {
+                if response.dragged() {
+                    // Draw box while dragging
+                    if let Some(current) = response.hover_pos() {
+                        let painter = response.ctx.debug_painter();
+                        let box_rect = egui::Rect::from_two_pos(start, current);
+                        painter.rect_stroke(
+                            box_rect,
+                            0.0,
+                            egui::Stroke::new(2.0, egui::Color32::from_rgb(255, 100, 100)),
+                        );
+                    }
+                }
+
+                if response.drag_stopped() {
+                    // Zoom to box, or save it as a region
+                    if let Some(end) = response.hover_pos() {
+                        if self.drag_action == Some(Action::Region) {
+                            let (x1, y1) = self.screen_to_genome(rect, start);
+                            let (x2, y2) = self.screen_to_genome(rect, end);
+                            self.add_region(x1, y1, x2, y2);
+                        } else if self.drag_action == Some(Action::Select) {
+                            let corners = (
+                                self.screen_to_genome(rect, start),
+                                self.screen_to_genome(rect, end),
+                            );
+                            self.select_segments(corners.0, corners.1);
+                        } else if self.drag_action == Some(Action::Stats) {
+                            let corners = (
+                                self.screen_to_genome(rect, start),
+                                self.screen_to_genome(rect, end),
+                            );
+                            self.summarise_box(corners.0, corners.1);
+                        } else {
+                            self.zoom_to_box(rect, start, end);
+                        }
+                    }
+                    self.box_zoom_start = None;
+                }
+            }
+        }
+
+        if response.dragged() && self.drag_action == Some(Action::Pan) {
+            let delta = response.drag_delta();
+            let dx = -delta.x as f64 * self.view.scale;
+            let dy = delta.y as f64 * self.view.scale;
+
+            self.view.x += dx;
+            self.view.y += dy;
+
+            // Clamp to genome bounds plus the fit margin
+            self.clamp_view_position(rect);
+        }
+
+        // Kinetic panning: a flick keeps the view gliding, slowing down
+        if response.is_pointer_button_down_on() {
+            self.pan_velocity = egui::Vec2::ZERO;
+        } else if response.drag_stopped()
+            && self.kinetic_pan
+            && self.drag_action == Some(Action::Pan)
+        {
+            self.pan_velocity = response.ctx.input(|i| i.pointer.velocity());
+        }
+        if response.drag_stopped() {
+            self.drag_action = None;
+        }
+        if self.pan_velocity.length() > KINETIC_MIN_SPEED {
+            let dt = response.ctx.input(|i| i.stable_dt).min(0.1);
+            let step = self.pan_velocity * dt;
+            self.view.x -= step.x as f64 * self.view.scale;
+            self.view.y += step.y as f64 * self.view.scale;
+            self.clamp_view_position(rect);
+            self.pan_velocity *= (-KINETIC_FRICTION * dt).exp();
+            response.ctx.request_repaint();
+        } else {
+            self.pan_velocity = egui::Vec2::ZERO;
+        }
+
+        // Scroll wheel: zoom with the modifiers bound to zoom (none by default);
+        // otherwise Shift pans horizontally and the rest pans vertically. The
+        // scroll-to-pan preference always pans, with Ctrl/Cmd+scroll zooming
+        if response.hovered() {
+            let wheel_zooms = !self.scroll_to_pan
+                && self.bindings.action(held, Gesture::Wheel) == Some(Action::Zoom);
+            let (raw_scroll, smooth_scroll, zoom_delta, shift, touch) = response.ctx.input(|i| {
+                (
+                    i.raw_scroll_delta,
+                    i.smooth_scroll_delta,
+                    i.zoom_delta(),
+                    i.modifiers.shift,
+                    i.multi_touch(),
+                )
+            });
+
+            if let Some(touch) = touch {
+                // Touchscreen pinch; the pointer follows the touch, so anchor there
+                if touch.zoom_delta != 1.0 {
+                    let anchor = response.hover_pos().unwrap_or(touch.start_pos);
+                    self.zoom_at_point(touch.zoom_delta as f64, anchor, rect);
+                }
+            } else if zoom_delta != 1.0 && raw_scroll == egui::Vec2::ZERO {
+                // Trackpad pinch (Ctrl+scroll zoom is handled below)
+                self.zoom_with_mouse(zoom_delta as f64, response, rect);
+            } else if wheel_zooms {
+                // Shift+wheel may arrive as horizontal scroll
+                let amount = if raw_scroll.y != 0.0 {
+                    raw_scroll.y
+                } else {
+                    raw_scroll.x
+                };
+                if amount != 0.0 {
+                    let zoom_factor = if amount > 0.0 { 1.2 } else { 0.8 };
+                    self.zoom_with_mouse(zoom_factor, response, rect);
+                }
+            } else if shift {
+                // Some platforms already turn Shift+wheel into horizontal scroll
+                let amount = if smooth_scroll.x != 0.0 {
+                    smooth_scroll.x
+                } else {
+                    smooth_scroll.y
+                };
+                self.scroll_pan(amount, 0.0, rect);
+            } else if zoom_delta != 1.0 {
+                self.zoom_with_mouse(zoom_delta as f64, response, rect);
+            } else {
+                self.scroll_pan(smooth_scroll.x, smooth_scroll.y, rect);
+            }
+        }
+    }
+
+    fn zoom_with_mouse(&mut self, factor: f64, response: &egui::Response, rect: egui::Rect) {
+        if let Some(mouse_pos) = response.hover_pos() {
+            self.zoom_at_point(factor, mouse_pos, rect);
+        } else {
+            self.zoom(factor);
+        }
+    }
+
+    /// Pan by a scroll delta in screen points (positive = content moves right/down)
+    fn scroll_pan(&mut self, dx: f32, dy: f32, rect: egui::Rect) {
+        if dx == 0.0 && dy == 0.0 {
+            return;
+        }
+        self.view.x -= dx as f64 * self.view.scale;
+        self.view.y += dy as f64 * self.view.scale;
+        self.clamp_view_position(rect);
+    }
+
+    fn zoom_to_box(
+        &mut self,
+        canvas_rect: egui::Rect,
+        screen_start: egui::Pos2,
+        screen_end: egui::Pos2,
+    ) {
+        let (x1, y1) = self.screen_to_genome(canvas_rect, screen_start);
+        let (x2, y2) = self.screen_to_genome(canvas_rect, screen_end);
+
+        self.zoom_to_region(canvas_rect, x1.min(x2), y1.min(y2), x1.max(x2), y1.max(y2));
+    }
+
+    /// Convert a screen position on the canvas to genome coordinates
+    fn screen_to_genome(&self, canvas_rect: egui::Rect, pos: egui::Pos2) -> (f64, f64) {
+        let pixel_x = (pos.x - canvas_rect.min.x) as f64;
+        let pixel_y = (canvas_rect.max.y - pos.y) as f64;
+        (
+            self.view.x + pixel_x * self.view.scale,
+            self.view.y + pixel_y * self.view.scale,
+        )
+    }
+
+    /// Save a genome rectangle as a new region of interest
+    fn add_region(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) {
+        let clamp_x = |v: f64| v.clamp(0.0, self.view.max_x) as i64;
+        let clamp_y = |v: f64| v.clamp(0.0, self.view.max_y) as i64;
+        let region = Region {
+            name: format!("Region {}", self.regions.len() + 1),
+            x_min: clamp_x(x1.min(x2)),
+            x_max: clamp_x(x1.max(x2)),
+            y_min: clamp_y(y1.min(y2)),
+            y_max: clamp_y(y1.max(y2)),
+            color: regions::REGION_PALETTE[self.regions.len() % regions::REGION_PALETTE.len()],
+        };
+        if region.x_max > region.x_min && region.y_max > region.y_min {
+            self.regions.push(region);
+            self.show_region_panel = true;
+        }
+    }
+
+    /// Window that fuzzy-matches sequence names on both axes; picking one
+    /// (or Enter for the best match) zooms to its band
+    fn sequence_search_window(&mut self, ctx: &egui::Context) {
+        let Some(plot) = self.plot.as_ref() else {
+            return;
+        };
+        let pattern = self.sequence_search.as_str();
+        let mut matches: Vec<(i32, Axis, usize)> = [
+            (Axis::Query, &plot.query_sequences),
+            (Axis::Target, &plot.target_sequences),
+        ]
+        .into_iter()
+        .flat_map(|(axis, names)| {
+            names.iter().enumerate().filter_map(move |(idx, name)| {
+                sequence_filter::fuzzy_score(pattern, name).map(|score| (score, axis, idx))
+            })
+        })
+        .collect();
+        matches.sort_by_key(|&(score, _, _)| std::cmp::Reverse(score));
+        let total = matches.len();
+        matches.truncate(SEQUENCE_SEARCH_RESULTS);
+
+        let mut open = true;
+        let mut picked = None;
+        egui::Window::new("Find sequence")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let edit = ui.add(
+                    egui::TextEdit::singleline(&mut self.sequence_search)
+                        .hint_text("Query or target name"),
+                );
+                if !edit.has_focus() && !edit.lost_focus() {
+                    edit.request_focus();
+                }
+                if edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    picked = matches.first().map(|&(_, axis, idx)| (axis, idx));
+                }
+                ui.separator();
+                for &(_, axis, idx) in &matches {
+                    let (names, lengths, tag) = match axis {
+                        Axis::Query => (&plot.query_sequences, &plot.query_lengths, "Q"),
+                        Axis::Target => (&plot.target_sequences, &plot.target_lengths, "T"),
+                    };
+                    let text = format!("{tag}  {}  ({})", names[idx], format_bp(lengths[idx]));
+                    if ui.selectable_label(false, text).clicked() {
+                        picked = Some((axis, idx));
+                    }
+                }
+                if total > matches.len() {
+                    ui.label(
+                        egui::RichText::new(format!("{} more", total - matches.len()))
+                            .weak()
+                            .small(),
+                    );
+                } else if matches.is_empty() {
+                    ui.label(egui::RichText::new("No matching sequence").weak());
+                }
+            });
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            open = false;
+        }
+        if let Some((axis, idx)) = picked {
+            self.zoom_to_sequence(axis, idx);
+            open = false;
+        }
+        self.show_sequence_search = open;
+    }
+
+    /// Zoom to a sequence's band: its own length along its axis, the whole
+    /// other genome along the other
+    fn zoom_to_sequence(&mut self, axis: Axis, idx: usize) {
+        let Some(plot) = self.plot.as_ref() else {
+            return;
+        };
+        let band = |boundaries: &[i64], lengths: &[i64]| {
+            let start = boundaries.get(idx).copied().unwrap_or(0) as f64;
+            (start, start + lengths.get(idx).copied().unwrap_or(0) as f64)
+        };
+        let (min_x, min_y, max_x, max_y) = match axis {
+            Axis::Query => {
+                let (start, end) = band(&plot.query_boundaries, &plot.query_lengths);
+                (start, 0.0, end, plot.target_genome_len as f64)
+            }
+            Axis::Target => {
+                let (start, end) = band(&plot.target_boundaries, &plot.target_lengths);
+                (0.0, start, plot.query_genome_len as f64, end)
+            }
+        };
+        self.zoom_to_fit(min_x, min_y, max_x, max_y);
+    }
+
+    /// Window reading `qchr:start-end x tchr:start-end` (or one locus) and
+    /// zooming there; an axis left out shows its whole genome
+    fn go_to_window(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        let mut go = false;
+        egui::Window::new("Go to")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let edit = ui.add(
+                    egui::TextEdit::singleline(&mut self.go_to_text)
+                        .hint_text("chr1:1,000-2,000 x chrA:5,000-9,000")
+                        .desired_width(280.0),
+                );
+                if !edit.has_focus() && !edit.lost_focus() {
+                    edit.request_focus();
+                }
+                if edit.changed() {
+                    self.go_to_error = None;
+                }
+                go = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                ui.label(
+                    egui::RichText::new(
+                        "Query locus, target locus or both; 0-based, end exclusive",
+                    )
+                    .weak()
+                    .small(),
+                );
+                if let Some(ref error) = self.go_to_error {
+                    ui.colored_label(egui::Color32::LIGHT_RED, error);
+                }
+                go |= ui.button("Go").clicked();
+            });
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            open = false;
+        }
+        if go {
+            if let Some(ref plot) = self.plot {
+                match regions::parse_go_to(&self.go_to_text, plot) {
+                    Ok(go_to) => {
+                        let (x_min, x_max) = go_to.x.unwrap_or((0, plot.query_genome_len));
+                        let (y_min, y_max) = go_to.y.unwrap_or((0, plot.target_genome_len));
+                        self.zoom_to_fit(x_min as f64, y_min as f64, x_max as f64, y_max as f64);
+                        open = false;
+                    }
+                    Err(e) => self.go_to_error = Some(e.to_string()),
+                }
+            }
+        }
+        self.show_go_to = open;
+    }
+
+    /// Zoom to a genome rectangle, centred in whatever room the canvas's
+    /// aspect leaves around it
+    fn zoom_to_fit(&mut self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) {
+        let (width, height) = self.last_canvas_size;
+        let scale = ((max_x - min_x) / width as f64).max((max_y - min_y) / height as f64);
+        let pad_x = (scale * width as f64 - (max_x - min_x)) / 2.0;
+        let pad_y = (scale * height as f64 - (max_y - min_y)) / 2.0;
+        let canvas = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width, height));
+        self.zoom_to_region(
+            canvas,
+            min_x - pad_x,
+            min_y - pad_y,
+            max_x + pad_x,
+            max_y + pad_y,
+        );
+    }
+
+    /// Zoom so the genome region [min_x, max_x] x [min_y, max_y] fills the canvas
+    fn zoom_to_region(
+        &mut self,
+        canvas_rect: egui::Rect,
+        min_x: f64,
+        min_y: f64,
+        max_x: f64,
+        max_y: f64,
+    ) {
+        let box_width = max_x - min_x;
+        let box_height = max_y - min_y;
+
+        // Set new view position
+        self.view.x = min_x;
+        self.view.y = min_y;
+
+        // Calculate new scale to fit the box in the canvas
+        let scale_for_width = box_width / canvas_rect.width() as f64;
+        let scale_for_height = box_height / canvas_rect.height() as f64;
+        self.view.scale = scale_for_width.max(scale_for_height).max(self.min_scale);
+
+        // Clamp position to the genome plus the fit margin
+        self.clamp_view_position(canvas_rect);
+    }
+}
+
+// ============================================================================
+// File Operations
+// ============================================================================
+
+impl AlnViewApp {
+    fn open_file_dialog(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Alignment Files", &["1aln", "delta", "gz"])
+            .pick_file()
+        {
+            self.load_file_async(path);
+        }
+    }
+
+    fn load_all_vs_all_async(&mut self, paths: Vec<PathBuf>) {
+        let Some(first) = paths.first().cloned() else {
+            return;
+        };
+        self.open_tab();
+        self.stop_following();
+        println!("🔍 Merging {} alignment files", paths.len());
+        self.current_file = Some(first.clone());
+        self.loading_source = Some(first);
+
+        let label = format!("{} alignment files", paths.len());
+        let log_timings = self.timings;
+        self.spawn_plot_loader(label, move |_| {
+            let mut timings = Timings::default();
+            let loaded = timings.time("read", || genome_groups::load_all_vs_all(&paths));
+            if log_timings {
+                print!("{}", timings.report());
+            }
+            loaded
+                .map(|(plot, groups)| (plot, None, Some(groups)))
+                .map_err(|e| format!("Failed to load all-vs-all set: {e:#}"))
+        });
+    }
+
+    /// Start reading a PAF file or pipe as it is written; the plot is replaced
+    /// by each snapshot the follower sends
+    fn follow_paf(&mut self, path: PathBuf) {
+        self.open_tab();
+        self.stop_following();
+        println!("📡 Following PAF: {}", path.display());
+        self.current_file = Some(path.clone());
+        self.loading_source = Some(path.clone());
+        self.plot = None;
+        self.full_plot = None;
+        self.unswept_plot = None;
+
+        let (tx, rx) = channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        self.follow_receiver = Some(rx);
+        self.follow_stop = Some(stop.clone());
+        thread::spawn(move || paf::follow(&path, tx, stop));
+    }
+
+    fn stop_following(&mut self) {
+        if let Some(stop) = self.follow_stop.take() {
+            stop.store(true, Ordering::Relaxed);
+        }
+        self.follow_receiver = None;
+    }
+
+    /// Swap in a newer snapshot of a followed PAF, keeping the view, sequence
+    /// layout, cutoffs and annotations
+    fn update_streamed_plot(&mut self, mut rust_plot: RustPlot) {
+        let Some(cutoff) = self.plot.as_ref().map(|plot| plot.cutoff) else {
+            self.install_plot(rust_plot, None);
+            return;
+        };
+        rust_plot.cutoff = cutoff;
+
+        // Sequences keep their numbers as the file grows; new ones go last
+        self.query_order
+            .extend(self.query_order.len()..rust_plot.query_sequences.len());
+        self.target_order
+            .extend(self.target_order.len()..rust_plot.target_sequences.len());
+
+        // Keep following the whole plot while it is all in view
+        let (width, height) = self.last_canvas_size;
+        let whole_in_view = self.view.x <= 0.0
+            && self.view.y <= 0.0
+            && self.view.x + width as f64 * self.view.scale >= self.view.max_x
+            && self.view.y + height as f64 * self.view.scale >= self.view.max_y;
+
+        self.full_plot = None;
+        self.unswept_plot = None;
+        self.plot = Some(rust_plot);
+        self.apply_sweep_filter(self.sweep_filter);
+        if let Some(ref plot) = self.plot {
+            self.view.max_x = plot.get_alen() as f64;
+            self.view.max_y = plot.get_blen() as f64;
+        }
+        self.needs_initial_fit |= whole_in_view;
+    }
+
+    fn load_file_async(&mut self, path: PathBuf) {
+        self.open_tab();
+        // An up-to-date `alnview index` opens without reading the .1aln
+        let index = segment_store::sidecar_path(&path);
+        if segment_store::is_fresh(&index, &path) {
+            println!("🔍 Opening index: {}", index.display());
+            self.load_file_via_store_async(path, index);
+            return;
+        }
+        self.stop_following();
+        let label = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("file")
+            .to_string();
+
+        println!("🔍 Starting async load: {}", path.display());
+        self.current_file = Some(path.clone());
+        self.loading_source = Some(path.clone());
+
+        // Load in a background thread using Rust reader
+        let log_timings = self.timings;
+        self.spawn_plot_loader(label, move |progress| {
+            println!("🧵 Background thread: Loading file with Rust reader...");
+
+            let mut timings = Timings::default();
+            match RustPlot::from_file_with_progress(&path, &mut timings, progress.records(&path)) {
+                Ok((plot, report)) => {
+                    println!("✅ Rust plot loaded successfully!");
+                    if log_timings {
+                        print!("{}", timings.report());
+                    }
+                    let warning = (!report.is_clean()).then(|| report.summary(plot.segments.len()));
+                    Ok((plot, warning, None))
+                }
+                Err(e) => Err(format!("Failed to load {}: {}", path.display(), e)),
+            }
+        });
+    }
+
+    /// Load `path` through the segment store at `store`: reuse the store if it
+    /// is at least as new as the file, otherwise build it from the file; only
+    /// a length-weighted overview is kept in memory
+    fn load_file_via_store_async(&mut self, path: PathBuf, store: PathBuf) {
+        self.open_tab();
+        self.stop_following();
+        let label = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("file")
+            .to_string();
+        self.current_file = Some(path.clone());
+        self.loading_source = Some(path.clone());
+
+        let max_segments = self.max_segments.unwrap_or(rust_plot::DEFAULT_MAX_SEGMENTS);
+        let store_path = store.clone();
+        self.spawn_plot_loader(label, move |progress| {
+            let mut warning = None;
+            if !segment_store::is_fresh(&store_path, &path) {
+                println!("💾 Building segment store: {}", store_path.display());
+                let (plot, report) = RustPlot::from_file_with_progress(
+                    &path,
+                    &mut Timings::default(),
+                    progress.records(&path),
+                )
+                .map_err(|e| format!("Failed to load {}: {e}", path.display()))?;
+                SegmentStore::write(&store_path, &plot, STORE_TILES)
+                    .map_err(|e| format!("{e:#}"))?;
+                warning = (!report.is_clean()).then(|| report.summary(plot.segments.len()));
+            }
+            let store = SegmentStore::open(&store_path).map_err(|e| format!("{e:#}"))?;
+            println!(
+                "💾 Segment store holds {} alignments; keeping an overview of {} in memory",
+                store.len(),
+                store.len().min(max_segments)
+            );
+            Ok((store.overview(max_segments), warning, None))
+        });
+        self.loaded_segment_store = Some(store);
+    }
+
+    /// Bin aligned bases per screen cell into the heatmap texture, again only
+    /// when the view has moved
+    /// The heatmap grid over the canvas at the current view
+    fn density_view(&self, rect: egui::Rect) -> DensityView {
+        DensityView {
+            x: self.view.x,
+            y: self.view.y,
+            bp_per_cell: self.view.scale * DENSITY_CELL_PX as f64,
+            columns: (rect.width() / DENSITY_CELL_PX).ceil().max(1.0) as usize,
+            rows: (rect.height() / DENSITY_CELL_PX).ceil().max(1.0) as usize,
+        }
+    }
+
+    /// Passing alignments in view, counted again only when the view moves
+    fn visible_segment_count(&mut self, rect: egui::Rect) -> usize {
+        let view = self.density_view(rect);
+        match self.visible_count {
+            Some((counted, count)) if counted == view => count,
+            _ => {
+                let count = self.plot.as_ref().map_or(0, |plot| {
+                    plot.count_segments_in_region(
+                        view.x,
+                        view.y,
+                        rect.width() as f64 * self.view.scale,
+                        rect.height() as f64 * self.view.scale,
+                    )
+                });
+                self.visible_count = Some((view, count));
+                count
+            }
+        }
+    }
+
+    fn update_density(&mut self, ctx: &egui::Context, rect: egui::Rect) {
+        let view = self.density_view(rect);
+        if self
+            .density
+            .as_ref()
+            .is_none_or(|(binned, _)| *binned != view)
+        {
+            let Some(plot) = self.plot.as_ref() else {
+                return;
+            };
+            let lines = density::cell_lines(plot, &view);
+            let grid = self.bin_density(&lines, &view);
+
+            // Log scale, so sparse cells still show next to dense ones
+            let scale = (1.0 + grid.max()).ln().max(f32::MIN_POSITIVE);
+            let mut image =
+                egui::ColorImage::new([view.columns, view.rows], egui::Color32::TRANSPARENT);
+            for (idx, &bp) in grid.cells.iter().enumerate() {
+                if bp > 0.0 {
+                    let (column, row) = (idx % view.columns, idx / view.columns);
+                    // Image rows run top down, grid rows bottom up
+                    image.pixels[(view.rows - 1 - row) * view.columns + column] =
+                        density_color((1.0 + bp).ln() / scale);
+                }
+            }
+            let texture = ctx.load_texture("density", image, egui::TextureOptions::NEAREST);
+            self.density = Some((view, texture));
+        }
+    }
+
+    /// Bin the whole plot into the minimap texture, once per plot, and place
+    /// it in the top-right corner; clicking or dragging there recentres the
+    /// view. None while the minimap is off or there is nothing to show.
+    fn minimap_interaction(&mut self, ui: &mut egui::Ui, rect: egui::Rect) -> Option<egui::Rect> {
+        if !self.show_minimap {
+            return None;
+        }
+        let plot = self.plot.as_ref()?;
+        let (width, height) = (plot.query_genome_len as f64, plot.target_genome_len as f64);
+        if width <= 0.0 || height <= 0.0 {
+            return None;
+        }
+        let bp_per_cell = width.max(height) / MINIMAP_PX as f64;
+        let size = egui::vec2(
+            (width / bp_per_cell).ceil().max(1.0) as f32,
+            (height / bp_per_cell).ceil().max(1.0) as f32,
+        );
+        if self.minimap.is_none() {
+            let view = DensityView {
+                x: 0.0,
+                y: 0.0,
+                bp_per_cell,
+                columns: size.x as usize,
+                rows: size.y as usize,
+            };
+            let grid =
+                density::bin_lines(&density::cell_lines(plot, &view), view.columns, view.rows);
+            let scale = (1.0 + grid.max()).ln().max(f32::MIN_POSITIVE);
+            let mut image =
+                egui::ColorImage::new([view.columns, view.rows], egui::Color32::TRANSPARENT);
+            for (idx, &bp) in grid.cells.iter().enumerate() {
+                if bp > 0.0 {
+                    let (column, row) = (idx % view.columns, idx / view.columns);
+                    image.pixels[(view.rows - 1 - row) * view.columns + column] =
+                        density_color((1.0 + bp).ln() / scale);
+                }
+            }
+            self.minimap = Some(ui.ctx().load_texture(
+                "minimap",
+                image,
+                egui::TextureOptions::LINEAR,
+            ));
+        }
+
+        let minimap_rect = egui::Rect::from_min_size(
+            rect.right_top() + egui::vec2(-MINIMAP_INSET_PX - size.x, MINIMAP_INSET_PX),
+            size,
+        );
+        if !rect.contains_rect(minimap_rect) {
+            return None;
+        }
+        let response = ui
+            .interact(
+                minimap_rect,
+                ui.id().with("minimap"),
+                egui::Sense::click_and_drag(),
+            )
+            .on_hover_cursor(egui::CursorIcon::Crosshair);
+        if let (true, Some(pos)) = (
+            response.clicked() || response.dragged(),
+            response.interact_pointer_pos(),
+        ) {
+            let gx = (pos.x - minimap_rect.min.x) as f64 * bp_per_cell;
+            let gy = (minimap_rect.max.y - pos.y) as f64 * bp_per_cell;
+            self.view.x = gx - rect.width() as f64 * self.view.scale / 2.0;
+            self.view.y = gy - rect.height() as f64 * self.view.scale / 2.0;
+            self.clamp_view_position(rect);
+        }
+        Some(minimap_rect)
+    }
+
+    /// The minimap over a dark backing, with the current viewport outlined
+    fn draw_minimap(&self, painter: &egui::Painter, rect: egui::Rect, minimap_rect: egui::Rect) {
+        let Some(ref texture) = self.minimap else {
+            return;
+        };
+        painter.rect_filled(
+            minimap_rect.expand(2.0),
+            2.0,
+            egui::Color32::from_black_alpha(200),
+        );
+        let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+        painter.image(texture.id(), minimap_rect, uv, egui::Color32::WHITE);
+
+        // Minimap pixels per bp along both axes
+        let px_per_bp = minimap_rect.width() as f64
+            / self
+                .plot
+                .as_ref()
+                .map_or(1, |plot| plot.query_genome_len.max(1)) as f64;
+        let to_minimap = |gx: f64, gy: f64| {
+            egui::pos2(
+                minimap_rect.min.x + (gx * px_per_bp) as f32,
+                minimap_rect.max.y - (gy * px_per_bp) as f32,
+            )
+        };
+        let viewport = egui::Rect::from_two_pos(
+            to_minimap(self.view.x, self.view.y),
+            to_minimap(
+                self.view.x + rect.width() as f64 * self.view.scale,
+                self.view.y + rect.height() as f64 * self.view.scale,
+            ),
+        )
+        .intersect(minimap_rect);
+        // Keep a far-zoomed viewport visible as at least a small box
+        let viewport = egui::Rect::from_center_size(
+            viewport.center(),
+            viewport.size().max(egui::vec2(3.0, 3.0)),
+        );
+        painter.rect_stroke(viewport, 0.0, egui::Stroke::new(1.5, egui::Color32::WHITE));
+        painter.rect_stroke(
+            minimap_rect.expand(2.0),
+            2.0,
+            egui::Stroke::new(1.0, egui::Color32::GRAY),
+        );
+    }
+
+    /// Paint the heatmap texture from its bottom-left corner
+    fn draw_density(&self, painter: &egui::Painter, rect: egui::Rect) {
+        if let Some((view, ref texture)) = self.density {
+            let size = egui::vec2(
+                view.columns as f32 * DENSITY_CELL_PX,
+                view.rows as f32 * DENSITY_CELL_PX,
+            );
+            let image_rect =
+                egui::Rect::from_min_size(rect.left_bottom() - egui::vec2(0.0, size.y), size);
+            let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+            painter.image(texture.id(), image_rect, uv, egui::Color32::WHITE);
+        }
+    }
+
+    /// Bin clipped alignments for the heatmap, on the GPU if asked for and
+    /// available
+    fn bin_density(&mut self, lines: &[CellLine], view: &DensityView) -> density::DensityGrid {
+        #[cfg(feature = "gpu")]
+        if self.gpu_density {
+            let binner = self.gpu_binner.get_or_insert_with(|| {
+                let binner = gpu_density::GpuBinner::new();
+                match binner {
+                    Some(ref binner) => println!("🎮 Binning density on {}", binner.adapter_name),
+                    None => eprintln!("⚠️  No usable GPU; binning density on the CPU"),
+                }
+                binner
+            });
+            if let Some(grid) = binner
+                .as_ref()
+                .and_then(|binner| binner.bin(lines, view.columns, view.rows))
+            {
+                return grid;
+            }
+        }
+        density::bin_lines(lines, view.columns, view.rows)
+    }
+
+    /// Full-resolution segments in the viewport, read from the segment store
+    /// once few enough of them are in view; None to draw the in-memory plot
+    fn store_viewport_segments(
+        &self,
+        view_width: f64,
+        view_height: f64,
+    ) -> Option<Vec<AlignmentSegment>> {
+        let store = self
+            .segment_store
+            .as_ref()
+            .filter(|store| !store.is_empty())?;
+        let plot = self.plot.as_ref()?;
+        // The store is in the file's own layout and bp coordinates, unswept
+        if self.full_plot.is_some() || self.normalized || self.sweep_filter.is_some() {
+            return None;
+        }
+        let (x_min, x_max) = (self.view.x as i64, (self.view.x + view_width) as i64);
+        let (y_min, y_max) = (self.view.y as i64, (self.view.y + view_height) as i64);
+        if store.count_in(x_min, x_max, y_min, y_max) > rust_plot::DEFAULT_MAX_SEGMENTS {
+            return None;
+        }
+        let mut segments = store.segments_in(x_min, x_max, y_min, y_max);
+        segments.retain(|seg| plot.passes(seg));
+        Some(segments)
+    }
+
+    fn open_comparison_dialog(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .set_title("Open alignment to compare against")
+            .add_filter("Alignment Files", &["1aln", "delta", "gz"])
+            .pick_file()
+        {
+            self.load_comparison_async(path);
+        }
+    }
+
+    fn load_comparison_async(&mut self, path: PathBuf) {
+        if self.normalized {
+            // The second file's records are in bp, not % of each sequence
+            self.notifications.info(
+                "Comparison needs bp coordinates",
+                "Turn off equal-width sequences to overlay a second alignment file.",
+            );
+            return;
+        }
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        // Records project onto the file's own axes; swap the result after
+        let swapped = self.axes_swapped;
+        let reference = if swapped {
+            plot.clone().transposed()
+        } else {
+            plot.clone()
+        };
+
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("file")
+            .to_string();
+        *self.loading.lock().unwrap() = LoadingState::Loading(format!("comparison {name}"), None);
+        println!("🔍 Starting comparison load: {}", path.display());
+
+        let (tx, rx) = channel();
+        self.comparison_receiver = Some(rx);
+        self.comparison_file = Some(path.clone());
+        self.loading_source = Some(path.clone());
+
+        thread::spawn(move || {
+            let result = Comparison::load(&path, &reference, comparison::DEFAULT_MIN_OVERLAP)
+                .map(|mut comparison| {
+                    if swapped {
+                        comparison.segments = comparison
+                            .segments
+                            .iter()
+                            .map(AlignmentSegment::transposed)
+                            .collect();
+                    }
+                    comparison
+                })
+                .map_err(|e| format!("Failed to load comparison {}: {}", path.display(), e));
+            if let Err(ref error_msg) = result {
+                eprintln!("❌ {error_msg}");
+            }
+            let _ = tx.send(result);
+        });
+    }
+
+    fn load_kmer_async(&mut self, query: PathBuf, target: PathBuf, k: usize) {
+        self.open_tab();
+        self.stop_following();
+        let name = |p: &PathBuf| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("fasta")
+                .to_string()
+        };
+        let label = format!("{k}-mers {} vs {}", name(&query), name(&target));
+
+        println!("🔍 Starting k-mer dotplot: {label}");
+        self.current_file = Some(query.clone());
+        self.loading_source = Some(query.clone());
+        self.kmer_query_fasta = Some(query.clone());
+        self.kmer_target_fasta = Some(target.clone());
+        self.query_gc = None;
+        self.target_gc = None;
+        if self.show_gc_tracks {
+            self.load_gc_async(Axis::Query, query.clone());
+            self.load_gc_async(Axis::Target, target.clone());
+        }
+
+        let log_timings = self.timings;
+        self.spawn_plot_loader(label, move |_| {
+            let mut timings = Timings::default();
+            let plot = timings.time("k-mers", || kmer_plot::kmer_dotplot(&query, &target, k));
+            if log_timings {
+                print!("{}", timings.report());
+            }
+            plot.map(|plot| (plot, None, None)).map_err(|e| {
+                format!(
+                    "Failed to build k-mer dotplot for {} vs {}: {}",
+                    query.display(),
+                    target.display(),
+                    e
+                )
+            })
+        });
+    }
+
+    fn import_layout_dialog(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Sequence layout", &["tsv", "txt"])
+            .pick_file()
+        {
+            match SequenceLayout::load(&path) {
+                Ok(layout) => self.apply_layout(&layout),
+                Err(e) => self.notifications.error(
+                    "Could not import layout",
+                    format!("{e:#}"),
+                    Some(&path),
+                ),
+            }
+        }
+    }
+
+    /// Open the current plot, in file order and before any sweep, in a new
+    /// tab in the scaffold coordinates of an AGP file
+    fn scaffold_with_agp_dialog(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("AGP", &["agp", "txt"])
+            .pick_file()
+        else {
+            return;
+        };
+        let agp = match Agp::load(&path) {
+            Ok(agp) => agp,
+            Err(e) => {
+                self.notifications
+                    .error("Could not read AGP file", format!("{e:#}"), Some(&path));
+                return;
+            }
+        };
+        let Some(base) = self
+            .unswept_plot
+            .as_ref()
+            .or(self.full_plot.as_ref())
+            .or(self.plot.as_ref())
+        else {
+            return;
+        };
+        if agp.scaffold(base).is_none() {
+            self.notifications.warning(
+                "AGP not applied",
+                "No sequences of this plot are in the AGP file.",
+                Some(&path),
+            );
+            return;
+        }
+        let base = base.clone();
+        let current_file = self.current_file.clone();
+        self.open_tab();
+        self.current_file = current_file;
+        self.pending_agp = Some(agp);
+        self.install_plot(base, None);
+    }
+
+    /// Order one axis as listed in an order file (one name per line, ` -` to flip)
+    fn import_order_dialog(&mut self, axis: Axis) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Sequence order", &["txt", "tsv"])
+            .pick_file()
+        {
+            match SequenceLayout::load_order(&path) {
+                Ok(entries) => {
+                    let mut order = SequenceLayout::default();
+                    match axis {
+                        Axis::Query => order.query = entries,
+                        Axis::Target => order.target = entries,
+                    }
+                    self.apply_order(&order);
+                }
+                Err(e) => self.notifications.error(
+                    "Could not import order",
+                    format!("{e:#}"),
+                    Some(&path),
+                ),
+            }
+        }
+    }
+
+    fn export_layout_dialog(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Sequence layout", &["tsv", "txt"])
+            .set_file_name("layout.tsv")
+            .save_file()
+        {
+            match self.current_layout().save(&path) {
+                Ok(()) => self.notifications.info(
+                    "Layout exported",
+                    format!("Saved sequence layout to {}", path.display()),
+                ),
+                Err(e) => self.notifications.error(
+                    "Could not export layout",
+                    format!("{e:#}"),
+                    Some(&path),
+                ),
+            }
+        }
+    }
+
+    /// Snapshot of the current layers, title and overlays for the exporters
+    fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            layers: self
+                .layers
+                .iter()
+                .take(self.num_layers)
+                .filter(|layer| layer.visible)
+                .cloned()
+                .collect(),
+            title: self.plot_title.clone(),
+            subtitle: self.plot_subtitle.clone(),
+            annotations: self.annotations.clone(),
+            regions: self.regions.clone(),
+            scale_bar: self.show_scale_bar && !self.normalized,
+            equal_aspect: self.export_equal_aspect,
+            genome_groups: self.genome_groups.clone(),
+            contig_boundaries: self.contig_boundaries.clone(),
+            viewport: None,
+        }
+    }
+
+    /// Render exactly the on-screen viewport to a PNG `width` pixels wide,
+    /// as tall as keeps the canvas's aspect
+    fn export_view_dialog(&mut self, width: u32) {
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        let (canvas_width, canvas_height) = self.last_canvas_size;
+        if canvas_width <= 0.0 || canvas_height <= 0.0 {
+            return;
+        }
+        let options = RenderOptions {
+            viewport: Some([
+                self.view.x,
+                self.view.y,
+                canvas_width as f64 * self.view.scale,
+                canvas_height as f64 * self.view.scale,
+            ]),
+            equal_aspect: false,
+            ..self.render_options()
+        };
+        let plot_height = ((width - PNG_MARGIN_LEFT) as f32 * canvas_height / canvas_width).round();
+        let height = plot_height as u32 + PNG_MARGIN_BOTTOM + png_title_band(&options);
+
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("PNG image", &["png"])
+            .set_file_name("alnview-view.png")
+            .save_file()
+        {
+            match render_plot_to_png(plot, &path, width, height, &options) {
+                Ok(()) => self.notifications.info(
+                    "View exported",
+                    format!("Saved {width} x {height} view to {}", path.display()),
+                ),
+                Err(e) => {
+                    self.notifications
+                        .error("Could not export view", format!("{e:#}"), Some(&path))
+                }
+            }
+        }
+    }
+
+    /// Render the displayed plot to a PNG the way it looks in the viewer
+    fn export_png_dialog(&mut self) {
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("PNG image", &["png"])
+            .set_file_name("alnview.png")
+            .save_file()
+        {
+            match render_plot_to_png(plot, &path, 1200, 1200, &self.render_options()) {
+                Ok(()) => self
+                    .notifications
+                    .info("Plot exported", format!("Saved plot to {}", path.display())),
+                Err(e) => {
+                    self.notifications
+                        .error("Could not export plot", format!("{e:#}"), Some(&path))
+                }
+            }
+        }
+    }
+
+    /// Export the regions of interest as BED (query intervals) or TSV (both axes)
+    fn export_regions_dialog(&mut self, tsv: bool) {
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        let (filter, extension) = if tsv { ("TSV", "tsv") } else { ("BED", "bed") };
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter(filter, &[extension])
+            .set_file_name(format!("regions.{extension}"))
+            .save_file()
+        {
+            let text = if tsv {
+                regions::to_tsv(&self.regions, plot)
+            } else {
+                regions::to_bed(&self.regions, plot)
+            };
+            match std::fs::write(&path, text) {
+                Ok(()) => self.notifications.info(
+                    "Regions exported",
+                    format!("Saved {} regions to {}", self.regions.len(), path.display()),
+                ),
+                Err(e) => {
+                    self.notifications
+                        .error("Could not export regions", e.to_string(), Some(&path))
+                }
+            }
+        }
+    }
+
+    fn export_tags_dialog(&mut self) {
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("TSV", &["tsv"])
+            .set_file_name("tags.tsv")
+            .save_file()
+        {
+            match std::fs::write(&path, tags::to_tsv(&self.tags, plot)) {
+                Ok(()) => self.notifications.info(
+                    "Tags exported",
+                    format!("Saved {} tags to {}", self.tags.len(), path.display()),
+                ),
+                Err(e) => {
+                    self.notifications
+                        .error("Could not export tags", e.to_string(), Some(&path))
+                }
+            }
+        }
+    }
+
+    /// Export candidate inversions and translocations as VCF
+    fn export_sv_vcf_dialog(&mut self) {
+        // The alignments shown, as the other exports take them
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("VCF", &["vcf"])
+            .set_file_name("sv_candidates.vcf")
+            .save_file()
+        {
+            let calls = sv_calls::find_sv_candidates(plot, sv_calls::DEFAULT_SV_MIN_LENGTH);
+            match std::fs::write(&path, sv_calls::to_vcf(&calls, plot)) {
+                Ok(()) => self.notifications.info(
+                    "SV candidates exported",
+                    format!(
+                        "Saved {} candidate structural variants to {}",
+                        calls.len(),
+                        path.display()
+                    ),
+                ),
+                Err(e) => {
+                    self.notifications
+                        .error("Could not export VCF", e.to_string(), Some(&path))
+                }
+            }
+        }
+    }
+
+    /// Select the passing alignments crossing a genome box; an empty box
+    /// clears the selection
+    fn select_segments(&mut self, corner: (f64, f64), other: (f64, f64)) {
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        self.selected_segments = selection::in_box(plot, corner, other);
+        if !self.selected_segments.is_empty() {
+            self.notifications.info(
+                "Alignments selected",
+                format!(
+                    "Selected {} alignments; File → Export selection... saves them as CSV or TSV",
+                    self.selected_segments.len()
+                ),
+            );
+        }
+    }
+
+    /// Sum up the passing alignments in a genome box, reading full-resolution
+    /// segments from the segment store's tiles when the plot is an overview
+    fn summarise_box(&mut self, corner: (f64, f64), other: (f64, f64)) {
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        let clamp_x = |v: f64| v.clamp(0.0, self.view.max_x) as i64;
+        let clamp_y = |v: f64| v.clamp(0.0, self.view.max_y) as i64;
+        let x = (
+            clamp_x(corner.0.min(other.0)),
+            clamp_x(corner.0.max(other.0)),
+        );
+        let y = (
+            clamp_y(corner.1.min(other.1)),
+            clamp_y(corner.1.max(other.1)),
+        );
+        if x.1 <= x.0 || y.1 <= y.0 {
+            return;
+        }
+        // The store is in the file's own layout and bp coordinates, unswept
+        let store = self.segment_store.as_ref().filter(|store| {
+            !store.is_empty()
+                && self.full_plot.is_none()
+                && !self.normalized
+                && self.sweep_filter.is_none()
+        });
+        let stats = match store {
+            Some(store) => {
+                let mut segments = store.segments_in(x.0, x.1, y.0, y.1);
+                segments.retain(|seg| plot.passes(seg));
+                selection::box_stats(&segments, x, y)
+            }
+            None => {
+                let indices = selection::in_box(plot, corner, other);
+                selection::box_stats(indices.iter().map(|&idx| &plot.segments[idx]), x, y)
+            }
+        };
+        self.box_summary = Some(BoxSummary {
+            stats,
+            locus: regions::box_locus(plot, x, y),
+        });
+    }
+
+    /// Distances between the ruler's ends; closing it leaves measure mode
+    fn measure_window(&mut self, ctx: &egui::Context) {
+        let measurement = match (self.plot.as_ref(), &self.ruler[..]) {
+            (Some(plot), &[from, to]) => Some(measure::measure(plot, from, to)),
+            _ => None,
+        };
+        let normalized = self.normalized;
+        let placed = self.ruler.len();
+        let mut open = true;
+
+        egui::Window::new("📏 Measure")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let Some(m) = measurement else {
+                    ui.label(if placed == 0 {
+                        "Click a point on the plot"
+                    } else {
+                        "Click a second point"
+                    });
+                    return;
+                };
+                let signed = |units: i64| {
+                    let sign = if units < 0 { "-" } else { "" };
+                    format!("{sign}{}", format_extent(units.abs(), normalized))
+                };
+                egui::Grid::new("measure_grid")
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        for (label, axis) in [("Query", &m.query), ("Target", &m.target)] {
+                            ui.label(label);
+                            ui.label(format!(
+                                "{}:{} → {}:{}",
+                                extract_display_name(&axis.from.0, 24),
+                                axis.from.1,
+                                extract_display_name(&axis.to.0, 24),
+                                axis.to.1
+                            ));
+                            let distance = signed(axis.distance);
+                            if axis.same_sequence {
+                                ui.strong(distance);
+                            } else {
+                                ui.strong(distance)
+                                    .on_hover_text("The ends are on different sequences");
+                            }
+                            ui.end_row();
+                        }
+                        ui.label("Off diagonal");
+                        ui.label("target − query");
+                        ui.strong(signed(m.diagonal_offset()));
+                        ui.end_row();
+                        ui.label("Off anti-diagonal");
+                        ui.label("target + query");
+                        ui.strong(signed(m.anti_diagonal_offset()));
+                        ui.end_row();
+                    });
+                if !(m.query.same_sequence && m.target.same_sequence) {
+                    ui.weak("Across sequences, distances are genome-wide");
+                }
+            });
+
+        if !open {
+            self.measuring = false;
+            self.ruler.clear();
+        }
+    }
+
+    /// Counts, bp, identity and strands of the alignments in the box summed
+    /// up by `summarise_box`
+    fn box_summary_window(&mut self, ctx: &egui::Context) {
+        let Some(summary) = self.box_summary.as_ref() else {
+            return;
+        };
+        let normalized = self.normalized;
+        let mut open = true;
+
+        egui::Window::new("Alignments in box")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if let Some(locus) = &summary.locus {
+                    ui.horizontal(|ui| {
+                        ui.monospace(locus);
+                        if ui
+                            .button("📋 Copy region")
+                            .on_hover_text("Paste into View → Go to... to come back here")
+                            .clicked()
+                        {
+                            ui.ctx().copy_text(locus.clone());
+                        }
+                    });
+                    ui.separator();
+                }
+                let stats = &summary.stats;
+                egui::Grid::new("box_summary_grid")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Alignments");
+                        ui.label(stats.segments.to_string());
+                        ui.end_row();
+                        ui.label("Aligned in box");
+                        ui.label(format_extent(stats.aligned_bp, normalized));
+                        ui.end_row();
+                        ui.label("Mean identity");
+                        ui.label(if stats.segments > 0 {
+                            format!("{:.2}%", stats.mean_identity)
+                        } else {
+                            "-".to_string()
+                        });
+                        ui.end_row();
+                        ui.label("Strands");
+                        ui.label(format!(
+                            "{} forward, {} reverse",
+                            stats.forward, stats.reverse
+                        ));
+                        ui.end_row();
+                    });
+            });
+
+        if !open {
+            self.box_summary = None;
+        }
+    }
+
+    /// Export the selected alignments as CSV, or TSV if the file name ends
+    /// in .tsv
+    fn export_selection_dialog(&mut self) {
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .add_filter("TSV", &["tsv"])
+            .set_file_name("selection.csv")
+            .save_file()
+        {
+            let separator = if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("tsv"))
+            {
+                '\t'
+            } else {
+                ','
+            };
+            let table = selection::to_table(plot, &self.selected_segments, separator);
+            match std::fs::write(&path, table) {
+                Ok(()) => self.notifications.info(
+                    "Selection exported",
+                    format!(
+                        "Saved {} alignments to {}",
+                        self.selected_segments.len(),
+                        path.display()
+                    ),
+                ),
+                Err(e) => self.notifications.error(
+                    "Could not export selection",
+                    e.to_string(),
+                    Some(&path),
+                ),
+            }
+        }
+    }
+
+    /// Export the alignments shown (visible sequences, passing the cutoffs
+    /// and sweep filter) as PAF, or copied whole to a new .1aln when they
+    /// were read from one
+    fn export_alignments_dialog(&mut self) {
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        let source = self
+            .current_file
+            .clone()
+            .filter(|file| aln_writer::is_1aln_path(file));
+        let dialog = match source {
+            Some(_) => rfd::FileDialog::new()
+                .add_filter("ONEcode alignments", &["1aln"])
+                .add_filter("PAF", &["paf"])
+                .set_file_name("filtered.1aln"),
+            None => rfd::FileDialog::new()
+                .add_filter("PAF", &["paf"])
+                .set_file_name("filtered.paf"),
+        };
+        if let Some(path) = dialog.save_file() {
+            let result = match source {
+                Some(ref source) if aln_writer::is_1aln_path(&path) => {
+                    aln_writer::write_plot(source, plot, &path)
+                }
+                _ => paf::write_plot(plot, &path),
+            };
+            match result {
+                Ok(written) => self.notifications.info(
+                    "Alignments exported",
+                    format!("Saved {written} alignments to {}", path.display()),
+                ),
+                Err(e) => self.notifications.error(
+                    "Could not export alignments",
+                    format!("{e:#}"),
+                    Some(&path),
+                ),
+            }
+        }
+    }
+
+    fn open_session_dialog(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("alnview session", &["alnsession", "tsv", "txt"])
+            .pick_file()
+        else {
+            return;
+        };
+        match Session::load(&path) {
+            Ok(session) => match session.file.clone() {
+                // Load the session's file first; the rest is applied once it arrives
+                Some(file) if self.current_file.as_ref() != Some(&file) || self.plot.is_none() => {
+                    self.pending_session = Some(session);
+                    self.load_file_async(file);
+                }
+                _ => self.apply_session(session),
+            },
+            Err(e) => {
+                self.notifications
+                    .error("Could not open session", format!("{e:#}"), Some(&path))
+            }
+        }
+    }
+
+    fn save_session_dialog(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("alnview session", &["alnsession"])
+            .set_file_name("session.alnsession")
+            .save_file()
+        {
+            let session = Session {
+                file: self.current_file.clone(),
+                view: Some(SessionView {
+                    x: self.view.x,
+                    y: self.view.y,
+                    scale: self.view.scale,
+                }),
+                title: self.plot_title.clone(),
+                subtitle: self.plot_subtitle.clone(),
+                annotations: self.annotations.clone(),
+                regions: self.regions.clone(),
+                tags: self.tags.clone(),
+            };
+            match session.save(&path) {
+                Ok(()) => self.notifications.info(
+                    "Session saved",
+                    format!("Saved session to {}", path.display()),
+                ),
+                Err(e) => self.notifications.error(
+                    "Could not save session",
+                    format!("{e:#}"),
+                    Some(&path),
+                ),
+            }
+        }
+    }
+
+    /// Restore a session's view and annotations onto the current plot
+    fn apply_session(&mut self, session: Session) {
+        self.plot_title = session.title;
+        self.plot_subtitle = session.subtitle;
+        self.annotations = session.annotations;
+        self.regions = session.regions;
+        self.tags = session.tags;
+        if let Some(view) = session.view {
+            self.view.x = view.x;
+            self.view.y = view.y;
+            self.view.scale = view.scale.max(self.min_scale);
+            self.needs_initial_fit = false;
+        }
+    }
+
+    /// Build a plot on a background thread; the result is picked up in `update()`
+    ///
+    /// `load` may report progress and should stop early once the load is
+    /// cancelled; a cancelled load's result is dropped either way.
+    fn spawn_plot_loader<F>(&mut self, label: String, load: F)
+    where
+        F: FnOnce(&LoadProgress) -> Result<LoadedPlot, String> + Send + 'static,
+    {
+        // Set loading state
+        *self.loading.lock().unwrap() = LoadingState::Loading(label, None);
+        self.load_started = Some(Instant::now());
+        let cancel = Arc::new(AtomicBool::new(false));
+        if let Some(previous) = self.load_cancel.replace(cancel.clone()) {
+            previous.store(true, Ordering::Relaxed);
+        }
+        let progress = LoadProgress {
+            loading: self.loading.clone(),
+            cancel,
+        };
+
+        // Create channel for receiving plot
+        let (tx, rx) = channel();
+        self.plot_receiver = Some(rx);
+        self.loaded_segment_store = None;
+
+        thread::spawn(move || {
+            let result = load(&progress);
+            if progress.is_cancelled() {
+                println!("🛑 Loading cancelled");
+                return;
+            }
+            if let Err(ref error_msg) = result {
+                eprintln!("❌ {error_msg}");
+            }
+            let _ = tx.send(result);
+        });
+    }
+
+    /// Abandon the plot or comparison being loaded; a reader stops at its
+    /// next progress report, anything else finishes unseen
+    fn cancel_loading(&mut self) {
+        if let Some(cancel) = self.load_cancel.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+        if self.comparison_receiver.take().is_some() {
+            self.comparison_file = None;
+        }
+        self.plot_receiver = None;
+        self.loaded_segment_store = None;
+        self.loaded_genome_groups = None;
+        *self.loading.lock().unwrap() = LoadingState::Idle;
+        // Drop the empty tab the file was to fill
+        if self.plot.is_none() && !self.tabs.is_empty() {
+            self.close_tab(self.active_tab);
+        }
+        self.notifications
+            .info("Loading cancelled", "Nothing was opened.");
+    }
+}
+
+// ============================================================================
+// Tabs
+// ============================================================================
+
+impl AlnViewApp {
+    fn is_loading(&self) -> bool {
+        matches!(*self.loading.lock().unwrap(), LoadingState::Loading(..))
+    }
+
+    /// Keep the open plot in a tab of its own and start an empty one for the
+    /// file about to load (nothing to keep if no plot is open)
+    fn open_tab(&mut self) {
+        if self.plot.is_none() {
+            return;
+        }
+        if self.tabs.is_empty() {
+            self.tabs.push(Tab::default());
+            self.active_tab = 0;
+        }
+        let (normalized, sweep_filter) = (self.normalized, self.sweep_filter);
+        let mut open = Tab::default();
+        self.swap_tab(&mut open);
+        self.tabs[self.active_tab] = open;
+        self.tabs.push(Tab::default());
+        self.active_tab = self.tabs.len() - 1;
+        self.normalized = normalized;
+        self.sweep_filter = sweep_filter;
+        self.clear_plot_caches();
+    }
+
+    /// Show tab `index`, stashing the active one
+    fn switch_tab(&mut self, index: usize) {
+        if index == self.active_tab || index >= self.tabs.len() {
+            return;
+        }
+        // A followed file stops updating once its tab is left
+        self.stop_following();
+        let mut placeholder = std::mem::take(&mut self.tabs[index]);
+        let mut active = std::mem::take(&mut self.tabs[self.active_tab]);
+        self.swap_tab(&mut active);
+        self.swap_tab(&mut placeholder);
+        self.tabs[self.active_tab] = active;
+        self.tabs[index] = placeholder;
+        self.active_tab = index;
+        self.clear_plot_caches();
+    }
+
+    fn close_tab(&mut self, index: usize) {
+        if index >= self.tabs.len() {
+            return;
+        }
+        if index == self.active_tab {
+            let neighbour = if index + 1 < self.tabs.len() {
+                index + 1
+            } else {
+                index.saturating_sub(1)
+            };
+            if neighbour == index {
+                return;
+            }
+            self.switch_tab(neighbour);
+        }
+        self.tabs.remove(index);
+        if index < self.active_tab {
+            self.active_tab -= 1;
+        }
+        // Back to a single file: no tab bar
+        if self.tabs.len() == 1 {
+            self.tabs.clear();
+            self.active_tab = 0;
+        }
+    }
+
+    /// Exchange the active document with `tab`'s
+    fn swap_tab(&mut self, tab: &mut Tab) {
+        use std::mem::swap;
+        swap(&mut self.plot, &mut tab.plot);
+        swap(&mut self.full_plot, &mut tab.full_plot);
+        swap(&mut self.unswept_plot, &mut tab.unswept_plot);
+        swap(&mut self.sweep_filter, &mut tab.sweep_filter);
+        swap(&mut self.hidden_query, &mut tab.hidden_query);
+        swap(&mut self.hidden_target, &mut tab.hidden_target);
+        swap(&mut self.flipped_query, &mut tab.flipped_query);
+        swap(&mut self.flipped_target, &mut tab.flipped_target);
+        swap(&mut self.query_order, &mut tab.query_order);
+        swap(&mut self.target_order, &mut tab.target_order);
+        swap(&mut self.annotations, &mut tab.annotations);
+        swap(&mut self.regions, &mut tab.regions);
+        swap(&mut self.tags, &mut tab.tags);
+        swap(&mut self.plot_title, &mut tab.plot_title);
+        swap(&mut self.plot_subtitle, &mut tab.plot_subtitle);
+        swap(&mut self.view, &mut tab.view);
+        swap(&mut self.view_history, &mut tab.view_history);
+        swap(&mut self.needs_initial_fit, &mut tab.needs_initial_fit);
+        swap(&mut self.normalized, &mut tab.normalized);
+        swap(&mut self.axes_swapped, &mut tab.axes_swapped);
+        swap(&mut self.layers, &mut tab.layers);
+        swap(&mut self.num_layers, &mut tab.num_layers);
+        swap(&mut self.current_file, &mut tab.current_file);
+        swap(&mut self.genome_groups, &mut tab.genome_groups);
+        swap(&mut self.contig_boundaries, &mut tab.contig_boundaries);
+        swap(&mut self.segment_store, &mut tab.segment_store);
+        swap(&mut self.comparison, &mut tab.comparison);
+        swap(&mut self.comparison_file, &mut tab.comparison_file);
+        swap(&mut self.query_gc, &mut tab.query_gc);
+        swap(&mut self.target_gc, &mut tab.target_gc);
+    }
+
+    /// Drop everything worked out from the current plot
+    fn clear_plot_caches(&mut self) {
+        self.repetitive = None;
+        self.clusters = None;
+        self.hull_clusters = None;
+        self.chain_ids = None;
+        self.cluster_index = None;
+        self.sequence_report = None;
+        self.pair_identity = None;
+        self.density = None;
+        self.visible_count = None;
+        self.minimap = None;
+        self.selected_segments.clear();
+        self.selected_segment = None;
+        self.segment_genomes = None;
+        self.partner_query = None;
+        self.box_summary = None;
+        self.ruler.clear();
+    }
+
+    /// Strip of tabs under the menu bar: click to switch, ✕ to close
+    fn tab_bar(&mut self, ctx: &egui::Context) {
+        let loading = self.is_loading();
+        let mut switch = None;
+        let mut close = None;
+        egui::TopBottomPanel::top("tab_bar").show(ctx, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for (index, tab) in self.tabs.iter().enumerate() {
+                    let active = index == self.active_tab;
+                    let file = if active {
+                        self.current_file.as_ref()
+                    } else {
+                        tab.current_file.as_ref()
+                    };
+                    let name = file
+                        .and_then(|path| path.file_name())
+                        .map_or("Untitled".to_string(), |name| {
+                            name.to_string_lossy().to_string()
+                        });
+                    let label = ui
+                        .add_enabled(!loading || active, egui::SelectableLabel::new(active, name));
+                    if let Some(path) = file {
+                        label.clone().on_hover_text(path.display().to_string());
+                    }
+                    if label.clicked() {
+                        switch = Some(index);
+                    }
+                    if ui
+                        .add_enabled(!loading, egui::Button::new("✕").small().frame(false))
+                        .on_hover_text("Close tab")
+                        .clicked()
+                    {
+                        close = Some(index);
+                    }
+                    ui.separator();
+                }
+            });
+        });
+        if let Some(index) = switch {
+            self.switch_tab(index);
+        }
+        if let Some(index) = close {
+            self.close_tab(index);
+        }
+    }
+}
+
+// ============================================================================
+// View Operations
+// ============================================================================
+
+impl AlnViewApp {
+    fn fit_view_to_canvas(&mut self, canvas_rect: egui::Rect) {
+        let scale_x = self.view.max_x / canvas_rect.width() as f64;
+        let scale_y = self.view.max_y / canvas_rect.height() as f64;
+
+        if self.fit_whole {
+            // Whole plot plus margin, centred
+            self.view.scale = self.max_scale(canvas_rect.width(), canvas_rect.height());
+            self.view.x = (self.view.max_x - canvas_rect.width() as f64 * self.view.scale) / 2.0;
+            self.view.y = (self.view.max_y - canvas_rect.height() as f64 * self.view.scale) / 2.0;
+        } else {
+            // Fit smaller dimension exactly (user can scroll for the longer one)
+            self.view.scale = scale_x.min(scale_y);
+            self.view.x = 0.0;
+            self.view.y = 0.0;
+        }
+    }
+
+    /// Most zoomed-out scale: the whole plot plus the fit margin on every side
+    fn max_scale(&self, canvas_width: f32, canvas_height: f32) -> f64 {
+        let scale_x = self.view.max_x / canvas_width as f64;
+        let scale_y = self.view.max_y / canvas_height as f64;
+        scale_x.max(scale_y) * (1.0 + 2.0 * self.fit_margin as f64)
+    }
+
+    /// Keep the view within the genome plus the fit margin; centre an axis that
+    /// is entirely visible
+    fn clamp_view_position(&mut self, canvas_rect: egui::Rect) {
+        let clamp_axis = |pos: f64, max: f64, visible: f64, margin: f64| {
+            let lo = -max * margin;
+            let hi = max * (1.0 + margin) - visible;
+            if hi >= lo {
+                pos.clamp(lo, hi)
+            } else {
+                (max - visible) / 2.0
+            }
+        };
+        let margin = self.fit_margin as f64;
+        let view_width = canvas_rect.width() as f64 * self.view.scale;
+        let view_height = canvas_rect.height() as f64 * self.view.scale;
+        self.view.x = clamp_axis(self.view.x, self.view.max_x, view_width, margin);
+        self.view.y = clamp_axis(self.view.y, self.view.max_y, view_height, margin);
+    }
+
+    fn zoom(&mut self, factor: f64) {
+        // Calculate new scale
+        let new_scale = self.view.scale / factor;
+
+        // Don't zoom out beyond the whole plot plus margin
+        // (higher scale = more zoomed out = more bp per pixel)
+        let max_scale = self.max_scale(self.last_canvas_size.0, self.last_canvas_size.1);
+
+        // Apply zoom with limits: not further out than the whole plot, nor
+        // further in than the deep-zoom limit
+        self.view.scale = new_scale.min(max_scale).max(self.min_scale);
+    }
+
+    fn zoom_at_point(&mut self, factor: f64, screen_pos: egui::Pos2, canvas_rect: egui::Rect) {
+        // Convert screen position to genome coordinates
+        let pixel_x = (screen_pos.x - canvas_rect.min.x) as f64;
+        let pixel_y = (canvas_rect.max.y - screen_pos.y) as f64;
+
+        let genome_x = self.view.x + pixel_x * self.view.scale;
+        let genome_y = self.view.y + pixel_y * self.view.scale;
+
+        // Calculate new scale
+        let new_scale = self.view.scale / factor;
+
+        // Don't zoom out beyond the whole plot plus margin
+        // (higher scale = more zoomed out = more bp per pixel)
+        let max_scale = self.max_scale(canvas_rect.width(), canvas_rect.height());
+
+        // Apply zoom with limits: not further out than the whole plot, nor
+        // further in than the deep-zoom limit
+        self.view.scale = new_scale.min(max_scale).max(self.min_scale);
+
+        // Keep the mouse position at the same genome coordinate
+        self.view.x = genome_x - pixel_x * self.view.scale;
+        self.view.y = genome_y - pixel_y * self.view.scale;
+
+        // Clamp position to prevent panning far outside genome bounds
+        self.clamp_view_position(canvas_rect);
+    }
+
+    fn reset_view(&mut self) {
+        self.needs_initial_fit = true;
+    }
+
+    /// Move the view's centre a viewport width along the x axis (back if
+    /// `direction` is negative) and onto the diagonal from corner to corner
+    fn step_along_diagonal(&mut self, direction: f64, rect: egui::Rect) {
+        let view_width = rect.width() as f64 * self.view.scale;
+        let view_height = rect.height() as f64 * self.view.scale;
+        let slope = self.view.max_y / self.view.max_x.max(1.0);
+        let centre_x = self.view.x + view_width / 2.0 + direction * view_width;
+        self.view.x = centre_x - view_width / 2.0;
+        self.view.y = centre_x * slope - view_height / 2.0;
+        self.clamp_view_position(rect);
+    }
+
+    /// Go `steps` views back or forward in the history
+    fn step_view_history(&mut self, steps: usize, forward: bool) {
+        if let Some(view) = self.view_history.step(&self.view, steps, forward) {
+            self.view = view;
+        }
+    }
+
+    /// Short description of a view for the History menu: the position at its
+    /// centre and its scale
+    fn describe_view(&self, view: &ViewState) -> String {
+        let (width, height) = self.last_canvas_size;
+        let x = view.x + width as f64 * view.scale / 2.0;
+        let y = view.y + height as f64 * view.scale / 2.0;
+        match self.plot {
+            Some(ref plot) => {
+                let (_, query, query_pos) = plot.query_coord_to_sequence(x as i64);
+                let (_, target, target_pos) = plot.target_coord_to_sequence(y as i64);
+                format!(
+                    "{}:{} × {}:{}  ({})",
+                    truncate_name(&query, 20),
+                    format_extent(query_pos, self.normalized),
+                    truncate_name(&target, 20),
+                    format_extent(target_pos, self.normalized),
+                    format_scale(view.scale)
+                )
+            }
+            None => format_scale(view.scale),
+        }
+    }
+
+    fn history_menu(&mut self, ui: &mut egui::Ui) {
+        let back = self.view_history.back.len();
+        let forward = self.view_history.forward.len();
+        if ui
+            .add_enabled(back > 0, egui::Button::new("⬅ Back").shortcut_text("Z"))
+            .clicked()
+        {
+            self.step_view_history(1, false);
+            ui.close_menu();
+        }
+        if ui
+            .add_enabled(
+                forward > 0,
+                egui::Button::new("➡ Forward").shortcut_text("⇧Z"),
+            )
+            .clicked()
+        {
+            self.step_view_history(1, true);
+            ui.close_menu();
+        }
+        ui.separator();
+        if back == 0 {
+            ui.label(egui::RichText::new("No earlier views").weak());
+        }
+        // Most recent first
+        let mut picked = None;
+        for (steps, view) in self
+            .view_history
+            .back
+            .iter()
+            .rev()
+            .enumerate()
+            .take(HISTORY_MENU_VIEWS)
+        {
+            if ui.button(self.describe_view(view)).clicked() {
+                picked = Some(steps + 1);
+            }
+        }
+        if let Some(steps) = picked {
+            self.step_view_history(steps, false);
+            ui.close_menu();
+        }
+    }
+
+    /// Hide or show one sequence; with `isolate`, show only that sequence on its axis
+    /// (or everything again if it was already isolated)
+    fn toggle_sequence(&mut self, axis: Axis, name: String, isolate: bool) {
+        let Some(plot) = self.full_plot.as_ref().or(self.plot.as_ref()) else {
+            return;
+        };
+        let (all, hidden) = match axis {
+            Axis::Query => (&plot.query_sequences, &mut self.hidden_query),
+            Axis::Target => (&plot.target_sequences, &mut self.hidden_target),
+        };
+
+        if isolate {
+            let others: HashSet<String> = all.iter().filter(|n| **n != name).cloned().collect();
+            if *hidden == others {
+                hidden.clear();
+            } else {
+                *hidden = others;
+            }
+        } else if !hidden.remove(&name) {
+            // Keep at least one sequence on each axis
+            if hidden.len() + 1 >= all.len() {
+                return;
+            }
+            hidden.insert(name);
+        }
+
+        self.apply_sequence_layout();
+    }
+
+    /// Open the filter panel with the currently hidden sequences unticked
+    fn open_sequence_filter(&mut self) {
+        let [query, target] = &mut self.sequence_filters;
+        query.unchecked = self.hidden_query.clone();
+        target.unchecked = self.hidden_target.clone();
+        self.show_sequence_filter = true;
+    }
+
+    /// Panel narrowing each axis to the sequences matching names, prefixes, a
+    /// /regex/ or an index range, minus any unticked; Apply hides the rest
+    fn sequence_filter_window(&mut self, ctx: &egui::Context) {
+        let Some(plot) = self.full_plot.as_ref().or(self.plot.as_ref()) else {
+            return;
+        };
+        let axes = [
+            ("Query", &plot.query_sequences),
+            ("Target", &plot.target_sequences),
+        ];
+        let filters = self.sequence_filters.each_ref().map(FilterDraft::filter);
+
+        let mut open = true;
+        let mut apply = false;
+        let mut reset = false;
+        egui::Window::new("Sequence filter")
+            .open(&mut open)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                ui.columns(2, |columns| {
+                    for (((ui, draft), filter), (title, names)) in columns
+                        .iter_mut()
+                        .zip(&mut self.sequence_filters)
+                        .zip(&filters)
+                        .zip(axes)
+                    {
+                        ui.strong(title);
+                        ui.add(
+                            egui::TextEdit::singleline(&mut draft.text)
+                                .hint_text("Names, prefixes or /regex/"),
+                        );
+                        ui.add(
+                            egui::TextEdit::singleline(&mut draft.range)
+                                .hint_text("Index range, e.g. 0-9"),
+                        );
+                        let filter = match filter {
+                            Ok(filter) => filter,
+                            Err(e) => {
+                                ui.colored_label(
+                                    egui::Color32::from_rgb(255, 120, 120),
+                                    format!("{e:#}"),
+                                );
+                                continue;
+                            }
+                        };
+                        let shown: Vec<usize> = (0..names.len())
+                            .filter(|&i| filter.matches(i, &names[i]))
+                            .collect();
+                        let kept = shown
+                            .iter()
+                            .filter(|&&i| !draft.unchecked.contains(&names[i]))
+                            .count();
+                        ui.label(format!("{kept} of {} sequences", names.len()));
+                        let row_height = ui.spacing().interact_size.y;
+                        egui::ScrollArea::vertical()
+                            .id_source(("filter", title))
+                            .max_height(320.0)
+                            .show_rows(ui, row_height, shown.len(), |ui, rows| {
+                                for &i in &shown[rows] {
+                                    let name = &names[i];
+                                    let mut keep = !draft.unchecked.contains(name);
+                                    if ui
+                                        .checkbox(&mut keep, extract_display_name(name, 36))
+                                        .on_hover_text(name.as_str())
+                                        .changed()
+                                    {
+                                        if keep {
+                                            draft.unchecked.remove(name);
+                                        } else {
+                                            draft.unchecked.insert(name.clone());
+                                        }
+                                    }
+                                }
+                            });
+                    }
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    apply = ui
+                        .add_enabled(
+                            filters.iter().all(Result::is_ok),
+                            egui::Button::new("Apply"),
+                        )
+                        .clicked();
+                    reset = ui
+                        .button("Reset")
+                        .on_hover_text("Clear the filters and show every sequence")
+                        .clicked();
+                });
+            });
+
+        if let (true, [Ok(query), Ok(target)]) = (apply, filters) {
+            // Everything not matching, or unticked, is hidden
+            let [query_draft, target_draft] = &self.sequence_filters;
+            let hidden = |filter: SequenceFilter, draft: &FilterDraft, names: &[String]| {
+                let filter = filter.with_hidden(draft.unchecked.clone());
+                names
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, name)| !filter.matches(i, name))
+                    .map(|(_, name)| name.clone())
+                    .collect::<HashSet<String>>()
+            };
+            let query = hidden(query, query_draft, &plot.query_sequences);
+            let target = hidden(target, target_draft, &plot.target_sequences);
+            if query.len() == plot.query_sequences.len()
+                || target.len() == plot.target_sequences.len()
+            {
+                self.notifications.warning(
+                    "Nothing to show",
+                    "The filter leaves no sequences on one of the axes.",
+                    None,
+                );
+            } else {
+                self.hidden_query = query;
+                self.hidden_target = target;
+                self.apply_sequence_layout();
             }
         }
+        if reset {
+            self.sequence_filters = Default::default();
+            self.show_all_sequences();
+        }
+        self.show_sequence_filter = open;
+    }
 
-        // Regular pan on drag (when shift not held)
-        if response.dragged() && !response.ctx.input(|i| i.modifiers.shift) {
-            let delta = response.drag_delta();
-            let dx = -delta.x as f64 * self.view.scale;
-            let dy = delta.y as f64 * self.view.scale;
-
-            let view_width = rect.width() as f64 * self.view.scale;
-            let view_height = rect.height() as f64 * self.view.scale;
+    fn show_all_sequences(&mut self) {
+        self.hidden_query.clear();
+        self.hidden_target.clear();
+        self.apply_sequence_layout();
+    }
 
-            // Clamp to genome bounds (0,0) to (max_x, max_y)
-            // When zoomed out, this prevents panning beyond genome edges
-            self.view.x = (self.view.x + dx)
-                .max(0.0)
-                .min((self.view.max_x - view_width).max(0.0));
-            self.view.y = (self.view.y + dy)
-                .max(0.0)
-                .min((self.view.max_y - view_height).max(0.0));
+    /// Recompute the repetitive-segment flags if the plot or depth changed
+    fn update_repeat_flags(&mut self) {
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        if !matches!(self.repetitive, Some((depth, _)) if depth == self.repeat_depth) {
+            self.repetitive = Some((
+                self.repeat_depth,
+                plot.repetitive_segments(self.repeat_depth),
+            ));
         }
+    }
 
-        // Scroll wheel zoom
-        if response.hovered() {
-            let scroll = response.ctx.input(|i| i.raw_scroll_delta.y);
-            if scroll != 0.0 {
-                let zoom_factor = if scroll > 0.0 { 1.2 } else { 0.8 };
-                if let Some(mouse_pos) = response.hover_pos() {
-                    self.zoom_at_point(zoom_factor, mouse_pos, rect);
-                } else {
-                    self.zoom(zoom_factor);
-                }
+    /// Hide one strand on every layer, or show it again if no layer shows it
+    fn toggle_strand(&mut self, reverse: bool) {
+        let count = self.num_layers.min(self.layers.len());
+        let layers = &mut self.layers[..count];
+        let show = !layers.iter().any(|layer| layer.shows_strand(reverse));
+        for layer in layers {
+            if reverse {
+                layer.show_reverse = show;
+            } else {
+                layer.show_forward = show;
             }
         }
     }
 
-    fn zoom_to_box(
-        &mut self,
-        canvas_rect: egui::Rect,
-        screen_start: egui::Pos2,
-        screen_end: egui::Pos2,
-    ) {
-        // Convert screen coordinates to genome coordinates
-        let screen_to_genome = |pos: egui::Pos2| -> (f64, f64) {
-            let pixel_x = (pos.x - canvas_rect.min.x) as f64;
-            let pixel_y = (canvas_rect.max.y - pos.y) as f64;
+    /// Zoom to the next (or previous) off-diagonal cluster
+    fn step_cluster(&mut self, forward: bool) {
+        let Some(ref plot) = self.plot else {
+            return;
+        };
+        let clusters = self.clusters.get_or_insert_with(|| {
+            plot.off_diagonal_clusters(rust_plot::DEFAULT_CLUSTER_MIN_SEGMENTS)
+        });
+        if clusters.is_empty() {
+            self.notifications.info(
+                "No off-diagonal clusters",
+                "Every dense group of alignments lies on the main diagonal.",
+            );
+            return;
+        }
 
-            let gx = self.view.x + pixel_x * self.view.scale;
-            let gy = self.view.y + pixel_y * self.view.scale;
-            (gx, gy)
+        let count = clusters.len();
+        let index = match (self.cluster_index, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(i), true) => (i + 1) % count,
+            (Some(i), false) => (i + count - 1) % count,
         };
+        let cluster = clusters[index].clone();
+        self.cluster_index = Some(index);
+
+        // Leave some context around the cluster
+        let pad_x = ((cluster.x_max - cluster.x_min) as f64 * 0.25).max(1000.0);
+        let pad_y = ((cluster.y_max - cluster.y_min) as f64 * 0.25).max(1000.0);
+        let (width, height) = self.last_canvas_size;
+        let canvas = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width, height));
+        self.zoom_to_region(
+            canvas,
+            cluster.x_min as f64 - pad_x,
+            cluster.y_min as f64 - pad_y,
+            cluster.x_max as f64 + pad_x,
+            cluster.y_max as f64 + pad_y,
+        );
+    }
 
-        let (x1, y1) = screen_to_genome(screen_start);
-        let (x2, y2) = screen_to_genome(screen_end);
+    /// Rebuild the displayed plot from the full plot in the chosen sequence
+    /// order, minus hidden sequences
+    fn apply_sequence_layout(&mut self) {
+        self.arrange_sequences();
+        if let Some(ref plot) = self.plot {
+            self.view.max_x = plot.get_alen() as f64;
+            self.view.max_y = plot.get_blen() as f64;
+            self.view_history.clear();
+            self.needs_initial_fit = true;
+        }
+
+        // Comparison segments live in the old coordinate system; rebuild them
+        if self.comparison.take().is_some() {
+            if let Some(path) = self.comparison_file.clone() {
+                self.load_comparison_async(path);
+            }
+        }
+    }
 
-        let min_x = x1.min(x2);
-        let max_x = x1.max(x2);
-        let min_y = y1.min(y2);
-        let max_y = y1.max(y2);
+    /// Put the target along x and the query along y (or back): transpose each
+    /// copy of the plot and everything placed on it, keeping the same part in
+    /// view
+    fn swap_axes(&mut self) {
+        use std::mem::swap;
+        for plot in [&mut self.plot, &mut self.full_plot, &mut self.unswept_plot] {
+            *plot = plot.take().map(RustPlot::transposed);
+        }
+        self.axes_swapped = !self.axes_swapped;
+        swap(&mut self.hidden_query, &mut self.hidden_target);
+        swap(&mut self.flipped_query, &mut self.flipped_target);
+        swap(&mut self.query_order, &mut self.target_order);
+        swap(&mut self.query_gc, &mut self.target_gc);
+        self.contig_boundaries = self
+            .contig_boundaries
+            .take()
+            .map(ContigBoundaries::transposed);
+        for annotation in &mut self.annotations {
+            swap(&mut annotation.x, &mut annotation.y);
+        }
+        for region in &mut self.regions {
+            swap(&mut region.x_min, &mut region.y_min);
+            swap(&mut region.x_max, &mut region.y_max);
+        }
+        for tag in &mut self.tags {
+            swap(&mut tag.x_min, &mut tag.y_min);
+            swap(&mut tag.x_max, &mut tag.y_max);
+        }
+        if let Some(ref mut comparison) = self.comparison {
+            comparison.segments = comparison
+                .segments
+                .iter()
+                .map(AlignmentSegment::transposed)
+                .collect();
+        }
+        for view in [&mut self.view, &mut self.second_view] {
+            swap(&mut view.x, &mut view.y);
+            swap(&mut view.max_x, &mut view.max_y);
+        }
+        self.view_history.clear();
+        self.clear_plot_caches();
+    }
 
-        let box_width = max_x - min_x;
-        let box_height = max_y - min_y;
+    /// Build `plot` from `full_plot` for the current order, hidden and flipped
+    /// sequences, dropping analyses of the old one
+    fn arrange_sequences(&mut self) {
+        // Only keep a second copy of the data while the layout differs from the file
+        if self.full_plot.is_none() {
+            self.full_plot = self.plot.take();
+        }
+        let Some(ref full) = self.full_plot else {
+            return;
+        };
 
-        // Save current view to history
-        self.view_history.push(self.view.clone());
+        let in_file_order = |order: &[usize]| order.iter().enumerate().all(|(i, &j)| i == j);
+        if self.hidden_query.is_empty()
+            && self.hidden_target.is_empty()
+            && self.flipped_query.is_empty()
+            && self.flipped_target.is_empty()
+            && in_file_order(&self.query_order)
+            && in_file_order(&self.target_order)
+            && !self.normalized
+        {
+            self.plot = self.full_plot.take();
+        } else {
+            let query_filter = SequenceFilter::new().with_hidden(self.hidden_query.clone());
+            let target_filter = SequenceFilter::new().with_hidden(self.hidden_target.clone());
+            let query_order: Vec<(usize, bool)> = self
+                .query_order
+                .iter()
+                .copied()
+                .filter(|&i| query_filter.matches(i, &full.query_sequences[i]))
+                .map(|i| (i, self.flipped_query.contains(&full.query_sequences[i])))
+                .collect();
+            let target_order: Vec<(usize, bool)> = self
+                .target_order
+                .iter()
+                .copied()
+                .filter(|&i| target_filter.matches(i, &full.target_sequences[i]))
+                .map(|i| (i, self.flipped_target.contains(&full.target_sequences[i])))
+                .collect();
+            let arranged = full.with_layout(&query_order, &target_order);
+            self.plot = Some(if self.normalized {
+                arranged.normalized()
+            } else {
+                arranged
+            });
+        }
 
-        // Set new view position
-        self.view.x = min_x.max(0.0);
-        self.view.y = min_y.max(0.0);
+        self.clear_plot_caches();
+    }
 
-        // Calculate new scale to fit the box in the canvas
-        let scale_for_width = box_width / canvas_rect.width() as f64;
-        let scale_for_height = box_height / canvas_rect.height() as f64;
-        self.view.scale = scale_for_width.max(scale_for_height).max(0.1);
+    /// The current order and visibility, as a layout file would record it
+    fn current_layout(&self) -> SequenceLayout {
+        let Some(plot) = self.full_plot.as_ref().or(self.plot.as_ref()) else {
+            return SequenceLayout::default();
+        };
+        let entries = |order: &[usize],
+                       names: &[String],
+                       hidden: &HashSet<String>,
+                       flipped: &HashSet<String>| {
+            order
+                .iter()
+                .map(|&i| &names[i])
+                .filter(|name| !hidden.contains(*name))
+                .map(|name| LayoutEntry {
+                    name: name.clone(),
+                    reverse: flipped.contains(name),
+                })
+                .collect()
+        };
 
-        // Clamp position (allow zooming out beyond genome bounds)
-        self.view.x = self.view.x.max(0.0);
-        self.view.y = self.view.y.max(0.0);
+        SequenceLayout {
+            query: entries(
+                &self.query_order,
+                &plot.query_sequences,
+                &self.hidden_query,
+                &self.flipped_query,
+            ),
+            target: entries(
+                &self.target_order,
+                &plot.target_sequences,
+                &self.hidden_target,
+                &self.flipped_target,
+            ),
+        }
     }
-}
 
-// ============================================================================
-// File Operations
-// ============================================================================
+    /// Adopt a layout's order; sequences it does not list are hidden
+    fn apply_layout(&mut self, layout: &SequenceLayout) {
+        let Some(plot) = self.full_plot.as_ref().or(self.plot.as_ref()) else {
+            return;
+        };
 
-impl AlnViewApp {
-    fn open_file_dialog(&mut self) {
-        if let Some(path) = rfd::FileDialog::new()
-            .add_filter("Alignment Files", &["1aln"])
-            .pick_file()
-        {
-            self.load_file_async(path);
+        let (mut query_order, query_unlisted, query_missing) =
+            sequence_layout::resolve(&layout.query, &plot.query_sequences);
+        let (mut target_order, target_unlisted, target_missing) =
+            sequence_layout::resolve(&layout.target, &plot.target_sequences);
+
+        self.hidden_query = query_unlisted
+            .iter()
+            .map(|&i| plot.query_sequences[i].clone())
+            .collect();
+        self.hidden_target = target_unlisted
+            .iter()
+            .map(|&i| plot.target_sequences[i].clone())
+            .collect();
+        query_order.extend(query_unlisted);
+        target_order.extend(target_unlisted);
+        (self.flipped_query, self.flipped_target) = layout.reversed_names();
+        self.query_order = query_order;
+        self.target_order = target_order;
+
+        let missing = query_missing + target_missing;
+        if missing > 0 {
+            self.notifications.warning(
+                "Layout partly applied",
+                format!("{missing} sequences in the layout are not in this plot."),
+                None,
+            );
         }
+
+        self.apply_sequence_layout();
     }
 
-    fn load_file_async(&mut self, path: PathBuf) {
-        let loading = Arc::clone(&self.loading);
+    /// Put the sequences an order lists first on their axes, in its order and
+    /// orientation; the rest follow in file order and hidden ones stay hidden
+    fn apply_order(&mut self, order: &SequenceLayout) {
+        let Some(plot) = self.full_plot.as_ref().or(self.plot.as_ref()) else {
+            return;
+        };
+        let (order, missing) = order.completed(plot);
+        let index = |names: &[String]| -> HashMap<String, usize> {
+            names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| (name.clone(), i))
+                .collect()
+        };
+        let query_index = index(&plot.query_sequences);
+        let target_index = index(&plot.target_sequences);
+        for (entries, index, order, flipped) in [
+            (
+                &order.query,
+                &query_index,
+                &mut self.query_order,
+                &mut self.flipped_query,
+            ),
+            (
+                &order.target,
+                &target_index,
+                &mut self.target_order,
+                &mut self.flipped_target,
+            ),
+        ] {
+            if entries.is_empty() {
+                continue;
+            }
+            *order = entries.iter().map(|entry| index[&entry.name]).collect();
+            *flipped = entries
+                .iter()
+                .filter(|entry| entry.reverse)
+                .map(|entry| entry.name.clone())
+                .collect();
+        }
 
-        // Set loading state
-        *loading.lock().unwrap() = LoadingState::Loading(
-            path.file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("file")
-                .to_string(),
-        );
+        if missing > 0 {
+            self.notifications.warning(
+                "Order partly applied",
+                format!("{missing} sequences in the order file are not in this plot."),
+                None,
+            );
+        }
 
-        println!("🔍 Starting async load: {}", path.display());
+        self.apply_sequence_layout();
+    }
 
-        // Create channel for receiving plot
-        let (tx, rx) = channel();
-        self.plot_receiver = Some(rx);
-        self.current_file = Some(path.clone());
+    /// Move the sequence at position `from` of an axis order to position `to`
+    fn move_sequence(&mut self, axis: Axis, from: usize, to: usize) {
+        let order = match axis {
+            Axis::Query => &mut self.query_order,
+            Axis::Target => &mut self.target_order,
+        };
+        if from == to || from >= order.len() {
+            return;
+        }
+        let idx = order.remove(from);
+        // Removing shifts later positions down by one
+        let to = if to > from { to - 1 } else { to };
+        order.insert(to.min(order.len()), idx);
+        self.apply_sequence_layout();
+    }
 
-        // Spawn background thread for loading using Rust reader
-        thread::spawn(move || {
-            println!("🧵 Background thread: Loading file with Rust reader...");
+    /// Reverse-complement a sequence in the display, or undo that
+    fn flip_sequence(&mut self, axis: Axis, name: String) {
+        let flipped = match axis {
+            Axis::Query => &mut self.flipped_query,
+            Axis::Target => &mut self.flipped_target,
+        };
+        if !flipped.remove(&name) {
+            flipped.insert(name);
+        }
+        self.apply_sequence_layout();
+    }
 
-            match RustPlot::from_file(&path) {
-                Ok(plot) => {
-                    println!("✅ Rust plot loaded successfully!");
-                    let _ = tx.send(Ok(plot));
-                }
-                Err(e) => {
-                    let error_msg = format!("Failed to load {}: {}", path.display(), e);
-                    eprintln!("❌ {error_msg}");
-                    let _ = tx.send(Err(error_msg));
+    /// Order and orient the visible query sequences along the target as
+    /// drawn; hidden ones keep their place after them
+    fn auto_arrange(&mut self) {
+        let (Some(plot), Some(full)) = (
+            self.plot.as_ref(),
+            self.full_plot.as_ref().or(self.plot.as_ref()),
+        ) else {
+            return;
+        };
+        let index: HashMap<&str, usize> = full
+            .query_sequences
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+        let mut order = Vec::with_capacity(self.query_order.len());
+        for (shown, flip) in plot.diagonal_layout() {
+            let name = &plot.query_sequences[shown];
+            if let Some(&i) = index.get(name.as_str()) {
+                order.push(i);
+                if flip && !self.flipped_query.remove(name) {
+                    self.flipped_query.insert(name.clone());
                 }
             }
-        });
+        }
+        let placed: HashSet<usize> = order.iter().copied().collect();
+        order.extend(self.query_order.iter().filter(|i| !placed.contains(i)));
+        self.query_order = order;
+        self.apply_sequence_layout();
     }
-}
-
-// ============================================================================
-// View Operations
-// ============================================================================
 
-impl AlnViewApp {
-    fn fit_view_to_canvas(&mut self, canvas_rect: egui::Rect) {
-        // Calculate scale to fit smaller dimension exactly (user can scroll for the longer one)
-        let scale_x = self.view.max_x / canvas_rect.width() as f64;
-        let scale_y = self.view.max_y / canvas_rect.height() as f64;
-        self.view.scale = scale_x.min(scale_y);
-        self.view.x = 0.0;
-        self.view.y = 0.0;
+    /// Where a sequence dragged to `pos` would land: the sequence there, the
+    /// screen position of the edge it goes next to, and whether it goes after
+    fn sequence_drop(
+        &self,
+        rect: egui::Rect,
+        axis: Axis,
+        pos: egui::Pos2,
+    ) -> Option<(String, f32, bool)> {
+        let plot = self.plot.as_ref()?;
+        let (x, y) = self.screen_to_genome(rect, pos);
+        let (coord, boundaries, (idx, name, _)) = match axis {
+            Axis::Query => (
+                x,
+                &plot.query_boundaries,
+                plot.query_coord_to_sequence(x as i64),
+            ),
+            Axis::Target => (
+                y,
+                &plot.target_boundaries,
+                plot.target_coord_to_sequence(y as i64),
+            ),
+        };
+        let (start, end) = (*boundaries.get(idx)?, *boundaries.get(idx + 1)?);
+        let after = coord > (start + end) as f64 / 2.0;
+        let edge = if after { end } else { start } as f64;
+        let screen = match axis {
+            Axis::Query => rect.min.x + ((edge - self.view.x) / self.view.scale) as f32,
+            Axis::Target => rect.max.y - ((edge - self.view.y) / self.view.scale) as f32,
+        };
+        Some((name, screen, after))
     }
 
-    fn zoom(&mut self, factor: f64) {
-        // Calculate new scale
-        let new_scale = self.view.scale / factor;
-
-        // Don't zoom out beyond where smaller dimension fills the window
-        // (higher scale = more zoomed out = more bp per pixel)
-        let max_scale_x = self.view.max_x / self.last_canvas_size.0 as f64;
-        let max_scale_y = self.view.max_y / self.last_canvas_size.1 as f64;
-        let max_scale = max_scale_x.min(max_scale_y);
+    /// Move sequence `name` next to `target` (before it, or after)
+    fn drop_sequence(&mut self, axis: Axis, name: &str, target: &str, after: bool) {
+        let Some(plot) = self.full_plot.as_ref().or(self.plot.as_ref()) else {
+            return;
+        };
+        let (names, order) = match axis {
+            Axis::Query => (&plot.query_sequences, &self.query_order),
+            Axis::Target => (&plot.target_sequences, &self.target_order),
+        };
+        let position = |wanted: &str| order.iter().position(|&i| names[i] == wanted);
+        if let (Some(from), Some(to)) = (position(name), position(target)) {
+            self.move_sequence(axis, from, to + usize::from(after));
+        }
+    }
 
-        // Apply zoom with limit: don't zoom out too far
-        self.view.scale = new_scale.min(max_scale);
+    fn reset_sequence_order(&mut self, axis: Axis) {
+        match axis {
+            Axis::Query => self.query_order.sort_unstable(),
+            Axis::Target => self.target_order.sort_unstable(),
+        }
+        self.apply_sequence_layout();
     }
+}
 
-    fn zoom_at_point(&mut self, factor: f64, screen_pos: egui::Pos2, canvas_rect: egui::Rect) {
-        // Convert screen position to genome coordinates
-        let pixel_x = (screen_pos.x - canvas_rect.min.x) as f64;
-        let pixel_y = (canvas_rect.max.y - screen_pos.y) as f64;
+// ============================================================================
+// Helper Functions
+// ============================================================================
 
-        let genome_x = self.view.x + pixel_x * self.view.scale;
-        let genome_y = self.view.y + pixel_y * self.view.scale;
+/// Colors for the comparison overlay
+fn presence_color(presence: Presence) -> egui::Color32 {
+    match presence {
+        Presence::Both => egui::Color32::from_gray(200),
+        Presence::OnlyFirst => egui::Color32::from_rgb(0, 170, 255),
+        Presence::OnlySecond => egui::Color32::from_rgb(255, 0, 200),
+    }
+}
 
-        // Calculate new scale
-        let new_scale = self.view.scale / factor;
+/// One of `count` evenly spaced hues, for telling genomes apart
+fn genome_color(idx: usize, count: usize) -> egui::Color32 {
+    let hue = idx as f32 / count.max(1) as f32;
+    egui::ecolor::Hsva::new(hue, 0.8, 1.0, 1.0).into()
+}
 
-        // Don't zoom out beyond where smaller dimension fills the window
-        // (higher scale = more zoomed out = more bp per pixel)
-        let max_scale_x = self.view.max_x / canvas_rect.width() as f64;
-        let max_scale_y = self.view.max_y / canvas_rect.height() as f64;
-        let max_scale = max_scale_x.min(max_scale_y);
+/// An `[r, g, b]` colour from the colour maps and palettes as egui's
+fn rgb([r, g, b]: [u8; 3]) -> egui::Color32 {
+    egui::Color32::from_rgb(r, g, b)
+}
 
-        // Apply zoom with limit: don't zoom out too far
-        self.view.scale = new_scale.min(max_scale);
+/// Colour of chain `id`: hues stepped by the golden ratio, so neighbouring chains
+/// never look alike however many there are
+fn chain_color(id: usize) -> egui::Color32 {
+    let hue = (id as f32 * 0.618_034).fract();
+    egui::ecolor::Hsva::new(hue, 0.75, 1.0, 1.0).into()
+}
 
-        // Keep the mouse position at the same genome coordinate
-        self.view.x = genome_x - pixel_x * self.view.scale;
-        self.view.y = genome_y - pixel_y * self.view.scale;
+/// GC% strip colour: blue at 30% GC or below, grey at 50%, red at 70% or above
+fn gc_color(gc: f32) -> egui::Color32 {
+    let t = ((gc - 0.3) / 0.4).clamp(0.0, 1.0);
+    let (from, to, t) = if t < 0.5 {
+        ([60.0, 100.0, 255.0], [200.0, 200.0, 200.0], t * 2.0)
+    } else {
+        ([200.0, 200.0, 200.0], [255.0, 60.0, 40.0], t * 2.0 - 1.0)
+    };
+    let channel = |i: usize| (from[i] + (to[i] - from[i]) * t) as u8;
+    egui::Color32::from_rgb(channel(0), channel(1), channel(2))
+}
 
-        // Clamp position to prevent panning outside genome bounds
-        let view_width = canvas_rect.width() as f64 * self.view.scale;
-        let view_height = canvas_rect.height() as f64 * self.view.scale;
+/// Background tint for a sequence pair's mean identity: blue at 70% or
+/// below through to yellow at 100%
+fn identity_color(identity: f32) -> egui::Color32 {
+    let t = ((identity - 70.0) / 30.0).clamp(0.0, 1.0);
+    let lerp = |a: f32, b: f32| (a + (b - a) * t) as u8;
+    egui::Color32::from_rgba_unmultiplied(
+        lerp(40.0, 255.0),
+        lerp(60.0, 220.0),
+        lerp(200.0, 0.0),
+        60,
+    )
+}
 
-        // Clamp to genome bounds (handle both zoomed in and zoomed out)
-        self.view.x = self
-            .view
-            .x
-            .max(0.0)
-            .min((self.view.max_x - view_width).max(0.0));
-        self.view.y = self
-            .view
-            .y
-            .max(0.0)
-            .min((self.view.max_y - view_height).max(0.0));
+/// Dark visuals with white text, bright outlines on every widget and a
+/// yellow selection, for the high-contrast mode
+fn high_contrast_visuals() -> egui::Visuals {
+    let mut visuals = egui::Visuals::dark();
+    visuals.override_text_color = Some(egui::Color32::WHITE);
+    visuals.panel_fill = egui::Color32::BLACK;
+    visuals.window_fill = egui::Color32::BLACK;
+    visuals.window_stroke = egui::Stroke::new(1.5, egui::Color32::WHITE);
+    visuals.extreme_bg_color = egui::Color32::BLACK;
+    visuals.hyperlink_color = HIGH_CONTRAST_REVERSE;
+    visuals.selection.bg_fill = egui::Color32::from_rgb(110, 90, 0);
+    visuals.selection.stroke = egui::Stroke::new(2.0, HIGH_CONTRAST_FORWARD);
+    let widgets = &mut visuals.widgets;
+    for (state, width) in [
+        (&mut widgets.noninteractive, 1.0),
+        (&mut widgets.inactive, 1.0),
+        (&mut widgets.hovered, 2.0),
+        (&mut widgets.active, 2.0),
+        (&mut widgets.open, 2.0),
+    ] {
+        state.bg_stroke = egui::Stroke::new(width, egui::Color32::WHITE);
+        state.fg_stroke = egui::Stroke::new(width, egui::Color32::WHITE);
     }
+    widgets.hovered.bg_stroke.color = HIGH_CONTRAST_FORWARD;
+    visuals
+}
 
-    fn reset_view(&mut self) {
-        self.needs_initial_fit = true;
+/// A spinner while a load's progress is unknown, then a bar with the percentage
+fn loading_progress(ui: &mut egui::Ui, progress: Option<f32>) {
+    match progress {
+        Some(fraction) => {
+            ui.add(
+                egui::ProgressBar::new(fraction)
+                    .desired_width(160.0)
+                    .show_percentage(),
+            );
+        }
+        None => {
+            ui.spinner();
+        }
     }
 }
 
-// ============================================================================
-// Helper Functions
-// ============================================================================
+/// `response` with `label` as both its tooltip and the name screen readers
+/// announce, for buttons that only show an icon
+fn labeled(response: egui::Response, label: &str) -> egui::Response {
+    response.widget_info(|| {
+        egui::WidgetInfo::labeled(egui::WidgetType::Button, response.enabled(), label)
+    });
+    response.on_hover_text(label)
+}
+
+/// Density heatmap colour for `t` from 0 (sparsest) to 1 (densest): dark
+/// purple through red to pale yellow
+fn density_color(t: f32) -> egui::Color32 {
+    const STOPS: [[f32; 3]; 4] = [
+        [60.0, 20.0, 110.0],
+        [200.0, 40.0, 90.0],
+        [250.0, 150.0, 30.0],
+        [255.0, 255.0, 190.0],
+    ];
+    let position = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f32;
+    let (low, frac) = ((position as usize).min(STOPS.len() - 2), position.fract());
+    let frac = if position >= (STOPS.len() - 1) as f32 {
+        1.0
+    } else {
+        frac
+    };
+    let channel = |c: usize| (STOPS[low][c] + (STOPS[low + 1][c] - STOPS[low][c]) * frac) as u8;
+    egui::Color32::from_rgb(channel(0), channel(1), channel(2))
+}
+
+/// Colour for a segment flagged as repetitive
+fn repeat_color(color: egui::Color32, display: RepeatDisplay) -> egui::Color32 {
+    match display {
+        RepeatDisplay::Normal => color,
+        RepeatDisplay::Dim => color.gamma_multiply(0.2),
+        RepeatDisplay::Recolor => egui::Color32::from_rgb(255, 165, 0),
+    }
+}
 
 /// Extract meaningful part of sequence name for display
 fn extract_display_name(name: &str, max_len: usize) -> String {
@@ -1245,9 +10260,12 @@ fn extract_display_name(name: &str, max_len: usize) -> String {
 
             // Prefer identifiers that look like scaffold/chromosome names
             for word in words.iter().rev() {
-                if word.contains("HSCHR") || word.contains("chr") ||
-                   word.starts_with("HLA-") || word.contains("CTG") ||
-                   (word.len() > 3 && word.chars().any(|c| c.is_uppercase())) {
+                if word.contains("HSCHR")
+                    || word.contains("chr")
+                    || word.starts_with("HLA-")
+                    || word.contains("CTG")
+                    || (word.len() > 3 && word.chars().any(|c| c.is_uppercase()))
+                {
                     return truncate_name(word, max_len);
                 }
             }
@@ -1263,11 +10281,375 @@ fn extract_display_name(name: &str, max_len: usize) -> String {
     truncate_name(name, max_len)
 }
 
+/// Draw regions of interest as tinted, outlined rectangles with their names
+fn draw_regions(
+    painter: &egui::Painter,
+    regions: &[Region],
+    genome_to_screen: impl Fn(f64, f64) -> egui::Pos2,
+) {
+    for region in regions {
+        let rect = egui::Rect::from_two_pos(
+            genome_to_screen(region.x_min as f64, region.y_min as f64),
+            genome_to_screen(region.x_max as f64, region.y_max as f64),
+        );
+        if !painter.clip_rect().intersects(rect) {
+            continue;
+        }
+        let [r, g, b] = region.color;
+        let color = egui::Color32::from_rgb(r, g, b);
+        painter.rect_filled(rect, 0.0, color.gamma_multiply(0.15));
+        painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.5, color));
+        painter.text(
+            rect.left_top() + egui::vec2(3.0, 2.0),
+            egui::Align2::LEFT_TOP,
+            &region.name,
+            egui::FontId::proportional(11.0),
+            color,
+        );
+    }
+}
+
+/// Draw tagged alignments as outlined lines, and tag labels on them and on
+/// tagged regions
+fn draw_tags(
+    painter: &egui::Painter,
+    tags: &[Tag],
+    genome_to_screen: impl Fn(f64, f64) -> egui::Pos2,
+) {
+    let color = egui::Color32::from_rgb(255, 120, 255);
+    for tag in tags {
+        let anchor = match tag.target {
+            TagTarget::Segment => {
+                let [(x1, y1), (x2, y2)] = tag.line();
+                let (p1, p2) = (
+                    genome_to_screen(x1 as f64, y1 as f64),
+                    genome_to_screen(x2 as f64, y2 as f64),
+                );
+                painter.line_segment([p1, p2], egui::Stroke::new(3.0, color));
+                p1.lerp(p2, 0.5)
+            }
+            // Under the region's own name
+            TagTarget::Region => {
+                genome_to_screen(tag.x_min as f64, tag.y_max as f64) + egui::vec2(3.0, 16.0)
+            }
+        };
+        if !painter.clip_rect().contains(anchor) {
+            continue;
+        }
+        let galley = painter.layout_no_wrap(
+            format!("🏷 {}", tag.tag),
+            egui::FontId::proportional(11.0),
+            color,
+        );
+        let rect = egui::Align2::LEFT_TOP
+            .anchor_size(anchor, galley.size())
+            .expand(2.0);
+        painter.rect_filled(rect, 2.0, egui::Color32::from_black_alpha(170));
+        painter.galley(rect.min + egui::vec2(2.0, 2.0), galley, color);
+    }
+}
+
+/// A length or local position for display: bp, or % of the sequence when
+/// every sequence is stretched to `NORMALIZED_BAND`
+fn format_extent(units: i64, normalized: bool) -> String {
+    if normalized {
+        format!(
+            "{:.2}%",
+            units as f64 * 100.0 / rust_plot::NORMALIZED_BAND as f64
+        )
+    } else {
+        format_bp(units)
+    }
+}
+
+/// Ruler ticks for one axis over [start, start + len], with the name of the
+/// sequence they count along if the view lies inside one sequence (otherwise
+/// they count along the whole genome)
+fn axis_ticks_in_view<'a>(
+    start: f64,
+    len: f64,
+    boundaries: &[i64],
+    lengths: &[i64],
+    reversed: &[bool],
+    names: &'a [String],
+    major: i64,
+) -> (Vec<rust_plot::AxisTick>, Option<&'a str>) {
+    let genome_len: i64 = lengths.iter().sum();
+    let sequence_at = |coord: f64| {
+        let coord = coord.clamp(0.0, (genome_len - 1).max(0) as f64) as i64;
+        boundaries
+            .partition_point(|&b| b <= coord)
+            .saturating_sub(1)
+    };
+    let idx = sequence_at(start);
+    if lengths.len() > 1 && idx == sequence_at(start + len) {
+        let ticks = rust_plot::axis_ticks(
+            start,
+            start + len,
+            boundaries[idx],
+            lengths[idx],
+            reversed.get(idx).copied().unwrap_or(false),
+            major,
+        );
+        (ticks, names.get(idx).map(String::as_str))
+    } else {
+        let ticks = rust_plot::axis_ticks(start, start + len, 0, genome_len, false, major);
+        (ticks, None)
+    }
+}
+
+/// Draw a round-length scale bar in the bottom-right corner of the canvas
+fn draw_scale_bar(painter: &egui::Painter, rect: egui::Rect, bp_per_px: f64) {
+    let (len, label) = scale_bar(bp_per_px, SCALE_BAR_MAX_PX);
+    let width = (len as f64 / bp_per_px) as f32;
+    let right = rect.right_bottom() + egui::vec2(-16.0, -16.0);
+    let left = right - egui::vec2(width, 0.0);
+    let color = egui::Color32::WHITE;
+
+    let galley = painter.layout_no_wrap(label, egui::FontId::proportional(12.0), color);
+    let text_pos = egui::pos2(right.x - galley.size().x, right.y - 6.0 - galley.size().y);
+    let background = egui::Rect::from_two_pos(left, text_pos).expand(4.0);
+    painter.rect_filled(
+        background.union(egui::Rect::from_min_size(text_pos, galley.size()).expand(4.0)),
+        3.0,
+        egui::Color32::from_black_alpha(160),
+    );
+    painter.galley(text_pos, galley, color);
+
+    let stroke = egui::Stroke::new(2.0, color);
+    painter.line_segment([left, right], stroke);
+    for x in [left.x, right.x] {
+        painter.line_segment(
+            [egui::pos2(x, right.y - 4.0), egui::pos2(x, right.y + 4.0)],
+            stroke,
+        );
+    }
+}
+
+/// Draw the identity colour bar of each layer coloured by identity in the
+/// top-right corner of the canvas
+fn draw_identity_legend(painter: &egui::Painter, rect: egui::Rect, layers: &[&LayerSettings]) {
+    const BAR: egui::Vec2 = egui::vec2(120.0, 10.0);
+    let color = egui::Color32::WHITE;
+    let font = egui::FontId::proportional(11.0);
+    let row_height = BAR.y + 34.0; // Name above the bar, identities below
+    let background = egui::Rect::from_min_size(
+        rect.right_top() + egui::vec2(-BAR.x - 28.0, 8.0),
+        egui::vec2(BAR.x + 20.0, row_height * layers.len() as f32 + 6.0),
+    );
+    painter.rect_filled(background, 3.0, egui::Color32::from_black_alpha(160));
+
+    let mut top = background.min + egui::vec2(10.0, 4.0);
+    for layer in layers {
+        let gradient = &layer.gradient;
+        painter.text(
+            top,
+            egui::Align2::LEFT_TOP,
+            truncate_name(&layer.name, 20),
+            font.clone(),
+            color,
+        );
+        let bar = egui::Rect::from_min_size(top + egui::vec2(0.0, 14.0), BAR);
+        let steps = BAR.x as usize / 2;
+        for step in 0..steps {
+            let [r, g, b] = gradient.colormap.color(step as f32 / (steps - 1) as f32);
+            let x = bar.min.x + step as f32 * 2.0;
+            painter.rect_filled(
+                egui::Rect::from_x_y_ranges(x..=x + 2.0, bar.y_range()),
+                0.0,
+                egui::Color32::from_rgb(r, g, b),
+            );
+        }
+        for (corner, align, identity) in [
+            (
+                bar.left_bottom(),
+                egui::Align2::LEFT_TOP,
+                gradient.min_identity,
+            ),
+            (
+                bar.right_bottom(),
+                egui::Align2::RIGHT_TOP,
+                gradient.max_identity,
+            ),
+        ] {
+            painter.text(
+                corner + egui::vec2(0.0, 2.0),
+                align,
+                format!("{identity:.0}%"),
+                font.clone(),
+                color,
+            );
+        }
+        top.y += row_height;
+    }
+}
+
+/// Draw each cluster's convex hull with its alignment count
+fn draw_cluster_hulls(
+    painter: &egui::Painter,
+    clusters: &[Cluster],
+    genome_to_screen: impl Fn(f64, f64) -> egui::Pos2,
+) {
+    let color = egui::Color32::from_rgb(120, 200, 255);
+    let stroke = egui::Stroke::new(1.0, color);
+    for cluster in clusters {
+        let points: Vec<egui::Pos2> = cluster
+            .hull
+            .iter()
+            .map(|&(x, y)| genome_to_screen(x as f64, y as f64))
+            .collect();
+        let bounds = egui::Rect::from_points(&points);
+        if !painter.clip_rect().intersects(bounds.expand(1.0)) {
+            continue;
+        }
+        if points.len() >= 3 {
+            painter.add(egui::Shape::convex_polygon(
+                points,
+                color.gamma_multiply(0.15),
+                stroke,
+            ));
+        } else if let [a, b] = points[..] {
+            painter.line_segment([a, b], egui::Stroke::new(3.0, color.gamma_multiply(0.5)));
+        }
+        painter.text(
+            bounds.center_top() - egui::vec2(0.0, 2.0),
+            egui::Align2::CENTER_BOTTOM,
+            cluster.segments.to_string(),
+            egui::FontId::proportional(11.0),
+            color,
+        );
+    }
+}
+
+/// Draw text annotations, with arrows pointing at their positions where asked
+fn draw_annotations(
+    painter: &egui::Painter,
+    annotations: &[Annotation],
+    genome_to_screen: impl Fn(f64, f64) -> egui::Pos2,
+) {
+    let font = egui::FontId::proportional(13.0);
+    for annotation in annotations {
+        let point = genome_to_screen(annotation.x, annotation.y);
+        let anchor = if annotation.arrow {
+            let label = point + egui::vec2(ANNOTATION_ARROW_PX, -ANNOTATION_ARROW_PX);
+            painter.arrow(
+                label,
+                point - label,
+                egui::Stroke::new(1.5, egui::Color32::WHITE),
+            );
+            label
+        } else {
+            point
+        };
+        if !painter.clip_rect().expand(200.0).contains(anchor) {
+            continue;
+        }
+        let galley =
+            painter.layout_no_wrap(annotation.text.clone(), font.clone(), egui::Color32::WHITE);
+        let align = if annotation.arrow {
+            egui::Align2::LEFT_BOTTOM
+        } else {
+            egui::Align2::CENTER_CENTER
+        };
+        let rect = align.anchor_size(anchor, galley.size()).expand(3.0);
+        painter.rect_filled(rect, 3.0, egui::Color32::from_black_alpha(180));
+        painter.galley(
+            rect.min + egui::vec2(3.0, 3.0),
+            galley,
+            egui::Color32::WHITE,
+        );
+    }
+}
+
+/// Draw the ruler's ends, the line between them and its legs along each axis
+fn draw_ruler(
+    painter: &egui::Painter,
+    ruler: &[(f64, f64)],
+    genome_to_screen: impl Fn(f64, f64) -> egui::Pos2,
+) {
+    let color = egui::Color32::from_rgb(0, 230, 230);
+    let points: Vec<egui::Pos2> = ruler.iter().map(|&(x, y)| genome_to_screen(x, y)).collect();
+    if let [a, b] = points[..] {
+        let corner = egui::pos2(b.x, a.y);
+        let leg = egui::Stroke::new(1.0, color.gamma_multiply(0.6));
+        painter.extend(egui::Shape::dashed_line(&[a, corner, b], leg, 6.0, 4.0));
+        painter.line_segment([a, b], egui::Stroke::new(2.0, color));
+    }
+    for point in points {
+        painter.circle_stroke(point, 4.0, egui::Stroke::new(2.0, color));
+    }
+}
+
+/// Draw axis sequence labels, highlighting the one under the cursor
+fn draw_sequence_labels(painter: &egui::Painter, labels: &[SequenceLabel], hovered: Option<usize>) {
+    for (idx, label) in labels.iter().enumerate() {
+        let (fill, text_color) = if hovered == Some(idx) {
+            (egui::Color32::from_gray(90), egui::Color32::WHITE)
+        } else {
+            (
+                egui::Color32::from_black_alpha(160),
+                egui::Color32::LIGHT_GRAY,
+            )
+        };
+        painter.rect_filled(label.rect, 2.0, fill);
+        painter.text(
+            label.rect.center(),
+            egui::Align2::CENTER_CENTER,
+            &label.text,
+            egui::FontId::proportional(10.0),
+            text_color,
+        );
+    }
+}
+
+/// Scale as bp/px when zoomed out, px/bp when bases are wider than a pixel
+fn format_scale(scale: f64) -> String {
+    if scale >= 1.0 {
+        format!("{scale:.1} bp/px")
+    } else {
+        format!("{:.1} px/bp", 1.0 / scale)
+    }
+}
+
+/// Human-readable byte count for the status bar
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Ask the user for a FASTA file
+fn pick_fasta(title: &str) -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .set_title(title)
+        .add_filter("FASTA Files", &["fa", "fasta", "fna", "fas"])
+        .add_filter("All Files", &["*"])
+        .pick_file()
+}
+
+/// Short label for an optional FASTA path in dialogs
+fn fasta_label(path: Option<&PathBuf>) -> String {
+    path.and_then(|p| p.file_name())
+        .and_then(|n| n.to_str())
+        .map(|n| truncate_name(n, 30))
+        .unwrap_or_else(|| "(none)".to_string())
+}
+
 /// Truncate long sequence names for display
 fn truncate_name(name: &str, max_len: usize) -> String {
-    if name.len() <= max_len {
+    if name.chars().count() <= max_len {
         name.to_string()
     } else {
-        format!("{}...", &name[..max_len.saturating_sub(3)])
+        let kept: String = name.chars().take(max_len.saturating_sub(3)).collect();
+        format!("{kept}...")
     }
 }
@@ -0,0 +1,112 @@
+// Measuring between two points on the canvas
+//
+// The ruler (View → Measure) takes two clicks and reports how far apart they
+// are along each axis, in the sequences' own coordinates when both ends lie on
+// one sequence, and how far the second point lies off the diagonal through the
+// first: the target distance less the query distance. Between two syntenic
+// blocks that is the size of the indel separating them (positive where the
+// target has the extra sequence). Blocks on opposite strands run along the
+// anti-diagonal instead, so the sum of the two distances is given too.
+use crate::rust_plot::RustPlot;
+
+/// Distance along one axis between the ruler's two ends
+#[derive(Clone, Debug, PartialEq)]
+pub struct AxisDistance {
+    pub from: (String, i64), // Sequence name and position
+    pub to: (String, i64),
+    pub distance: i64, // Along the sequence, or genome-wide across sequences
+    pub same_sequence: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Measurement {
+    pub query: AxisDistance,
+    pub target: AxisDistance,
+}
+
+impl Measurement {
+    /// Target distance less query distance; 0 on the diagonal
+    pub fn diagonal_offset(&self) -> i64 {
+        self.target.distance - self.query.distance
+    }
+
+    /// Target distance plus query distance; 0 on the anti-diagonal
+    pub fn anti_diagonal_offset(&self) -> i64 {
+        self.target.distance + self.query.distance
+    }
+}
+
+/// Measure from one genome point to another
+pub fn measure(plot: &RustPlot, from: (f64, f64), to: (f64, f64)) -> Measurement {
+    let (query_from, query_to) = (from.0 as i64, to.0 as i64);
+    let (target_from, target_to) = (from.1 as i64, to.1 as i64);
+    Measurement {
+        query: axis_distance(
+            plot.query_coord_to_sequence(query_from),
+            plot.query_coord_to_sequence(query_to),
+            query_to - query_from,
+        ),
+        target: axis_distance(
+            plot.target_coord_to_sequence(target_from),
+            plot.target_coord_to_sequence(target_to),
+            target_to - target_from,
+        ),
+    }
+}
+
+/// Distance between two (index, name, position) points on one axis, falling
+/// back to `genome_distance` when they are on different sequences
+fn axis_distance(
+    (from_idx, from_name, from_pos): (usize, String, i64),
+    (to_idx, to_name, to_pos): (usize, String, i64),
+    genome_distance: i64,
+) -> AxisDistance {
+    let same_sequence = from_idx == to_idx;
+    AxisDistance {
+        from: (from_name, from_pos),
+        to: (to_name, to_pos),
+        distance: if same_sequence {
+            to_pos - from_pos
+        } else {
+            genome_distance
+        },
+        same_sequence,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aln_reader::AlnRecord;
+
+    #[test]
+    fn test_measure_in_sequence_coordinates() {
+        let records: Vec<AlnRecord> = Vec::new();
+        let mut plot = RustPlot::from_records(
+            &records,
+            vec!["q1".to_string(), "q2".to_string()],
+            vec!["t1".to_string()],
+            vec![1000, 500],
+            vec![2000],
+        );
+
+        // A 30 bp deletion from the target between two blocks
+        let m = measure(&plot, (100.0, 400.0), (300.0, 570.0));
+        assert_eq!(m.query.from, ("q1".to_string(), 100));
+        assert_eq!((m.query.distance, m.target.distance), (200, 170));
+        assert!(m.query.same_sequence && m.target.same_sequence);
+        assert_eq!(m.diagonal_offset(), -30);
+
+        // Across a sequence boundary the distance is genome-wide
+        let m = measure(&plot, (900.0, 0.0), (1100.0, 0.0));
+        assert_eq!(m.query.to, ("q2".to_string(), 100));
+        assert!(!m.query.same_sequence);
+        assert_eq!(m.query.distance, 200);
+
+        // A sequence shown reverse-complemented counts along its own strand
+        plot.target_reversed = vec![true];
+        let m = measure(&plot, (100.0, 400.0), (300.0, 600.0));
+        assert_eq!(m.target.distance, -200);
+        assert_eq!(m.anti_diagonal_offset(), 0);
+    }
+}
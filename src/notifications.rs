@@ -0,0 +1,215 @@
+// In-GUI error and warning reporting
+//
+// Errors are shown one at a time in a centered dialog that must be dismissed;
+// warnings and info messages appear as toasts in the bottom-right corner and
+// fade out on their own.
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How long a toast stays on screen
+const TOAST_LIFETIME: Duration = Duration::from_secs(8);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Level {
+    Error,
+    Warning,
+    Info,
+}
+
+pub struct Notification {
+    pub level: Level,
+    pub title: String,
+    pub message: String,
+    pub file: Option<PathBuf>,
+    pub remedy: Option<String>,
+    created: Instant,
+}
+
+#[derive(Default)]
+pub struct Notifications {
+    items: Vec<Notification>,
+}
+
+impl Notifications {
+    /// Report an error; a remedy is suggested from the message when one applies
+    pub fn error(
+        &mut self,
+        title: impl Into<String>,
+        message: impl Into<String>,
+        file: Option<&Path>,
+    ) {
+        let message = message.into();
+        let remedy = suggest_remedy(&message, file);
+        self.push(Level::Error, title.into(), message, file, remedy);
+    }
+
+    pub fn warning(
+        &mut self,
+        title: impl Into<String>,
+        message: impl Into<String>,
+        file: Option<&Path>,
+    ) {
+        self.push(Level::Warning, title.into(), message.into(), file, None);
+    }
+
+    pub fn info(&mut self, title: impl Into<String>, message: impl Into<String>) {
+        self.push(Level::Info, title.into(), message.into(), None, None);
+    }
+
+    fn push(
+        &mut self,
+        level: Level,
+        title: String,
+        message: String,
+        file: Option<&Path>,
+        remedy: Option<String>,
+    ) {
+        self.items.push(Notification {
+            level,
+            title,
+            message,
+            file: file.map(Path::to_path_buf),
+            remedy,
+            created: Instant::now(),
+        });
+    }
+
+    /// Draw the oldest pending error dialog and all live toasts
+    pub fn show(&mut self, ctx: &egui::Context) {
+        self.items
+            .retain(|n| n.level == Level::Error || n.created.elapsed() < TOAST_LIFETIME);
+
+        if let Some(idx) = self.items.iter().position(|n| n.level == Level::Error) {
+            if !show_error_dialog(ctx, &self.items[idx]) {
+                self.items.remove(idx);
+            }
+        }
+
+        let mut dismissed = None;
+        let toasts: Vec<usize> = (0..self.items.len())
+            .filter(|&i| self.items[i].level != Level::Error)
+            .collect();
+        if !toasts.is_empty() {
+            egui::Area::new(egui::Id::new("toasts"))
+                .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -40.0])
+                .order(egui::Order::Foreground)
+                .show(ctx, |ui| {
+                    for &idx in &toasts {
+                        if !show_toast(ui, &self.items[idx]) {
+                            dismissed = Some(idx);
+                        }
+                        ui.add_space(4.0);
+                    }
+                });
+            // Keep repainting so toasts expire even without input
+            ctx.request_repaint_after(Duration::from_millis(500));
+        }
+        if let Some(idx) = dismissed {
+            self.items.remove(idx);
+        }
+    }
+}
+
+/// Returns false once the user dismisses the dialog
+fn show_error_dialog(ctx: &egui::Context, notification: &Notification) -> bool {
+    let mut keep = true;
+
+    egui::Window::new(format!("⚠ {}", notification.title))
+        .id(egui::Id::new("error_dialog"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.set_max_width(420.0);
+            ui.label(&notification.message);
+
+            if let Some(ref file) = notification.file {
+                ui.add_space(5.0);
+                ui.label(egui::RichText::new(format!("File: {}", file.display())).weak());
+            }
+            if let Some(ref remedy) = notification.remedy {
+                ui.add_space(5.0);
+                ui.label(egui::RichText::new(format!("💡 {remedy}")).italics());
+            }
+
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                if ui.button("Dismiss").clicked() {
+                    keep = false;
+                }
+                if ui.button("📋 Copy details").clicked() {
+                    ui.output_mut(|o| o.copied_text = details(notification));
+                }
+            });
+        });
+
+    keep
+}
+
+/// Returns false once the user closes the toast
+fn show_toast(ui: &mut egui::Ui, notification: &Notification) -> bool {
+    let mut keep = true;
+    let (icon, color) = match notification.level {
+        Level::Warning => ("⚠", egui::Color32::from_rgb(255, 190, 0)),
+        _ => ("ℹ", egui::Color32::from_rgb(120, 180, 255)),
+    };
+
+    egui::Frame::popup(ui.style()).show(ui, |ui| {
+        ui.set_max_width(320.0);
+        ui.horizontal(|ui| {
+            ui.colored_label(color, icon);
+            ui.strong(&notification.title);
+            if ui.small_button("✖").clicked() {
+                keep = false;
+            }
+        });
+        ui.label(&notification.message);
+        if let Some(ref file) = notification.file {
+            ui.label(
+                egui::RichText::new(file.display().to_string())
+                    .weak()
+                    .small(),
+            );
+        }
+    });
+
+    keep
+}
+
+/// Plain-text report for the clipboard
+fn details(notification: &Notification) -> String {
+    let mut text = format!("{}\n{}", notification.title, notification.message);
+    if let Some(ref file) = notification.file {
+        text.push_str(&format!("\nFile: {}", file.display()));
+    }
+    if let Some(ref remedy) = notification.remedy {
+        text.push_str(&format!("\nSuggestion: {remedy}"));
+    }
+    text
+}
+
+/// Guess a next step for common failure messages
+fn suggest_remedy(message: &str, file: Option<&Path>) -> Option<String> {
+    let lower = message.to_lowercase();
+    let is_1aln = file
+        .and_then(|f| f.extension())
+        .is_some_and(|ext| ext == "1aln");
+
+    let remedy = if lower.contains("no such file") || lower.contains("not found") {
+        "Check that the file exists and the path is spelled correctly."
+    } else if lower.contains("permission denied") {
+        "Check that you have read permission for the file."
+    } else if lower.contains("k-mer size") {
+        "Choose a k-mer size between 1 and 32."
+    } else if lower.contains("fasta") {
+        "Make sure both inputs are FASTA files whose records start with '>'."
+    } else if file.is_some() && !is_1aln {
+        "alnview reads FastGA .1aln files; convert other formats to .1aln first."
+    } else if file.is_some() {
+        "The file may be truncated or corrupt. Try re-running FastGA, or open a different file."
+    } else {
+        return None;
+    };
+    Some(remedy.to_string())
+}
@@ -0,0 +1,320 @@
+// PAF input, read as it is written
+//
+// Aligners such as minimap2 and FastGA can write PAF to a pipe or a file that
+// grows over a long run. A follower reads it line by line, numbering sequences
+// in order of first appearance, and hands out plot snapshots of everything
+// read so far, so the run can be watched while it is going.
+use crate::aln_reader::AlnRecord;
+use crate::input;
+use crate::rust_plot::RustPlot;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a follower sends a new snapshot while alignments keep arriving
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// How long to wait for a growing file to grow before looking again
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Sent by `follow` as the input is read
+pub enum FollowEvent {
+    /// Everything read so far
+    Snapshot(Box<RustPlot>),
+    /// The writer closed the pipe: (alignments read, lines skipped)
+    Finished(usize, usize),
+    Failed(String),
+}
+
+/// Sequence names and lengths of one genome, numbered as they turn up
+#[derive(Default)]
+pub(crate) struct SequenceIds {
+    ids: HashMap<String, usize>,
+    pub(crate) names: Vec<String>,
+    pub(crate) lengths: Vec<i64>,
+}
+
+impl SequenceIds {
+    pub(crate) fn id(&mut self, name: &str, len: i64) -> usize {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len();
+        self.ids.insert(name.to_string(), id);
+        self.names.push(name.to_string());
+        self.lengths.push(len);
+        id
+    }
+}
+
+/// Alignment records gathered from PAF lines
+#[derive(Default)]
+pub struct PafCollector {
+    query: SequenceIds,
+    target: SequenceIds,
+    records: Vec<AlnRecord>,
+    pub skipped: usize,
+}
+
+impl PafCollector {
+    /// Add one PAF line; blank and '#' lines are ignored, malformed ones counted
+    /// in `skipped`
+    pub fn push_line(&mut self, line: &str) {
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line.trim().is_empty() || line.starts_with('#') {
+            return;
+        }
+        match self.parse_line(line) {
+            Ok(record) => self.records.push(record),
+            Err(_) => self.skipped += 1,
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<AlnRecord> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 12 {
+            anyhow::bail!(
+                "expected at least 12 tab-separated fields, got {}",
+                fields.len()
+            );
+        }
+        let number = |i: usize| {
+            fields[i]
+                .parse::<i64>()
+                .with_context(|| format!("field {}: invalid number '{}'", i + 1, fields[i]))
+        };
+        let (query_len, query_start, query_end) = (number(1)?, number(2)?, number(3)?);
+        let (target_len, target_start, target_end) = (number(6)?, number(7)?, number(8)?);
+        let (matches, block_len) = (number(9)?, number(10)?);
+        let reverse = match fields[4] {
+            "+" => 0,
+            "-" => 1,
+            other => anyhow::bail!("strand must be + or -, got '{other}'"),
+        };
+        let within = |start: i64, end: i64, len: i64| 0 <= start && start <= end && end <= len;
+        if !within(query_start, query_end, query_len)
+            || !within(target_start, target_end, target_len)
+        {
+            anyhow::bail!("coordinates outside the sequence");
+        }
+
+        // Identity is matches over the block; express it as diffs over the
+        // query span, which is what `calculate_identity` expects
+        let span = query_end - query_start;
+        let identity = if block_len > 0 {
+            matches as f64 / block_len as f64
+        } else {
+            0.0
+        };
+        let diffs = span - (span as f64 * identity).round() as i64;
+
+        // PAF gives target coordinates on the forward strand; .1aln records
+        // (and so `RustPlot`) count reverse ones from the end of the sequence
+        let (target_start, target_end) = if reverse == 1 {
+            (target_len - target_end, target_len - target_start)
+        } else {
+            (target_start, target_end)
+        };
+
+        Ok(AlnRecord {
+            query_id: self.query.id(fields[0], query_len) as i64,
+            target_id: self.target.id(fields[5], target_len) as i64,
+            query_name: fields[0].to_string(),
+            target_name: fields[5].to_string(),
+            query_len,
+            target_len,
+            query_start,
+            query_end,
+            target_start,
+            target_end,
+            reverse,
+            diffs: diffs.clamp(0, i32::MAX as i64) as i32,
+        })
+    }
+
+    /// Number of alignments read
+    pub fn alignments(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Plot of everything read so far
+    pub fn to_plot(&self) -> RustPlot {
+        RustPlot::from_records(
+            &self.records,
+            self.query.names.clone(),
+            self.target.names.clone(),
+            self.query.lengths.clone(),
+            self.target.lengths.clone(),
+        )
+    }
+}
+
+/// A record as a PAF line (no newline): the block is the query span, the
+/// differences are NM and their share of the block dv, and mapping quality is
+/// missing (255)
+pub fn paf_line(rec: &AlnRecord) -> String {
+    let span = rec.query_end - rec.query_start;
+    let diffs = (rec.diffs as i64).clamp(0, span);
+    // Back to forward-strand target coordinates, as PAF has them
+    let (target_start, target_end) = if rec.reverse == 1 {
+        (
+            rec.target_len - rec.target_end,
+            rec.target_len - rec.target_start,
+        )
+    } else {
+        (rec.target_start, rec.target_end)
+    };
+    let divergence = if span > 0 {
+        diffs as f64 / span as f64
+    } else {
+        0.0
+    };
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t255\tNM:i:{}\tdv:f:{:.4}",
+        rec.query_name,
+        rec.query_len,
+        rec.query_start,
+        rec.query_end,
+        if rec.reverse == 1 { '-' } else { '+' },
+        rec.target_name,
+        rec.target_len,
+        target_start,
+        target_end,
+        span - diffs,
+        span,
+        diffs,
+        divergence
+    )
+}
+
+/// Write the plot's passing alignments to `path` as PAF, returning how many
+pub fn write_plot(plot: &RustPlot, path: &Path) -> Result<usize> {
+    let file =
+        fs::File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+    let mut written = 0;
+    for seg in plot.segments.iter().filter(|seg| plot.passes(seg)) {
+        writeln!(out, "{}", paf_line(&plot.segment_record(seg)))?;
+        written += 1;
+    }
+    out.flush()?;
+    Ok(written)
+}
+
+/// Read PAF from `path` ("-" for stdin) until the writer finishes or `stop` is
+/// set, sending a snapshot whenever new alignments have arrived and
+/// `SNAPSHOT_INTERVAL` has passed
+///
+/// A regular file is followed like `tail -f`: reaching its end means waiting
+/// for more. On a pipe or stdin, or for a gzipped file, the end means the
+/// aligner is done.
+pub fn follow(path: &Path, events: Sender<FollowEvent>, stop: Arc<AtomicBool>) {
+    let (mut reader, growing): (Box<dyn BufRead + Send>, bool) = if path.as_os_str() == "-" {
+        (Box::new(BufReader::new(io::stdin())), false)
+    } else {
+        match input::open(path) {
+            Ok((reader, compressed)) => {
+                let growing =
+                    !compressed && fs::metadata(path).map(|m| m.is_file()).unwrap_or(false);
+                (reader, growing)
+            }
+            Err(e) => {
+                let _ = events.send(FollowEvent::Failed(format!("{e:#}")));
+                return;
+            }
+        }
+    };
+
+    let mut collector = PafCollector::default();
+    let mut line = String::new();
+    let mut sent = 0;
+    let mut last_sent = Instant::now();
+
+    while !stop.load(Ordering::Relaxed) {
+        match reader.read_line(&mut line) {
+            Ok(0) if growing => thread::sleep(POLL_INTERVAL),
+            Ok(0) => break,
+            // A growing file may end mid-line; keep the start until the rest arrives
+            Ok(_) if !line.ends_with('\n') && growing => continue,
+            Ok(_) => {
+                collector.push_line(&line);
+                line.clear();
+            }
+            Err(e) => {
+                let _ = events.send(FollowEvent::Failed(format!(
+                    "Failed to read {}: {e}",
+                    path.display()
+                )));
+                return;
+            }
+        }
+
+        if collector.alignments() > sent && last_sent.elapsed() >= SNAPSHOT_INTERVAL {
+            sent = collector.alignments();
+            last_sent = Instant::now();
+            if events
+                .send(FollowEvent::Snapshot(Box::new(collector.to_plot())))
+                .is_err()
+            {
+                return; // Viewer went away
+            }
+        }
+    }
+
+    // Flush a last line without a newline, then the final state
+    if !line.is_empty() {
+        collector.push_line(&line);
+    }
+    if collector.alignments() > sent {
+        let _ = events.send(FollowEvent::Snapshot(Box::new(collector.to_plot())));
+    }
+    let _ = events.send(FollowEvent::Finished(
+        collector.alignments(),
+        collector.skipped,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collects_sequences_in_order_of_appearance() {
+        let mut collector = PafCollector::default();
+        collector.push_line("q2\t500\t0\t100\t+\tt1\t1000\t200\t300\t90\t100\t60\n");
+        collector.push_line("q1\t800\t10\t20\t-\tt1\t1000\t0\t10\t10\t10\t60\n");
+        collector.push_line("# comment\n");
+        collector.push_line("q1\tnot a number\t0\t1\t+\tt1\t1000\t0\t1\t1\t1\t0\n");
+
+        assert_eq!(collector.alignments(), 2);
+        assert_eq!(collector.skipped, 1);
+        let plot = collector.to_plot();
+        assert_eq!(plot.query_sequences, vec!["q2", "q1"]);
+        assert_eq!(plot.query_lengths, vec![500, 800]);
+        assert_eq!(plot.get_blen(), 1000);
+        assert!((plot.segments[0].identity - 90.0).abs() < 1e-3);
+        let seg = &plot.segments[1];
+        assert!(seg.reverse);
+        assert_eq!((seg.abeg, seg.aend, seg.bbeg, seg.bend), (510, 520, 10, 0));
+    }
+
+    #[test]
+    fn test_paf_line_reads_back() {
+        let mut collector = PafCollector::default();
+        let line = "q1\t800\t10\t110\t-\tt1\t1000\t0\t100\t95\t100\t255\tNM:i:5\tdv:f:0.0500";
+        collector.push_line(line);
+        let rec = &collector.records[0];
+        assert_eq!(
+            (rec.target_start, rec.target_end, rec.diffs),
+            (900, 1000, 5)
+        );
+        assert_eq!(paf_line(rec), line);
+    }
+}
@@ -0,0 +1,371 @@
+// Vector PDF output
+//
+// Enough of PDF 1.4 for plot figures: stroked lines and filled rectangles
+// (either optionally translucent), outlined rectangles and text in one embedded TrueType font,
+// so figures look the same on any machine. Each page is drawn on its own and
+// the pages gathered into one document. Callers work top-down in points, like
+// image coordinates; the page flips them. Text is WinAnsi-encoded, with
+// characters outside it written as '?'. Streams are Flate-compressed.
+use ab_glyph::{Font, FontRef};
+use anyhow::Result;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+/// Character codes given widths (WinAnsi printable range)
+const FIRST_CHAR: u8 = 32;
+const LAST_CHAR: u8 = 255;
+
+pub struct PdfPage<'a> {
+    width: f32,
+    height: f32,
+    font: FontRef<'a>,
+    font_data: &'a [u8],
+    content: Vec<u8>,
+    opacities: Vec<(&'static str, f32)>, // Fill (ca) and stroke (CA) opacities used, one graphics state each
+    stroke: Option<(f32, [u8; 3])>,      // Current line width and colour
+    line_opacity: f32,
+}
+
+impl<'a> PdfPage<'a> {
+    /// A blank `width` x `height` point page writing text in the TrueType
+    /// font `font_data`
+    pub fn new(width: f32, height: f32, font_data: &'a [u8]) -> Result<Self> {
+        let font = FontRef::try_from_slice(font_data)
+            .map_err(|e| anyhow::anyhow!("Failed to load font: {e}"))?;
+        Ok(Self {
+            width,
+            height,
+            font,
+            font_data,
+            // Round caps, so zero-length lines still show as dots
+            content: b"1 J 1 j\n".to_vec(),
+            opacities: Vec::new(),
+            stroke: None,
+            line_opacity: 1.0,
+        })
+    }
+
+    /// Line from `from` to `to`, `width` points wide
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32), width: f32, color: [u8; 3]) {
+        if self.stroke != Some((width, color)) {
+            let [r, g, b] = rgb(color);
+            let _ = write!(self.content, "{} w {r} {g} {b} RG ", num(width));
+            self.stroke = Some((width, color));
+        }
+        let _ = writeln!(
+            self.content,
+            "{} {} m {} {} l S",
+            num(from.0),
+            num(self.height - from.1),
+            num(to.0),
+            num(self.height - to.1)
+        );
+    }
+
+    /// Opacity from 0 to 1 of the lines drawn from here on
+    pub fn set_line_opacity(&mut self, opacity: f32) {
+        if opacity != self.line_opacity {
+            let state = self.opacity_state("CA", opacity);
+            let _ = writeln!(self.content, "/GS{state} gs");
+            self.line_opacity = opacity;
+        }
+    }
+
+    /// Rectangle outline, top-left corner at (x, y)
+    pub fn stroke_rect(&mut self, x: f32, y: f32, w: f32, h: f32, width: f32, color: [u8; 3]) {
+        let corners = [(x, y), (x + w, y), (x + w, y + h), (x, y + h), (x, y)];
+        for pair in corners.windows(2) {
+            self.line(pair[0], pair[1], width, color);
+        }
+    }
+
+    /// Filled rectangle, top-left corner at (x, y), with `opacity` from 0 to 1
+    pub fn fill_rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: [u8; 3], opacity: f32) {
+        let [r, g, b] = rgb(color);
+        let _ = write!(self.content, "q ");
+        if opacity < 1.0 {
+            let state = self.opacity_state("ca", opacity);
+            let _ = write!(self.content, "/GS{state} gs ");
+        }
+        let _ = writeln!(
+            self.content,
+            "{r} {g} {b} rg {} {} {} {} re f Q",
+            num(x),
+            num(self.height - y - h),
+            num(w),
+            num(h)
+        );
+    }
+
+    /// Text whose top-left corner is at (x, y)
+    pub fn text(&mut self, x: f32, y: f32, size: f32, color: [u8; 3], text: &str) {
+        let baseline = self.height - y - self.ascent(size);
+        self.show_text("1 0 0 1", x, baseline, size, color, text);
+    }
+
+    /// Text reading downwards from (x, y), its glyphs' tops facing right
+    pub fn text_down(&mut self, x: f32, y: f32, size: f32, color: [u8; 3], text: &str) {
+        let baseline = x - self.descent(size);
+        self.show_text("0 -1 1 0", baseline, self.height - y, size, color, text);
+    }
+
+    /// Text reading upwards from (x, y), its glyphs' tops facing left
+    pub fn text_up(&mut self, x: f32, y: f32, size: f32, color: [u8; 3], text: &str) {
+        let baseline = x + self.ascent(size);
+        self.show_text("0 1 -1 0", baseline, self.height - y, size, color, text);
+    }
+
+    /// Width of `text` at `size` points
+    pub fn text_width(&self, size: f32, text: &str) -> f32 {
+        text.chars()
+            .map(|c| self.advance(char::from(win_ansi(c))))
+            .sum::<f32>()
+            * size
+    }
+
+    /// The finished PDF file
+    pub fn finish(self) -> Result<Vec<u8>> {
+        finish_document(vec![self])
+    }
+
+    fn show_text(&mut self, matrix: &str, x: f32, y: f32, size: f32, color: [u8; 3], text: &str) {
+        let [r, g, b] = rgb(color);
+        let _ = write!(
+            self.content,
+            "BT /F1 {} Tf {r} {g} {b} rg {matrix} {} {} Tm (",
+            num(size),
+            num(x),
+            num(y)
+        );
+        for c in text.chars() {
+            match win_ansi(c) {
+                code @ (b'(' | b')' | b'\\') => self.content.extend_from_slice(&[b'\\', code]),
+                code => self.content.push(code),
+            }
+        }
+        let _ = writeln!(self.content, ") Tj ET");
+    }
+
+    /// Graphics state index for a fill ("ca") or stroke ("CA") opacity
+    fn opacity_state(&mut self, key: &'static str, opacity: f32) -> usize {
+        match self.opacities.iter().position(|&o| o == (key, opacity)) {
+            Some(idx) => idx,
+            None => {
+                self.opacities.push((key, opacity));
+                self.opacities.len() - 1
+            }
+        }
+    }
+
+    /// Advance of `c` as a fraction of the em
+    fn advance(&self, c: char) -> f32 {
+        self.font.h_advance_unscaled(self.font.glyph_id(c))
+            / self.font.units_per_em().unwrap_or(1000.0)
+    }
+
+    fn ascent(&self, size: f32) -> f32 {
+        self.font.ascent_unscaled() / self.font.units_per_em().unwrap_or(1000.0) * size
+    }
+
+    fn descent(&self, size: f32) -> f32 {
+        self.font.descent_unscaled() / self.font.units_per_em().unwrap_or(1000.0) * size
+    }
+}
+
+/// A PDF of `pages` in order, their text in the first page's font
+pub fn finish_document(pages: Vec<PdfPage>) -> Result<Vec<u8>> {
+    let Some(first) = pages.first() else {
+        anyhow::bail!("A PDF needs at least one page");
+    };
+    let widths: Vec<String> = (FIRST_CHAR..=LAST_CHAR)
+        .map(|code| match code {
+            127..=159 => "0".to_string(), // Unused here; written as '?'
+            code => num(first.advance(char::from(code)) * 1000.0),
+        })
+        .collect();
+    let font = &first.font;
+    let em = |value: f32| num(value / font.units_per_em().unwrap_or(1000.0) * 1000.0);
+    // Outline bounds keep the glyph's top in `min.y` and bottom in `max.y`
+    let (mut x_min, mut y_min, mut x_max, mut y_max) = (0.0f32, 0.0f32, 0.0f32, 0.0f32);
+    for code in FIRST_CHAR..=LAST_CHAR {
+        if let Some(outline) = font.outline(font.glyph_id(char::from(code))) {
+            x_min = x_min.min(outline.bounds.min.x);
+            y_min = y_min.min(outline.bounds.max.y);
+            x_max = x_max.max(outline.bounds.max.x);
+            y_max = y_max.max(outline.bounds.min.y);
+        }
+    }
+    let cap_height = font
+        .outline(font.glyph_id('H'))
+        .map_or(y_max, |outline| outline.bounds.min.y);
+
+    // Shared objects first (catalog, page tree, font), then a page and its
+    // content stream for each page
+    const FIRST_PAGE: usize = 6;
+    let kids: Vec<String> = (0..pages.len())
+        .map(|idx| format!("{} 0 R", FIRST_PAGE + 2 * idx))
+        .collect();
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        format!(
+            "<< /Type /Font /Subtype /TrueType /BaseFont /DejaVuSans /FirstChar {FIRST_CHAR} \
+             /LastChar {LAST_CHAR} /Widths [{}] /Encoding /WinAnsiEncoding /FontDescriptor 4 0 R >>",
+            widths.join(" ")
+        )
+        .into_bytes(),
+        format!(
+            "<< /Type /FontDescriptor /FontName /DejaVuSans /Flags 32 /FontBBox [{} {} {} {}] \
+             /ItalicAngle 0 /Ascent {} /Descent {} /CapHeight {} /StemV 80 /FontFile2 5 0 R >>",
+            em(x_min),
+            em(y_min),
+            em(x_max),
+            em(y_max),
+            em(font.ascent_unscaled()),
+            em(font.descent_unscaled()),
+            em(cap_height)
+        )
+        .into_bytes(),
+        stream(
+            &format!(" /Length1 {}", first.font_data.len()),
+            first.font_data,
+        )?,
+    ];
+    for (idx, page) in pages.iter().enumerate() {
+        let states: Vec<String> = page
+            .opacities
+            .iter()
+            .enumerate()
+            .map(|(idx, (key, opacity))| format!("/GS{idx} << /{key} {} >>", num(*opacity)))
+            .collect();
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Contents {} 0 R \
+                 /Resources << /Font << /F1 3 0 R >> /ExtGState << {} >> >> >>",
+                num(page.width),
+                num(page.height),
+                FIRST_PAGE + 2 * idx + 1,
+                states.join(" ")
+            )
+            .into_bytes(),
+        );
+        objects.push(stream("", &page.content)?);
+    }
+
+    let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (idx, object) in objects.iter_mut().enumerate() {
+        offsets.push(pdf.len());
+        writeln!(pdf, "{} 0 obj", idx + 1)?;
+        pdf.append(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1)?;
+    for offset in &offsets {
+        writeln!(pdf, "{offset:010} 00000 n ")?;
+    }
+    write!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        offsets.len() + 1
+    )?;
+    Ok(pdf)
+}
+
+/// A Flate-compressed stream object, with `extra` dictionary entries (each
+/// after a space)
+fn stream(extra: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    let compressed = encoder.finish()?;
+    let mut object = format!(
+        "<< /Length {} /Filter /FlateDecode{extra} >>\nstream\n",
+        compressed.len()
+    )
+    .into_bytes();
+    object.extend(compressed);
+    object.extend_from_slice(b"\nendstream");
+    Ok(object)
+}
+
+/// WinAnsi code for `c`: ASCII and Latin-1 map to themselves, anything else
+/// to '?'
+fn win_ansi(c: char) -> u8 {
+    match c as u32 {
+        code @ (0x20..=0x7e | 0xa0..=0xff) => code as u8,
+        _ => b'?',
+    }
+}
+
+fn rgb(color: [u8; 3]) -> [String; 3] {
+    color.map(|c| num(c as f32 / 255.0))
+}
+
+/// A number as PDF wants it: no exponent, at most three decimals
+fn num(value: f32) -> String {
+    let text = format!("{value:.3}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" | "" => "0".to_string(),
+        text => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cross_reference_offsets() {
+        let font = include_bytes!("../fonts/DejaVuSans.ttf");
+        let mut page = PdfPage::new(200.0, 100.0, font).unwrap();
+        page.line((10.0, 10.0), (190.0, 90.0), 1.5, [0, 255, 0]);
+        page.fill_rect(0.0, 0.0, 50.0, 50.0, [255, 0, 0], 0.15);
+        page.set_line_opacity(0.5);
+        page.line((10.0, 90.0), (190.0, 10.0), 1.5, [0, 255, 0]);
+        page.set_line_opacity(1.0);
+        page.text(5.0, 5.0, 12.0, [255, 255, 255], "chr1 (50%) é ∑");
+        assert!(page.text_width(10.0, "ii") < page.text_width(10.0, "MM"));
+        let pdf = page.finish().unwrap();
+
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text
+            .contains("/ExtGState << /GS0 << /ca 0.15 >> /GS1 << /CA 0.5 >> /GS2 << /CA 1 >> >>"));
+        let startxref: usize = text
+            .rsplit("startxref\n")
+            .next()
+            .and_then(|rest| rest.lines().next())
+            .and_then(|line| line.parse().ok())
+            .unwrap();
+        assert!(pdf[startxref..].starts_with(b"xref\n0 8\n"));
+        // Every entry points at its object
+        let entries = String::from_utf8_lossy(&pdf[startxref..]).into_owned();
+        for (idx, line) in entries.lines().skip(3).take(7).enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(format!("{} 0 obj", idx + 1).as_bytes()));
+        }
+
+        // Pages after the first share its font
+        let pages = vec![
+            PdfPage::new(200.0, 100.0, font).unwrap(),
+            PdfPage::new(200.0, 100.0, font).unwrap(),
+        ];
+        let pdf = String::from_utf8_lossy(&finish_document(pages).unwrap()).into_owned();
+        assert!(pdf.contains("/Kids [6 0 R 8 0 R] /Count 2"));
+        assert!(finish_document(Vec::new()).is_err());
+
+        assert_eq!(num(2.5), "2.5");
+        assert_eq!(num(-0.0001), "0");
+        assert_eq!(num(100.0), "100");
+        assert_eq!(win_ansi('∑'), b'?');
+    }
+}
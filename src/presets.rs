@@ -0,0 +1,431 @@
+// Named display presets
+//
+// A preset captures how alignments are drawn — layer colours, widths and
+// filters, the plot-wide cutoffs and best-N sweep, and the rendering mode — so
+// settings tuned on one file can be applied to the next, in the GUI or from the
+// CLI. Presets live in one file in the user's config directory
+// ($XDG_CONFIG_HOME/alnview/presets.tsv, else ~/.config/alnview/presets.tsv).
+// Format: tab-separated records like a session file, each preset opened by a
+// `preset` line that the records after it belong to:
+//
+//   preset       name
+//   layer        name  visible  #forward  #reverse  width  show_fwd  show_rev  min_len  min_id  strand
+//   identity     viridis|magma|custom  min_id  max_id  [#low  #high]
+//   opacity      0-1
+//   width        fixed|length|identity
+//   cutoff       min_len  min_id  strand
+//   sweep        QUERY:TARGET
+//   mode         dotplot | synteny | wrapped
+//   tiny_dots    0|1
+//   length_dots  0|1
+//   equal_width  0|1
+//
+// An `identity` record after a layer colours that layer by identity; a custom
+// colour map takes its low and high colours. An `opacity` record after a layer
+// makes it translucent, and a `width` record scales its line width with each
+// alignment's length or identity.
+use crate::colormap::{Colormap, IdentityGradient};
+use crate::rust_plot::{SegmentFilter, StrandFilter, SweepFilter, WidthScale};
+use crate::session::{escape, parse_color, unescape};
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One drawing layer, as `LayerSettings` in the viewer
+#[derive(Clone, Debug, PartialEq)]
+pub struct PresetLayer {
+    pub name: String,
+    pub visible: bool,
+    pub color_forward: [u8; 3],
+    pub color_reverse: [u8; 3],
+    pub thickness: f32,
+    pub opacity: f32,
+    pub width_scale: WidthScale,
+    pub show_forward: bool,
+    pub show_reverse: bool,
+    pub filter: SegmentFilter,
+    pub color_by_identity: bool,
+    pub gradient: IdentityGradient,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preset {
+    pub name: String,
+    pub layers: Vec<PresetLayer>,
+    pub cutoff: SegmentFilter,
+    pub sweep: Option<SweepFilter>,
+    pub mode: String, // "dotplot", "synteny" or "wrapped"
+    pub tiny_dots: bool,
+    pub length_dots: bool,
+    pub equal_width: bool,
+}
+
+impl Preset {
+    fn named(name: String) -> Self {
+        Self {
+            name,
+            layers: Vec::new(),
+            cutoff: SegmentFilter::default(),
+            sweep: None,
+            mode: "dotplot".to_string(),
+            tiny_dots: true,
+            length_dots: false,
+            equal_width: false,
+        }
+    }
+}
+
+/// All saved presets, in the order they were first saved
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PresetStore {
+    pub presets: Vec<Preset>,
+}
+
+impl PresetStore {
+    /// Where presets are kept, if a config directory can be found
+    pub fn default_path() -> Option<PathBuf> {
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(config.join("alnview").join("presets.tsv"))
+    }
+
+    /// Presets from the default path; none yet is not an error
+    pub fn load_default() -> Result<Self> {
+        match Self::default_path() {
+            Some(path) if path.exists() => Self::load(path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn save_default(&self) -> Result<()> {
+        let path = Self::default_path().context("No config directory (HOME is not set)")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        self.save(path)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read presets: {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid presets file: {}", path.display()))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, self.to_text())
+            .with_context(|| format!("Failed to write presets: {}", path.display()))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Preset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    /// Add `preset`, replacing any of the same name in place
+    pub fn insert(&mut self, preset: Preset) {
+        match self.presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.presets.retain(|preset| preset.name != name);
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut store = Self::default();
+
+        for (line_no, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let at = |e: anyhow::Error| e.context(format!("line {}", line_no + 1));
+            let expect = |count: usize| {
+                if fields.len() == count {
+                    Ok(())
+                } else {
+                    Err(anyhow::anyhow!(
+                        "line {}: '{}' needs {} fields, got {}",
+                        line_no + 1,
+                        fields[0],
+                        count,
+                        fields.len()
+                    ))
+                }
+            };
+
+            if fields[0] == "preset" {
+                expect(2)?;
+                store.presets.push(Preset::named(unescape(fields[1])));
+                continue;
+            }
+            let Some(preset) = store.presets.last_mut() else {
+                anyhow::bail!("line {}: '{}' before any preset", line_no + 1, fields[0]);
+            };
+            match fields[0] {
+                "layer" => {
+                    expect(11)?;
+                    preset.layers.push(PresetLayer {
+                        name: unescape(fields[1]),
+                        visible: flag(fields[2]),
+                        color_forward: parse_color(fields[3]).map_err(at)?,
+                        color_reverse: parse_color(fields[4]).map_err(at)?,
+                        thickness: number(fields[5]).map_err(at)? as f32,
+                        opacity: 1.0,
+                        width_scale: WidthScale::Fixed,
+                        show_forward: flag(fields[6]),
+                        show_reverse: flag(fields[7]),
+                        filter: parse_filter(&fields[8..11]).map_err(at)?,
+                        color_by_identity: false,
+                        gradient: IdentityGradient::default(),
+                    });
+                }
+                "identity" => {
+                    if fields.len() != 4 && fields.len() != 6 {
+                        anyhow::bail!(
+                            "line {}: 'identity' needs 4 or 6 fields, got {}",
+                            line_no + 1,
+                            fields.len()
+                        );
+                    }
+                    let Some(layer) = preset.layers.last_mut() else {
+                        anyhow::bail!("line {}: 'identity' before any layer", line_no + 1);
+                    };
+                    let (low, high) = if fields.len() == 6 {
+                        (
+                            parse_color(fields[4]).map_err(at)?,
+                            parse_color(fields[5]).map_err(at)?,
+                        )
+                    } else {
+                        ([0, 0, 0], [255, 255, 255])
+                    };
+                    let colormap =
+                        Colormap::from_name(fields[1].trim(), low, high).with_context(|| {
+                            format!(
+                                "line {}: colour map must be viridis, magma or custom, got '{}'",
+                                line_no + 1,
+                                fields[1]
+                            )
+                        })?;
+                    layer.color_by_identity = true;
+                    layer.gradient = IdentityGradient {
+                        colormap,
+                        min_identity: number(fields[2]).map_err(at)? as f32,
+                        max_identity: number(fields[3]).map_err(at)? as f32,
+                    };
+                }
+                "opacity" => {
+                    expect(2)?;
+                    let Some(layer) = preset.layers.last_mut() else {
+                        anyhow::bail!("line {}: 'opacity' before any layer", line_no + 1);
+                    };
+                    layer.opacity = number(fields[1]).map_err(at)? as f32;
+                }
+                "width" => {
+                    expect(2)?;
+                    let Some(layer) = preset.layers.last_mut() else {
+                        anyhow::bail!("line {}: 'width' before any layer", line_no + 1);
+                    };
+                    layer.width_scale = WidthScale::ALL
+                        .into_iter()
+                        .find(|scale| scale.name() == fields[1].trim())
+                        .with_context(|| {
+                            format!(
+                                "line {}: width must be fixed, length or identity, got '{}'",
+                                line_no + 1,
+                                fields[1]
+                            )
+                        })?;
+                }
+                "cutoff" => {
+                    expect(4)?;
+                    preset.cutoff = parse_filter(&fields[1..4]).map_err(at)?;
+                }
+                "sweep" => {
+                    expect(2)?;
+                    preset.sweep = Some(SweepFilter::parse(fields[1]).map_err(at)?);
+                }
+                "mode" => {
+                    expect(2)?;
+                    preset.mode = fields[1].trim().to_string();
+                }
+                "tiny_dots" | "length_dots" | "equal_width" => {
+                    expect(2)?;
+                    let value = flag(fields[1]);
+                    match fields[0] {
+                        "tiny_dots" => preset.tiny_dots = value,
+                        "length_dots" => preset.length_dots = value,
+                        _ => preset.equal_width = value,
+                    }
+                }
+                other => anyhow::bail!("line {}: unknown record type '{other}'", line_no + 1),
+            }
+        }
+
+        Ok(store)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# alnview presets\n");
+        for preset in &self.presets {
+            text.push_str(&format!("preset\t{}\n", escape(&preset.name)));
+            for layer in &preset.layers {
+                text.push_str(&format!(
+                    "layer\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                    escape(&layer.name),
+                    u8::from(layer.visible),
+                    hex(layer.color_forward),
+                    hex(layer.color_reverse),
+                    layer.thickness,
+                    u8::from(layer.show_forward),
+                    u8::from(layer.show_reverse),
+                    filter_fields(&layer.filter)
+                ));
+                if layer.color_by_identity {
+                    let gradient = &layer.gradient;
+                    text.push_str(&format!(
+                        "identity\t{}\t{}\t{}",
+                        gradient.colormap.name(),
+                        gradient.min_identity,
+                        gradient.max_identity
+                    ));
+                    if let Colormap::TwoColor(low, high) = gradient.colormap {
+                        text.push_str(&format!("\t{}\t{}", hex(low), hex(high)));
+                    }
+                    text.push('\n');
+                }
+                if layer.opacity < 1.0 {
+                    text.push_str(&format!("opacity\t{}\n", layer.opacity));
+                }
+                if layer.width_scale != WidthScale::Fixed {
+                    text.push_str(&format!("width\t{}\n", layer.width_scale.name()));
+                }
+            }
+            text.push_str(&format!("cutoff\t{}\n", filter_fields(&preset.cutoff)));
+            if let Some(sweep) = preset.sweep {
+                text.push_str(&format!("sweep\t{sweep}\n"));
+            }
+            text.push_str(&format!("mode\t{}\n", preset.mode));
+            text.push_str(&format!("tiny_dots\t{}\n", u8::from(preset.tiny_dots)));
+            text.push_str(&format!("length_dots\t{}\n", u8::from(preset.length_dots)));
+            text.push_str(&format!("equal_width\t{}\n", u8::from(preset.equal_width)));
+        }
+        text
+    }
+}
+
+fn flag(field: &str) -> bool {
+    field.trim() == "1"
+}
+
+fn number(field: &str) -> Result<f64> {
+    field
+        .trim()
+        .parse::<f64>()
+        .with_context(|| format!("invalid number '{field}'"))
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// min_length, min_identity, strand
+fn parse_filter(fields: &[&str]) -> Result<SegmentFilter> {
+    Ok(SegmentFilter {
+        min_length: number(fields[0])? as i64,
+        min_identity: number(fields[1])? as f32,
+        strand: match fields[2].trim() {
+            "both" => StrandFilter::Both,
+            "forward" => StrandFilter::Forward,
+            "reverse" => StrandFilter::Reverse,
+            other => anyhow::bail!("strand must be both, forward or reverse, got '{other}'"),
+        },
+    })
+}
+
+fn filter_fields(filter: &SegmentFilter) -> String {
+    let strand = match filter.strand {
+        StrandFilter::Both => "both",
+        StrandFilter::Forward => "forward",
+        StrandFilter::Reverse => "reverse",
+    };
+    format!("{}\t{}\t{strand}", filter.min_length, filter.min_identity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut preset = Preset::named("figure\tstyle".to_string());
+        preset.layers.push(PresetLayer {
+            name: "Layer 0".to_string(),
+            visible: true,
+            color_forward: [0, 128, 255],
+            color_reverse: [255, 64, 0],
+            thickness: 1.5,
+            opacity: 0.4,
+            width_scale: WidthScale::Length,
+            show_forward: true,
+            show_reverse: false,
+            filter: SegmentFilter {
+                min_length: 5000,
+                min_identity: 95.5,
+                strand: StrandFilter::Forward,
+            },
+            color_by_identity: false,
+            gradient: IdentityGradient::default(),
+        });
+        preset.layers.push(PresetLayer {
+            name: "Identity".to_string(),
+            visible: true,
+            color_forward: [0, 255, 0],
+            color_reverse: [255, 0, 0],
+            thickness: 1.0,
+            opacity: 1.0,
+            width_scale: WidthScale::Fixed,
+            show_forward: true,
+            show_reverse: true,
+            filter: SegmentFilter::default(),
+            color_by_identity: true,
+            gradient: IdentityGradient {
+                colormap: Colormap::TwoColor([0, 0, 255], [255, 200, 0]),
+                min_identity: 90.0,
+                max_identity: 99.5,
+            },
+        });
+        preset.cutoff.min_length = 1000;
+        preset.sweep = Some(SweepFilter::ONE_TO_ONE);
+        preset.mode = "wrapped".to_string();
+        preset.length_dots = true;
+
+        let mut store = PresetStore::default();
+        store.insert(preset.clone());
+        store.insert(Preset::named("plain".to_string()));
+        let parsed = PresetStore::parse(&store.to_text()).unwrap();
+        assert_eq!(parsed, store);
+
+        // Saving under an existing name replaces it in place
+        preset.equal_width = true;
+        store.insert(preset);
+        assert_eq!(store.presets.len(), 2);
+        assert!(store.get("figure\tstyle").unwrap().equal_width);
+        store.remove("plain");
+        assert_eq!(store.presets.len(), 1);
+    }
+
+    #[test]
+    fn test_rejects_records_outside_a_preset() {
+        let err = PresetStore::parse("cutoff\t0\t0\tboth\n").unwrap_err();
+        assert!(err.to_string().contains("before any preset"));
+        assert!(PresetStore::parse("preset\tx\ncutoff\t0\t0\tsideways\n").is_err());
+        assert!(PresetStore::parse("preset\tx\nidentity\tmagma\t70\t100\n").is_err());
+    }
+}
@@ -0,0 +1,173 @@
+// Anti-aliased line accumulation for PNG export
+//
+// Alignments are drawn into a floating-point buffer rather than straight onto
+// the image: each pixel sums the colour and coverage of every line crossing
+// it, with fractional coverage along line edges, so lines come out smooth and
+// places where many alignments pile up grow brighter instead of every pixel
+// taking the colour of the last line drawn over it. The buffer is then laid
+// over the image: a pixel one line fully covers shows that line's colour, and
+// more heavily covered ones are lifted towards white.
+use image::RgbaImage;
+
+/// Coverage (in full-pixel lines) at which a pixel is lifted halfway from
+/// its colour to white
+const HALF_WHITE_COVERAGE: f32 = 16.0;
+
+pub struct Accumulator {
+    width: u32,
+    height: u32,
+    cells: Vec<[f32; 4]>, // Red, green and blue (0-1) summed by coverage, then the coverage
+}
+
+impl Accumulator {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![[0.0; 4]; width as usize * height as usize],
+        }
+    }
+
+    /// Empty the buffer for the next layer
+    pub fn clear(&mut self) {
+        self.cells.fill([0.0; 4]);
+    }
+
+    /// Add a line `width` pixels wide between two points in pixel units (the
+    /// centre of pixel (0, 0) is at (0.5, 0.5)), with round ends, so a line
+    /// of no length is a dot. Lines under a pixel wide are drawn a pixel wide
+    /// and fainter.
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32), width: f32, color: [u8; 3]) {
+        let half = width.max(1.0) / 2.0;
+        let strength = width.clamp(0.0, 1.0);
+        let reach = half + 1.0;
+
+        // Walk along the longer axis and cover the pixels across it near the
+        // line; `a` and `b` are (along, across), `a` first
+        let steep = (to.1 - from.1).abs() > (to.0 - from.0).abs();
+        let swap = |(x, y): (f32, f32)| if steep { (y, x) } else { (x, y) };
+        let (a, b) = (swap(from), swap(to));
+        let (a, b) = if a.0 <= b.0 { (a, b) } else { (b, a) };
+        let (along_limit, across_limit) = if steep {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        };
+        let slope = if b.0 > a.0 {
+            (b.1 - a.1) / (b.0 - a.0)
+        } else {
+            0.0
+        };
+        let spread = reach * (1.0 + slope * slope).sqrt();
+
+        let first = ((a.0 - reach).floor() as i64).max(0);
+        let last = ((b.0 + reach).ceil() as i64).min(along_limit as i64 - 1);
+        for along in first..=last {
+            let centre = along as f32 + 0.5;
+            let across_at = a.1 + slope * (centre.clamp(a.0, b.0) - a.0);
+            let low = ((across_at - spread).floor() as i64).max(0);
+            let high = ((across_at + spread).ceil() as i64).min(across_limit as i64 - 1);
+            for across in low..=high {
+                let distance = distance_to_segment((centre, across as f32 + 0.5), a, b);
+                let coverage = (half + 0.5 - distance).clamp(0.0, 1.0) * strength;
+                if coverage > 0.0 {
+                    let (x, y) = if steep {
+                        (across, along)
+                    } else {
+                        (along, across)
+                    };
+                    let cell = &mut self.cells[y as usize * self.width as usize + x as usize];
+                    for (c, &value) in color.iter().enumerate() {
+                        cell[c] += value as f32 / 255.0 * coverage;
+                    }
+                    cell[3] += coverage;
+                }
+            }
+        }
+    }
+
+    /// Lay the buffer over `image` (the same size), at `opacity` from 0 to 1
+    pub fn composite(&self, image: &mut RgbaImage, opacity: f32) {
+        for (cell, pixel) in self.cells.iter().zip(image.pixels_mut()) {
+            let coverage = cell[3];
+            if coverage <= 0.0 {
+                continue;
+            }
+            let alpha = coverage.min(1.0) * opacity;
+            let extra = (coverage - 1.0).max(0.0);
+            let lift = extra / (extra + HALF_WHITE_COVERAGE);
+            for c in 0..3 {
+                let mean = cell[c] / coverage;
+                let lit = (mean + (1.0 - mean) * lift) * 255.0;
+                pixel[c] = (pixel[c] as f32 * (1.0 - alpha) + lit * alpha).round() as u8;
+            }
+        }
+    }
+}
+
+/// Distance from `p` to the segment from `a` to `b`
+fn distance_to_segment(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq > 0.0 {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    ((p.0 - a.0 - t * dx).powi(2) + (p.1 - a.1 - t * dy).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn black(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]))
+    }
+
+    #[test]
+    fn test_lines_are_smooth_and_pile_up_brighter() {
+        // A pixel-wide line through pixel centres covers just its row
+        let mut accumulator = Accumulator::new(6, 5);
+        accumulator.line((0.5, 2.5), (5.5, 2.5), 1.0, [0, 200, 0]);
+        let mut image = black(6, 5);
+        accumulator.composite(&mut image, 1.0);
+        assert_eq!(image.get_pixel(3, 2).0, [0, 200, 0, 255]);
+        assert_eq!(image.get_pixel(3, 1).0, [0, 0, 0, 255]);
+
+        // Many lines over the same pixels lift them towards white
+        for _ in 0..16 {
+            accumulator.line((0.5, 2.5), (5.5, 2.5), 1.0, [0, 200, 0]);
+        }
+        let mut image = black(6, 5);
+        accumulator.composite(&mut image, 1.0);
+        assert_eq!(image.get_pixel(3, 2).0, [128, 228, 128, 255]);
+
+        // A diagonal partly covers the pixels beside it; half opacity halves it
+        accumulator.clear();
+        accumulator.line((0.5, 0.5), (4.5, 4.5), 1.0, [255, 255, 255]);
+        let mut image = black(6, 5);
+        accumulator.composite(&mut image, 0.5);
+        assert_eq!(image.get_pixel(2, 2).0, [128, 128, 128, 255]);
+        let beside = image.get_pixel(3, 2).0[0];
+        assert!(beside > 0 && beside < 128, "{beside}");
+
+        // Lines running off the image are clipped, not wrapped onto the next
+        // row or the far edge
+        accumulator.clear();
+        accumulator.line((3.5, 2.5), (100.5, 2.5), 1.0, [255, 0, 0]);
+        accumulator.line((1.5, 1.5), (1.5, 50.5), 1.0, [0, 0, 255]);
+        let mut image = black(6, 5);
+        accumulator.composite(&mut image, 1.0);
+        for x in 3..6 {
+            assert_eq!(image.get_pixel(x, 2).0, [255, 0, 0, 255]);
+        }
+        for y in 1..5 {
+            assert_eq!(image.get_pixel(1, y).0, [0, 0, 255, 255]);
+        }
+        for (x, y) in [(0, 2), (2, 2), (0, 3), (1, 0), (0, 0), (5, 3)] {
+            assert_eq!(image.get_pixel(x, y).0, [0, 0, 0, 255], "({x}, {y})");
+        }
+    }
+}
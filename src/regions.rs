@@ -0,0 +1,294 @@
+// Named regions of interest
+//
+// A region is a rectangle in the plot's concatenated genome coordinates: a
+// query interval against a target interval. Regions are exported per sequence,
+// so one that spans a sequence boundary becomes several rows. Going the other
+// way, "Go to" reads per-sequence loci back into genome coordinates.
+use crate::rust_plot::RustPlot;
+use anyhow::Result;
+
+/// Overlay colours handed out to new regions in turn
+pub const REGION_PALETTE: [[u8; 3]; 6] = [
+    [255, 200, 0],
+    [0, 200, 255],
+    [255, 100, 200],
+    [150, 255, 100],
+    [200, 150, 255],
+    [255, 140, 60],
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    pub name: String,
+    pub x_min: i64,
+    pub x_max: i64,
+    pub y_min: i64,
+    pub y_max: i64,
+    pub color: [u8; 3],
+}
+
+/// BED intervals on the query sequences, one row per sequence a region spans
+pub fn to_bed(regions: &[Region], plot: &RustPlot) -> String {
+    let mut text = String::new();
+    for region in regions {
+        for (idx, start, end) in plot.sequence_intervals(0, region.x_min, region.x_max) {
+            text.push_str(&format!(
+                "{}\t{start}\t{end}\t{}\n",
+                plot.query_sequences[idx],
+                bed_name(&region.name)
+            ));
+        }
+    }
+    text
+}
+
+/// Tab-separated table of both axes, one row per query x target sequence pair
+/// a region spans
+pub fn to_tsv(regions: &[Region], plot: &RustPlot) -> String {
+    let mut text =
+        String::from("name\tquery\tquery_start\tquery_end\ttarget\ttarget_start\ttarget_end\n");
+    for region in regions {
+        let targets = plot.sequence_intervals(1, region.y_min, region.y_max);
+        for (qi, q_start, q_end) in plot.sequence_intervals(0, region.x_min, region.x_max) {
+            for &(ti, t_start, t_end) in &targets {
+                text.push_str(&format!(
+                    "{}\t{}\t{q_start}\t{q_end}\t{}\t{t_start}\t{t_end}\n",
+                    bed_name(&region.name),
+                    plot.query_sequences[qi],
+                    plot.target_sequences[ti]
+                ));
+            }
+        }
+    }
+    text
+}
+
+/// Genome intervals to show on each axis, from "Go to"; None leaves an axis
+/// whole
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GoTo {
+    pub x: Option<(i64, i64)>,
+    pub y: Option<(i64, i64)>,
+}
+
+/// Read `qchr:start-end x tchr:start-end`, or one locus alone (looked up as a
+/// query name first, then a target name). Coordinates are 0-based, end
+/// exclusive, on the sequence's forward strand as the viewer shows them; a
+/// bare name means the whole sequence, and commas in numbers are ignored.
+pub fn parse_go_to(text: &str, plot: &RustPlot) -> Result<GoTo> {
+    let loci: Vec<&str> = text
+        .split([' ', '\t'])
+        .filter(|word| !word.is_empty())
+        .collect();
+    match loci.as_slice() {
+        [locus] => {
+            if let Some(x) = locus_interval(locus, plot, 0)? {
+                Ok(GoTo {
+                    x: Some(x),
+                    y: None,
+                })
+            } else if let Some(y) = locus_interval(locus, plot, 1)? {
+                Ok(GoTo {
+                    x: None,
+                    y: Some(y),
+                })
+            } else {
+                anyhow::bail!("no sequence named '{}'", locus_name(locus))
+            }
+        }
+        [query, "x" | "X" | "×", target] => {
+            let x = locus_interval(query, plot, 0)?;
+            let y = locus_interval(target, plot, 1)?;
+            Ok(GoTo {
+                x: Some(x.ok_or_else(|| {
+                    anyhow::anyhow!("no query sequence named '{}'", locus_name(query))
+                })?),
+                y: Some(y.ok_or_else(|| {
+                    anyhow::anyhow!("no target sequence named '{}'", locus_name(target))
+                })?),
+            })
+        }
+        _ => anyhow::bail!("expected NAME[:START-END], optionally followed by x NAME[:START-END]"),
+    }
+}
+
+/// Name and optional range of `name[:start-end]`; names may hold colons of
+/// their own, so the range is only split off if it reads as one
+fn split_locus(locus: &str) -> (&str, Option<&str>) {
+    match locus.rsplit_once(':') {
+        Some((name, range))
+            if range.contains('-')
+                && range
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || c == '-' || c == ',') =>
+        {
+            (name, Some(range))
+        }
+        _ => (locus, None),
+    }
+}
+
+fn locus_name(locus: &str) -> &str {
+    split_locus(locus).0
+}
+
+/// Genome interval of a locus on one axis (0 = query, 1 = target), or None if
+/// the axis has no sequence of that name
+fn locus_interval(locus: &str, plot: &RustPlot, genome: i32) -> Result<Option<(i64, i64)>> {
+    let (name, range) = split_locus(locus);
+    let (names, lengths, boundaries, reversed) = match genome {
+        0 => (
+            &plot.query_sequences,
+            &plot.query_lengths,
+            &plot.query_boundaries,
+            &plot.query_reversed,
+        ),
+        _ => (
+            &plot.target_sequences,
+            &plot.target_lengths,
+            &plot.target_boundaries,
+            &plot.target_reversed,
+        ),
+    };
+    let Some(idx) = names.iter().position(|n| n == name) else {
+        return Ok(None);
+    };
+    let length = lengths[idx];
+    let (start, end) = match range {
+        Some(range) => {
+            let (start, end) = range.split_once('-').unwrap_or((range, ""));
+            let number = |part: &str| -> Result<i64> {
+                part.replace(',', "")
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("'{part}' in '{locus}' is not a position"))
+            };
+            (number(start)?, number(end)?)
+        }
+        None => (0, length),
+    };
+    if start >= end || end > length {
+        anyhow::bail!("{name} is {length} bp long; {start}-{end} is not a range inside it");
+    }
+    let offset = boundaries[idx];
+    Ok(Some(if reversed.get(idx).copied().unwrap_or(false) {
+        (offset + length - end, offset + length - start)
+    } else {
+        (offset + start, offset + end)
+    }))
+}
+
+/// A genome box as `qchr:start-end x tchr:start-end`, which "Go to" reads
+/// back; an axis spanning several sequences lists each piece, comma
+/// separated. None if the box misses the sequences on either axis.
+pub fn box_locus(plot: &RustPlot, x: (i64, i64), y: (i64, i64)) -> Option<String> {
+    let axis = |genome: i32, (start, end): (i64, i64)| {
+        let names = match genome {
+            0 => &plot.query_sequences,
+            _ => &plot.target_sequences,
+        };
+        let pieces: Vec<String> = plot
+            .sequence_intervals(genome, start, end)
+            .into_iter()
+            .map(|(idx, lo, hi)| format!("{}:{lo}-{hi}", names[idx]))
+            .collect();
+        (!pieces.is_empty()).then(|| pieces.join(","))
+    };
+    Some(format!("{} x {}", axis(0, x)?, axis(1, y)?))
+}
+
+/// Region names go in a single tab-separated column
+fn bed_name(name: &str) -> String {
+    let name = name.trim().replace(['\t', '\n'], " ");
+    if name.is_empty() {
+        ".".to_string()
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aln_reader::AlnRecord;
+
+    fn region(x_min: i64, x_max: i64, y_min: i64, y_max: i64) -> Region {
+        Region {
+            name: "dup 1".to_string(),
+            x_min,
+            x_max,
+            y_min,
+            y_max,
+            color: REGION_PALETTE[0],
+        }
+    }
+
+    fn plot() -> RustPlot {
+        let records: Vec<AlnRecord> = Vec::new();
+        RustPlot::from_records(
+            &records,
+            vec!["q1".to_string(), "q2".to_string()],
+            vec!["t1".to_string()],
+            vec![1000, 500],
+            vec![2000],
+        )
+    }
+
+    #[test]
+    fn test_bed_splits_at_sequence_boundaries() {
+        let bed = to_bed(&[region(900, 1200, 0, 10)], &plot());
+        assert_eq!(bed, "q1\t900\t1000\tdup 1\nq2\t0\t200\tdup 1\n");
+    }
+
+    #[test]
+    fn test_box_locus_goes_back_to_the_box() {
+        let plot = plot();
+        let locus = box_locus(&plot, (100, 200), (1500, 1600)).unwrap();
+        assert_eq!(locus, "q1:100-200 x t1:1500-1600");
+        let go_to = parse_go_to(&locus, &plot).unwrap();
+        assert_eq!(go_to.x, Some((100, 200)));
+        assert_eq!(go_to.y, Some((1500, 1600)));
+        assert_eq!(
+            box_locus(&plot, (900, 1200), (0, 10)).unwrap(),
+            "q1:900-1000,q2:0-200 x t1:0-10"
+        );
+        assert_eq!(box_locus(&plot, (0, 10), (3000, 4000)), None);
+    }
+
+    #[test]
+    fn test_tsv_pairs_both_axes() {
+        let tsv = to_tsv(&[region(100, 200, 1500, 1600)], &plot());
+        let rows: Vec<&str> = tsv.lines().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1], "dup 1\tq1\t100\t200\tt1\t1500\t1600");
+    }
+
+    #[test]
+    fn test_go_to_loci() {
+        let plot = plot();
+        assert_eq!(
+            parse_go_to("q2:100-200 x t1:1,000-1,500", &plot).unwrap(),
+            GoTo {
+                x: Some((1100, 1200)),
+                y: Some((1000, 1500)),
+            }
+        );
+        // One locus: a bare name is the whole sequence, on whichever axis has it
+        assert_eq!(
+            parse_go_to(" t1 ", &plot).unwrap(),
+            GoTo {
+                x: None,
+                y: Some((0, 2000)),
+            }
+        );
+        assert!(parse_go_to("q1:900-1100", &plot).is_err()); // Past the end
+        assert!(parse_go_to("chr9", &plot).is_err());
+        assert!(parse_go_to("t1 x q1", &plot).is_err()); // Axes swapped
+
+        let mut flipped = plot.clone();
+        flipped.query_reversed[0] = true;
+        assert_eq!(
+            parse_go_to("q1:0-100", &flipped).unwrap().x,
+            Some((900, 1000))
+        );
+    }
+}
@@ -1,16 +1,335 @@
 // Pure Rust implementation of plot data structures
-use crate::aln_reader::AlnFile;
+use crate::aln_reader::{AlnFile, AlnRecord, ReadReport};
+use crate::delta;
 use crate::sequence_filter::SequenceFilter;
+use crate::timings::Timings;
 use anyhow::Result;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
 use std::path::Path;
 
-#[derive(Debug, Clone)]
+/// Segment count above which plots are downsampled by default
+pub const DEFAULT_MAX_SEGMENTS: usize = 2_000_000;
+
+/// Query depth at which a segment counts as repetitive by default
+pub const DEFAULT_REPEAT_DEPTH: u32 = 3;
+
+/// Fewest off-diagonal segments that make a cluster worth visiting
+pub const DEFAULT_CLUSTER_MIN_SEGMENTS: usize = 3;
+
+/// Largest gap along either axis between consecutive alignments of one chain
+pub const DEFAULT_CHAIN_GAP: i64 = 50_000;
+
+/// Segments further than this fraction of their query sequence's length from
+/// the main diagonal count as off-diagonal
+const OFF_DIAGONAL_TOLERANCE: f64 = 0.1;
+
+/// Off-diagonal segments are grouped on a grid this many cells across the larger genome
+const CLUSTER_GRID_CELLS: f64 = 200.0;
+
+/// A dense group of segments away from the main syntenic diagonal, such as a
+/// duplication or translocation (genome coordinates)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    pub x_min: i64,
+    pub x_max: i64,
+    pub y_min: i64,
+    pub y_max: i64,
+    pub segments: usize,
+    pub aligned_bp: i64,
+    pub hull: Vec<(i64, i64)>, // Convex hull of the segment endpoints, counter-clockwise
+}
+
+/// Width every sequence is stretched to by `RustPlot::normalized`, so a
+/// position of 1% of a sequence is 10,000 units into its band
+pub const NORMALIZED_BAND: i64 = 1_000_000;
+
+/// Intervals on the same partner sequence closer than this are merged when
+/// listing where a region aligns
+pub const DEFAULT_PARTNER_MERGE_GAP: i64 = 1000;
+
+/// Where part of a queried interval aligns on the other genome (local
+/// coordinates on the partner's forward strand)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartnerInterval {
+    pub seq: usize, // Index on the other axis
+    pub start: i64,
+    pub end: i64,
+    pub aligned_bp: i64, // Bases of the queried interval aligned here
+    pub identity: f32,   // Length-weighted mean
+    pub alignments: usize,
+}
+
+/// The alignments involving one sequence, for a per-sequence report
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SequenceReport {
+    pub segments: Vec<usize>,               // Indices into the plot's segments
+    pub aligned_bp: i64,                    // Summed over alignments, on this sequence's axis
+    pub partners: Vec<(usize, usize, i64)>, // (Other-axis sequence, alignments, aligned bp), most aligned first
+}
+
+/// Average nucleotide identity of the query against the target
+#[derive(Debug, Clone, Default)]
+pub struct AniSummary {
+    pub ani: Option<f64>,               // Percent, over covered query bases
+    pub covered_bp: i64,                // Query bases under at least one alignment
+    pub query_bp: i64,                  // Whole query length
+    pub per_sequence: Vec<SequenceAni>, // Query sequences with any coverage, in plot order
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceAni {
+    pub sequence: usize, // Query sequence index
+    pub ani: f64,
+    pub covered_bp: i64,
+    pub length: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct AlignmentSegment {
     pub abeg: i64,
     pub aend: i64,
     pub bbeg: i64,
     pub bend: i64,
     pub reverse: bool,
+    pub identity: f32, // Percent identity
+}
+
+impl AlignmentSegment {
+    /// The segment with its axes exchanged, x still increasing
+    pub fn transposed(&self) -> Self {
+        let ((abeg, bbeg), (aend, bend)) = if self.bbeg <= self.bend {
+            ((self.bbeg, self.abeg), (self.bend, self.aend))
+        } else {
+            ((self.bend, self.aend), (self.bbeg, self.abeg))
+        };
+        Self {
+            abeg,
+            aend,
+            bbeg,
+            bend,
+            ..*self
+        }
+    }
+}
+
+/// Which strand of alignments a filter keeps
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum StrandFilter {
+    #[default]
+    Both,
+    Forward,
+    Reverse,
+}
+
+/// Per-segment cutoffs, applied while drawing so they can change without
+/// rebuilding the plot
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SegmentFilter {
+    pub min_length: i64,   // Query bases
+    pub min_identity: f32, // Percent
+    pub strand: StrandFilter,
+}
+
+impl SegmentFilter {
+    /// True if any cutoff would drop something
+    pub fn is_active(&self) -> bool {
+        *self != Self::default()
+    }
+
+    pub fn matches(&self, seg: &AlignmentSegment) -> bool {
+        let strand_ok = match self.strand {
+            StrandFilter::Both => true,
+            StrandFilter::Forward => !seg.reverse,
+            StrandFilter::Reverse => seg.reverse,
+        };
+        strand_ok && seg.aend - seg.abeg >= self.min_length && seg.identity >= self.min_identity
+    }
+}
+
+/// How a layer's line width follows its alignments, so long or confident
+/// ones stand out when zoomed out
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum WidthScale {
+    #[default]
+    Fixed,
+    Length,   // With the log of the length, from 1 kb to 1 Mb
+    Identity, // From 70% to 100% identity
+}
+
+impl WidthScale {
+    pub const ALL: [WidthScale; 3] = [WidthScale::Fixed, WidthScale::Length, WidthScale::Identity];
+
+    /// Name in preset files
+    pub fn name(self) -> &'static str {
+        match self {
+            WidthScale::Fixed => "fixed",
+            WidthScale::Length => "length",
+            WidthScale::Identity => "identity",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WidthScale::Fixed => "Fixed",
+            WidthScale::Length => "By length",
+            WidthScale::Identity => "By identity",
+        }
+    }
+
+    /// Multiple of the layer's width to draw `seg` at: from half for the
+    /// shortest or least identical up to three times
+    pub fn factor(self, seg: &AlignmentSegment) -> f32 {
+        let t = match self {
+            WidthScale::Fixed => return 1.0,
+            WidthScale::Length => ((seg.aend - seg.abeg).max(1) as f32).log10() / 3.0 - 1.0,
+            WidthScale::Identity => (seg.identity - 70.0) / 30.0,
+        };
+        0.5 + 2.5 * t.clamp(0.0, 1.0)
+    }
+}
+
+/// Best-N plane-sweep filter, written query:target as in sweepga: at each
+/// position of the query axis keep the `per_query` best alignments covering
+/// it, likewise along the target axis; None means no limit on that axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SweepFilter {
+    pub per_query: Option<usize>,
+    pub per_target: Option<usize>,
+}
+
+impl SweepFilter {
+    pub const ONE_TO_ONE: Self = Self {
+        per_query: Some(1),
+        per_target: Some(1),
+    };
+    pub const ONE_TO_MANY: Self = Self {
+        per_query: Some(1),
+        per_target: None,
+    };
+    pub const MANY_TO_ONE: Self = Self {
+        per_query: None,
+        per_target: Some(1),
+    };
+
+    /// Parse "1:1", "1:N", "2:N" etc.; N (or "inf") means no limit
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (query, target) = spec
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("expected QUERY:TARGET, e.g. 1:1 or 1:N"))?;
+        let limit = |part: &str| -> Result<Option<usize>> {
+            match part.trim() {
+                "N" | "n" | "inf" => Ok(None),
+                number => match number.parse::<usize>() {
+                    Ok(0) | Err(_) => {
+                        anyhow::bail!("'{number}' is not a positive number or N")
+                    }
+                    Ok(n) => Ok(Some(n)),
+                },
+            }
+        };
+        Ok(Self {
+            per_query: limit(query)?,
+            per_target: limit(target)?,
+        })
+    }
+}
+
+impl fmt::Display for SweepFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let limit = |n: Option<usize>| n.map_or("N".to_string(), |n| n.to_string());
+        write!(f, "{}:{}", limit(self.per_query), limit(self.per_target))
+    }
+}
+
+/// Which spans survive a best-`k` sweep along one axis: a span is kept if at
+/// some position it is among the `k` highest-scoring spans covering it
+fn sweep_axis(spans: &[(i64, i64)], scores: &[f64], k: usize) -> Vec<bool> {
+    // (position, starts here, index); ends sort before starts at the same
+    // position since spans are half-open
+    let mut events: Vec<(i64, bool, usize)> = Vec::with_capacity(spans.len() * 2);
+    for (idx, &(lo, hi)) in spans.iter().enumerate() {
+        events.push((lo, true, idx));
+        events.push((hi.max(lo + 1), false, idx));
+    }
+    events.sort_unstable();
+
+    // Scores are non-negative, so their bit patterns order like the values
+    let key = |idx: usize| (Reverse(scores[idx].max(0.0).to_bits()), idx);
+    let mut active = BTreeSet::new();
+    let mut keep = vec![false; spans.len()];
+    let mut i = 0;
+    while i < events.len() {
+        let pos = events[i].0;
+        while i < events.len() && events[i].0 == pos {
+            let (_, starts, idx) = events[i];
+            if starts {
+                active.insert(key(idx));
+            } else {
+                active.remove(&key(idx));
+            }
+            i += 1;
+        }
+        // The active set now covers [pos, next event)
+        for &(_, idx) in active.iter().take(k) {
+            keep[idx] = true;
+        }
+    }
+    keep
+}
+
+impl AlignmentSegment {
+    /// Check if the segment's bounding box intersects [x_min, x_max] x [y_min, y_max]
+    pub fn intersects(&self, x_min: i64, x_max: i64, y_min: i64, y_max: i64) -> bool {
+        let seg_x_min = self.abeg.min(self.aend);
+        let seg_x_max = self.abeg.max(self.aend);
+        let seg_y_min = self.bbeg.min(self.bend);
+        let seg_y_max = self.bbeg.max(self.bend);
+
+        // Intersection test
+        seg_x_max >= x_min && seg_x_min <= x_max && seg_y_max >= y_min && seg_y_min <= y_max
+    }
+}
+
+/// Convert one record to genome-wide coordinates, given the sequence indices it maps to
+fn record_to_segment(
+    rec: &AlnRecord,
+    qid: usize,
+    tid: usize,
+    query_boundaries: &[i64],
+    target_boundaries: &[i64],
+    target_lengths: &[i64],
+) -> AlignmentSegment {
+    // Get scaffold offsets
+    let query_offset = query_boundaries.get(qid).copied().unwrap_or(0);
+    let target_offset = target_boundaries.get(tid).copied().unwrap_or(0);
+
+    // For reverse complement: subtract from END of target sequence (like C code)
+    // C code: bbeg = (offset + seqlen) - rec.target_start
+    let (bbeg, bend) = if rec.reverse != 0 {
+        let target_seq_len = target_lengths.get(tid).copied().unwrap_or(0);
+        let target_end_pos = target_offset + target_seq_len;
+        (
+            target_end_pos - rec.target_start,
+            target_end_pos - rec.target_end,
+        )
+    } else {
+        (
+            target_offset + rec.target_start,
+            target_offset + rec.target_end,
+        )
+    };
+
+    // Convert to genome-wide coordinates
+    AlignmentSegment {
+        abeg: query_offset + rec.query_start,
+        aend: query_offset + rec.query_end,
+        bbeg,
+        bend,
+        reverse: rec.reverse != 0,
+        identity: crate::aln_reader::calculate_identity(rec) as f32,
+    }
 }
 
 pub struct RustPlot {
@@ -30,26 +349,97 @@ pub struct RustPlot {
     // Scaffold boundaries (cumulative positions)
     pub query_boundaries: Vec<i64>,
     pub target_boundaries: Vec<i64>,
+
+    // Per sequence: drawn reverse-complemented (coordinates mirrored)
+    pub query_reversed: Vec<bool>,
+    pub target_reversed: Vec<bool>,
+
+    // Segments failing this stay in `segments` but are skipped by every query,
+    // so cutoffs can change without rebuilding the plot
+    pub cutoff: SegmentFilter,
 }
 
 impl RustPlot {
     /// Load a .1aln file and create plot data
+    #[allow(dead_code)] // Library API; the binary wants the read report
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let mut aln_file = AlnFile::open(path)?;
+        Self::from_file_with_report(path).map(|(plot, _)| plot)
+    }
+
+    /// Load a .1aln file, keeping whatever is readable if it is damaged
+    ///
+    /// Fails only if nothing at all could be read; otherwise the report says how
+    /// many records were skipped and whether reading stopped early.
+    pub fn from_file_with_report<P: AsRef<Path>>(path: P) -> Result<(Self, ReadReport)> {
+        Self::from_file_timed(path, &mut Timings::default())
+    }
+
+    /// `from_file_with_report`, timing the read and convert stages
+    pub fn from_file_timed<P: AsRef<Path>>(
+        path: P,
+        timings: &mut Timings,
+    ) -> Result<(Self, ReadReport)> {
+        Self::from_file_with_progress(path, timings, |_| true)
+    }
+
+    /// `from_file_timed`, passing the records read so far to `progress` as a
+    /// .1aln file is read; fails as cancelled once it returns false. A .delta
+    /// file is read whole without reports
+    pub fn from_file_with_progress<P: AsRef<Path>>(
+        path: P,
+        timings: &mut Timings,
+        progress: impl FnMut(usize) -> bool,
+    ) -> Result<(Self, ReadReport)> {
+        let path = path.as_ref();
+        if delta::is_delta_path(path) {
+            return timings.time("read", || delta::from_file(path));
+        }
 
         // Read all alignment records
-        let records = aln_file.read_all_records()?;
+        let (aln_file, records, report) = timings.time("read", || {
+            let mut aln_file = AlnFile::open(path)?;
+            let (records, report) = aln_file.read_records_with_progress(progress);
+            anyhow::Ok((aln_file, records, report))
+        })?;
+        if report.cancelled {
+            anyhow::bail!("Loading cancelled");
+        }
+        if records.is_empty() {
+            if let Some(ref error) = report.error {
+                anyhow::bail!("No readable alignment records: {error}");
+            }
+        }
 
         // Get sequence information (may be empty if file has no names)
-        let mut query_sequences = aln_file.query_sequences.clone();
-        let mut target_sequences = aln_file.target_sequences.clone();
+        let plot = timings.time("convert", || {
+            Self::from_records(
+                &records,
+                aln_file.query_sequences.clone(),
+                aln_file.target_sequences.clone(),
+                Vec::new(),
+                Vec::new(),
+            )
+        });
+        Ok((plot, report))
+    }
 
+    /// Build plot data from alignment records
+    ///
+    /// Known sequence lengths may be passed in (e.g. from FASTA input); any missing
+    /// or shorter length is grown to the max coordinate seen in the records.
+    pub fn from_records(
+        records: &[AlnRecord],
+        mut query_sequences: Vec<String>,
+        mut target_sequences: Vec<String>,
+        mut query_lengths: Vec<i64>,
+        mut target_lengths: Vec<i64>,
+    ) -> Self {
         // Calculate sequence lengths from the records
         // Use max coordinates seen in alignments. Grow vectors dynamically as needed.
-        let mut query_lengths = vec![0i64; query_sequences.len()];
-        let mut target_lengths = vec![0i64; target_sequences.len()];
+        query_lengths.resize(query_lengths.len().max(query_sequences.len()), 0);
+        target_lengths.resize(target_lengths.len().max(target_sequences.len()), 0);
 
-        for rec in &records {
+        for rec in records {
             let qid = rec.query_id as usize;
             let tid = rec.target_id as usize;
 
@@ -100,53 +490,20 @@ impl RustPlot {
         let segments: Vec<AlignmentSegment> = records
             .iter()
             .map(|rec| {
-                let qid = rec.query_id as usize;
-                let tid = rec.target_id as usize;
-
-                // Get scaffold offsets
-                let query_offset = if qid < query_boundaries.len() {
-                    query_boundaries[qid]
-                } else {
-                    0
-                };
-                let target_offset = if tid < target_boundaries.len() {
-                    target_boundaries[tid]
-                } else {
-                    0
-                };
-
-                // For reverse complement: subtract from END of target sequence (like C code)
-                // C code: bbeg = (offset + seqlen) - rec.target_start
-                let (bbeg, bend) = if rec.reverse != 0 {
-                    let target_seq_len = if tid < target_lengths.len() {
-                        target_lengths[tid]
-                    } else {
-                        0
-                    };
-                    let target_end_pos = target_offset + target_seq_len;
-                    (
-                        target_end_pos - rec.target_start,
-                        target_end_pos - rec.target_end,
-                    )
-                } else {
-                    (
-                        target_offset + rec.target_start,
-                        target_offset + rec.target_end,
-                    )
-                };
-
-                // Convert to genome-wide coordinates
-                AlignmentSegment {
-                    abeg: query_offset + rec.query_start,
-                    aend: query_offset + rec.query_end,
-                    bbeg,
-                    bend,
-                    reverse: rec.reverse != 0,
-                }
+                record_to_segment(
+                    rec,
+                    rec.query_id as usize,
+                    rec.target_id as usize,
+                    &query_boundaries,
+                    &target_boundaries,
+                    &target_lengths,
+                )
             })
             .collect();
 
-        Ok(Self {
+        Self {
+            query_reversed: vec![false; query_sequences.len()],
+            target_reversed: vec![false; target_sequences.len()],
             query_sequences,
             target_sequences,
             query_lengths,
@@ -156,7 +513,105 @@ impl RustPlot {
             segments,
             query_boundaries,
             target_boundaries,
-        })
+            cutoff: SegmentFilter::default(),
+        }
+    }
+
+    /// Plot over the given sequences from segments already in genome-wide
+    /// coordinates (e.g. read back from a segment store)
+    pub fn from_segments(
+        query_sequences: Vec<String>,
+        target_sequences: Vec<String>,
+        query_lengths: Vec<i64>,
+        target_lengths: Vec<i64>,
+        segments: Vec<AlignmentSegment>,
+    ) -> Self {
+        let empty = Self::from_records(
+            &[],
+            query_sequences,
+            target_sequences,
+            query_lengths,
+            target_lengths,
+        );
+        empty.with_segments(segments)
+    }
+
+    /// Convert records from another file over the same genomes into this plot's
+    /// genome-wide coordinates, matching sequences by name
+    ///
+    /// Returns the segments and the number of records skipped because one of their
+    /// sequences is not part of this plot.
+    pub fn project_records(&self, records: &[AlnRecord]) -> (Vec<AlignmentSegment>, usize) {
+        let query_ids: HashMap<&str, usize> = self
+            .query_sequences
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+        let target_ids: HashMap<&str, usize> = self
+            .target_sequences
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+
+        let mut skipped = 0;
+        let mut segments = Vec::with_capacity(records.len());
+        for rec in records {
+            match (
+                query_ids.get(rec.query_name.as_str()),
+                target_ids.get(rec.target_name.as_str()),
+            ) {
+                (Some(&qid), Some(&tid)) => {
+                    let seg = record_to_segment(
+                        rec,
+                        qid,
+                        tid,
+                        &self.query_boundaries,
+                        &self.target_boundaries,
+                        &self.target_lengths,
+                    );
+                    segments.push(self.orient_segment(seg, qid, tid));
+                }
+                _ => skipped += 1,
+            }
+        }
+        (segments, skipped)
+    }
+
+    /// Mirror a file-orientation segment within any flipped sequence bands
+    fn orient_segment(&self, seg: AlignmentSegment, qid: usize, tid: usize) -> AlignmentSegment {
+        let q_flip = self.query_reversed.get(qid).copied().unwrap_or(false);
+        let t_flip = self.target_reversed.get(tid).copied().unwrap_or(false);
+        if !q_flip && !t_flip {
+            return seg;
+        }
+
+        let mirror =
+            |x: i64, boundaries: &[i64], idx: usize| boundaries[idx] + boundaries[idx + 1] - x;
+        let (mut abeg, mut aend) = (seg.abeg, seg.aend);
+        let (mut bbeg, mut bend) = (seg.bbeg, seg.bend);
+        if t_flip {
+            bbeg = mirror(bbeg, &self.target_boundaries, tid);
+            bend = mirror(bend, &self.target_boundaries, tid);
+        }
+        if q_flip {
+            // Mirror, then swap ends to keep query coordinates increasing
+            (abeg, aend) = (
+                mirror(aend, &self.query_boundaries, qid),
+                mirror(abeg, &self.query_boundaries, qid),
+            );
+            (bbeg, bend) = (bend, bbeg);
+        }
+
+        AlignmentSegment {
+            abeg,
+            aend,
+            bbeg,
+            bend,
+            reverse: seg.reverse != (q_flip != t_flip),
+            identity: seg.identity,
+        }
     }
 
     /// Get query genome length (A genome)
@@ -174,6 +629,25 @@ impl RustPlot {
         1
     }
 
+    /// Approximate heap memory held by the plot data, in bytes
+    pub fn estimated_memory_bytes(&self) -> usize {
+        let names: usize = self
+            .query_sequences
+            .iter()
+            .chain(&self.target_sequences)
+            .map(|name| name.capacity() + std::mem::size_of::<String>())
+            .sum();
+        let coords = self.query_lengths.capacity()
+            + self.target_lengths.capacity()
+            + self.query_boundaries.capacity()
+            + self.target_boundaries.capacity();
+
+        std::mem::size_of::<Self>()
+            + names
+            + coords * std::mem::size_of::<i64>()
+            + self.segments.capacity() * std::mem::size_of::<AlignmentSegment>()
+    }
+
     /// Get scaffold boundaries for a genome (0 = query, 1 = target)
     pub fn get_scaffold_boundaries(&self, genome: i32) -> Vec<i64> {
         match genome {
@@ -200,20 +674,540 @@ impl RustPlot {
 
         self.segments
             .iter()
-            .filter(|seg| {
-                // Check if segment intersects with visible region
-                let seg_x_min = seg.abeg.min(seg.aend);
-                let seg_x_max = seg.abeg.max(seg.aend);
-                let seg_y_min = seg.bbeg.min(seg.bend);
-                let seg_y_max = seg.bbeg.max(seg.bend);
-
-                // Intersection test
-                seg_x_max >= x_min && seg_x_min <= x_max && seg_y_max >= y_min && seg_y_min <= y_max
-            })
+            .filter(|seg| self.passes(seg) && seg.intersects(x_min, x_max, y_min, y_max))
             .cloned()
             .collect()
     }
 
+    /// Like `query_segments_in_region`, but returns indices into `segments`
+    pub fn query_segment_indices_in_region(
+        &self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    ) -> Vec<usize> {
+        let x_min = x as i64;
+        let x_max = (x + width) as i64;
+        let y_min = y as i64;
+        let y_max = (y + height) as i64;
+
+        self.segments
+            .iter()
+            .enumerate()
+            .filter(|(_, seg)| self.passes(seg) && seg.intersects(x_min, x_max, y_min, y_max))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// How many passing segments `query_segment_indices_in_region` would return
+    pub fn count_segments_in_region(&self, x: f64, y: f64, width: f64, height: f64) -> usize {
+        let x_min = x as i64;
+        let x_max = (x + width) as i64;
+        let y_min = y as i64;
+        let y_max = (y + height) as i64;
+
+        self.segments
+            .iter()
+            .filter(|seg| self.passes(seg) && seg.intersects(x_min, x_max, y_min, y_max))
+            .count()
+    }
+
+    /// Whether a segment passes the plot's cutoff
+    pub fn passes(&self, seg: &AlignmentSegment) -> bool {
+        !self.cutoff.is_active() || self.cutoff.matches(seg)
+    }
+
+    /// Number of segments passing the cutoff
+    pub fn passing_segments(&self) -> usize {
+        if !self.cutoff.is_active() {
+            return self.segments.len();
+        }
+        self.segments.iter().filter(|seg| self.passes(seg)).count()
+    }
+
+    /// Keep at most `max_segments` segments, sampled with probability weighted
+    /// by length so long alignments almost always survive
+    ///
+    /// Uses weighted sampling without replacement (Efraimidis-Spirakis) keyed by
+    /// a hash of each segment's index, so the same plot always gives the same subset.
+    /// Only segments passing the cutoff are counted and sampled; the hidden
+    /// ones are dropped with the rest.
+    pub fn downsampled(&self, max_segments: usize) -> Self {
+        if self.passing_segments() <= max_segments {
+            return self.clone();
+        }
+
+        let mut keyed: Vec<(f64, usize)> = self
+            .segments
+            .iter()
+            .enumerate()
+            .filter(|(_, seg)| self.passes(seg))
+            .map(|(idx, seg)| (Self::sample_key(idx, seg), idx))
+            .collect();
+
+        let mut keep: Vec<usize> = if max_segments == 0 {
+            Vec::new()
+        } else {
+            keyed.select_nth_unstable_by(max_segments - 1, |a, b| b.0.total_cmp(&a.0));
+            keyed[..max_segments].iter().map(|&(_, idx)| idx).collect()
+        };
+        keep.sort_unstable();
+
+        self.with_segments(keep.iter().map(|&idx| self.segments[idx].clone()).collect())
+    }
+
+    /// The same plot with segments sorted by position (query start, target
+    /// start, ends, strand, identity), so draw order, downsampling and the
+    /// plane sweep don't depend on the order records were read in
+    pub fn sorted(&self) -> Self {
+        let mut segments = self.segments.clone();
+        segments.sort_by(|a, b| {
+            (a.abeg, a.bbeg, a.aend, a.bend, a.reverse)
+                .cmp(&(b.abeg, b.bbeg, b.aend, b.bend, b.reverse))
+                .then(a.identity.total_cmp(&b.identity))
+        });
+        self.with_segments(segments)
+    }
+
+    /// Sampling key of segment `idx` for `downsampled`: ln(u) / length, the
+    /// largest keys forming the sample
+    pub fn sample_key(idx: usize, seg: &AlignmentSegment) -> f64 {
+        let weight = (seg.aend - seg.abeg).max(1) as f64;
+        unit_random(idx as u64).ln() / weight
+    }
+
+    /// Keep only the alignments that survive `filter`'s plane sweep, scored by
+    /// matching bases (query length times identity)
+    ///
+    /// Every segment takes part, whatever the cutoff; the cutoff still applies
+    /// to the result.
+    pub fn plane_sweep(&self, filter: SweepFilter) -> Self {
+        let kept = self.swept(filter);
+        self.with_segments(
+            self.segments
+                .iter()
+                .zip(&kept)
+                .filter(|&(_, &keep)| keep)
+                .map(|(seg, _)| seg.clone())
+                .collect(),
+        )
+    }
+
+    /// Whether each segment survives `filter`'s plane sweep
+    pub fn swept(&self, filter: SweepFilter) -> Vec<bool> {
+        let scores: Vec<f64> = self
+            .segments
+            .iter()
+            .map(|seg| (seg.aend - seg.abeg).abs() as f64 * seg.identity as f64)
+            .collect();
+        let keep_on = |limit: Option<usize>, span: fn(&AlignmentSegment) -> (i64, i64)| {
+            limit.map(|k| {
+                let spans: Vec<(i64, i64)> = self.segments.iter().map(span).collect();
+                sweep_axis(&spans, &scores, k)
+            })
+        };
+        let query = keep_on(filter.per_query, |seg| {
+            (seg.abeg.min(seg.aend), seg.abeg.max(seg.aend))
+        });
+        let target = keep_on(filter.per_target, |seg| {
+            (seg.bbeg.min(seg.bend), seg.bbeg.max(seg.bend))
+        });
+
+        (0..self.segments.len())
+            .map(|idx| {
+                query.as_ref().is_none_or(|keep| keep[idx])
+                    && target.as_ref().is_none_or(|keep| keep[idx])
+            })
+            .collect()
+    }
+
+    /// Same sequences and coordinate system, different segments
+    fn with_segments(&self, segments: Vec<AlignmentSegment>) -> Self {
+        Self {
+            query_sequences: self.query_sequences.clone(),
+            target_sequences: self.target_sequences.clone(),
+            query_lengths: self.query_lengths.clone(),
+            target_lengths: self.target_lengths.clone(),
+            query_genome_len: self.query_genome_len,
+            target_genome_len: self.target_genome_len,
+            segments,
+            query_boundaries: self.query_boundaries.clone(),
+            target_boundaries: self.target_boundaries.clone(),
+            query_reversed: self.query_reversed.clone(),
+            target_reversed: self.target_reversed.clone(),
+            cutoff: self.cutoff,
+        }
+    }
+
+    /// Index of the segment closest to (x, y), if any lies within `tolerance`
+    /// (all in genome coordinates)
+    pub fn segment_at(&self, x: f64, y: f64, tolerance: f64) -> Option<usize> {
+        let candidates = self.query_segment_indices_in_region(
+            x - tolerance,
+            y - tolerance,
+            2.0 * tolerance,
+            2.0 * tolerance,
+        );
+
+        candidates
+            .into_iter()
+            .map(|idx| {
+                let seg = &self.segments[idx];
+                let (x0, y0) = (seg.abeg as f64, seg.bbeg as f64);
+                let (dx, dy) = (seg.aend as f64 - x0, seg.bend as f64 - y0);
+                let len_sq = dx * dx + dy * dy;
+                // Closest point on the segment, as a fraction along it
+                let t = if len_sq > 0.0 {
+                    (((x - x0) * dx + (y - y0) * dy) / len_sq).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let (px, py) = (x0 + t * dx, y0 + t * dy);
+                (idx, (x - px).hypot(y - py))
+            })
+            .filter(|&(_, dist)| dist <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(idx, _)| idx)
+    }
+
+    /// One-line description of a segment in sequence-local coordinates, e.g.
+    /// `chr1:100-45100 ↔ scaf2:200-45150, 98.7% id, 45.0 kb, reverse`
+    pub fn describe_segment(&self, idx: usize) -> String {
+        let seg = &self.segments[idx];
+
+        let (_, query_name, q0) = self.query_coord_to_sequence(seg.abeg);
+        let (_, _, q1) = self.query_coord_to_sequence(seg.aend - 1);
+        let (_, target_name, t0) = self.target_coord_to_sequence(seg.bbeg.min(seg.bend));
+        let (_, _, t1) = self.target_coord_to_sequence(seg.bbeg.max(seg.bend) - 1);
+
+        format!(
+            "{query_name}:{}-{} ↔ {target_name}:{}-{}, {:.1}% id, {}, {}",
+            q0.min(q1),
+            q0.max(q1) + 1,
+            t0.min(t1),
+            t0.max(t1) + 1,
+            seg.identity,
+            format_bp(seg.aend - seg.abeg),
+            if seg.reverse { "reverse" } else { "forward" }
+        )
+    }
+
+    /// Mean alignment depth along one genome axis (0 = query, 1 = target)
+    ///
+    /// Splits [start, end) into `bins` equal-width bins and returns, for each, the
+    /// aligned bp falling in the bin divided by its width. Unaligned gaps show up as
+    /// 0 and collapsed duplications as depth above 1.
+    pub fn coverage_depth(&self, genome: i32, start: f64, end: f64, bins: usize) -> Vec<f64> {
+        let mut depth = vec![0.0; bins];
+        if bins == 0 || end <= start {
+            return depth;
+        }
+        let bin_width = (end - start) / bins as f64;
+
+        for seg in self.segments.iter().filter(|seg| self.passes(seg)) {
+            let (lo, hi) = match genome {
+                0 => (seg.abeg.min(seg.aend), seg.abeg.max(seg.aend)),
+                _ => (seg.bbeg.min(seg.bend), seg.bbeg.max(seg.bend)),
+            };
+            let lo = (lo as f64).max(start);
+            let hi = (hi as f64).min(end);
+            if hi <= lo {
+                continue;
+            }
+
+            let first = ((lo - start) / bin_width) as usize;
+            let last = (((hi - start) / bin_width) as usize).min(bins - 1);
+            for (bin, value) in depth.iter_mut().enumerate().take(last + 1).skip(first) {
+                let bin_lo = start + bin as f64 * bin_width;
+                let overlap = hi.min(bin_lo + bin_width) - lo.max(bin_lo);
+                if overlap > 0.0 {
+                    *value += overlap;
+                }
+            }
+        }
+
+        for value in &mut depth {
+            *value /= bin_width;
+        }
+        depth
+    }
+
+    /// Flag segments that lie mostly in query intervals covered by at least
+    /// `min_depth` alignments (repeats and multi-mapping sequence)
+    pub fn repetitive_segments(&self, min_depth: u32) -> Vec<bool> {
+        // Sweep the query axis, collecting the intervals at or above min_depth
+        let mut events: Vec<(i64, i32)> = Vec::with_capacity(self.segments.len() * 2);
+        for seg in self.segments.iter().filter(|seg| self.passes(seg)) {
+            events.push((seg.abeg.min(seg.aend), 1));
+            events.push((seg.abeg.max(seg.aend), -1));
+        }
+        // Ends sort before starts at the same position, so abutting segments don't overlap
+        events.sort_unstable();
+
+        let mut deep: Vec<(i64, i64)> = Vec::new();
+        let mut depth = 0i64;
+        let mut deep_start = None;
+        for (pos, delta) in events {
+            depth += delta as i64;
+            match deep_start {
+                None if depth >= min_depth as i64 => deep_start = Some(pos),
+                Some(start) if depth < min_depth as i64 => {
+                    if pos > start {
+                        deep.push((start, pos));
+                    }
+                    deep_start = None;
+                }
+                _ => {}
+            }
+        }
+
+        self.segments
+            .iter()
+            .map(|seg| {
+                let (lo, hi) = (seg.abeg.min(seg.aend), seg.abeg.max(seg.aend));
+                let first = deep.partition_point(|&(_, end)| end <= lo);
+                let covered: i64 = deep[first..]
+                    .iter()
+                    .take_while(|&&(start, _)| start < hi)
+                    .map(|&(start, end)| end.min(hi) - start.max(lo))
+                    .sum();
+                self.passes(seg) && hi > lo && covered * 2 > hi - lo
+            })
+            .collect()
+    }
+
+    /// Dense groups of at least `min_segments` segments lying off the main
+    /// syntenic diagonal, ordered left to right
+    ///
+    /// Each query sequence's main diagonal is the target sequence it has the
+    /// most aligned bases on, at the length-weighted median diagonal offset
+    /// (anti-diagonal if most of those bases align in reverse). Segments on
+    /// other targets, or far from that offset, are binned on a coarse grid and
+    /// neighbouring cells joined into clusters.
+    pub fn off_diagonal_clusters(&self, min_segments: usize) -> Vec<Cluster> {
+        if self.segments.is_empty() {
+            return Vec::new();
+        }
+
+        let placed: Vec<(usize, usize, i64)> = self
+            .segments
+            .iter()
+            .map(|seg| {
+                let amid = (seg.abeg + seg.aend) / 2;
+                let bmid = (seg.bbeg + seg.bend) / 2;
+                (
+                    self.find_sequence_index(&self.query_boundaries, amid),
+                    self.find_sequence_index(&self.target_boundaries, bmid),
+                    (seg.aend - seg.abeg).abs().max(1),
+                )
+            })
+            .collect();
+
+        // Dominant target and orientation per query sequence
+        let mut pair_bp: HashMap<(usize, usize), (i64, i64)> = HashMap::new();
+        for (seg, &(qi, ti, len)) in self.segments.iter().zip(&placed) {
+            if !self.passes(seg) {
+                continue;
+            }
+            let entry = pair_bp.entry((qi, ti)).or_default();
+            entry.0 += len;
+            if seg.reverse {
+                entry.1 += len;
+            }
+        }
+        let mut main_pair: HashMap<usize, (usize, bool, i64)> = HashMap::new();
+        for (&(qi, ti), &(bp, reverse_bp)) in &pair_bp {
+            let best = main_pair.entry(qi).or_insert((ti, false, 0));
+            if bp > best.2 || (bp == best.2 && ti < best.0) {
+                *best = (ti, reverse_bp * 2 > bp, bp);
+            }
+        }
+
+        let offset = |seg: &AlignmentSegment, anti: bool| {
+            let (amid, bmid) = ((seg.abeg + seg.aend) / 2, (seg.bbeg + seg.bend) / 2);
+            if anti {
+                bmid + amid
+            } else {
+                bmid - amid
+            }
+        };
+
+        // Length-weighted median offset of each query's main pair
+        let mut offsets: HashMap<usize, Vec<(i64, i64)>> = HashMap::new();
+        for (seg, &(qi, ti, len)) in self.segments.iter().zip(&placed) {
+            if !self.passes(seg) {
+                continue;
+            }
+            let (main_ti, anti, _) = main_pair[&qi];
+            if ti == main_ti {
+                offsets
+                    .entry(qi)
+                    .or_default()
+                    .push((offset(seg, anti), len));
+            }
+        }
+        let median_offset: HashMap<usize, i64> = offsets
+            .into_iter()
+            .map(|(qi, mut values)| {
+                values.sort_unstable();
+                let half = values.iter().map(|&(_, len)| len).sum::<i64>() / 2;
+                let mut seen = 0;
+                let median = values
+                    .iter()
+                    .find(|&&(_, len)| {
+                        seen += len;
+                        seen > half
+                    })
+                    .map_or(0, |&(off, _)| off);
+                (qi, median)
+            })
+            .collect();
+
+        let off_diagonal: Vec<usize> = (0..self.segments.len())
+            .filter(|&idx| self.passes(&self.segments[idx]))
+            .filter(|&idx| {
+                let (qi, ti, _) = placed[idx];
+                let (main_ti, anti, _) = main_pair[&qi];
+                if ti != main_ti {
+                    return true;
+                }
+                let tolerance = self.query_lengths.get(qi).copied().unwrap_or(0) as f64
+                    * OFF_DIAGONAL_TOLERANCE;
+                (offset(&self.segments[idx], anti) - median_offset[&qi]).abs() as f64 > tolerance
+            })
+            .collect();
+
+        self.grid_clusters(&off_diagonal, min_segments)
+    }
+
+    /// Groups of neighbouring passing segments anywhere on the plot, ordered
+    /// left to right: segment midpoints are binned on a coarse grid and
+    /// touching cells joined, much like DBSCAN with a cell-sized radius
+    pub fn segment_clusters(&self, min_segments: usize) -> Vec<Cluster> {
+        let passing: Vec<usize> = (0..self.segments.len())
+            .filter(|&idx| self.passes(&self.segments[idx]))
+            .collect();
+        self.grid_clusters(&passing, min_segments)
+    }
+
+    /// Chain number of every segment, and how many chains there are. Segments
+    /// of one sequence pair running the same way are chained when each starts
+    /// at most `max_gap` past where the one before it ended, on both axes, so
+    /// the pieces of one syntenic block share a number; chains are numbered
+    /// left to right.
+    pub fn chain_ids(&self, max_gap: i64) -> (Vec<usize>, usize) {
+        let mut order: Vec<usize> = (0..self.segments.len()).collect();
+        order.sort_by_key(|&idx| (self.segments[idx].abeg, self.segments[idx].aend));
+
+        let mut ids = vec![0; self.segments.len()];
+        let mut count = 0;
+        // Chains still open per (query, target, descending): (id, last segment)
+        let mut open: HashMap<(usize, usize, bool), Vec<(usize, usize)>> = HashMap::new();
+        for idx in order {
+            let seg = &self.segments[idx];
+            let (query, target) = self.segment_sequences(seg);
+            let descending = seg.bend < seg.bbeg;
+            let direction = if descending { -1 } else { 1 };
+            let chains = open.entry((query, target, descending)).or_default();
+            chains.retain(|&(_, last)| self.segments[last].aend + max_gap >= seg.abeg);
+
+            // Join the chain whose end this segment continues most closely
+            let best = chains
+                .iter_mut()
+                .filter_map(|entry| {
+                    let last = &self.segments[entry.1];
+                    let dx = seg.abeg - last.aend;
+                    let dy = (seg.bbeg - last.bend) * direction;
+                    let onwards = (seg.bbeg - last.bbeg) * direction >= 0;
+                    (onwards && dy <= max_gap)
+                        .then(|| (dx.max(0) + dy.max(0) + (dx - dy).abs(), entry))
+                })
+                .min_by_key(|(cost, _)| *cost);
+            match best {
+                Some((_, entry)) => {
+                    ids[idx] = entry.0;
+                    entry.1 = idx;
+                }
+                None => {
+                    ids[idx] = count;
+                    chains.push((count, idx));
+                    count += 1;
+                }
+            }
+        }
+        (ids, count)
+    }
+
+    /// Bin the midpoints of the given segments on a coarse grid and join
+    /// 8-connected cells into clusters of at least `min_segments`
+    fn grid_clusters(&self, indices: &[usize], min_segments: usize) -> Vec<Cluster> {
+        // Bin midpoints on a coarse grid and join neighbouring cells
+        let cell = (self.query_genome_len.max(self.target_genome_len) as f64 / CLUSTER_GRID_CELLS)
+            .max(1.0);
+        let mut cells: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for &idx in indices {
+            let seg = &self.segments[idx];
+            let key = (
+                ((seg.abeg + seg.aend) as f64 / 2.0 / cell) as i64,
+                ((seg.bbeg + seg.bend) as f64 / 2.0 / cell) as i64,
+            );
+            cells.entry(key).or_default().push(idx);
+        }
+
+        let mut keys: Vec<(i64, i64)> = cells.keys().copied().collect();
+        keys.sort_unstable();
+        let mut visited = HashSet::new();
+        let mut clusters = Vec::new();
+        for start in keys {
+            if !visited.insert(start) {
+                continue;
+            }
+            let mut members = Vec::new();
+            let mut stack = vec![start];
+            while let Some((cx, cy)) = stack.pop() {
+                members.extend_from_slice(&cells[&(cx, cy)]);
+                for dx in -1..=1 {
+                    for dy in -1..=1 {
+                        let next = (cx + dx, cy + dy);
+                        if cells.contains_key(&next) && visited.insert(next) {
+                            stack.push(next);
+                        }
+                    }
+                }
+            }
+            if members.len() < min_segments {
+                continue;
+            }
+
+            let mut cluster = Cluster {
+                x_min: i64::MAX,
+                x_max: i64::MIN,
+                y_min: i64::MAX,
+                y_max: i64::MIN,
+                segments: members.len(),
+                aligned_bp: 0,
+                hull: Vec::new(),
+            };
+            let mut corners = Vec::with_capacity(members.len() * 2);
+            for &idx in &members {
+                let seg = &self.segments[idx];
+                cluster.x_min = cluster.x_min.min(seg.abeg.min(seg.aend));
+                cluster.x_max = cluster.x_max.max(seg.abeg.max(seg.aend));
+                cluster.y_min = cluster.y_min.min(seg.bbeg.min(seg.bend));
+                cluster.y_max = cluster.y_max.max(seg.bbeg.max(seg.bend));
+                cluster.aligned_bp += (seg.aend - seg.abeg).abs();
+                corners.push((seg.abeg, seg.bbeg));
+                corners.push((seg.aend, seg.bend));
+            }
+            cluster.hull = convex_hull(corners);
+            clusters.push(cluster);
+        }
+
+        clusters.sort_by_key(|c| (c.x_min, c.y_min));
+        clusters
+    }
+
     /// Apply sequence filters to create a subset view
     /// Returns a new RustPlot with only segments involving selected sequences
     pub fn with_filters(
@@ -232,34 +1226,60 @@ impl RustPlot {
             return Ok(self.clone());
         }
 
-        // Filter and re-index sequences
+        // Keep the selected sequences in their original order
+        let mut query_order: Vec<usize> = query_indices.into_iter().collect();
+        let mut target_order: Vec<usize> = target_indices.into_iter().collect();
+        query_order.sort_unstable();
+        target_order.sort_unstable();
+
+        Ok(self.with_order(&query_order, &target_order))
+    }
+
+    /// Lay sequences out in the given order, dropping any index not listed
+    /// Segments are moved with their sequences into the new coordinate system
+    pub fn with_order(&self, query_order: &[usize], target_order: &[usize]) -> Self {
+        let forward = |order: &[usize]| order.iter().map(|&idx| (idx, false)).collect::<Vec<_>>();
+        self.with_layout(&forward(query_order), &forward(target_order))
+    }
+
+    /// Like `with_order`, with a per-sequence flag to reverse-complement it
+    /// Flipping mirrors the sequence's coordinate band and swaps the strand of
+    /// its segments, as if it had been assembled in the other orientation
+    pub fn with_layout(
+        &self,
+        query_order: &[(usize, bool)],
+        target_order: &[(usize, bool)],
+    ) -> Self {
+        // Re-index sequences
         let mut new_query_sequences = Vec::new();
         let mut new_query_lengths = Vec::new();
-        let mut old_to_new_query: Vec<Option<usize>> = vec![None; self.query_sequences.len()];
+        let mut new_query_reversed = Vec::new();
+        let mut old_to_new_query: Vec<Option<(usize, bool)>> =
+            vec![None; self.query_sequences.len()];
 
-        for (old_idx, name) in self.query_sequences.iter().enumerate() {
-            if query_indices.contains(&old_idx) {
-                let new_idx = new_query_sequences.len();
-                old_to_new_query[old_idx] = Some(new_idx);
-                new_query_sequences.push(name.clone());
-                new_query_lengths.push(self.query_lengths[old_idx]);
-            }
+        for &(old_idx, flip) in query_order {
+            let new_idx = new_query_sequences.len();
+            old_to_new_query[old_idx] = Some((new_idx, flip));
+            new_query_sequences.push(self.query_sequences[old_idx].clone());
+            new_query_lengths.push(self.query_lengths[old_idx]);
+            new_query_reversed.push(self.query_reversed[old_idx] != flip);
         }
 
         let mut new_target_sequences = Vec::new();
         let mut new_target_lengths = Vec::new();
-        let mut old_to_new_target: Vec<Option<usize>> = vec![None; self.target_sequences.len()];
+        let mut new_target_reversed = Vec::new();
+        let mut old_to_new_target: Vec<Option<(usize, bool)>> =
+            vec![None; self.target_sequences.len()];
 
-        for (old_idx, name) in self.target_sequences.iter().enumerate() {
-            if target_indices.contains(&old_idx) {
-                let new_idx = new_target_sequences.len();
-                old_to_new_target[old_idx] = Some(new_idx);
-                new_target_sequences.push(name.clone());
-                new_target_lengths.push(self.target_lengths[old_idx]);
-            }
+        for &(old_idx, flip) in target_order {
+            let new_idx = new_target_sequences.len();
+            old_to_new_target[old_idx] = Some((new_idx, flip));
+            new_target_sequences.push(self.target_sequences[old_idx].clone());
+            new_target_lengths.push(self.target_lengths[old_idx]);
+            new_target_reversed.push(self.target_reversed[old_idx] != flip);
         }
 
-        // Recalculate boundaries for filtered sequences
+        // Recalculate boundaries for the new layout
         let mut new_query_boundaries = Vec::new();
         let mut cumulative = 0i64;
         for &len in &new_query_lengths {
@@ -279,7 +1299,7 @@ impl RustPlot {
         let new_target_genome_len = cumulative;
 
         // Filter and re-map segments
-        // We need to remap coordinates to the new filtered coordinate system
+        // We need to remap coordinates to the new coordinate system
         let mut new_segments = Vec::new();
 
         for seg in &self.segments {
@@ -288,31 +1308,52 @@ impl RustPlot {
             let target_idx =
                 self.find_sequence_index(&self.target_boundaries, seg.bbeg.min(seg.bend));
 
-            // Check if both sequences are in our filter
-            if let (Some(new_qidx), Some(new_tidx)) = (
+            // Check if both sequences are in the new layout
+            if let (Some((new_qidx, q_flip)), Some((new_tidx, t_flip))) = (
                 old_to_new_query.get(query_idx).and_then(|&x| x),
                 old_to_new_target.get(target_idx).and_then(|&x| x),
             ) {
-                // Remap coordinates to new coordinate system
+                // Remap coordinates to new coordinate system, mirroring flipped bands
                 let old_q_offset = self.query_boundaries[query_idx];
                 let new_q_offset = new_query_boundaries[new_qidx];
-                let q_delta = new_q_offset - old_q_offset;
+                let q_len = self.query_lengths[query_idx];
+                let map_q = |a: i64| {
+                    let local = a - old_q_offset;
+                    new_q_offset + if q_flip { q_len - local } else { local }
+                };
 
                 let old_t_offset = self.target_boundaries[target_idx];
                 let new_t_offset = new_target_boundaries[new_tidx];
-                let t_delta = new_t_offset - old_t_offset;
+                let t_len = self.target_lengths[target_idx];
+                let map_t = |b: i64| {
+                    let local = b - old_t_offset;
+                    new_t_offset + if t_flip { t_len - local } else { local }
+                };
 
-                new_segments.push(AlignmentSegment {
-                    abeg: seg.abeg + q_delta,
-                    aend: seg.aend + q_delta,
-                    bbeg: seg.bbeg + t_delta,
-                    bend: seg.bend + t_delta,
-                    reverse: seg.reverse,
-                });
+                let mut remapped = AlignmentSegment {
+                    abeg: map_q(seg.abeg),
+                    aend: map_q(seg.aend),
+                    bbeg: map_t(seg.bbeg),
+                    bend: map_t(seg.bend),
+                    reverse: seg.reverse != (q_flip != t_flip),
+                    identity: seg.identity,
+                };
+                if q_flip {
+                    // Keep query coordinates increasing along the segment
+                    remapped = AlignmentSegment {
+                        abeg: remapped.aend,
+                        aend: remapped.abeg,
+                        bbeg: remapped.bend,
+                        bend: remapped.bbeg,
+                        reverse: remapped.reverse,
+                        identity: remapped.identity,
+                    };
+                }
+                new_segments.push(remapped);
             }
         }
 
-        Ok(Self {
+        Self {
             query_sequences: new_query_sequences,
             target_sequences: new_target_sequences,
             query_lengths: new_query_lengths,
@@ -322,17 +1363,423 @@ impl RustPlot {
             segments: new_segments,
             query_boundaries: new_query_boundaries,
             target_boundaries: new_target_boundaries,
-        })
+            query_reversed: new_query_reversed,
+            target_reversed: new_target_reversed,
+            cutoff: self.cutoff,
+        }
+    }
+
+    /// Scale every sequence to the same band width (`NORMALIZED_BAND`), so
+    /// positions read as a fraction of their sequence rather than bp
+    pub fn normalized(&self) -> Self {
+        // Position `coord` of sequence `idx` (starting at `start`) in its band
+        let stretch = |idx: usize, start: i64, len: i64, coord: i64| {
+            let local = (coord - start) as i128 * NORMALIZED_BAND as i128 / len.max(1) as i128;
+            idx as i64 * NORMALIZED_BAND + local as i64
+        };
+        let segments = self
+            .segments
+            .iter()
+            .map(|seg| {
+                // Both ends of a segment lie in the sequence of its start
+                let q_idx = self.find_sequence_index(&self.query_boundaries, seg.abeg);
+                let t_idx =
+                    self.find_sequence_index(&self.target_boundaries, seg.bbeg.min(seg.bend));
+                let (q_start, q_len) = (self.query_boundaries[q_idx], self.query_lengths[q_idx]);
+                let (t_start, t_len) = (self.target_boundaries[t_idx], self.target_lengths[t_idx]);
+                let map_q = |a: i64| stretch(q_idx, q_start, q_len, a);
+                let map_t = |b: i64| stretch(t_idx, t_start, t_len, b);
+                AlignmentSegment {
+                    abeg: map_q(seg.abeg),
+                    aend: map_q(seg.aend),
+                    bbeg: map_t(seg.bbeg),
+                    bend: map_t(seg.bend),
+                    ..*seg
+                }
+            })
+            .collect();
+
+        let bands = |count: usize| (0..=count as i64).map(|i| i * NORMALIZED_BAND).collect();
+        let query_count = self.query_lengths.len();
+        let target_count = self.target_lengths.len();
+        Self {
+            query_sequences: self.query_sequences.clone(),
+            target_sequences: self.target_sequences.clone(),
+            query_lengths: vec![NORMALIZED_BAND; query_count],
+            target_lengths: vec![NORMALIZED_BAND; target_count],
+            query_genome_len: query_count as i64 * NORMALIZED_BAND,
+            target_genome_len: target_count as i64 * NORMALIZED_BAND,
+            segments,
+            query_boundaries: bands(query_count),
+            target_boundaries: bands(target_count),
+            query_reversed: self.query_reversed.clone(),
+            target_reversed: self.target_reversed.clone(),
+            cutoff: self.cutoff,
+        }
+    }
+
+    /// Query order and orientation that bring the alignments onto the
+    /// diagonal, as D-GENIES sorts contigs: each query goes where it aligns on
+    /// its best-hit target (most aligned bases), by the bp-weighted mean
+    /// position there, flipped if most of those bases align in reverse.
+    /// Queries without passing alignments follow in their current order.
+    /// Flips are relative to the plot as drawn.
+    pub fn diagonal_layout(&self) -> Vec<(usize, bool)> {
+        #[derive(Clone, Copy, Default)]
+        struct Hits {
+            bp: i64,
+            reverse_bp: i64,
+            weighted_y: f64,
+        }
+        let mut hits: Vec<BTreeMap<usize, Hits>> =
+            vec![BTreeMap::new(); self.query_sequences.len()];
+        for seg in self.segments.iter().filter(|seg| self.passes(seg)) {
+            let query = self.find_sequence_index(&self.query_boundaries, seg.abeg);
+            let target = self.find_sequence_index(&self.target_boundaries, seg.bbeg.min(seg.bend));
+            let bp = seg.aend - seg.abeg;
+            let Some(by_target) = hits.get_mut(query) else {
+                continue;
+            };
+            let hit = by_target.entry(target).or_default();
+            hit.bp += bp;
+            if seg.reverse {
+                hit.reverse_bp += bp;
+            }
+            hit.weighted_y += (seg.bbeg + seg.bend) as f64 / 2.0 * bp as f64;
+        }
+
+        let mut placed = Vec::new();
+        let mut unplaced = Vec::new();
+        for (query, by_target) in hits.iter().enumerate() {
+            match by_target.values().max_by_key(|hit| hit.bp) {
+                Some(hit) if hit.bp > 0 => placed.push((
+                    hit.weighted_y / hit.bp as f64,
+                    query,
+                    hit.reverse_bp * 2 > hit.bp,
+                )),
+                _ => unplaced.push((query, false)),
+            }
+        }
+        placed.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        placed
+            .into_iter()
+            .map(|(_, query, flip)| (query, flip))
+            .chain(unplaced)
+            .collect()
+    }
+
+    /// The same plot with query and target exchanged, so the target runs
+    /// along x; segments keep x increasing from start to end
+    pub fn transposed(self) -> Self {
+        let segments = self
+            .segments
+            .iter()
+            .map(AlignmentSegment::transposed)
+            .collect();
+        Self {
+            query_sequences: self.target_sequences,
+            target_sequences: self.query_sequences,
+            query_lengths: self.target_lengths,
+            target_lengths: self.query_lengths,
+            query_genome_len: self.target_genome_len,
+            target_genome_len: self.query_genome_len,
+            segments,
+            query_boundaries: self.target_boundaries,
+            target_boundaries: self.query_boundaries,
+            query_reversed: self.target_reversed,
+            target_reversed: self.query_reversed,
+            cutoff: self.cutoff,
+        }
     }
 
     /// Find which sequence a genome coordinate belongs to
     fn find_sequence_index(&self, boundaries: &[i64], coord: i64) -> usize {
-        for i in 0..boundaries.len().saturating_sub(1) {
-            if coord >= boundaries[i] && coord < boundaries[i + 1] {
-                return i;
+        // Boundaries are sorted, so binary search for the sequence starting at or before coord
+        match boundaries.partition_point(|&b| b <= coord) {
+            after if after >= 1 && after < boundaries.len() => after - 1,
+            _ => boundaries.len().saturating_sub(2),
+        }
+    }
+
+    /// (query, target) sequence indices a segment lies in, judged by its midpoint
+    pub fn segment_sequences(&self, seg: &AlignmentSegment) -> (usize, usize) {
+        (
+            self.find_sequence_index(&self.query_boundaries, (seg.abeg + seg.aend) / 2),
+            self.find_sequence_index(&self.target_boundaries, (seg.bbeg + seg.bend) / 2),
+        )
+    }
+
+    /// The record a segment stands for: its sequences' own coordinates in
+    /// file orientation, with identity rounded to whole differences
+    pub fn segment_record(&self, seg: &AlignmentSegment) -> AlnRecord {
+        let (qid, tid) = self.segment_sequences(seg);
+        // Mirroring flipped bands is its own inverse
+        let seg = self.orient_segment(seg.clone(), qid, tid);
+        let query_offset = self.query_boundaries.get(qid).copied().unwrap_or(0);
+        let target_offset = self.target_boundaries.get(tid).copied().unwrap_or(0);
+        let target_len = self.target_lengths.get(tid).copied().unwrap_or(0);
+        let (target_start, target_end) = if seg.reverse {
+            let target_end_pos = target_offset + target_len;
+            (target_end_pos - seg.bbeg, target_end_pos - seg.bend)
+        } else {
+            (seg.bbeg - target_offset, seg.bend - target_offset)
+        };
+        let span = seg.aend - seg.abeg;
+        let diffs = (span as f64 * (1.0 - seg.identity as f64 / 100.0)).round() as i64;
+        AlnRecord {
+            query_id: qid as i64,
+            target_id: tid as i64,
+            query_name: self.query_sequences.get(qid).cloned().unwrap_or_default(),
+            target_name: self.target_sequences.get(tid).cloned().unwrap_or_default(),
+            query_len: self.query_lengths.get(qid).copied().unwrap_or(0),
+            target_len,
+            query_start: seg.abeg - query_offset,
+            query_end: seg.aend - query_offset,
+            target_start,
+            target_end,
+            reverse: i32::from(seg.reverse),
+            diffs: diffs.clamp(0, i32::MAX as i64) as i32,
+        }
+    }
+
+    /// Length-weighted mean identity of the passing alignments between each
+    /// (query, target) sequence pair that has any
+    pub fn pair_identity(&self) -> HashMap<(usize, usize), f32> {
+        let mut sums: HashMap<(usize, usize), (f64, f64)> = HashMap::new();
+        for seg in self.segments.iter().filter(|seg| self.passes(seg)) {
+            let (query, target) = self.segment_sequences(seg);
+            let len = (seg.aend - seg.abeg) as f64;
+            let entry = sums.entry((query, target)).or_default();
+            entry.0 += seg.identity as f64 * len;
+            entry.1 += len;
+        }
+        sums.into_iter()
+            .filter(|(_, (_, len))| *len > 0.0)
+            .map(|(pair, (weighted, len))| (pair, (weighted / len) as f32))
+            .collect()
+    }
+
+    /// Whole-genome and per-query-sequence ANI from the passing alignments
+    ///
+    /// Each query base counts once, with the identity of the best alignment
+    /// covering it, so repeats and overlapping alignments do not weigh in
+    /// twice; bases no alignment covers are left out and reported as coverage.
+    pub fn ani(&self) -> AniSummary {
+        let mut events: Vec<(i64, bool, usize)> = Vec::new();
+        for (idx, seg) in self.segments.iter().enumerate() {
+            let (lo, hi) = (seg.abeg.min(seg.aend), seg.abeg.max(seg.aend));
+            if self.passes(seg) && hi > lo {
+                events.push((lo, true, idx));
+                events.push((hi, false, idx));
+            }
+        }
+        events.sort_unstable();
+
+        // Per query sequence: (identity x bp, bp)
+        let mut sums = vec![(0.0f64, 0i64); self.query_sequences.len()];
+        let key = |idx: usize| (Reverse(self.segments[idx].identity.max(0.0).to_bits()), idx);
+        let mut active = BTreeSet::new();
+        let mut i = 0;
+        while i < events.len() {
+            let pos = events[i].0;
+            while i < events.len() && events[i].0 == pos {
+                let (_, starts, idx) = events[i];
+                if starts {
+                    active.insert(key(idx));
+                } else {
+                    active.remove(&key(idx));
+                }
+                i += 1;
+            }
+            // The best covering alignment holds until the next event
+            if let (Some(&(_, best)), Some(&(next, _, _))) = (active.first(), events.get(i)) {
+                let sequence = self.find_sequence_index(&self.query_boundaries, pos);
+                if let Some(sum) = sums.get_mut(sequence) {
+                    let bp = next - pos;
+                    sum.0 += self.segments[best].identity as f64 * bp as f64;
+                    sum.1 += bp;
+                }
             }
         }
-        boundaries.len().saturating_sub(2).max(0)
+
+        let per_sequence: Vec<SequenceAni> = sums
+            .iter()
+            .enumerate()
+            .filter(|(_, &(_, bp))| bp > 0)
+            .map(|(sequence, &(weighted, bp))| SequenceAni {
+                sequence,
+                ani: weighted / bp as f64,
+                covered_bp: bp,
+                length: self.query_lengths.get(sequence).copied().unwrap_or(0),
+            })
+            .collect();
+        let covered_bp: i64 = sums.iter().map(|&(_, bp)| bp).sum();
+        let weighted: f64 = sums.iter().map(|&(w, _)| w).sum();
+        AniSummary {
+            ani: (covered_bp > 0).then(|| weighted / covered_bp as f64),
+            covered_bp,
+            query_bp: self.get_alen(),
+            per_sequence,
+        }
+    }
+
+    /// Where the interval [start, end) of one axis (0 = query, 1 = target, in
+    /// genome coordinates) aligns on the other: each passing alignment's overlap
+    /// is projected across, and projections on the same partner sequence
+    /// within `merge_gap` bp are merged. Most aligned bases first.
+    pub fn partner_intervals(
+        &self,
+        genome: i32,
+        start: i64,
+        end: i64,
+        merge_gap: i64,
+    ) -> Vec<PartnerInterval> {
+        let (start, end) = (start.min(end), start.max(end));
+        let (boundaries, lengths, reversed) = if genome == 0 {
+            (
+                &self.target_boundaries,
+                &self.target_lengths,
+                &self.target_reversed,
+            )
+        } else {
+            (
+                &self.query_boundaries,
+                &self.query_lengths,
+                &self.query_reversed,
+            )
+        };
+
+        // (partner, local start, local end, aligned bp, identity)
+        let mut pieces: Vec<(usize, i64, i64, i64, f32)> = Vec::new();
+        for seg in self.segments.iter().filter(|seg| self.passes(seg)) {
+            let (own0, own1, other0, other1) = if genome == 0 {
+                (seg.abeg, seg.aend, seg.bbeg, seg.bend)
+            } else {
+                (seg.bbeg, seg.bend, seg.abeg, seg.aend)
+            };
+            let lo = start.max(own0.min(own1));
+            let hi = end.min(own0.max(own1));
+            if hi <= lo {
+                continue;
+            }
+            let project = |pos: i64| {
+                if own1 == own0 {
+                    other0
+                } else {
+                    other0
+                        + ((pos - own0) as f64 * (other1 - other0) as f64 / (own1 - own0) as f64)
+                            .round() as i64
+                }
+            };
+            let (p0, p1) = (project(lo), project(hi));
+            let (g_lo, g_hi) = (p0.min(p1), p0.max(p1));
+            let idx = self.find_sequence_index(boundaries, (g_lo + g_hi) / 2);
+            let (mut l_lo, mut l_hi) = (g_lo - boundaries[idx], g_hi - boundaries[idx]);
+            if reversed.get(idx).copied().unwrap_or(false) {
+                (l_lo, l_hi) = (lengths[idx] - l_hi, lengths[idx] - l_lo);
+            }
+            pieces.push((idx, l_lo, l_hi, hi - lo, seg.identity));
+        }
+
+        pieces.sort_by_key(|&(idx, lo, hi, _, _)| (idx, lo, hi));
+        let mut merged: Vec<PartnerInterval> = Vec::new();
+        let mut weighted_identity: Vec<f64> = Vec::new();
+        for (idx, lo, hi, bp, identity) in pieces {
+            match merged.last_mut() {
+                Some(last) if last.seq == idx && lo <= last.end + merge_gap => {
+                    last.end = last.end.max(hi);
+                    last.aligned_bp += bp;
+                    last.alignments += 1;
+                    *weighted_identity.last_mut().unwrap() += identity as f64 * bp as f64;
+                }
+                _ => {
+                    merged.push(PartnerInterval {
+                        seq: idx,
+                        start: lo,
+                        end: hi,
+                        aligned_bp: bp,
+                        identity,
+                        alignments: 1,
+                    });
+                    weighted_identity.push(identity as f64 * bp as f64);
+                }
+            }
+        }
+        for (interval, weighted) in merged.iter_mut().zip(weighted_identity) {
+            interval.identity = (weighted / interval.aligned_bp.max(1) as f64) as f32;
+        }
+        merged.sort_by(|a, b| b.aligned_bp.cmp(&a.aligned_bp).then(a.seq.cmp(&b.seq)));
+        merged
+    }
+
+    /// Every passing alignment with its midpoint on sequence `idx` of one axis
+    /// (0 = query, 1 = target), with totals per partner sequence
+    pub fn sequence_report(&self, genome: i32, idx: usize) -> SequenceReport {
+        let mut report = SequenceReport::default();
+        let mut partners: HashMap<usize, (usize, i64)> = HashMap::new();
+        for (i, seg) in self.segments.iter().enumerate() {
+            if !self.passes(seg) {
+                continue;
+            }
+            let amid = (seg.abeg + seg.aend) / 2;
+            let bmid = (seg.bbeg + seg.bend) / 2;
+            let query = self.find_sequence_index(&self.query_boundaries, amid);
+            let target = self.find_sequence_index(&self.target_boundaries, bmid);
+            let (own, partner, len) = match genome {
+                0 => (query, target, seg.aend - seg.abeg),
+                _ => (target, query, (seg.bend - seg.bbeg).abs()),
+            };
+            if own != idx {
+                continue;
+            }
+            report.segments.push(i);
+            report.aligned_bp += len;
+            let entry = partners.entry(partner).or_default();
+            entry.0 += 1;
+            entry.1 += len;
+        }
+        report.partners = partners
+            .into_iter()
+            .map(|(partner, (count, bp))| (partner, count, bp))
+            .collect();
+        report
+            .partners
+            .sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+        report
+    }
+
+    /// Split an interval of one genome axis (0 = query, 1 = target) into
+    /// per-sequence pieces: (sequence index, local start, local end), with local
+    /// coordinates on the sequence's forward strand
+    pub fn sequence_intervals(&self, genome: i32, start: i64, end: i64) -> Vec<(usize, i64, i64)> {
+        let (boundaries, lengths, reversed) = match genome {
+            0 => (
+                &self.query_boundaries,
+                &self.query_lengths,
+                &self.query_reversed,
+            ),
+            _ => (
+                &self.target_boundaries,
+                &self.target_lengths,
+                &self.target_reversed,
+            ),
+        };
+        let (start, end) = (start.min(end), start.max(end));
+
+        let mut pieces = Vec::new();
+        for idx in 0..boundaries.len().saturating_sub(1) {
+            let lo = start.max(boundaries[idx]) - boundaries[idx];
+            let hi = end.min(boundaries[idx + 1]) - boundaries[idx];
+            if hi <= lo {
+                continue;
+            }
+            if reversed.get(idx).copied().unwrap_or(false) {
+                pieces.push((idx, lengths[idx] - hi, lengths[idx] - lo));
+            } else {
+                pieces.push((idx, lo, hi));
+            }
+        }
+        pieces
     }
 
     /// Get sequence info for a query genome coordinate
@@ -344,7 +1791,10 @@ impl RustPlot {
             .get(idx)
             .cloned()
             .unwrap_or_else(|| format!("query_{idx}"));
-        let local_pos = coord - self.query_boundaries.get(idx).copied().unwrap_or(0);
+        let mut local_pos = coord - self.query_boundaries.get(idx).copied().unwrap_or(0);
+        if self.query_reversed.get(idx).copied().unwrap_or(false) {
+            local_pos = self.query_lengths[idx] - local_pos;
+        }
         (idx, name, local_pos)
     }
 
@@ -357,11 +1807,164 @@ impl RustPlot {
             .get(idx)
             .cloned()
             .unwrap_or_else(|| format!("target_{idx}"));
-        let local_pos = coord - self.target_boundaries.get(idx).copied().unwrap_or(0);
+        let mut local_pos = coord - self.target_boundaries.get(idx).copied().unwrap_or(0);
+        if self.target_reversed.get(idx).copied().unwrap_or(false) {
+            local_pos = self.target_lengths[idx] - local_pos;
+        }
         (idx, name, local_pos)
     }
 }
 
+/// Deterministic pseudo-random number in (0, 1) from a seed (splitmix64)
+fn unit_random(seed: u64) -> f64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    ((z >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+}
+
+/// Convex hull of a point set, counter-clockwise from the lowest-leftmost
+/// point (Andrew's monotone chain); fewer than three points come back as-is
+fn convex_hull(mut points: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    points.sort_unstable();
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let cross = |o: (i64, i64), a: (i64, i64), b: (i64, i64)| {
+        (a.0 - o.0) as i128 * (b.1 - o.1) as i128 - (a.1 - o.1) as i128 * (b.0 - o.0) as i128
+    };
+
+    // Lower chain left to right, then upper chain back; each drops its last
+    // point, which starts the other
+    let chain = |ordered: &mut dyn Iterator<Item = &(i64, i64)>| {
+        let mut chain: Vec<(i64, i64)> = Vec::new();
+        for &p in ordered {
+            while chain.len() >= 2 && cross(chain[chain.len() - 2], chain[chain.len() - 1], p) <= 0
+            {
+                chain.pop();
+            }
+            chain.push(p);
+        }
+        chain.pop();
+        chain
+    };
+    let mut hull = chain(&mut points.iter());
+    hull.extend(chain(&mut points.iter().rev()));
+    hull
+}
+
+/// Human-readable length: bp, kb, Mb or Gb
+pub fn format_bp(len: i64) -> String {
+    let len_f = len as f64;
+    if len.abs() >= 1_000_000_000 {
+        format!("{:.2} Gb", len_f / 1e9)
+    } else if len.abs() >= 1_000_000 {
+        format!("{:.2} Mb", len_f / 1e6)
+    } else if len.abs() >= 1_000 {
+        format!("{:.1} kb", len_f / 1e3)
+    } else {
+        format!("{len} bp")
+    }
+}
+
+/// Longest round length (1, 2 or 5 times a power of ten) that fits in
+/// `max_px` pixels at `bp_per_px`, with its label, for scale bars
+pub fn scale_bar(bp_per_px: f64, max_px: f64) -> (i64, String) {
+    let max_bp = bp_per_px * max_px;
+    let mut len = 1i64;
+    'search: for power in 0..13 {
+        for step in [1, 2, 5] {
+            let candidate = step * 10i64.pow(power);
+            if candidate as f64 > max_bp {
+                break 'search;
+            }
+            len = candidate;
+        }
+    }
+    let label = match len {
+        l if l >= 1_000_000_000 => format!("{} Gb", l / 1_000_000_000),
+        l if l >= 1_000_000 => format!("{} Mb", l / 1_000_000),
+        l if l >= 1_000 => format!("{} kb", l / 1_000),
+        l => format!("{l} bp"),
+    };
+    (len, label)
+}
+
+/// A ruler tick: where it sits in genome coordinates, the coordinate it is
+/// labelled with, and whether it is a major (labelled) tick
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AxisTick {
+    pub coord: f64,
+    pub local: i64,
+    pub major: bool,
+}
+
+/// Ruler ticks for genome coordinates [start, end], every `major` bp with
+/// minor ticks between, in the coordinates of a `length` bp sequence placed
+/// at `offset` (read backwards if `reversed`); 0 and the genome length rule
+/// the whole genome
+pub fn axis_ticks(
+    start: f64,
+    end: f64,
+    offset: i64,
+    length: i64,
+    reversed: bool,
+    major: i64,
+) -> Vec<AxisTick> {
+    let major = major.max(1);
+    // Five minor ticks per major interval, four for a 2 x 10^k one
+    let minor = match major {
+        1 => 1,
+        m if m.to_string().starts_with('2') => m / 4,
+        m => m / 5,
+    };
+    let local = |coord: f64| {
+        let along = coord - offset as f64;
+        if reversed {
+            length as f64 - along
+        } else {
+            along
+        }
+    };
+    let (lo, hi) = (local(start).min(local(end)), local(start).max(local(end)));
+    let (lo, hi) = (lo.max(0.0), hi.min(length as f64));
+    if lo > hi {
+        return Vec::new();
+    }
+    let first = (lo / minor as f64).ceil() as i64;
+    let last = (hi / minor as f64).floor() as i64;
+    (first..=last)
+        .map(|k| {
+            let local = k * minor;
+            let along = if reversed { length - local } else { local };
+            AxisTick {
+                coord: (offset + along) as f64,
+                local,
+                major: local % major == 0,
+            }
+        })
+        .collect()
+}
+
+/// Tick label for `pos` on a ruler ticked every `step` bp, in the largest
+/// unit no longer than ten steps, with just enough decimals to tell
+/// neighbouring ticks apart
+pub fn tick_label(pos: i64, step: i64) -> String {
+    let (unit, name) = match step.saturating_mul(10) {
+        s if s >= 1_000_000_000 => (1_000_000_000, "Gb"),
+        s if s >= 1_000_000 => (1_000_000, "Mb"),
+        s if s >= 1_000 => (1_000, "kb"),
+        _ => (1, "bp"),
+    };
+    let mut decimals = 0;
+    while (step * 10i64.pow(decimals)) % unit != 0 {
+        decimals += 1;
+    }
+    format!("{:.*} {name}", decimals as usize, pos as f64 / unit as f64)
+}
+
 impl Clone for RustPlot {
     fn clone(&self) -> Self {
         Self {
@@ -374,6 +1977,678 @@ impl Clone for RustPlot {
             segments: self.segments.clone(),
             query_boundaries: self.query_boundaries.clone(),
             target_boundaries: self.target_boundaries.clone(),
+            query_reversed: self.query_reversed.clone(),
+            target_reversed: self.target_reversed.clone(),
+            cutoff: self.cutoff,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aln_reader::test_record;
+
+    #[test]
+    fn test_coverage_depth_shows_gaps_and_duplications() {
+        // Query 0..100 aligned once, 100..200 twice (collapsed duplication), 200..300 unaligned
+        let records = vec![
+            test_record(0, 0, 200, 0, 0, 200),
+            test_record(0, 100, 200, 0, 300, 400),
+        ];
+        let plot = RustPlot::from_records(&records, Vec::new(), Vec::new(), vec![300], vec![400]);
+
+        let depth = plot.coverage_depth(0, 0.0, 300.0, 3);
+        assert_eq!(depth, vec![1.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn test_coverage_depth_partial_bins() {
+        let records = vec![test_record(0, 50, 150, 0, 0, 100)];
+        let plot = RustPlot::from_records(&records, Vec::new(), Vec::new(), vec![200], vec![100]);
+
+        let depth = plot.coverage_depth(0, 0.0, 200.0, 2);
+        assert_eq!(depth, vec![0.5, 0.5]);
+        assert_eq!(plot.coverage_depth(1, 0.0, 100.0, 1), vec![1.0]);
+    }
+
+    #[test]
+    fn test_sorted_ignores_record_order() {
+        let records = vec![
+            test_record(0, 50, 90, 0, 10, 50),
+            test_record(0, 0, 40, 0, 60, 100),
+            test_record(0, 0, 40, 0, 0, 40),
+        ];
+        let mut shuffled = records.clone();
+        shuffled.rotate_left(1);
+        let plot = |records: &[AlnRecord]| {
+            RustPlot::from_records(records, Vec::new(), Vec::new(), vec![100], vec![100]).sorted()
+        };
+
+        let (plot, other) = (plot(&records), plot(&shuffled));
+        assert_eq!(plot.segments, other.segments);
+        let starts: Vec<(i64, i64)> = plot.segments.iter().map(|s| (s.abeg, s.bbeg)).collect();
+        assert_eq!(starts, vec![(0, 0), (0, 60), (50, 10)]);
+        assert_eq!(plot.downsampled(2).segments, other.downsampled(2).segments);
+    }
+
+    #[test]
+    fn test_segment_record_undoes_layout() {
+        let mut records = vec![
+            test_record(0, 10, 20, 0, 0, 10),
+            test_record(1, 5, 15, 0, 50, 60),
+        ];
+        records[1].reverse = 1;
+        records[1].diffs = 1;
+        let plot =
+            RustPlot::from_records(&records, Vec::new(), Vec::new(), vec![100, 50], vec![80]);
+        // Second query first and flipped
+        let arranged = plot.with_layout(&[(1, true), (0, false)], &[(0, false)]);
+
+        for seg in &arranged.segments {
+            let rec = arranged.segment_record(seg);
+            let original = &records[rec.query_id as usize ^ 1];
+            assert_eq!(
+                (
+                    rec.query_start,
+                    rec.query_end,
+                    rec.target_start,
+                    rec.target_end
+                ),
+                (
+                    original.query_start,
+                    original.query_end,
+                    original.target_start,
+                    original.target_end
+                )
+            );
+            assert_eq!((rec.reverse, rec.diffs), (original.reverse, original.diffs));
+        }
+    }
+
+    #[test]
+    fn test_with_order_moves_segments_with_sequences() {
+        // Two query sequences of 100 and 50 bp; one alignment on each
+        let records = vec![
+            test_record(0, 10, 20, 0, 0, 10),
+            test_record(1, 5, 15, 0, 50, 60),
+        ];
+        let plot =
+            RustPlot::from_records(&records, Vec::new(), Vec::new(), vec![100, 50], vec![80]);
+
+        let swapped = plot.with_order(&[1, 0], &[0]);
+        assert_eq!(swapped.query_boundaries, vec![0, 50, 150]);
+        assert_eq!(swapped.query_sequences, vec!["query_1", "query_0"]);
+        let starts: Vec<i64> = swapped.segments.iter().map(|s| s.abeg).collect();
+        assert_eq!(starts, vec![60, 5]);
+
+        // Dropping a sequence drops its segments
+        let only_second = plot.with_order(&[1], &[0]);
+        assert_eq!(only_second.get_alen(), 50);
+        assert_eq!(only_second.segments.len(), 1);
+    }
+
+    #[test]
+    fn test_with_layout_flips_sequence_band() {
+        let records = vec![test_record(0, 10, 30, 0, 0, 20)];
+        let plot = RustPlot::from_records(&records, Vec::new(), Vec::new(), vec![100], vec![80]);
+
+        // Flipping the target mirrors b and makes the alignment reverse
+        let flipped = plot.with_layout(&[(0, false)], &[(0, true)]);
+        let seg = &flipped.segments[0];
+        assert_eq!((seg.abeg, seg.aend, seg.bbeg, seg.bend), (10, 30, 80, 60));
+        assert!(seg.reverse);
+        assert!(flipped.target_reversed[0]);
+        assert_eq!(flipped.target_coord_to_sequence(75).2, 5);
+
+        // Flipping both axes gives a forward alignment again, query still increasing
+        let both = plot.with_layout(&[(0, true)], &[(0, true)]);
+        let seg = &both.segments[0];
+        assert_eq!((seg.abeg, seg.aend, seg.bbeg, seg.bend), (70, 90, 60, 80));
+        assert!(!seg.reverse);
+    }
+
+    #[test]
+    fn test_diagonal_layout() {
+        // Query 0 hits the end of the target forward, query 1 its start in
+        // reverse (plus a short stray hit elsewhere); query 2 has no hits
+        let mut records = vec![
+            test_record(0, 0, 100, 0, 500, 600),
+            test_record(1, 0, 100, 0, 900, 1000),
+            test_record(1, 100, 110, 0, 0, 10),
+        ];
+        records[1].reverse = 1;
+        records[2].reverse = 1;
+        let plot = RustPlot::from_records(
+            &records,
+            Vec::new(),
+            Vec::new(),
+            vec![100, 110, 50],
+            vec![1000],
+        );
+        assert_eq!(
+            plot.diagonal_layout(),
+            vec![(1, true), (0, false), (2, false)]
+        );
+    }
+
+    #[test]
+    fn test_normalized_gives_equal_bands() {
+        // A 1000 bp and a 100 bp query sequence; alignments at the middle of each
+        let records = vec![
+            test_record(0, 500, 600, 0, 0, 50),
+            test_record(1, 50, 60, 0, 100, 200),
+        ];
+        let plot =
+            RustPlot::from_records(&records, Vec::new(), Vec::new(), vec![1000, 100], vec![200]);
+
+        let normalized = plot.normalized();
+        assert_eq!(
+            normalized.query_boundaries,
+            vec![0, NORMALIZED_BAND, 2 * NORMALIZED_BAND]
+        );
+        assert_eq!(normalized.get_blen(), NORMALIZED_BAND);
+        let seg = &normalized.segments[0];
+        assert_eq!((seg.abeg, seg.aend), (500_000, 600_000));
+        assert_eq!((seg.bbeg, seg.bend), (0, 250_000));
+        let seg = &normalized.segments[1];
+        assert_eq!((seg.abeg, seg.aend), (1_500_000, 1_600_000));
+        assert_eq!((seg.bbeg, seg.bend), (500_000, 1_000_000));
+    }
+
+    #[test]
+    fn test_transposed_swaps_axes() {
+        let mut records = vec![
+            test_record(0, 100, 200, 1, 10, 60),
+            test_record(1, 0, 50, 0, 20, 70),
+        ];
+        records[1].reverse = 1;
+        let plot = RustPlot::from_records(
+            &records,
+            vec!["q0".to_string(), "q1".to_string()],
+            vec!["t0".to_string(), "t1".to_string()],
+            vec![300, 100],
+            vec![100, 100],
+        );
+        let original = plot.segments.clone();
+
+        let swapped = plot.transposed();
+        assert_eq!(swapped.query_sequences, vec!["t0", "t1"]);
+        assert_eq!(swapped.query_boundaries, vec![0, 100, 200]);
+        assert_eq!((swapped.get_alen(), swapped.get_blen()), (200, 400));
+        let seg = &swapped.segments[0];
+        assert_eq!(
+            (seg.abeg, seg.aend, seg.bbeg, seg.bend),
+            (110, 160, 100, 200)
+        );
+        // A reverse alignment still runs left to right, now downwards
+        let seg = &swapped.segments[1];
+        assert_eq!((seg.abeg, seg.aend, seg.bbeg, seg.bend), (30, 80, 350, 300));
+        assert!(seg.reverse);
+        assert_eq!(swapped.transposed().segments, original);
+    }
+
+    #[test]
+    fn test_downsampled_prefers_long_segments() {
+        // One 10 kb alignment among a hundred 10 bp ones
+        let mut records: Vec<AlnRecord> = (0..100)
+            .map(|i| test_record(0, i * 10, i * 10 + 10, 0, i * 10, i * 10 + 10))
+            .collect();
+        records.push(test_record(0, 2000, 12000, 0, 2000, 12000));
+        let plot =
+            RustPlot::from_records(&records, Vec::new(), Vec::new(), vec![12000], vec![12000]);
+
+        let sampled = plot.downsampled(10);
+        assert_eq!(sampled.segments.len(), 10);
+        assert!(sampled.segments.iter().any(|s| s.aend - s.abeg == 10000));
+        assert_eq!(sampled.query_boundaries, plot.query_boundaries);
+
+        // Deterministic, and a no-op below the limit
+        let again = plot.downsampled(10);
+        let starts = |p: &RustPlot| p.segments.iter().map(|s| s.abeg).collect::<Vec<_>>();
+        assert_eq!(starts(&sampled), starts(&again));
+        assert_eq!(plot.downsampled(1000).segments.len(), 101);
+
+        // Segments under the cutoff are neither counted nor sampled
+        let mut cut = plot.clone();
+        cut.cutoff = SegmentFilter {
+            min_length: 100,
+            ..SegmentFilter::default()
+        };
+        assert_eq!(cut.downsampled(10).segments.len(), 101);
+        let records: Vec<AlnRecord> = (0..100)
+            .map(|i| test_record(0, i * 20, i * 20 + 10 + i % 2 * 10, 0, i * 20, i * 20 + 10))
+            .collect();
+        let mut plot =
+            RustPlot::from_records(&records, Vec::new(), Vec::new(), vec![2000], vec![2000]);
+        plot.cutoff = SegmentFilter {
+            min_length: 20,
+            ..SegmentFilter::default()
+        };
+        let sampled = plot.downsampled(10);
+        assert_eq!(sampled.segments.len(), 10);
+        assert!(sampled.segments.iter().all(|s| sampled.passes(s)));
+    }
+
+    #[test]
+    fn test_repetitive_segments() {
+        // Three copies stacked on query 0-100, one unique alignment at 200-300,
+        // and one that only grazes the repeat
+        let records = vec![
+            test_record(0, 0, 100, 0, 0, 100),
+            test_record(0, 0, 100, 0, 500, 600),
+            test_record(0, 0, 100, 0, 800, 900),
+            test_record(0, 200, 300, 0, 200, 300),
+            test_record(0, 90, 190, 0, 300, 400),
+        ];
+        let plot = RustPlot::from_records(&records, Vec::new(), Vec::new(), vec![1000], vec![1000]);
+
+        assert_eq!(
+            plot.repetitive_segments(3),
+            vec![true, true, true, false, false]
+        );
+        assert!(plot.repetitive_segments(5).iter().all(|&r| !r));
+    }
+
+    #[test]
+    fn test_off_diagonal_clusters() {
+        // Collinear alignments along the diagonal, a tight group of three
+        // far off it (a duplication) and one stray off-diagonal hit
+        let mut records: Vec<AlnRecord> = (0..10)
+            .map(|i| test_record(0, i * 1000, i * 1000 + 900, 0, i * 1000, i * 1000 + 900))
+            .collect();
+        for i in 0..3 {
+            records.push(test_record(
+                0,
+                1000 + i * 40,
+                1040 + i * 40,
+                0,
+                8000 + i * 40,
+                8040 + i * 40,
+            ));
         }
+        records.push(test_record(0, 9000, 9050, 0, 500, 550));
+        let plot =
+            RustPlot::from_records(&records, Vec::new(), Vec::new(), vec![10000], vec![10000]);
+
+        let clusters = plot.off_diagonal_clusters(3);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].segments, 3);
+        assert_eq!((clusters[0].x_min, clusters[0].x_max), (1000, 1120));
+        assert_eq!((clusters[0].y_min, clusters[0].y_max), (8000, 8120));
+
+        assert_eq!(plot.off_diagonal_clusters(1).len(), 2);
+    }
+
+    #[test]
+    fn test_width_scale() {
+        let seg = |len: i64, identity: f32| AlignmentSegment {
+            abeg: 0,
+            aend: len,
+            bbeg: 0,
+            bend: len,
+            reverse: false,
+            identity,
+        };
+        assert_eq!(WidthScale::Fixed.factor(&seg(10, 50.0)), 1.0);
+        assert_eq!(WidthScale::Length.factor(&seg(500, 100.0)), 0.5);
+        assert!((WidthScale::Length.factor(&seg(31_623, 100.0)) - 1.75).abs() < 1e-3);
+        assert_eq!(WidthScale::Length.factor(&seg(5_000_000, 0.0)), 3.0);
+        assert_eq!(WidthScale::Identity.factor(&seg(10, 85.0)), 1.75);
+        assert_eq!(WidthScale::Identity.factor(&seg(10, 100.0)), 3.0);
+    }
+
+    #[test]
+    fn test_segment_filter() {
+        let seg = AlignmentSegment {
+            abeg: 0,
+            aend: 5000,
+            bbeg: 5000,
+            bend: 0,
+            reverse: true,
+            identity: 95.0,
+        };
+        assert!(!SegmentFilter::default().is_active());
+        assert!(SegmentFilter::default().matches(&seg));
+
+        let strict = SegmentFilter {
+            min_length: 10_000,
+            ..Default::default()
+        };
+        assert!(strict.is_active());
+        assert!(!strict.matches(&seg));
+        let identity = SegmentFilter {
+            min_identity: 99.0,
+            ..Default::default()
+        };
+        assert!(!identity.matches(&seg));
+        let forward = SegmentFilter {
+            strand: StrandFilter::Forward,
+            ..Default::default()
+        };
+        assert!(!forward.matches(&seg));
+        let reverse = SegmentFilter {
+            strand: StrandFilter::Reverse,
+            min_length: 5000,
+            min_identity: 95.0,
+        };
+        assert!(reverse.matches(&seg));
+    }
+
+    #[test]
+    fn test_cutoff_applies_at_query_time() {
+        let records = vec![
+            test_record(0, 0, 100, 0, 0, 100),
+            test_record(0, 200, 5200, 0, 200, 5200),
+        ];
+        let mut plot =
+            RustPlot::from_records(&records, Vec::new(), Vec::new(), vec![6000], vec![6000]);
+        assert_eq!(
+            plot.query_segment_indices_in_region(0.0, 0.0, 6000.0, 6000.0)
+                .len(),
+            2
+        );
+
+        plot.cutoff.min_length = 1000;
+        assert_eq!(
+            plot.query_segment_indices_in_region(0.0, 0.0, 6000.0, 6000.0),
+            vec![1]
+        );
+        assert_eq!(plot.segment_at(50.0, 50.0, 5.0), None);
+        assert_eq!(plot.passing_segments(), 1);
+        assert_eq!(plot.coverage_depth(0, 0.0, 200.0, 1), vec![0.0]);
+
+        // Nothing was thrown away
+        plot.cutoff = SegmentFilter::default();
+        assert_eq!(plot.segments.len(), 2);
+        assert_eq!(plot.segment_at(50.0, 50.0, 5.0), Some(0));
+    }
+
+    #[test]
+    fn test_segment_at_and_describe() {
+        let mut rec = test_record(0, 1000, 46000, 0, 0, 45000);
+        rec.diffs = 450;
+        let plot = RustPlot::from_records(
+            &[rec],
+            vec!["chr1".to_string()],
+            vec!["scaf2".to_string()],
+            vec![50000],
+            vec![50000],
+        );
+
+        // On the diagonal line from (1000, 0) to (46000, 45000)
+        assert_eq!(plot.segment_at(11000.0, 10005.0, 10.0), Some(0));
+        assert_eq!(plot.segment_at(11000.0, 12000.0, 10.0), None);
+
+        assert_eq!(
+            plot.describe_segment(0),
+            "chr1:1000-46000 ↔ scaf2:0-45000, 99.0% id, 45.0 kb, forward"
+        );
+    }
+
+    #[test]
+    fn test_scale_bar_picks_round_lengths() {
+        // 10 kb per pixel, up to 150 px: 1.5 Mb fits, so 1 Mb
+        assert_eq!(scale_bar(10_000.0, 150.0), (1_000_000, "1 Mb".to_string()));
+        assert_eq!(scale_bar(40.0, 150.0), (5_000, "5 kb".to_string()));
+        assert_eq!(scale_bar(1.5, 150.0), (200, "200 bp".to_string()));
+        // Zoomed in past single bases
+        assert_eq!(scale_bar(0.001, 150.0), (1, "1 bp".to_string()));
+    }
+
+    #[test]
+    fn test_axis_ticks() {
+        // Whole genome, 0-2500 in view: majors every kb, minors every 200 bp
+        let ticks = axis_ticks(-100.0, 2500.0, 0, 10_000, false, 1000);
+        assert_eq!(ticks.len(), 13);
+        assert_eq!(
+            ticks[0],
+            AxisTick {
+                coord: 0.0,
+                local: 0,
+                major: true
+            }
+        );
+        assert!(!ticks[1].major);
+        assert_eq!(ticks.iter().filter(|t| t.major).count(), 3);
+
+        // A reversed 1 kb sequence at 5000: local 0 sits at its right end
+        let ticks = axis_ticks(5000.0, 6000.0, 5000, 1000, true, 500);
+        let majors: Vec<(f64, i64)> = ticks
+            .iter()
+            .filter(|t| t.major)
+            .map(|t| (t.coord, t.local))
+            .collect();
+        assert_eq!(majors, vec![(6000.0, 0), (5500.0, 500), (5000.0, 1000)]);
+        assert_eq!(ticks.len(), 11);
+
+        assert_eq!(tick_label(1_200_000, 200_000), "1.2 Mb");
+        assert_eq!(tick_label(3_000_000, 1_000_000), "3 Mb");
+        assert_eq!(tick_label(25_000, 5_000), "25 kb");
+        assert_eq!(tick_label(1_500, 500), "1.5 kb");
+        assert_eq!(tick_label(40, 20), "40 bp");
+        assert_eq!(tick_label(7, 1), "7 bp");
+    }
+
+    #[test]
+    fn test_sequence_report() {
+        let records = vec![
+            test_record(0, 0, 100, 0, 0, 100),
+            test_record(0, 200, 500, 1, 0, 300),
+            test_record(0, 600, 650, 1, 400, 450),
+            test_record(1, 0, 80, 0, 200, 280),
+        ];
+        let plot = RustPlot::from_records(
+            &records,
+            vec!["q1".to_string(), "q2".to_string()],
+            vec!["t1".to_string(), "t2".to_string()],
+            vec![1000, 1000],
+            vec![1000, 1000],
+        );
+
+        let report = plot.sequence_report(0, 0);
+        assert_eq!(report.segments, vec![0, 1, 2]);
+        assert_eq!(report.aligned_bp, 450);
+        assert_eq!(report.partners, vec![(1, 2, 350), (0, 1, 100)]);
+
+        // Target t1 is hit by both queries
+        let report = plot.sequence_report(1, 0);
+        assert_eq!(report.partners, vec![(0, 1, 100), (1, 1, 80)]);
+    }
+
+    #[test]
+    fn test_pair_identity() {
+        let mut records = vec![
+            test_record(0, 0, 300, 0, 0, 300),
+            test_record(0, 400, 500, 0, 400, 500),
+            test_record(1, 0, 100, 0, 600, 700),
+        ];
+        records[0].diffs = 30; // 90%
+        let plot = RustPlot::from_records(
+            &records,
+            vec!["q1".to_string(), "q2".to_string()],
+            vec!["t1".to_string()],
+            vec![1000, 1000],
+            vec![1000],
+        );
+
+        let identity = plot.pair_identity();
+        assert_eq!(identity.len(), 2);
+        // (300 bp at 90% + 100 bp at 100%) / 400 bp
+        assert!((identity[&(0, 0)] - 92.5).abs() < 1e-3);
+        assert!((identity[&(1, 0)] - 100.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_convex_hull() {
+        let square = vec![(0, 0), (10, 0), (10, 10), (0, 10), (5, 5), (5, 0)];
+        assert_eq!(
+            convex_hull(square),
+            vec![(0, 0), (10, 0), (10, 10), (0, 10)]
+        );
+        assert_eq!(convex_hull(vec![(3, 3), (3, 3)]), vec![(3, 3)]);
+    }
+
+    #[test]
+    fn test_segment_clusters() {
+        // Two tight groups far apart, and one lone segment
+        let mut records: Vec<AlnRecord> = (0..4)
+            .map(|i| test_record(0, i * 20, i * 20 + 15, 0, i * 20, i * 20 + 15))
+            .collect();
+        records.extend((0..3).map(|i| test_record(0, 8000 + i * 20, 8015 + i * 20, 0, 100, 115)));
+        records.push(test_record(0, 5000, 5010, 0, 9000, 9010));
+        let plot =
+            RustPlot::from_records(&records, Vec::new(), Vec::new(), vec![10000], vec![10000]);
+
+        let clusters = plot.segment_clusters(2);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].segments, 4);
+        assert_eq!(clusters[0].hull, vec![(0, 0), (75, 75)]);
+        assert_eq!(clusters[1].segments, 3);
+        assert_eq!((clusters[1].x_min, clusters[1].x_max), (8000, 8055));
+        assert_eq!(clusters[1].hull.len(), 4);
+    }
+
+    #[test]
+    fn test_chain_ids() {
+        // Three collinear pieces with small gaps, one far along the same
+        // diagonal, and a reverse piece among the first three
+        let mut records = vec![
+            test_record(0, 0, 100, 0, 0, 100),
+            test_record(0, 120, 200, 0, 110, 190),
+            test_record(0, 210, 300, 0, 230, 320),
+            test_record(0, 5000, 5100, 0, 5000, 5100),
+            test_record(0, 150, 250, 0, 600, 700),
+        ];
+        records[4].reverse = 1;
+        let plot =
+            RustPlot::from_records(&records, Vec::new(), Vec::new(), vec![10000], vec![10000]);
+
+        let (ids, count) = plot.chain_ids(100);
+        assert_eq!(count, 3);
+        assert_eq!(&ids[..3], &[0, 0, 0]);
+        assert_eq!((ids[3], ids[4]), (2, 1));
+        assert_eq!(plot.chain_ids(10_000).1, 2);
+    }
+
+    #[test]
+    fn test_partner_intervals() {
+        let mut records = vec![
+            // q1:0-1000 -> t1:5000-6000 in two pieces 200 bp apart
+            test_record(0, 0, 400, 0, 5000, 5400),
+            test_record(0, 400, 1000, 0, 5600, 6200),
+            // q1:500-700 also lands on t2 in reverse
+            test_record(0, 500, 700, 1, 100, 300),
+        ];
+        records[2].reverse = 1;
+        records[0].diffs = 40; // 90%
+        let plot = RustPlot::from_records(
+            &records,
+            vec!["q1".to_string()],
+            vec!["t1".to_string(), "t2".to_string()],
+            vec![1000],
+            vec![10000, 1000],
+        );
+
+        let partners = plot.partner_intervals(0, 200, 800, DEFAULT_PARTNER_MERGE_GAP);
+        assert_eq!(partners.len(), 2);
+        let t1 = &partners[0];
+        assert_eq!((t1.seq, t1.start, t1.end), (0, 5200, 6000));
+        assert_eq!((t1.aligned_bp, t1.alignments), (600, 2));
+        // 200 bp at 90% and 400 bp at 100%
+        assert!((t1.identity - 96.666).abs() < 0.01);
+        assert_eq!((partners[1].seq, partners[1].aligned_bp), (1, 200));
+
+        // With no merging the two t1 pieces stay apart
+        assert_eq!(plot.partner_intervals(0, 200, 800, 0).len(), 3);
+    }
+
+    #[test]
+    fn test_plane_sweep_keeps_best_per_position() {
+        let mut best = test_record(0, 0, 1000, 0, 0, 1000);
+        best.diffs = 10;
+        // Overlap `best` on the query axis but each is best somewhere
+        let mut weaker = test_record(0, 500, 1500, 0, 2000, 3000);
+        weaker.diffs = 100;
+        let mut partly = test_record(0, 900, 2000, 0, 4000, 5100);
+        partly.diffs = 500;
+        let plot = RustPlot::from_records(
+            &[best, weaker, partly],
+            Vec::new(),
+            Vec::new(),
+            vec![6000],
+            vec![6000],
+        );
+
+        let one_to_one = plot.plane_sweep(SweepFilter::ONE_TO_ONE);
+        let kept: Vec<i64> = one_to_one.segments.iter().map(|s| s.abeg).collect();
+        assert_eq!(kept, vec![0, 500, 900]);
+
+        // A span inside a better one never makes the top 1
+        assert_eq!(
+            sweep_axis(&[(0, 1000), (100, 900)], &[10.0, 5.0], 1),
+            vec![true, false]
+        );
+        assert_eq!(
+            sweep_axis(&[(0, 1000), (100, 900)], &[10.0, 5.0], 2),
+            vec![true, true]
+        );
+
+        // An alignment inside a better one on the query axis goes, whatever
+        // the target axis says, unless only the target is limited
+        let inner = test_record(0, 100, 900, 0, 5000, 5800);
+        let plot = RustPlot::from_records(
+            &[test_record(0, 0, 1000, 0, 0, 1000), inner],
+            Vec::new(),
+            Vec::new(),
+            vec![6000],
+            vec![6000],
+        );
+        assert_eq!(plot.plane_sweep(SweepFilter::ONE_TO_MANY).segments.len(), 1);
+        assert_eq!(plot.plane_sweep(SweepFilter::MANY_TO_ONE).segments.len(), 2);
+    }
+
+    #[test]
+    fn test_sweep_filter_parse() {
+        assert_eq!(SweepFilter::parse("1:1").unwrap(), SweepFilter::ONE_TO_ONE);
+        assert_eq!(SweepFilter::parse("1:N").unwrap(), SweepFilter::ONE_TO_MANY);
+        let filter = SweepFilter::parse("2:inf").unwrap();
+        assert_eq!((filter.per_query, filter.per_target), (Some(2), None));
+        assert_eq!(filter.to_string(), "2:N");
+        assert!(SweepFilter::parse("0:1").is_err());
+        assert!(SweepFilter::parse("1").is_err());
+    }
+
+    #[test]
+    fn test_ani_counts_each_query_base_once() {
+        // chr0 0..1000 at 99%, with a 90% alignment over 500..1000 underneath;
+        // chr1 0..500 at 95% out of 1000
+        let mut first = test_record(0, 0, 1000, 0, 0, 1000);
+        first.diffs = 10;
+        let mut overlap = test_record(0, 500, 1000, 0, 3000, 3500);
+        overlap.diffs = 50;
+        let mut second = test_record(1, 0, 500, 0, 1000, 1500);
+        second.diffs = 25;
+        let plot = RustPlot::from_records(
+            &[first, overlap, second],
+            Vec::new(),
+            Vec::new(),
+            vec![1000, 1000],
+            vec![4000],
+        );
+
+        let ani = plot.ani();
+        assert_eq!(ani.covered_bp, 1500);
+        assert_eq!(ani.query_bp, 2000);
+        assert!((ani.ani.unwrap() - (99.0 * 1000.0 + 95.0 * 500.0) / 1500.0).abs() < 1e-3);
+        assert_eq!(ani.per_sequence.len(), 2);
+        assert!((ani.per_sequence[0].ani - 99.0).abs() < 1e-3);
+        assert_eq!(
+            (ani.per_sequence[1].covered_bp, ani.per_sequence[1].length),
+            (500, 1000)
+        );
+
+        let empty = RustPlot::from_records(&[], Vec::new(), Vec::new(), vec![10], vec![10]);
+        assert_eq!(empty.ani().ani, None);
     }
 }
@@ -0,0 +1,507 @@
+// Disk-backed alignment segments
+//
+// For files with more segments than fit comfortably in memory, the segments
+// are written once to a store file, sorted into a grid of tiles over the
+// genome plane, and memory-mapped. The viewer keeps only a downsampled
+// overview in memory and reads the tiles under the viewport when zoomed in,
+// so the operating system pages in just the part being looked at. The store
+// also records the sequence names and lengths, so it can be reopened without
+// reading the original .1aln again.
+//
+// `alnview index FILE` writes the store next to FILE (FILE.alnidx), and the
+// GUI and CLI open that instead of the .1aln while it is newer than FILE. For
+// the overview, the store keeps a level-of-detail list: record indices in
+// descending order of their length-weighted sampling key, so the first N of
+// them are the overview at N segments for any N, without reading every record.
+//
+// Layout, little-endian: magic, the query and target sequence tables (count,
+// then length-prefixed names, then lengths), the tile grid (tiles per axis,
+// tile width and height), cumulative record counts per bucket, then one
+// fixed-size record per segment, bucket by bucket. Buckets are the tiles in
+// row-major order plus a last one for segments larger than a tile, which
+// every query scans. Version 2 adds the level-of-detail list after the
+// records: its length, then the record indices.
+use crate::rust_plot::{AlignmentSegment, RustPlot};
+use anyhow::{Context, Result};
+use memmap2::Mmap;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Tiles along each axis of the genome plane
+pub const STORE_TILES: usize = 256;
+
+/// Longest level-of-detail list kept; larger overviews scan the records
+pub const LOD_MAX_SEGMENTS: usize = 1 << 22;
+
+const MAGIC: &[u8; 8] = b"ALNSEG02";
+const MAGIC_V1: &[u8; 8] = b"ALNSEG01"; // No level-of-detail list
+
+/// abeg, aend, bbeg, bend (i64), identity (f32), reverse (u8), 3 bytes padding
+const RECORD_BYTES: usize = 40;
+
+pub struct SegmentStore {
+    map: Mmap,
+    pub query_sequences: Vec<String>,
+    pub target_sequences: Vec<String>,
+    pub query_lengths: Vec<i64>,
+    pub target_lengths: Vec<i64>,
+    tiles: usize,
+    tile_width: i64,
+    tile_height: i64,
+    offsets: Vec<usize>, // Cumulative record counts; bucket b is offsets[b]..offsets[b + 1]
+    data_start: usize,
+    lod_start: usize, // Level-of-detail record indices, lod_len of them
+    lod_len: usize,
+}
+
+/// Where `alnview index` puts the store for `file`
+pub fn sidecar_path(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(".alnidx");
+    PathBuf::from(name)
+}
+
+/// Whether the store at `store` exists and is at least as new as `file`
+pub fn is_fresh(store: &Path, file: &Path) -> bool {
+    let modified = |p: &Path| std::fs::metadata(p).and_then(|m| m.modified()).ok();
+    match (modified(store), modified(file)) {
+        (Some(store_time), Some(file_time)) => store_time >= file_time,
+        (Some(_), None) => true, // The .1aln is gone; the store has all we need
+        (None, _) => false,
+    }
+}
+
+impl SegmentStore {
+    /// Write all of `plot`'s segments (cutoff or not) to a store at `path`
+    pub fn write<P: AsRef<Path>>(path: P, plot: &RustPlot, tiles: usize) -> Result<()> {
+        let path = path.as_ref();
+        let tiles = tiles.max(1);
+        let tile_width = (plot.get_alen() / tiles as i64).max(1);
+        let tile_height = (plot.get_blen() / tiles as i64).max(1);
+        let long_bucket = tiles * tiles;
+
+        let bucket = |seg: &AlignmentSegment| {
+            let (x_lo, x_hi) = (seg.abeg.min(seg.aend), seg.abeg.max(seg.aend));
+            let (y_lo, y_hi) = (seg.bbeg.min(seg.bend), seg.bbeg.max(seg.bend));
+            if x_hi - x_lo > tile_width || y_hi - y_lo > tile_height {
+                return long_bucket;
+            }
+            let tx = (((x_lo + x_hi) / 2 / tile_width).max(0) as usize).min(tiles - 1);
+            let ty = (((y_lo + y_hi) / 2 / tile_height).max(0) as usize).min(tiles - 1);
+            ty * tiles + tx
+        };
+        let mut order: Vec<(usize, usize)> = plot
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(idx, seg)| (bucket(seg), idx))
+            .collect();
+        order.sort_unstable();
+
+        let mut counts = vec![0u64; long_bucket + 2];
+        for &(b, _) in &order {
+            counts[b + 1] += 1;
+        }
+        for b in 1..counts.len() {
+            counts[b] += counts[b - 1];
+        }
+
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create segment store: {}", path.display()))?;
+        let mut out = BufWriter::new(file);
+        out.write_all(MAGIC)?;
+        for (names, lengths) in [
+            (&plot.query_sequences, &plot.query_lengths),
+            (&plot.target_sequences, &plot.target_lengths),
+        ] {
+            out.write_all(&(names.len() as u64).to_le_bytes())?;
+            for name in names {
+                out.write_all(&(name.len() as u32).to_le_bytes())?;
+                out.write_all(name.as_bytes())?;
+            }
+            for &len in lengths.iter().take(names.len()) {
+                out.write_all(&len.to_le_bytes())?;
+            }
+        }
+        out.write_all(&(tiles as u64).to_le_bytes())?;
+        out.write_all(&tile_width.to_le_bytes())?;
+        out.write_all(&tile_height.to_le_bytes())?;
+        for count in &counts {
+            out.write_all(&count.to_le_bytes())?;
+        }
+        for &(_, idx) in &order {
+            let seg = &plot.segments[idx];
+            let mut record = [0u8; RECORD_BYTES];
+            for (i, value) in [seg.abeg, seg.aend, seg.bbeg, seg.bend].iter().enumerate() {
+                record[i * 8..i * 8 + 8].copy_from_slice(&value.to_le_bytes());
+            }
+            record[32..36].copy_from_slice(&seg.identity.to_le_bytes());
+            record[36] = u8::from(seg.reverse);
+            out.write_all(&record)?;
+        }
+
+        // Level of detail: records by descending sampling key, keyed by their
+        // position in the store as `overview` does
+        let mut keyed: Vec<(f64, usize)> = order
+            .iter()
+            .enumerate()
+            .map(|(record, &(_, idx))| (RustPlot::sample_key(record, &plot.segments[idx]), record))
+            .collect();
+        let lod_len = keyed.len().min(LOD_MAX_SEGMENTS);
+        if lod_len > 0 && lod_len < keyed.len() {
+            keyed.select_nth_unstable_by(lod_len - 1, |a, b| b.0.total_cmp(&a.0));
+            keyed.truncate(lod_len);
+        }
+        keyed.sort_unstable_by(|a, b| b.0.total_cmp(&a.0));
+        out.write_all(&(lod_len as u64).to_le_bytes())?;
+        for &(_, record) in &keyed {
+            out.write_all(&(record as u64).to_le_bytes())?;
+        }
+        out.flush()
+            .with_context(|| format!("Failed to write segment store: {}", path.display()))
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open segment store: {}", path.display()))?;
+        // Safety: the store is only ever written whole by `write`; a file
+        // changed underneath a running viewer would give wrong segments, not
+        // undefined behaviour beyond what reading plain bytes allows
+        let map = unsafe { Mmap::map(&file) }
+            .with_context(|| format!("Failed to map segment store: {}", path.display()))?;
+        Self::parse(map).with_context(|| format!("Invalid segment store: {}", path.display()))
+    }
+
+    fn parse(map: Mmap) -> Result<Self> {
+        let mut reader = Reader {
+            bytes: &map,
+            pos: 0,
+        };
+        let version = match reader.take(MAGIC.len())? {
+            magic if magic == MAGIC => 2,
+            magic if magic == MAGIC_V1 => 1,
+            _ => anyhow::bail!("not a segment store"),
+        };
+        let mut axis = || -> Result<(Vec<String>, Vec<i64>)> {
+            let count = reader.u64()? as usize;
+            let names = (0..count)
+                .map(|_| {
+                    let len = reader.u32()? as usize;
+                    Ok(String::from_utf8_lossy(reader.take(len)?).into_owned())
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let lengths = (0..count)
+                .map(|_| reader.u64().map(|v| v as i64))
+                .collect::<Result<Vec<_>>>()?;
+            Ok((names, lengths))
+        };
+        let (query_sequences, query_lengths) = axis()?;
+        let (target_sequences, target_lengths) = axis()?;
+
+        let tiles = reader.u64()? as usize;
+        let tile_width = reader.u64()? as i64;
+        let tile_height = reader.u64()? as i64;
+        let buckets = tiles
+            .checked_mul(tiles)
+            .filter(|_| tiles > 0 && tile_width > 0 && tile_height > 0)
+            .context("bad tile grid")?;
+        let offsets = (0..buckets.checked_add(2).context("bad tile grid")?)
+            .map(|_| reader.u64().map(|v| v as usize))
+            .collect::<Result<Vec<_>>>()?;
+        let data_start = reader.pos;
+        let records = offsets.last().copied().unwrap_or(0);
+        // Counts come from the file, so a damaged one mustn't overflow
+        let records_end = records
+            .checked_mul(RECORD_BYTES)
+            .and_then(|bytes| data_start.checked_add(bytes));
+        match records_end {
+            Some(end) if end <= map.len() && offsets.windows(2).all(|w| w[0] <= w[1]) => {
+                reader.pos = end;
+            }
+            _ => anyhow::bail!("truncated or inconsistent segment table"),
+        }
+        let lod_len = if version >= 2 {
+            reader.u64()? as usize
+        } else {
+            0
+        };
+        let lod_start = reader.pos;
+        let lod_end = lod_len
+            .checked_mul(8)
+            .and_then(|bytes| lod_start.checked_add(bytes));
+        if lod_len > records || lod_end.is_none_or(|end| end > map.len()) {
+            anyhow::bail!("truncated level-of-detail list");
+        }
+
+        Ok(Self {
+            map,
+            query_sequences,
+            target_sequences,
+            query_lengths,
+            target_lengths,
+            tiles,
+            tile_width,
+            tile_height,
+            offsets,
+            data_start,
+            lod_start,
+            lod_len,
+        })
+    }
+
+    /// Number of segments in the store
+    pub fn len(&self) -> usize {
+        self.offsets.last().copied().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn record(&self, idx: usize) -> AlignmentSegment {
+        let start = self.data_start + idx * RECORD_BYTES;
+        let bytes = &self.map[start..start + RECORD_BYTES];
+        let int = |i: usize| i64::from_le_bytes(bytes[i * 8..i * 8 + 8].try_into().unwrap());
+        AlignmentSegment {
+            abeg: int(0),
+            aend: int(1),
+            bbeg: int(2),
+            bend: int(3),
+            identity: f32::from_le_bytes(bytes[32..36].try_into().unwrap()),
+            reverse: bytes[36] != 0,
+        }
+    }
+
+    /// Record ranges that may hold segments meeting the rectangle: one run of
+    /// tiles per tile row, widened by a tile since a segment is filed under the
+    /// tile of its midpoint, plus the bucket of long segments
+    fn ranges(&self, x_min: i64, x_max: i64, y_min: i64, y_max: i64) -> Vec<Range<usize>> {
+        let last = self.tiles as i64 - 1;
+        let tile = |v: i64, size: i64| (v.div_euclid(size)).clamp(0, last) as usize;
+        let (tx0, tx1) = (
+            tile(x_min - self.tile_width, self.tile_width),
+            tile(x_max + self.tile_width, self.tile_width),
+        );
+        let (ty0, ty1) = (
+            tile(y_min - self.tile_height, self.tile_height),
+            tile(y_max + self.tile_height, self.tile_height),
+        );
+        let mut ranges: Vec<Range<usize>> = (ty0..=ty1)
+            .map(|ty| self.offsets[ty * self.tiles + tx0]..self.offsets[ty * self.tiles + tx1 + 1])
+            .collect();
+        let long = self.tiles * self.tiles;
+        ranges.push(self.offsets[long]..self.offsets[long + 1]);
+        ranges
+    }
+
+    /// Upper bound on the segments `segments_in` would return, from the tile
+    /// counts alone
+    pub fn count_in(&self, x_min: i64, x_max: i64, y_min: i64, y_max: i64) -> usize {
+        self.ranges(x_min, x_max, y_min, y_max)
+            .iter()
+            .map(|r| r.len())
+            .sum()
+    }
+
+    /// Segments meeting the rectangle, reading only the tiles around it
+    pub fn segments_in(
+        &self,
+        x_min: i64,
+        x_max: i64,
+        y_min: i64,
+        y_max: i64,
+    ) -> Vec<AlignmentSegment> {
+        self.ranges(x_min, x_max, y_min, y_max)
+            .into_iter()
+            .flatten()
+            .map(|idx| self.record(idx))
+            .filter(|seg| seg.intersects(x_min, x_max, y_min, y_max))
+            .collect()
+    }
+
+    /// Plot of the store's sequences with a length-weighted sample of at most
+    /// `max_segments` segments: the head of the level-of-detail list, or else
+    /// read in one pass without holding the rest
+    pub fn overview(&self, max_segments: usize) -> RustPlot {
+        let keep: Vec<usize> = if max_segments >= self.len() {
+            (0..self.len()).collect()
+        } else if max_segments <= self.lod_len {
+            let mut keep: Vec<usize> = (0..max_segments)
+                .map(|i| {
+                    let start = self.lod_start + i * 8;
+                    u64::from_le_bytes(self.map[start..start + 8].try_into().unwrap()) as usize
+                })
+                .filter(|&idx| idx < self.len())
+                .collect();
+            keep.sort_unstable();
+            keep
+        } else {
+            self.sample_scan(max_segments)
+        };
+
+        RustPlot::from_segments(
+            self.query_sequences.clone(),
+            self.target_sequences.clone(),
+            self.query_lengths.clone(),
+            self.target_lengths.clone(),
+            keep.into_iter().map(|idx| self.record(idx)).collect(),
+        )
+    }
+
+    /// Indices of the `max_segments` records with the largest sampling keys
+    fn sample_scan(&self, max_segments: usize) -> Vec<usize> {
+        // Efraimidis-Spirakis as in `RustPlot::downsampled`, keeping the
+        // largest keys in a bounded min-heap
+        struct Keyed(f64, usize);
+        impl PartialEq for Keyed {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other) == Ordering::Equal
+            }
+        }
+        impl Eq for Keyed {}
+        impl PartialOrd for Keyed {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl Ord for Keyed {
+            // Reversed, so the heap's top is the smallest key
+            fn cmp(&self, other: &Self) -> Ordering {
+                other.0.total_cmp(&self.0)
+            }
+        }
+
+        let mut heap = BinaryHeap::with_capacity(max_segments.min(self.len()) + 1);
+        for idx in 0..self.len() {
+            let key = RustPlot::sample_key(idx, &self.record(idx));
+            if heap.len() < max_segments {
+                heap.push(Keyed(key, idx));
+            } else if heap.peek().is_some_and(|top| key > top.0) {
+                heap.pop();
+                heap.push(Keyed(key, idx));
+            }
+        }
+        let mut keep: Vec<usize> = heap.into_iter().map(|k| k.1).collect();
+        keep.sort_unstable();
+        keep
+    }
+}
+
+/// Cursor over the store's header
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .context("unexpected end of file")?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aln_reader::{test_record, AlnRecord};
+
+    #[test]
+    fn test_store_round_trip_and_viewport_query() {
+        // Short alignments along the diagonal and one spanning the whole plot
+        let mut records: Vec<AlnRecord> = (0..100)
+            .map(|i| test_record(0, i * 100, i * 100 + 50, 0, i * 100, i * 100 + 50))
+            .collect();
+        records.push(test_record(0, 0, 10000, 0, 0, 10000));
+        let plot = RustPlot::from_records(
+            &records,
+            vec!["q".to_string()],
+            vec!["t".to_string()],
+            vec![10000],
+            vec![10000],
+        );
+
+        let path = std::env::temp_dir().join(format!("alnview-store-{}.bin", std::process::id()));
+        SegmentStore::write(&path, &plot, 10).unwrap();
+        let store = SegmentStore::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(store.len(), 101);
+        assert_eq!(store.query_sequences, vec!["q"]);
+        assert_eq!(store.target_lengths, vec![10000]);
+
+        // A window around (5000, 5000): one short alignment plus the long one
+        let mut found = store.segments_in(4990, 5060, 4990, 5060);
+        found.sort_by_key(|seg| seg.aend);
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].abeg, found[0].aend), (5000, 5050));
+        assert_eq!(found[1].aend, 10000);
+        assert!(store.count_in(4990, 5060, 4990, 5060) < store.len());
+
+        let overview = store.overview(10);
+        assert_eq!(overview.segments.len(), 10);
+        assert_eq!(overview.get_alen(), 10000);
+        assert!(overview.segments.iter().any(|seg| seg.aend == 10000));
+        // The level-of-detail list gives the same sample as a full scan
+        let mut scanned: Vec<i64> = store
+            .sample_scan(10)
+            .iter()
+            .map(|&idx| store.record(idx).abeg)
+            .collect();
+        scanned.sort_unstable();
+        let mut listed: Vec<i64> = overview.segments.iter().map(|seg| seg.abeg).collect();
+        listed.sort_unstable();
+        assert_eq!(listed, scanned);
+        assert_eq!(store.overview(1000).segments.len(), 101);
+    }
+
+    #[test]
+    fn test_damaged_store_is_an_error() {
+        let records = vec![
+            test_record(0, 0, 50, 0, 0, 50),
+            test_record(0, 100, 150, 0, 100, 150),
+        ];
+        let plot = RustPlot::from_records(
+            &records,
+            vec!["q".to_string()],
+            vec!["t".to_string()],
+            vec![200],
+            vec![200],
+        );
+        let path = std::env::temp_dir().join(format!("alnview-damaged-{}.bin", std::process::id()));
+        SegmentStore::write(&path, &plot, 2).unwrap();
+        let data_start = SegmentStore::open(&path).unwrap().data_start;
+        let mut bytes = std::fs::read(&path).unwrap();
+
+        // A record count so large its byte length overflows
+        bytes[data_start - 8..data_start].copy_from_slice(&(u64::MAX / 2).to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        let err = SegmentStore::open(&path).err().unwrap();
+        assert!(format!("{err:#}").contains("inconsistent segment table"));
+
+        // Cut off partway through the records
+        SegmentStore::write(&path, &plot, 2).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..data_start + RECORD_BYTES]).unwrap();
+        assert!(SegmentStore::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
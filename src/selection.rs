@@ -0,0 +1,230 @@
+// Alignments selected on the canvas
+//
+// Shift+Ctrl+drag picks the passing alignments that cross a box, so a set of
+// them can be taken out of the viewer as a CSV or TSV table: one row per
+// alignment, in its sequences' own coordinates with target coordinates on the
+// forward strand (as PAF has them). Ctrl+drag instead sums up the alignments
+// in a box: how many there are, how many bp of them lie inside it, their
+// identity and strands.
+use crate::rust_plot::{AlignmentSegment, RustPlot};
+
+/// Indices of passing segments crossing the genome box between two corners
+pub fn in_box(plot: &RustPlot, corner: (f64, f64), other: (f64, f64)) -> Vec<usize> {
+    let (x_min, x_max) = (corner.0.min(other.0) as i64, corner.0.max(other.0) as i64);
+    let (y_min, y_max) = (corner.1.min(other.1) as i64, corner.1.max(other.1) as i64);
+    plot.segments
+        .iter()
+        .enumerate()
+        .filter(|(_, seg)| plot.passes(seg) && seg.intersects(x_min, x_max, y_min, y_max))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Summary of the alignments in a box
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BoxStats {
+    pub segments: usize,
+    pub aligned_bp: i64,    // Query bp of the alignments inside the box
+    pub mean_identity: f32, // Percent, weighted by the bp inside the box
+    pub forward: usize,
+    pub reverse: usize,
+}
+
+/// Sum up the segments crossing the genome box `x` by `y` (min, max); only
+/// the part of each segment inside the box counts towards its bp
+pub fn box_stats<'a>(
+    segments: impl IntoIterator<Item = &'a AlignmentSegment>,
+    x: (i64, i64),
+    y: (i64, i64),
+) -> BoxStats {
+    let mut stats = BoxStats::default();
+    let mut identity_sum = 0.0;
+    for seg in segments {
+        let Some((t0, t1)) = clip(seg, x, y) else {
+            continue;
+        };
+        let bp = ((t1 - t0) * (seg.aend - seg.abeg).abs() as f64).round() as i64;
+        stats.segments += 1;
+        stats.aligned_bp += bp;
+        identity_sum += seg.identity as f64 * bp as f64;
+        if seg.reverse {
+            stats.reverse += 1;
+        } else {
+            stats.forward += 1;
+        }
+    }
+    if stats.aligned_bp > 0 {
+        stats.mean_identity = (identity_sum / stats.aligned_bp as f64) as f32;
+    }
+    stats
+}
+
+/// Part of a segment inside a box, as fractions of the way from its start to
+/// its end (Liang-Barsky clipping), or None if it misses the box
+fn clip(seg: &AlignmentSegment, x: (i64, i64), y: (i64, i64)) -> Option<(f64, f64)> {
+    let (dx, dy) = ((seg.aend - seg.abeg) as f64, (seg.bend - seg.bbeg) as f64);
+    let (mut t0, mut t1) = (0.0_f64, 1.0_f64);
+    for (p, q) in [
+        (-dx, (seg.abeg - x.0) as f64),
+        (dx, (x.1 - seg.abeg) as f64),
+        (-dy, (seg.bbeg - y.0) as f64),
+        (dy, (y.1 - seg.bbeg) as f64),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            t0 = t0.max(q / p);
+        } else {
+            t1 = t1.min(q / p);
+        }
+    }
+    (t0 <= t1).then_some((t0, t1))
+}
+
+/// Table of the selected segments with a header row, fields split by
+/// `separator` (',' for CSV, '\t' for TSV)
+pub fn to_table(plot: &RustPlot, selected: &[usize], separator: char) -> String {
+    let header = [
+        "query",
+        "query_start",
+        "query_end",
+        "target",
+        "target_start",
+        "target_end",
+        "strand",
+        "length",
+        "identity",
+    ];
+    let mut text = header.join(&separator.to_string());
+    text.push('\n');
+    for seg in selected.iter().filter_map(|&idx| plot.segments.get(idx)) {
+        let rec = plot.segment_record(seg);
+        // The record's strand, not the segment's: a flipped band swaps that
+        let reverse = rec.reverse != 0;
+        let (target_start, target_end) = if reverse {
+            (
+                rec.target_len - rec.target_end,
+                rec.target_len - rec.target_start,
+            )
+        } else {
+            (rec.target_start, rec.target_end)
+        };
+        let row = [
+            field(&rec.query_name, separator),
+            rec.query_start.to_string(),
+            rec.query_end.to_string(),
+            field(&rec.target_name, separator),
+            target_start.to_string(),
+            target_end.to_string(),
+            if reverse { "-" } else { "+" }.to_string(),
+            (rec.query_end - rec.query_start).to_string(),
+            format!("{:.2}", seg.identity),
+        ];
+        text.push_str(&row.join(&separator.to_string()));
+        text.push('\n');
+    }
+    text
+}
+
+/// A name as one field: quoted for CSV if it holds the separator or a quote,
+/// with tabs and newlines made spaces for TSV
+fn field(name: &str, separator: char) -> String {
+    if separator == '\t' {
+        name.replace(['\t', '\n'], " ")
+    } else if name.contains([separator, '"', '\n']) {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aln_reader::{test_record, AlnRecord};
+
+    #[test]
+    fn test_box_selection_table() {
+        let records = vec![
+            AlnRecord {
+                diffs: 10,
+                ..test_record(0, 0, 100, 0, 0, 100)
+            },
+            AlnRecord {
+                reverse: 1,
+                diffs: 10,
+                ..test_record(0, 500, 600, 0, 700, 800)
+            },
+        ];
+        let plot = RustPlot::from_records(
+            &records,
+            vec!["ctg,1".to_string()],
+            vec!["chr1".to_string()],
+            vec![1000],
+            vec![1000],
+        );
+
+        // Corners in either order; only the second alignment crosses the box
+        let selected = in_box(&plot, (1000.0, 0.0), (450.0, 500.0));
+        assert_eq!(selected, vec![1]);
+        let csv = to_table(&plot, &selected, ',');
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            rows,
+            vec![
+                "query,query_start,query_end,target,target_start,target_end,strand,length,identity",
+                "\"ctg,1\",500,600,chr1,200,300,-,100,90.00",
+            ]
+        );
+        assert!(to_table(&plot, &selected, '\t').contains("ctg,1\t500\t600\tchr1\t200\t300\t-"));
+
+        // Flipping the target band changes the segments' strand on screen,
+        // not the alignments'
+        let flipped = plot.with_layout(&[(0, false)], &[(0, true)]);
+        let all: Vec<usize> = (0..flipped.segments.len()).collect();
+        assert!(!flipped.segments[1].reverse);
+        let csv = to_table(&flipped, &all, ',');
+        let rows: Vec<&str> = csv.lines().skip(1).collect();
+        assert_eq!(
+            rows,
+            vec![
+                "\"ctg,1\",0,100,chr1,0,100,+,100,90.00",
+                "\"ctg,1\",500,600,chr1,200,300,-,100,90.00",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_box_stats_clip_to_the_box() {
+        let segment = |abeg, bbeg, bend, identity| AlignmentSegment {
+            abeg,
+            aend: abeg + 100,
+            bbeg,
+            bend,
+            reverse: bend < bbeg,
+            identity,
+        };
+        let segments = [
+            segment(0, 0, 100, 90.0),      // Half inside
+            segment(200, 300, 200, 100.0), // Reverse, all inside
+            segment(0, 260, 360, 80.0),    // Its bounding box meets the box, the line doesn't
+        ];
+        let stats = box_stats(&segments, (50, 300), (0, 300));
+        assert_eq!(
+            stats,
+            BoxStats {
+                segments: 2,
+                aligned_bp: 150,
+                mean_identity: (90.0 * 50.0 + 100.0 * 100.0) / 150.0,
+                forward: 1,
+                reverse: 1,
+            }
+        );
+        assert_eq!(
+            box_stats(&segments, (500, 600), (0, 300)),
+            BoxStats::default()
+        );
+    }
+}
@@ -1,4 +1,5 @@
 // Sequence filtering for subset views
+use regex::Regex;
 use std::collections::HashSet;
 
 #[derive(Clone, Debug)]
@@ -7,6 +8,10 @@ pub struct SequenceFilter {
     pub names: Vec<String>,
     /// Selected sequence index range (inclusive)
     pub range: Option<(usize, usize)>,
+    /// Selected sequence names matching a regular expression
+    pub pattern: Option<Regex>,
+    /// Exact sequence names to leave out, whatever else matches
+    pub hidden: HashSet<String>,
 }
 
 impl SequenceFilter {
@@ -14,6 +19,8 @@ impl SequenceFilter {
         Self {
             names: Vec::new(),
             range: None,
+            pattern: None,
+            hidden: HashSet::new(),
         }
     }
 
@@ -25,7 +32,12 @@ impl SequenceFilter {
             .filter(|s| !s.is_empty())
             .collect();
 
-        Self { names, range: None }
+        Self {
+            names,
+            range: None,
+            pattern: None,
+            hidden: HashSet::new(),
+        }
     }
 
     /// Create from range string like "0-5" or "3-10"
@@ -45,18 +57,52 @@ impl SequenceFilter {
         Ok(Self {
             names: Vec::new(),
             range: Some((start, end)),
+            pattern: None,
+            hidden: HashSet::new(),
         })
     }
 
-    /// Check if this filter matches any sequences
+    /// Create from the text typed into the filter panel: a regular expression
+    /// between slashes (`/^chr[0-9]+$/`), else comma-separated names/prefixes
+    pub fn from_text(text: &str) -> anyhow::Result<Self> {
+        let text = text.trim();
+        match text.strip_prefix('/').and_then(|t| t.strip_suffix('/')) {
+            Some(pattern) => Ok(Self {
+                pattern: Some(
+                    Regex::new(pattern)
+                        .map_err(|e| anyhow::anyhow!("Invalid regular expression: {e}"))?,
+                ),
+                ..Self::new()
+            }),
+            None => Ok(Self::from_names(text)),
+        }
+    }
+
+    /// Also exclude these exact sequence names
+    pub fn with_hidden(mut self, hidden: HashSet<String>) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Check if this filter keeps every sequence: it picks none out and
+    /// hides none
     pub fn is_empty(&self) -> bool {
-        self.names.is_empty() && self.range.is_none()
+        !self.picks() && self.hidden.is_empty()
+    }
+
+    /// Whether names, a range or a pattern pick sequences out
+    fn picks(&self) -> bool {
+        !self.names.is_empty() || self.range.is_some() || self.pattern.is_some()
     }
 
     /// Check if a sequence at given index with given name matches this filter
     pub fn matches(&self, index: usize, name: &str) -> bool {
-        if self.is_empty() {
-            return true; // No filter = match all
+        if self.hidden.contains(name) {
+            return false;
+        }
+
+        if !self.picks() {
+            return true; // Nothing picked = match all not hidden
         }
 
         // Check range filter
@@ -66,6 +112,10 @@ impl SequenceFilter {
             }
         }
 
+        if self.pattern.as_ref().is_some_and(|re| re.is_match(name)) {
+            return true;
+        }
+
         // Check name/prefix filter
         for filter_name in &self.names {
             if name == filter_name || name.starts_with(filter_name) {
@@ -87,6 +137,46 @@ impl SequenceFilter {
     }
 }
 
+/// How well `pattern` fuzzy-matches `name`: None unless its characters all
+/// appear in order (ignoring case). Higher is better; runs of consecutive
+/// characters and matches at the start of a word score more, gaps and long
+/// names less.
+pub fn fuzzy_score(pattern: &str, name: &str) -> Option<i32> {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let Some(&first) = pattern.first() else {
+        return Some(0);
+    };
+    let length_penalty = (name.len() - pattern.len().min(name.len())).min(20) as i32;
+
+    // Greedy match from each place the first character occurs, keeping the best
+    let from = |start: usize| -> Option<i32> {
+        let mut score = 0;
+        let mut prev: Option<usize> = None;
+        let mut next = start;
+        for &c in &pattern {
+            let pos = next + name[next..].iter().position(|&n| n == c)?;
+            score += 10;
+            match prev {
+                Some(prev) if prev + 1 == pos => score += 15,
+                Some(prev) => score -= (pos - prev - 1).min(10) as i32,
+                None => score -= pos.min(10) as i32,
+            }
+            if pos == 0 || !name[pos - 1].is_alphanumeric() {
+                score += 10;
+            }
+            prev = Some(pos);
+            next = pos + 1;
+        }
+        Some(score)
+    };
+    (0..name.len())
+        .filter(|&start| name[start] == first)
+        .filter_map(from)
+        .max()
+        .map(|score| score - length_penalty)
+}
+
 impl Default for SequenceFilter {
     fn default() -> Self {
         Self::new()
@@ -139,4 +229,47 @@ mod tests {
         assert!(filter.matches(5, "scaffold")); // matches range
         assert!(!filter.matches(15, "scaffold")); // matches neither
     }
+
+    #[test]
+    fn test_hidden_names_excluded() {
+        let hidden = ["chr10".to_string()].into_iter().collect();
+        let filter = SequenceFilter::from_names("chr1").with_hidden(hidden);
+        assert!(filter.matches(0, "chr1"));
+        assert!(!filter.matches(1, "chr10")); // prefix match, but hidden
+
+        let filter = SequenceFilter::new().with_hidden(["chr2".to_string()].into_iter().collect());
+        assert!(filter.matches(0, "chr1"));
+        assert!(!filter.matches(1, "chr2"));
+        // Hiding alone is still a filter, so callers that skip empty ones keep it
+        assert!(!filter.is_empty());
+        assert!(SequenceFilter::new().is_empty());
+    }
+
+    #[test]
+    fn test_regex_text() {
+        let filter = SequenceFilter::from_text("/^chr[0-9]+$/").unwrap();
+        assert!(filter.matches(0, "chr12"));
+        assert!(!filter.matches(1, "chrX"));
+        assert!(!filter.matches(2, "scaffold_chr1"));
+
+        // Without slashes the text is names and prefixes
+        let filter = SequenceFilter::from_text(" chrX, scaffold ").unwrap();
+        assert!(filter.matches(0, "chrX"));
+        assert!(filter.matches(1, "scaffold_7"));
+        assert!(SequenceFilter::from_text("/chr(/").is_err());
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("xyz", "chr1"), None);
+        assert_eq!(fuzzy_score("1c", "chr1"), None); // Out of order
+        assert_eq!(fuzzy_score("", "chr1"), Some(0));
+        assert!(fuzzy_score("CHR", "chr1").is_some());
+
+        // Consecutive and word-start matches rank first
+        let score = |name| fuzzy_score("chr2", name).unwrap();
+        assert!(score("chr2") > score("chr21"));
+        assert!(score("chr21") > score("scaffold_chr2_alt"));
+        assert!(score("scaffold_chr2_alt") > score("contig_h_r_2"));
+    }
 }
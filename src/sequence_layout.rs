@@ -0,0 +1,307 @@
+// Saved sequence orderings
+//
+// A layout file lists, per axis, the sequences to show and their order, so an
+// arrangement curated in the GUI can be re-applied to CLI renders. Sequences
+// not listed for an axis are left out; an axis with no entries keeps the file
+// order. Format: one tab-separated line per sequence, `Q|T  name  +|-`, with
+// '#' comment lines.
+//
+// An order file is the simpler per-axis form for matching a published
+// karyotype: one name per line, followed by whitespace and `-` to show the
+// sequence flipped. It only reorders: sequences it does not list follow in
+// file order.
+use crate::rust_plot::RustPlot;
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayoutEntry {
+    pub name: String,
+    pub reverse: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SequenceLayout {
+    pub query: Vec<LayoutEntry>,
+    pub target: Vec<LayoutEntry>,
+}
+
+impl SequenceLayout {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read layout file: {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid layout file: {}", path.display()))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, self.to_text())
+            .with_context(|| format!("Failed to write layout file: {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut layout = Self::default();
+
+        for (line_no, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 3 {
+                anyhow::bail!(
+                    "line {}: expected 3 tab-separated fields, got {}",
+                    line_no + 1,
+                    fields.len()
+                );
+            }
+
+            let reverse = match fields[2].trim() {
+                "+" => false,
+                "-" => true,
+                other => anyhow::bail!(
+                    "line {}: orientation must be + or -, got '{other}'",
+                    line_no + 1
+                ),
+            };
+            let entry = LayoutEntry {
+                name: fields[1].to_string(),
+                reverse,
+            };
+
+            match fields[0].trim() {
+                "Q" => layout.query.push(entry),
+                "T" => layout.target.push(entry),
+                other => anyhow::bail!("line {}: axis must be Q or T, got '{other}'", line_no + 1),
+            }
+        }
+
+        Ok(layout)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# alnview sequence layout\n# axis\tname\torientation\n");
+        for (axis, entries) in [("Q", &self.query), ("T", &self.target)] {
+            for entry in entries {
+                let strand = if entry.reverse { '-' } else { '+' };
+                text.push_str(&format!("{axis}\t{}\t{strand}\n", entry.name));
+            }
+        }
+        text
+    }
+
+    /// Read an order file's entries
+    pub fn load_order<P: AsRef<Path>>(path: P) -> Result<Vec<LayoutEntry>> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read order file: {}", path.display()))?;
+        Ok(Self::parse_order(&text))
+    }
+
+    /// Entries of an order file: a trailing `-` (or `+`) after whitespace is
+    /// the orientation, the rest of the line the name
+    pub fn parse_order(text: &str) -> Vec<LayoutEntry> {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (name, reverse) = match line.rsplit_once(char::is_whitespace) {
+                    Some((name, "-")) => (name.trim_end(), true),
+                    Some((name, "+")) => (name.trim_end(), false),
+                    _ => (line, false),
+                };
+                LayoutEntry {
+                    name: name.to_string(),
+                    reverse,
+                }
+            })
+            .collect()
+    }
+
+    /// This layout as an order for `plot`: on each axis with entries, the
+    /// sequences it does not list are added after them in file order, so
+    /// applying it hides nothing. Also returns the number of listed names the
+    /// plot does not contain
+    pub fn completed(&self, plot: &RustPlot) -> (Self, usize) {
+        let complete = |entries: &[LayoutEntry], names: &[String]| {
+            if entries.is_empty() {
+                return (Vec::new(), 0);
+            }
+            let (listed, unlisted, missing) = resolve(entries, names);
+            let order = with_orientation(&listed, entries, names)
+                .into_iter()
+                .chain(unlisted.into_iter().map(|i| (i, false)))
+                .map(|(i, reverse)| LayoutEntry {
+                    name: names[i].clone(),
+                    reverse,
+                })
+                .collect();
+            (order, missing)
+        };
+        let (query, query_missing) = complete(&self.query, &plot.query_sequences);
+        let (target, target_missing) = complete(&self.target, &plot.target_sequences);
+        (Self { query, target }, query_missing + target_missing)
+    }
+
+    /// Apply the layout to a plot, returning the rearranged plot and the number
+    /// of listed names the plot does not contain
+    pub fn apply(&self, plot: &RustPlot) -> (RustPlot, usize) {
+        let (query_order, _, query_missing) = resolve(&self.query, &plot.query_sequences);
+        let (target_order, _, target_missing) = resolve(&self.target, &plot.target_sequences);
+        let query_order = with_orientation(&query_order, &self.query, &plot.query_sequences);
+        let target_order = with_orientation(&target_order, &self.target, &plot.target_sequences);
+        (
+            plot.with_layout(&query_order, &target_order),
+            query_missing + target_missing,
+        )
+    }
+
+    /// Names listed with reverse orientation on each axis: (query, target)
+    pub fn reversed_names(&self) -> (HashSet<String>, HashSet<String>) {
+        let reversed = |entries: &[LayoutEntry]| {
+            entries
+                .iter()
+                .filter(|e| e.reverse)
+                .map(|e| e.name.clone())
+                .collect()
+        };
+        (reversed(&self.query), reversed(&self.target))
+    }
+}
+
+/// Pair each index of `order` with whether its entry asks for reverse orientation
+fn with_orientation(
+    order: &[usize],
+    entries: &[LayoutEntry],
+    names: &[String],
+) -> Vec<(usize, bool)> {
+    let reversed: HashSet<&str> = entries
+        .iter()
+        .filter(|e| e.reverse)
+        .map(|e| e.name.as_str())
+        .collect();
+    order
+        .iter()
+        .map(|&i| (i, reversed.contains(names[i].as_str())))
+        .collect()
+}
+
+/// Match one axis' entries against sequence names
+///
+/// Returns (indices of listed sequences in layout order, indices of unlisted
+/// sequences, number of listed names not found). With no entries, or none that
+/// match, every sequence counts as listed in its original order.
+pub fn resolve(entries: &[LayoutEntry], names: &[String]) -> (Vec<usize>, Vec<usize>, usize) {
+    if entries.is_empty() {
+        return ((0..names.len()).collect(), Vec::new(), 0);
+    }
+
+    let index: HashMap<&str, usize> = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.as_str(), i))
+        .collect();
+
+    let mut listed = Vec::new();
+    let mut seen = vec![false; names.len()];
+    let mut missing = 0;
+    for entry in entries {
+        match index.get(entry.name.as_str()) {
+            Some(&i) if !seen[i] => {
+                seen[i] = true;
+                listed.push(i);
+            }
+            Some(_) => {}
+            None => missing += 1,
+        }
+    }
+    if listed.is_empty() {
+        return ((0..names.len()).collect(), Vec::new(), missing);
+    }
+    let unlisted = (0..names.len()).filter(|&i| !seen[i]).collect();
+
+    (listed, unlisted, missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let layout = SequenceLayout {
+            query: vec![LayoutEntry {
+                name: "chr1 some description".to_string(),
+                reverse: false,
+            }],
+            target: vec![LayoutEntry {
+                name: "scaf_2".to_string(),
+                reverse: true,
+            }],
+        };
+        assert_eq!(SequenceLayout::parse(&layout.to_text()).unwrap(), layout);
+    }
+
+    #[test]
+    fn test_resolve_orders_listed_and_reports_missing() {
+        let entries: Vec<LayoutEntry> = ["c", "a", "zzz"]
+            .iter()
+            .map(|n| LayoutEntry {
+                name: n.to_string(),
+                reverse: false,
+            })
+            .collect();
+        let (listed, unlisted, missing) = resolve(&entries, &names(&["a", "b", "c"]));
+        assert_eq!(listed, vec![2, 0]);
+        assert_eq!(unlisted, vec![1]);
+        assert_eq!(missing, 1);
+
+        let (listed, unlisted, _) = resolve(&[], &names(&["a", "b"]));
+        assert_eq!(listed, vec![0, 1]);
+        assert!(unlisted.is_empty());
+    }
+
+    #[test]
+    fn test_order_file_lists_first_and_hides_nothing() {
+        let entries = SequenceLayout::parse_order("# karyotype\nchr 2\t-\n\n  chr1 +\nchrX\n");
+        assert_eq!(
+            entries
+                .iter()
+                .map(|e| (e.name.as_str(), e.reverse))
+                .collect::<Vec<_>>(),
+            vec![("chr 2", true), ("chr1", false), ("chrX", false)]
+        );
+
+        let order = SequenceLayout {
+            query: entries,
+            target: Vec::new(),
+        };
+        let plot = RustPlot::from_records(
+            &[],
+            names(&["chr1", "chr3", "chr 2"]),
+            names(&["ref"]),
+            vec![10, 30, 20],
+            vec![100],
+        );
+        let (completed, missing) = order.completed(&plot);
+        assert_eq!(missing, 1);
+        assert!(completed.target.is_empty());
+        let (arranged, _) = completed.apply(&plot);
+        assert_eq!(arranged.query_sequences, names(&["chr 2", "chr1", "chr3"]));
+        assert_eq!(arranged.target_sequences, names(&["ref"]));
+        assert_eq!(completed.reversed_names().0.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_lines() {
+        assert!(SequenceLayout::parse("X\tchr1\t+\n").is_err());
+        assert!(SequenceLayout::parse("Q\tchr1\n").is_err());
+        assert!(SequenceLayout::parse("# comment\n\nQ\tchr1\t+\n").is_ok());
+    }
+}
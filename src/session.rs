@@ -0,0 +1,321 @@
+// Saved viewer sessions
+//
+// A session file records what was open, where the view was, the figure's
+// title, the text annotations and regions of interest placed on the plot, and
+// curation tags, so a figure can be picked up again in the GUI or rendered
+// from the CLI. Format: one
+// tab-separated record per line, keyed by its first field, with '#' comment
+// lines:
+//
+//   file        path
+//   view        x  y  scale
+//   title       text
+//   subtitle    text
+//   annotation  x  y  arrow(0|1)  text
+//   region      x_min  x_max  y_min  y_max  #rrggbb  name
+//   tag         segment|region  x_min  x_max  y_min  y_max  reverse(0|1)  tag  note
+//
+// Coordinates are in the displayed plot's concatenated genome space. Tabs,
+// newlines and backslashes in text are escaped as \t, \n and \\.
+use crate::regions::Region;
+use crate::tags::{Tag, TagTarget};
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A text label pinned to a genome position, optionally with an arrow
+/// pointing at it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Annotation {
+    pub x: f64,
+    pub y: f64,
+    pub text: String,
+    pub arrow: bool,
+}
+
+/// Viewport position and zoom (bp per pixel)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SessionView {
+    pub x: f64,
+    pub y: f64,
+    pub scale: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Session {
+    pub file: Option<PathBuf>,
+    pub view: Option<SessionView>,
+    pub title: String,
+    pub subtitle: String,
+    pub annotations: Vec<Annotation>,
+    pub regions: Vec<Region>,
+    pub tags: Vec<Tag>,
+}
+
+impl Session {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read session file: {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid session file: {}", path.display()))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, self.to_text())
+            .with_context(|| format!("Failed to write session file: {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut session = Self::default();
+
+        for (line_no, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let expect = |count: usize| {
+                if fields.len() == count {
+                    Ok(())
+                } else {
+                    Err(anyhow::anyhow!(
+                        "line {}: '{}' needs {} fields, got {}",
+                        line_no + 1,
+                        fields[0],
+                        count,
+                        fields.len()
+                    ))
+                }
+            };
+            let number = |field: &str| {
+                field
+                    .trim()
+                    .parse::<f64>()
+                    .with_context(|| format!("line {}: invalid number '{field}'", line_no + 1))
+            };
+
+            match fields[0] {
+                "file" => {
+                    expect(2)?;
+                    session.file = Some(PathBuf::from(unescape(fields[1])));
+                }
+                "view" => {
+                    expect(4)?;
+                    session.view = Some(SessionView {
+                        x: number(fields[1])?,
+                        y: number(fields[2])?,
+                        scale: number(fields[3])?,
+                    });
+                }
+                "title" => {
+                    expect(2)?;
+                    session.title = unescape(fields[1]);
+                }
+                "subtitle" => {
+                    expect(2)?;
+                    session.subtitle = unescape(fields[1]);
+                }
+                "annotation" => {
+                    expect(5)?;
+                    session.annotations.push(Annotation {
+                        x: number(fields[1])?,
+                        y: number(fields[2])?,
+                        arrow: fields[3].trim() == "1",
+                        text: unescape(fields[4]),
+                    });
+                }
+                "region" => {
+                    expect(7)?;
+                    let coord = |field: &str| {
+                        field.trim().parse::<i64>().with_context(|| {
+                            format!("line {}: invalid coordinate '{field}'", line_no + 1)
+                        })
+                    };
+                    session.regions.push(Region {
+                        x_min: coord(fields[1])?,
+                        x_max: coord(fields[2])?,
+                        y_min: coord(fields[3])?,
+                        y_max: coord(fields[4])?,
+                        color: parse_color(fields[5]).with_context(|| {
+                            format!("line {}: invalid colour '{}'", line_no + 1, fields[5])
+                        })?,
+                        name: unescape(fields[6]),
+                    });
+                }
+                "tag" => {
+                    expect(9)?;
+                    let coord = |field: &str| {
+                        field.trim().parse::<i64>().with_context(|| {
+                            format!("line {}: invalid coordinate '{field}'", line_no + 1)
+                        })
+                    };
+                    let target = TagTarget::parse(fields[1]).with_context(|| {
+                        format!(
+                            "line {}: tag target must be segment or region, got '{}'",
+                            line_no + 1,
+                            fields[1]
+                        )
+                    })?;
+                    session.tags.push(Tag {
+                        target,
+                        x_min: coord(fields[2])?,
+                        x_max: coord(fields[3])?,
+                        y_min: coord(fields[4])?,
+                        y_max: coord(fields[5])?,
+                        reverse: fields[6].trim() == "1",
+                        tag: unescape(fields[7]),
+                        note: unescape(fields[8]),
+                    });
+                }
+                other => anyhow::bail!("line {}: unknown record type '{other}'", line_no + 1),
+            }
+        }
+
+        Ok(session)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# alnview session\n");
+        if let Some(ref file) = self.file {
+            text.push_str(&format!("file\t{}\n", escape(&file.to_string_lossy())));
+        }
+        if let Some(view) = self.view {
+            text.push_str(&format!("view\t{}\t{}\t{}\n", view.x, view.y, view.scale));
+        }
+        if !self.title.is_empty() {
+            text.push_str(&format!("title\t{}\n", escape(&self.title)));
+        }
+        if !self.subtitle.is_empty() {
+            text.push_str(&format!("subtitle\t{}\n", escape(&self.subtitle)));
+        }
+        for annotation in &self.annotations {
+            text.push_str(&format!(
+                "annotation\t{}\t{}\t{}\t{}\n",
+                annotation.x,
+                annotation.y,
+                u8::from(annotation.arrow),
+                escape(&annotation.text)
+            ));
+        }
+        for region in &self.regions {
+            let [r, g, b] = region.color;
+            text.push_str(&format!(
+                "region\t{}\t{}\t{}\t{}\t#{r:02x}{g:02x}{b:02x}\t{}\n",
+                region.x_min,
+                region.x_max,
+                region.y_min,
+                region.y_max,
+                escape(&region.name)
+            ));
+        }
+        for tag in &self.tags {
+            text.push_str(&format!(
+                "tag\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                tag.target.as_str(),
+                tag.x_min,
+                tag.x_max,
+                tag.y_min,
+                tag.y_max,
+                u8::from(tag.reverse),
+                escape(&tag.tag),
+                escape(&tag.note)
+            ));
+        }
+        text
+    }
+}
+
+/// Parse a `#rrggbb` colour
+pub(crate) fn parse_color(text: &str) -> Result<[u8; 3]> {
+    let hex = text.trim().strip_prefix('#').unwrap_or(text.trim());
+    if hex.len() != 6 || !hex.is_ascii() {
+        anyhow::bail!("expected #rrggbb");
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+pub(crate) fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let session = Session {
+            file: Some(PathBuf::from("/data/x vs y.1aln")),
+            view: Some(SessionView {
+                x: 1500.5,
+                y: -20.0,
+                scale: 0.25,
+            }),
+            title: "HG002 vs CHM13".to_string(),
+            subtitle: "chr6, MHC region".to_string(),
+            annotations: vec![
+                Annotation {
+                    x: 100.0,
+                    y: 2000.0,
+                    text: "inversion\tchr2\nsecond line \\ backslash".to_string(),
+                    arrow: true,
+                },
+                Annotation {
+                    x: 0.0,
+                    y: 0.0,
+                    text: "origin".to_string(),
+                    arrow: false,
+                },
+            ],
+            regions: vec![Region {
+                name: "translocation?".to_string(),
+                x_min: 10,
+                x_max: 5000,
+                y_min: 200,
+                y_max: 900,
+                color: [255, 8, 160],
+            }],
+            tags: vec![Tag {
+                target: TagTarget::Segment,
+                x_min: 10,
+                x_max: 5000,
+                y_min: 200,
+                y_max: 900,
+                reverse: true,
+                tag: "assembly artifact".to_string(),
+                note: "gap in\nboth assemblies".to_string(),
+            }],
+        };
+        assert_eq!(Session::parse(&session.to_text()).unwrap(), session);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_lines() {
+        assert!(Session::parse("annotation\t1\t2\t0\n").is_err());
+        assert!(Session::parse("view\tx\t0\t1\n").is_err());
+        assert!(Session::parse("bogus\t1\n").is_err());
+        assert!(Session::parse("region\t0\t1\t0\t1\tred\tname\n").is_err());
+        assert!(Session::parse("# comment\n\nview\t0\t0\t1\n").is_ok());
+    }
+}
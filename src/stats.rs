@@ -0,0 +1,213 @@
+// Alignment statistics for pipelines
+//
+// What `--stats` prints, in a form scripts can read: counts by strand,
+// aligned bases, the identity distribution and aligned bases per sequence,
+// as JSON or as one long tab-separated table (stat, sequence, value).
+use crate::aln_reader::{calculate_identity, AlnRecord};
+use crate::json::json_string;
+use crate::rust_plot::RustPlot;
+
+/// Identity quantiles reported, with their names
+const QUANTILES: [(&str, f64); 5] = [
+    ("min", 0.0),
+    ("q25", 0.25),
+    ("median", 0.5),
+    ("q75", 0.75),
+    ("max", 1.0),
+];
+
+pub struct SequenceStats {
+    pub name: String,
+    pub length: i64,
+    pub aligned_bp: i64, // Summed over alignments, so overlaps count twice
+}
+
+pub struct AlignmentStats {
+    pub alignments: usize,
+    pub forward: usize,
+    pub reverse: usize,
+    pub aligned_bp: i64,                      // Query bases
+    pub mean_identity: f64,                   // Percent, weighted by query length
+    pub identity_quantiles: Option<[f64; 5]>, // Per alignment, as in QUANTILES
+    pub query: Vec<SequenceStats>,
+    pub target: Vec<SequenceStats>,
+}
+
+impl AlignmentStats {
+    /// Statistics of `records`, with sequence names and lengths from `plot`
+    /// (built from the same records)
+    pub fn new(records: &[AlnRecord], plot: &RustPlot) -> Self {
+        let sequences = |names: &[String], lengths: &[i64]| -> Vec<SequenceStats> {
+            names
+                .iter()
+                .zip(lengths)
+                .map(|(name, &length)| SequenceStats {
+                    name: name.clone(),
+                    length,
+                    aligned_bp: 0,
+                })
+                .collect()
+        };
+        let mut stats = Self {
+            alignments: records.len(),
+            forward: 0,
+            reverse: 0,
+            aligned_bp: 0,
+            mean_identity: 0.0,
+            identity_quantiles: None,
+            query: sequences(&plot.query_sequences, &plot.query_lengths),
+            target: sequences(&plot.target_sequences, &plot.target_lengths),
+        };
+
+        let mut identities = Vec::with_capacity(records.len());
+        let mut weighted_identity = 0.0;
+        for rec in records {
+            let length = rec.query_end - rec.query_start;
+            let identity = calculate_identity(rec);
+            identities.push(identity);
+            weighted_identity += identity * length as f64;
+            stats.aligned_bp += length;
+            if rec.reverse == 0 {
+                stats.forward += 1;
+            } else {
+                stats.reverse += 1;
+            }
+            if let Some(seq) = stats.query.get_mut(rec.query_id as usize) {
+                seq.aligned_bp += length;
+            }
+            if let Some(seq) = stats.target.get_mut(rec.target_id as usize) {
+                seq.aligned_bp += rec.target_end - rec.target_start;
+            }
+        }
+        if stats.aligned_bp > 0 {
+            stats.mean_identity = weighted_identity / stats.aligned_bp as f64;
+        }
+        if !identities.is_empty() {
+            identities.sort_by(f64::total_cmp);
+            let last = identities.len() - 1;
+            stats.identity_quantiles =
+                Some(QUANTILES.map(|(_, q)| identities[(q * last as f64).round() as usize]));
+        }
+        stats
+    }
+
+    pub fn to_json(&self) -> String {
+        let quantiles = match self.identity_quantiles {
+            Some(values) => format!(
+                "{{{}}}",
+                QUANTILES
+                    .iter()
+                    .zip(values)
+                    .map(|((name, _), value)| format!("\"{name}\":{value:.4}"))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+            None => "null".to_string(),
+        };
+        let sequences = |seqs: &[SequenceStats]| {
+            seqs.iter()
+                .map(|seq| {
+                    format!(
+                        "{{\"name\":{},\"length\":{},\"aligned_bp\":{}}}",
+                        json_string(&seq.name),
+                        seq.length,
+                        seq.aligned_bp
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        format!(
+            "{{\"alignments\":{},\"forward\":{},\"reverse\":{},\"aligned_bp\":{},\"mean_identity\":{:.4},\"identity_quantiles\":{quantiles},\"query\":[{}],\"target\":[{}]}}\n",
+            self.alignments,
+            self.forward,
+            self.reverse,
+            self.aligned_bp,
+            self.mean_identity,
+            sequences(&self.query),
+            sequences(&self.target)
+        )
+    }
+
+    /// Long table: whole-file statistics have '.' for the sequence
+    pub fn to_tsv(&self) -> String {
+        let mut text = String::from("stat\tsequence\tvalue\n");
+        for (stat, value) in [
+            ("alignments", self.alignments.to_string()),
+            ("forward", self.forward.to_string()),
+            ("reverse", self.reverse.to_string()),
+            ("aligned_bp", self.aligned_bp.to_string()),
+            ("mean_identity", format!("{:.4}", self.mean_identity)),
+        ] {
+            text.push_str(&format!("{stat}\t.\t{value}\n"));
+        }
+        if let Some(values) = self.identity_quantiles {
+            for ((name, _), value) in QUANTILES.iter().zip(values) {
+                text.push_str(&format!("identity_{name}\t.\t{value:.4}\n"));
+            }
+        }
+        for (stat, seqs) in [
+            ("query_aligned_bp", &self.query),
+            ("target_aligned_bp", &self.target),
+        ] {
+            for seq in seqs {
+                let name = seq.name.replace(['\t', '\n'], " ");
+                text.push_str(&format!("{stat}\t{name}\t{}\n", seq.aligned_bp));
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aln_reader::test_record;
+
+    #[test]
+    fn test_stats_formats() {
+        let records = vec![
+            test_record(0, 0, 100, 0, 0, 100),
+            AlnRecord {
+                reverse: 1,
+                diffs: 10,
+                ..test_record(0, 100, 200, 0, 0, 100)
+            },
+            AlnRecord {
+                diffs: 5,
+                ..test_record(1, 0, 100, 0, 0, 100)
+            },
+        ];
+        let plot = RustPlot::from_records(
+            &records,
+            vec!["q\"1".to_string(), "q2".to_string()],
+            vec!["t1".to_string()],
+            Vec::new(),
+            Vec::new(),
+        );
+        let stats = AlignmentStats::new(&records, &plot);
+        assert_eq!(
+            (stats.forward, stats.reverse, stats.aligned_bp),
+            (2, 1, 300)
+        );
+        assert!((stats.mean_identity - 95.0).abs() < 1e-9);
+        assert_eq!(
+            stats.identity_quantiles,
+            Some([90.0, 95.0, 95.0, 100.0, 100.0])
+        );
+        assert_eq!(stats.query[0].aligned_bp, 200);
+
+        let json = stats.to_json();
+        assert!(
+            json.starts_with("{\"alignments\":3,\"forward\":2,\"reverse\":1,\"aligned_bp\":300,")
+        );
+        assert!(json.contains("\"median\":95.0000"));
+        assert!(json.contains("{\"name\":\"q\\\"1\",\"length\":200,\"aligned_bp\":200}"));
+        let tsv = stats.to_tsv();
+        assert!(tsv.contains("identity_median\t.\t95.0000\n"));
+        assert!(tsv.ends_with("query_aligned_bp\tq2\t100\ntarget_aligned_bp\tt1\t300\n"));
+
+        let empty = AlignmentStats::new(&[], &plot);
+        assert!(empty.to_json().contains("\"identity_quantiles\":null"));
+    }
+}
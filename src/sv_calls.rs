@@ -0,0 +1,307 @@
+// Candidate structural variants from the alignments, as VCF
+//
+// Each query sequence is placed by its main target sequence and strand (the
+// ones with the most aligned bases). Alignments on the main target in the
+// other orientation are inversions; alignments on another target sequence are
+// translocations, anchored where the neighbouring query bases align on the main
+// target. Neighbouring pieces of one event are merged, and each call carries
+// its supporting alignments in INFO so it can be checked against the plot and
+// merged with other SV callsets. Calls are on the target's coordinates: the
+// target is the reference in the VCF.
+use crate::rust_plot::RustPlot;
+use std::collections::HashMap;
+
+/// Alignments shorter than this are not taken as evidence
+pub const DEFAULT_SV_MIN_LENGTH: i64 = 1000;
+
+/// Pieces of one event closer than this on the query are merged
+const SV_MERGE_GAP: i64 = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SvKind {
+    Inversion,
+    Translocation,
+}
+
+/// One candidate, in local 0-based coordinates
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvCall {
+    pub kind: SvKind,
+    pub chrom: usize, // Target sequence
+    pub pos: i64,
+    pub end: i64,                   // Inversions: end of the inverted span
+    pub mate: Option<(usize, i64)>, // Translocations: target sequence and position joined to
+    pub query: (usize, i64, i64),   // Query sequence and the span the evidence covers
+    pub alignments: usize,          // Supporting alignments
+    pub aligned_bp: i64,
+    pub identity: f32, // Length-weighted mean of the supporting alignments
+}
+
+/// A passing alignment in local coordinates
+#[derive(Clone, Copy)]
+struct Piece {
+    query: usize,
+    q_start: i64,
+    q_end: i64,
+    target: usize,
+    t_start: i64,
+    t_end: i64,
+    reverse: bool,
+    identity: f32,
+}
+
+/// Inversions and translocations supported by passing alignments of at least
+/// `min_length` query bases, sorted by target position
+pub fn find_sv_candidates(plot: &RustPlot, min_length: i64) -> Vec<SvCall> {
+    let mut by_query: HashMap<usize, Vec<Piece>> = HashMap::new();
+    for seg in plot.segments.iter().filter(|seg| plot.passes(seg)) {
+        let query = plot.sequence_intervals(0, seg.abeg, seg.aend);
+        let target = plot.sequence_intervals(1, seg.bbeg, seg.bend);
+        let (Some(&(q, q_start, q_end)), Some(&(t, t_start, t_end))) =
+            (query.first(), target.first())
+        else {
+            continue;
+        };
+        by_query.entry(q).or_default().push(Piece {
+            query: q,
+            q_start,
+            q_end,
+            target: t,
+            t_start,
+            t_end,
+            // The alignment's own strand, whichever bands are shown flipped
+            reverse: seg.reverse
+                ^ plot.query_reversed.get(q).copied().unwrap_or(false)
+                ^ plot.target_reversed.get(t).copied().unwrap_or(false),
+            identity: seg.identity,
+        });
+    }
+
+    let mut calls = Vec::new();
+    for (_, mut pieces) in by_query {
+        pieces.sort_by_key(|p| (p.q_start, p.q_end));
+
+        // Main placement: the (target, strand) with the most aligned bases
+        let mut placement: HashMap<(usize, bool), i64> = HashMap::new();
+        for p in &pieces {
+            *placement.entry((p.target, p.reverse)).or_default() += p.q_end - p.q_start;
+        }
+        let Some((&(main, main_reverse), _)) = placement
+            .iter()
+            .max_by_key(|&(&(target, reverse), &bp)| (bp, std::cmp::Reverse((target, reverse))))
+        else {
+            continue;
+        };
+
+        let kind_of = |p: &Piece| {
+            if p.q_end - p.q_start < min_length {
+                None
+            } else if p.target != main {
+                Some(SvKind::Translocation)
+            } else if p.reverse != main_reverse {
+                Some(SvKind::Inversion)
+            } else {
+                None
+            }
+        };
+
+        // Runs of evidence for the same event along the query
+        let mut runs: Vec<(SvKind, Vec<Piece>)> = Vec::new();
+        for p in &pieces {
+            let Some(kind) = kind_of(p) else {
+                continue;
+            };
+            match runs.last_mut() {
+                Some((last_kind, run))
+                    if *last_kind == kind
+                        && run[0].target == p.target
+                        && run[0].reverse == p.reverse
+                        && p.q_start - run.iter().map(|r| r.q_end).max().unwrap_or(0)
+                            <= SV_MERGE_GAP =>
+                {
+                    run.push(*p)
+                }
+                _ => runs.push((kind, vec![*p])),
+            }
+        }
+
+        for (kind, run) in runs {
+            let t_start = run.iter().map(|p| p.t_start).min().unwrap_or(0);
+            let t_end = run.iter().map(|p| p.t_end).max().unwrap_or(0);
+            let q_start = run.iter().map(|p| p.q_start).min().unwrap_or(0);
+            let q_end = run.iter().map(|p| p.q_end).max().unwrap_or(0);
+            let aligned_bp: i64 = run.iter().map(|p| p.q_end - p.q_start).sum();
+            let weighted: f64 = run
+                .iter()
+                .map(|p| p.identity as f64 * (p.q_end - p.q_start) as f64)
+                .sum();
+            let identity = (weighted / aligned_bp.max(1) as f64) as f32;
+
+            let (pos, end, mate) = match kind {
+                SvKind::Inversion => (t_start, t_end, None),
+                SvKind::Translocation => {
+                    // Where the query next to the event sits on the main target
+                    let on_main = |p: &&Piece| p.target == main && p.reverse == main_reverse;
+                    let before = pieces
+                        .iter()
+                        .filter(on_main)
+                        .filter(|p| p.q_end <= q_start)
+                        .max_by_key(|p| p.q_end);
+                    let after = pieces
+                        .iter()
+                        .filter(on_main)
+                        .filter(|p| p.q_start >= q_end)
+                        .min_by_key(|p| p.q_start);
+                    // The query runs along the main target backwards when
+                    // placed on the reverse strand
+                    let anchor = match (before, after) {
+                        (Some(p), _) if main_reverse => p.t_start,
+                        (Some(p), _) => p.t_end,
+                        (None, Some(p)) if main_reverse => p.t_end,
+                        (None, Some(p)) => p.t_start,
+                        (None, None) => continue,
+                    };
+                    (anchor, anchor + 1, Some((run[0].target, t_start)))
+                }
+            };
+            calls.push(SvCall {
+                kind,
+                chrom: main,
+                pos,
+                end,
+                mate,
+                query: (run[0].query, q_start, q_end),
+                alignments: run.len(),
+                aligned_bp,
+                identity,
+            });
+        }
+    }
+    calls.sort_by_key(|call| (call.chrom, call.pos, call.end, call.query));
+    calls
+}
+
+/// VCF 4.2 with symbolic ALTs: <INV> spanning POS..END, and <TRA> with the
+/// joined position in CHR2/POS2
+pub fn to_vcf(calls: &[SvCall], plot: &RustPlot) -> String {
+    let mut text = String::from("##fileformat=VCFv4.2\n##source=alnview\n");
+    for (name, len) in plot.target_sequences.iter().zip(&plot.target_lengths) {
+        text.push_str(&format!("##contig=<ID={name},length={len}>\n"));
+    }
+    for line in [
+        "##ALT=<ID=INV,Description=\"Inversion\">",
+        "##ALT=<ID=TRA,Description=\"Translocation\">",
+        "##INFO=<ID=SVTYPE,Number=1,Type=String,Description=\"Type of structural variant\">",
+        "##INFO=<ID=END,Number=1,Type=Integer,Description=\"End position of the variant\">",
+        "##INFO=<ID=SVLEN,Number=1,Type=Integer,Description=\"Length of the inverted sequence\">",
+        "##INFO=<ID=CHR2,Number=1,Type=String,Description=\"Chromosome of the joined breakend\">",
+        "##INFO=<ID=POS2,Number=1,Type=Integer,Description=\"Position of the joined breakend\">",
+        "##INFO=<ID=QNAME,Number=1,Type=String,Description=\"Query sequence of the evidence\">",
+        "##INFO=<ID=QSTART,Number=1,Type=Integer,Description=\"Query start of the evidence (1-based)\">",
+        "##INFO=<ID=QEND,Number=1,Type=Integer,Description=\"Query end of the evidence\">",
+        "##INFO=<ID=NALN,Number=1,Type=Integer,Description=\"Supporting alignments\">",
+        "##INFO=<ID=ALNBP,Number=1,Type=Integer,Description=\"Query bases in supporting alignments\">",
+        "##INFO=<ID=ALNID,Number=1,Type=Float,Description=\"Mean identity of supporting alignments (%)\">",
+    ] {
+        text.push_str(line);
+        text.push('\n');
+    }
+    text.push_str("#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n");
+
+    for (number, call) in calls.iter().enumerate() {
+        let (query, q_start, q_end) = call.query;
+        let (alt, mut info) = match (call.kind, call.mate) {
+            (SvKind::Translocation, Some((chrom2, pos2))) => (
+                "<TRA>",
+                format!(
+                    "SVTYPE=TRA;CHR2={};POS2={}",
+                    plot.target_sequences[chrom2],
+                    pos2 + 1
+                ),
+            ),
+            _ => (
+                "<INV>",
+                format!("SVTYPE=INV;END={};SVLEN={}", call.end, call.end - call.pos),
+            ),
+        };
+        info.push_str(&format!(
+            ";QNAME={};QSTART={};QEND={q_end};NALN={};ALNBP={};ALNID={:.2}",
+            plot.query_sequences[query],
+            q_start + 1,
+            call.alignments,
+            call.aligned_bp,
+            call.identity
+        ));
+        text.push_str(&format!(
+            "{}\t{}\talnview_{}_{}\tN\t{alt}\t.\tPASS\t{info}\n",
+            plot.target_sequences[call.chrom],
+            call.pos + 1,
+            alt.trim_matches(['<', '>']),
+            number + 1
+        ));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aln_reader::{test_record, AlnRecord};
+
+    #[test]
+    fn test_finds_inversion_and_translocation() {
+        // chrA aligns forward to chr1, except an inverted 20-30 kb piece and a
+        // 50-60 kb piece that sits on chr2
+        let records = vec![
+            test_record(0, 0, 20_000, 0, 0, 20_000),
+            // Reverse target coordinates count from the end of chr1 (100 kb)
+            AlnRecord {
+                reverse: 1,
+                ..test_record(0, 20_000, 30_000, 0, 70_000, 80_000)
+            },
+            test_record(0, 30_000, 50_000, 0, 30_000, 50_000),
+            test_record(0, 50_000, 60_000, 1, 5_000, 15_000),
+            test_record(0, 60_000, 100_000, 0, 50_000, 90_000),
+            // Too short to count
+            test_record(0, 95_000, 95_500, 1, 0, 500),
+        ];
+        let plot = RustPlot::from_records(
+            &records,
+            vec!["chrA".to_string()],
+            vec!["chr1".to_string(), "chr2".to_string()],
+            vec![100_000],
+            vec![100_000, 50_000],
+        );
+
+        let calls = find_sv_candidates(&plot, DEFAULT_SV_MIN_LENGTH);
+        assert_eq!(calls.len(), 2);
+        let inversion = &calls[0];
+        assert_eq!(inversion.kind, SvKind::Inversion);
+        assert_eq!(
+            (inversion.chrom, inversion.pos, inversion.end),
+            (0, 20_000, 30_000)
+        );
+        assert_eq!(inversion.query, (0, 20_000, 30_000));
+        let translocation = &calls[1];
+        assert_eq!(translocation.kind, SvKind::Translocation);
+        assert_eq!((translocation.chrom, translocation.pos), (0, 50_000));
+        assert_eq!(translocation.mate, Some((1, 5_000)));
+
+        let vcf = to_vcf(&calls, &plot);
+        assert!(vcf.contains("##contig=<ID=chr2,length=50000>"));
+        let records: Vec<&str> = vcf.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(
+            records[0],
+            "chr1\t20001\talnview_INV_1\tN\t<INV>\t.\tPASS\tSVTYPE=INV;END=30000;SVLEN=10000;QNAME=chrA;QSTART=20001;QEND=30000;NALN=1;ALNBP=10000;ALNID=100.00"
+        );
+        assert!(records[1].starts_with("chr1\t50001\talnview_TRA_2\tN\t<TRA>"));
+        assert!(records[1].contains("CHR2=chr2;POS2=5001"));
+
+        // The same calls with the query drawn flipped and the targets swapped
+        let arranged = plot.with_layout(&[(0, true)], &[(1, false), (0, false)]);
+        let calls = find_sv_candidates(&arranged, DEFAULT_SV_MIN_LENGTH);
+        let vcf = to_vcf(&calls, &arranged);
+        let arranged_records: Vec<&str> = vcf.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(arranged_records, records);
+    }
+}
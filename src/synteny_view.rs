@@ -0,0 +1,189 @@
+// Linear synteny ribbon view
+//
+// Query sequences are drawn as a horizontal bar along the top of the canvas and
+// target sequences along the bottom, with a ribbon joining the two intervals of
+// every alignment. Each bar shows the window currently visible on that axis of
+// the dotplot, so both views share one viewport and pan/zoom state.
+use crate::extract_display_name;
+use crate::rust_plot::{AlignmentSegment, RustPlot};
+use eframe::egui;
+
+const BAR_HEIGHT: f32 = 18.0;
+const BAR_MARGIN: f32 = 40.0;
+const RIBBON_ALPHA: u8 = 110;
+
+/// Genome window [start, end) shown across the full canvas width
+#[derive(Clone, Copy)]
+pub struct AxisWindow {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl AxisWindow {
+    fn to_x(self, rect: egui::Rect, pos: f64) -> f32 {
+        let frac = (pos - self.start) / (self.end - self.start).max(1.0);
+        rect.min.x + frac as f32 * rect.width()
+    }
+}
+
+/// Draw the synteny view for the given windows and pre-queried segments
+pub fn draw_synteny(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    plot: &RustPlot,
+    query_window: AxisWindow,
+    target_window: AxisWindow,
+    segments: &[AlignmentSegment],
+) {
+    let painter = painter.with_clip_rect(rect);
+    let query_top = rect.min.y + BAR_MARGIN;
+    let target_top = rect.max.y - BAR_MARGIN - BAR_HEIGHT;
+
+    // Ribbons first so the bars sit on top of their ends
+    let ribbon_top = query_top + BAR_HEIGHT;
+    let ribbon_bottom = target_top;
+    for seg in segments {
+        let a0 = egui::pos2(query_window.to_x(rect, seg.abeg as f64), ribbon_top);
+        let a1 = egui::pos2(query_window.to_x(rect, seg.aend as f64), ribbon_top);
+        let b0 = egui::pos2(target_window.to_x(rect, seg.bbeg as f64), ribbon_bottom);
+        let b1 = egui::pos2(target_window.to_x(rect, seg.bend as f64), ribbon_bottom);
+
+        let color = if seg.reverse {
+            egui::Color32::from_rgba_unmultiplied(255, 0, 0, RIBBON_ALPHA)
+        } else {
+            egui::Color32::from_rgba_unmultiplied(0, 255, 0, RIBBON_ALPHA)
+        };
+
+        // Sub-pixel ribbons are just a line between the interval midpoints
+        if (a1.x - a0.x).abs() < 1.0 && (b1.x - b0.x).abs() < 1.0 {
+            painter.line_segment(
+                [
+                    egui::pos2((a0.x + a1.x) / 2.0, ribbon_top),
+                    egui::pos2((b0.x + b1.x) / 2.0, ribbon_bottom),
+                ],
+                egui::Stroke::new(1.0, color),
+            );
+            continue;
+        }
+
+        painter.add(egui::Shape::mesh(ribbon_mesh(a0, a1, b0, b1, color)));
+    }
+
+    draw_bar(
+        &painter,
+        rect,
+        query_top,
+        &plot.query_boundaries,
+        &plot.query_sequences,
+        query_window,
+        true,
+    );
+    draw_bar(
+        &painter,
+        rect,
+        target_top,
+        &plot.target_boundaries,
+        &plot.target_sequences,
+        target_window,
+        false,
+    );
+
+    let label_font = egui::FontId::proportional(11.0);
+    painter.text(
+        egui::pos2(rect.min.x + 5.0, query_top - 20.0),
+        egui::Align2::LEFT_BOTTOM,
+        "Query",
+        label_font.clone(),
+        egui::Color32::LIGHT_GRAY,
+    );
+    painter.text(
+        egui::pos2(rect.min.x + 5.0, target_top + BAR_HEIGHT + 20.0),
+        egui::Align2::LEFT_TOP,
+        "Target",
+        label_font,
+        egui::Color32::LIGHT_GRAY,
+    );
+}
+
+/// Triangulate a ribbon joining query edge a0-a1 to target edge b0-b1
+///
+/// a0 pairs with b0 and a1 with b1, so a reverse alignment yields a twisted
+/// (bow-tie) ribbon whose crossing point is computed explicitly.
+fn ribbon_mesh(
+    a0: egui::Pos2,
+    a1: egui::Pos2,
+    b0: egui::Pos2,
+    b1: egui::Pos2,
+    color: egui::Color32,
+) -> egui::Mesh {
+    let mut mesh = egui::Mesh::default();
+    let top_span = a1.x - a0.x;
+    let bottom_span = b1.x - b0.x;
+
+    if top_span * bottom_span >= 0.0 {
+        // Same orientation: a plain quad
+        for p in [a0, a1, b1, b0] {
+            mesh.colored_vertex(p, color);
+        }
+        mesh.add_triangle(0, 1, 2);
+        mesh.add_triangle(0, 2, 3);
+    } else {
+        // Edges a0-b0 and a1-b1 cross at fraction t of the way down
+        let t = top_span / (top_span - bottom_span);
+        let cross = egui::pos2(a0.x + (b0.x - a0.x) * t, a0.y + (b0.y - a0.y) * t);
+        for p in [a0, a1, cross, b1, b0] {
+            mesh.colored_vertex(p, color);
+        }
+        mesh.add_triangle(0, 1, 2);
+        mesh.add_triangle(2, 3, 4);
+    }
+    mesh
+}
+
+/// Draw a genome as alternating sequence blocks, labelled where there is room
+fn draw_bar(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    top: f32,
+    boundaries: &[i64],
+    names: &[String],
+    window: AxisWindow,
+    labels_above: bool,
+) {
+    let font = egui::FontId::proportional(10.0);
+
+    for (idx, name) in names.iter().enumerate() {
+        let (Some(&beg), Some(&end)) = (boundaries.get(idx), boundaries.get(idx + 1)) else {
+            break;
+        };
+        let x0 = window.to_x(rect, beg as f64).max(rect.min.x);
+        let x1 = window.to_x(rect, end as f64).min(rect.max.x);
+        if x1 <= x0 {
+            continue;
+        }
+
+        let shade = if idx % 2 == 0 { 110 } else { 70 };
+        painter.rect_filled(
+            egui::Rect::from_min_max(egui::pos2(x0, top), egui::pos2(x1, top + BAR_HEIGHT)),
+            0.0,
+            egui::Color32::from_gray(shade),
+        );
+
+        // Roughly 6 px per character at this font size
+        let max_chars = ((x1 - x0) / 6.0) as usize;
+        if max_chars >= 4 {
+            let (y, align) = if labels_above {
+                (top - 2.0, egui::Align2::CENTER_BOTTOM)
+            } else {
+                (top + BAR_HEIGHT + 2.0, egui::Align2::CENTER_TOP)
+            };
+            painter.text(
+                egui::pos2((x0 + x1) / 2.0, y),
+                align,
+                extract_display_name(name, max_chars),
+                font.clone(),
+                egui::Color32::from_gray(200),
+            );
+        }
+    }
+}
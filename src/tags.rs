@@ -0,0 +1,156 @@
+// Curation tags on alignments and regions
+//
+// A tag attaches a short label ("validated inversion", "assembly artifact")
+// and a free-text note to one alignment or one region of interest. Tags are
+// kept by their genome rectangle rather than an index, so they survive
+// reloading, filtering and re-sorting of the alignments, and are saved with
+// the session. Exported per sequence like regions, so a curated list can be
+// taken out of the viewer.
+use crate::rust_plot::{AlignmentSegment, RustPlot};
+
+/// Labels offered when tagging, besides free text
+pub const TAG_SUGGESTIONS: [&str; 5] = [
+    "validated inversion",
+    "validated translocation",
+    "assembly artifact",
+    "collapsed repeat",
+    "needs review",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TagTarget {
+    Segment,
+    Region,
+}
+
+impl TagTarget {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TagTarget::Segment => "segment",
+            TagTarget::Region => "region",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim() {
+            "segment" => Some(TagTarget::Segment),
+            "region" => Some(TagTarget::Region),
+            _ => None,
+        }
+    }
+}
+
+/// A tag on the rectangle [x_min, x_max] x [y_min, y_max] of the plot's
+/// concatenated genome coordinates. Segment tags also keep the alignment's
+/// orientation, so they can be drawn along it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tag {
+    pub target: TagTarget,
+    pub x_min: i64,
+    pub x_max: i64,
+    pub y_min: i64,
+    pub y_max: i64,
+    pub reverse: bool,
+    pub tag: String,
+    pub note: String,
+}
+
+impl Tag {
+    /// An empty tag on one alignment
+    pub fn on_segment(seg: &AlignmentSegment) -> Self {
+        Self {
+            target: TagTarget::Segment,
+            x_min: seg.abeg.min(seg.aend),
+            x_max: seg.abeg.max(seg.aend),
+            y_min: seg.bbeg.min(seg.bend),
+            y_max: seg.bbeg.max(seg.bend),
+            reverse: seg.reverse,
+            tag: String::new(),
+            note: String::new(),
+        }
+    }
+
+    /// Whether this tag is on exactly `seg`
+    pub fn is_on(&self, seg: &AlignmentSegment) -> bool {
+        self.target == TagTarget::Segment
+            && (self.x_min, self.x_max) == (seg.abeg.min(seg.aend), seg.abeg.max(seg.aend))
+            && (self.y_min, self.y_max) == (seg.bbeg.min(seg.bend), seg.bbeg.max(seg.bend))
+            && self.reverse == seg.reverse
+    }
+
+    /// Ends of the tagged alignment in drawing order, (x, y) each
+    pub fn line(&self) -> [(i64, i64); 2] {
+        if self.reverse {
+            [(self.x_min, self.y_max), (self.x_max, self.y_min)]
+        } else {
+            [(self.x_min, self.y_min), (self.x_max, self.y_max)]
+        }
+    }
+}
+
+/// Tab-separated table of tags, one row per query x target sequence pair a
+/// tag spans
+pub fn to_tsv(tags: &[Tag], plot: &RustPlot) -> String {
+    let mut text = String::from(
+        "kind\ttag\tnote\tquery\tquery_start\tquery_end\ttarget\ttarget_start\ttarget_end\n",
+    );
+    for tag in tags {
+        let targets = plot.sequence_intervals(1, tag.y_min, tag.y_max);
+        for (qi, q_start, q_end) in plot.sequence_intervals(0, tag.x_min, tag.x_max) {
+            for &(ti, t_start, t_end) in &targets {
+                text.push_str(&format!(
+                    "{}\t{}\t{}\t{}\t{q_start}\t{q_end}\t{}\t{t_start}\t{t_end}\n",
+                    tag.target.as_str(),
+                    column(&tag.tag),
+                    column(&tag.note),
+                    plot.query_sequences[qi],
+                    plot.target_sequences[ti]
+                ));
+            }
+        }
+    }
+    text
+}
+
+/// Free text in a single tab-separated column
+fn column(text: &str) -> String {
+    let text = text.trim().replace(['\t', '\n'], " ");
+    if text.is_empty() {
+        ".".to_string()
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aln_reader::{test_record, AlnRecord};
+
+    #[test]
+    fn test_segment_tag_export() {
+        let records = vec![AlnRecord {
+            reverse: 1,
+            ..test_record(1, 100, 300, 0, 50, 250)
+        }];
+        let plot = RustPlot::from_records(
+            &records,
+            vec!["q1".to_string(), "q2".to_string()],
+            vec!["t1".to_string()],
+            vec![1000, 500],
+            vec![2000],
+        );
+
+        let mut tag = Tag::on_segment(&plot.segments[0]);
+        assert!(tag.is_on(&plot.segments[0]));
+        tag.tag = "validated inversion".to_string();
+        tag.note = "checked\twith reads".to_string();
+        let tsv = to_tsv(&[tag], &plot);
+        let rows: Vec<&str> = tsv.lines().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[1],
+            "segment\tvalidated inversion\tchecked with reads\tq2\t100\t300\tt1\t1750\t1950"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+// Per-stage wall time and memory, for --timings
+//
+// Each pipeline stage (reading the file, converting records to a plot,
+// arranging sequences, rendering) is timed as it runs, together with the
+// process's peak resident memory once it is done, so a slow or memory-hungry
+// load can be reported with numbers. Peak memory comes from /proc and is only
+// known on Linux.
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub struct Stage {
+    pub name: &'static str,
+    pub wall: Duration,
+    /// Peak resident memory of the whole process after the stage, in bytes
+    pub peak_memory: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Timings {
+    pub stages: Vec<Stage>,
+}
+
+impl Timings {
+    /// Run `f` as stage `name`, recording how long it took
+    pub fn time<T>(&mut self, name: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record(name, start.elapsed());
+        result
+    }
+
+    /// Record a stage timed elsewhere (e.g. a frame drawn by the GUI)
+    pub fn record(&mut self, name: &'static str, wall: Duration) {
+        self.stages.push(Stage {
+            name,
+            wall,
+            peak_memory: peak_memory_bytes(),
+        });
+    }
+
+    /// One line per stage, then the total
+    pub fn report(&self) -> String {
+        let mut text = String::new();
+        for stage in &self.stages {
+            text.push_str(&format_stage(stage.name, stage.wall, stage.peak_memory));
+        }
+        let total: Duration = self.stages.iter().map(|s| s.wall).sum();
+        let peak = self.stages.iter().filter_map(|s| s.peak_memory).max();
+        text.push_str(&format_stage("total", total, peak));
+        text
+    }
+}
+
+fn format_stage(name: &str, wall: Duration, peak_memory: Option<u64>) -> String {
+    let memory = match peak_memory {
+        Some(bytes) => format!("{:.1} MB", bytes as f64 / 1e6),
+        None => "n/a".to_string(),
+    };
+    format!(
+        "⏱  {name:<10} {:>10.3} s   peak memory {memory}\n",
+        wall.as_secs_f64()
+    )
+}
+
+/// Peak resident set size of this process so far (VmHWM), on Linux
+pub fn peak_memory_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    parse_vm_hwm(&status)
+}
+
+fn parse_vm_hwm(status: &str) -> Option<u64> {
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_lists_stages_and_total() {
+        let mut timings = Timings::default();
+        timings.stages.push(Stage {
+            name: "read",
+            wall: Duration::from_millis(1500),
+            peak_memory: Some(2_000_000),
+        });
+        timings.stages.push(Stage {
+            name: "render",
+            wall: Duration::from_millis(500),
+            peak_memory: None,
+        });
+
+        let lines: Vec<String> = timings.report().lines().map(str::to_string).collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("read") && lines[0].contains("1.500 s"));
+        assert!(lines[0].contains("2.0 MB"));
+        assert!(lines[1].contains("n/a"));
+        assert!(lines[2].contains("total") && lines[2].contains("2.000 s"));
+    }
+
+    #[test]
+    fn test_parse_vm_hwm() {
+        let status = "Name:\talnview\nVmPeak:\t  900 kB\nVmHWM:\t  1234 kB\n";
+        assert_eq!(parse_vm_hwm(status), Some(1234 * 1024));
+        assert_eq!(parse_vm_hwm("Name:\tx\n"), None);
+    }
+}
@@ -0,0 +1,247 @@
+// Built-in web viewer
+//
+// `alnview serve FILE` answers HTTP with a small canvas frontend
+// (web/index.html) and the plot as JSON, so alignments on a compute server can
+// be explored from a browser over an SSH tunnel. The frontend asks for the
+// segments in its viewport; when there are more than it can draw, a
+// length-weighted sample is sent, as for downsampled plots. Plain std::net,
+// one thread per connection, GET only.
+use crate::json::{json_numbers, json_string};
+use crate::rust_plot::RustPlot;
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+pub const DEFAULT_PORT: u16 = 8080;
+
+/// Most segments sent for one viewport
+const MAX_VIEW_SEGMENTS: usize = 20_000;
+
+const INDEX_HTML: &str = include_str!("../web/index.html");
+
+/// Serve `plot` on `addr` (host:port) until the process is stopped
+pub fn serve(plot: RustPlot, addr: &str) -> Result<()> {
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("Failed to listen on {addr}"))?;
+    println!(
+        "🌐 Serving {} alignments on http://{}/ (Ctrl-C to stop)",
+        plot.segments.len(),
+        listener.local_addr()?
+    );
+    let plot = Arc::new(plot);
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let plot = Arc::clone(&plot);
+        thread::spawn(move || {
+            if let Err(e) = handle(stream, &plot) {
+                eprintln!("⚠️  Request failed: {e:#}");
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle(mut stream: TcpStream, plot: &RustPlot) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Headers are not needed; read past them
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => respond(plot, target),
+        _ => (405, "text/plain", "Only GET is supported".to_string()),
+    };
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        _ => "Method Not Allowed",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body.as_bytes())?;
+    Ok(())
+}
+
+/// Status, content type and body for a request target (path and query)
+pub fn respond(plot: &RustPlot, target: &str) -> (u16, &'static str, String) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    match path {
+        "/" | "/index.html" => (200, "text/html; charset=utf-8", INDEX_HTML.to_string()),
+        "/plot.json" => (200, "application/json", plot_json(plot)),
+        "/segments.json" => match Viewport::parse(query) {
+            Ok(view) => (200, "application/json", segments_json(plot, &view)),
+            Err(e) => (400, "text/plain", e.to_string()),
+        },
+        _ => (404, "text/plain", "Not found".to_string()),
+    }
+}
+
+/// Genome rectangle asked for by the frontend, and how many segments it wants
+#[derive(Debug, PartialEq)]
+pub struct Viewport {
+    pub x_min: i64,
+    pub x_max: i64,
+    pub y_min: i64,
+    pub y_max: i64,
+    pub max: usize,
+}
+
+impl Viewport {
+    /// From `x_min=..&x_max=..&y_min=..&y_max=..[&max=..]`
+    pub fn parse(query: &str) -> Result<Self> {
+        let mut view = Self {
+            x_min: 0,
+            x_max: i64::MAX,
+            y_min: 0,
+            y_max: i64::MAX,
+            max: MAX_VIEW_SEGMENTS,
+        };
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let number = || {
+                value
+                    .parse::<f64>()
+                    .with_context(|| format!("invalid number for {key}: '{value}'"))
+            };
+            match key {
+                "x_min" => view.x_min = number()? as i64,
+                "x_max" => view.x_max = number()? as i64,
+                "y_min" => view.y_min = number()? as i64,
+                "y_max" => view.y_max = number()? as i64,
+                "max" => view.max = (number()? as usize).min(MAX_VIEW_SEGMENTS),
+                _ => {}
+            }
+        }
+        Ok(view)
+    }
+}
+
+/// Sequence names, lengths and starts on both axes
+pub fn plot_json(plot: &RustPlot) -> String {
+    let axis = |names: &[String], lengths: &[i64], starts: &[i64]| {
+        format!(
+            "{{\"names\":[{}],\"lengths\":{},\"starts\":{}}}",
+            names
+                .iter()
+                .map(|name| json_string(name))
+                .collect::<Vec<_>>()
+                .join(","),
+            json_numbers(lengths),
+            json_numbers(&starts[..lengths.len().min(starts.len())])
+        )
+    };
+    format!(
+        "{{\"query\":{},\"target\":{},\"query_len\":{},\"target_len\":{},\"segments\":{}}}",
+        axis(
+            &plot.query_sequences,
+            &plot.query_lengths,
+            &plot.query_boundaries
+        ),
+        axis(
+            &plot.target_sequences,
+            &plot.target_lengths,
+            &plot.target_boundaries
+        ),
+        plot.query_genome_len,
+        plot.target_genome_len,
+        plot.passing_segments()
+    )
+}
+
+/// Passing segments in the viewport as [abeg, bbeg, aend, bend, reverse,
+/// identity] rows, sampled by length if there are more than `view.max`
+pub fn segments_json(plot: &RustPlot, view: &Viewport) -> String {
+    let mut visible: Vec<usize> = plot
+        .segments
+        .iter()
+        .enumerate()
+        .filter(|(_, seg)| {
+            plot.passes(seg) && seg.intersects(view.x_min, view.x_max, view.y_min, view.y_max)
+        })
+        .map(|(idx, _)| idx)
+        .collect();
+    let total = visible.len();
+    if total > view.max {
+        let mut keyed: Vec<(f64, usize)> = visible
+            .iter()
+            .map(|&idx| (RustPlot::sample_key(idx, &plot.segments[idx]), idx))
+            .collect();
+        visible = if view.max == 0 {
+            Vec::new()
+        } else {
+            keyed.select_nth_unstable_by(view.max - 1, |a, b| b.0.total_cmp(&a.0));
+            keyed[..view.max].iter().map(|&(_, idx)| idx).collect()
+        };
+        visible.sort_unstable();
+    }
+
+    let rows: Vec<String> = visible
+        .iter()
+        .map(|&idx| {
+            let seg = &plot.segments[idx];
+            format!(
+                "[{},{},{},{},{},{:.2}]",
+                seg.abeg,
+                seg.bbeg,
+                seg.aend,
+                seg.bend,
+                u8::from(seg.reverse),
+                seg.identity
+            )
+        })
+        .collect();
+    format!("{{\"total\":{total},\"segments\":[{}]}}", rows.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aln_reader::test_record;
+
+    #[test]
+    fn test_viewport_segments() {
+        let records = vec![
+            test_record(0, 0, 100, 0, 0, 100),
+            test_record(0, 500, 900, 0, 500, 900),
+        ];
+        let plot = RustPlot::from_records(
+            &records,
+            vec!["q\"1".to_string()],
+            vec!["t1".to_string()],
+            vec![1000],
+            vec![1000],
+        );
+
+        let view = Viewport::parse("x_min=400&x_max=1000&y_min=0&y_max=1000").unwrap();
+        assert_eq!(
+            segments_json(&plot, &view),
+            "{\"total\":1,\"segments\":[[500,500,900,900,0,100.00]]}"
+        );
+        // Over the limit, a sample is sent along with the full count
+        let view = Viewport::parse("max=1").unwrap();
+        let json = segments_json(&plot, &view);
+        assert!(json.starts_with("{\"total\":2,\"segments\":[["));
+        assert_eq!(json.matches("],[").count(), 0);
+        assert!(Viewport::parse("x_min=left").is_err());
+
+        assert!(plot_json(&plot)
+            .starts_with("{\"query\":{\"names\":[\"q\\\"1\"],\"lengths\":[1000],\"starts\":[0]}"));
+        let (status, _, _) = respond(&plot, "/nowhere");
+        assert_eq!(status, 404);
+    }
+}
@@ -0,0 +1,189 @@
+// Wrapped dotplot for genomes of very different lengths
+//
+// The longer genome is cut into equal slices stacked as rows, each row a
+// dotplot of its slice against the whole shorter genome, so a chromosome
+// against a few small contigs keeps a useful resolution on a normal monitor.
+// The longer genome always runs along the rows, whichever axis it is on.
+use crate::extract_display_name;
+use crate::rust_plot::{AlignmentSegment, RustPlot};
+use eframe::egui;
+
+const ROW_GAP: f32 = 8.0;
+const MAX_ROWS: usize = 32;
+
+/// Number of rows that makes a bp about the same size on both axes, for a
+/// long genome wrapped across a `width` x `height` canvas
+pub fn auto_rows(long: f64, short: f64, width: f32, height: f32) -> usize {
+    if long <= 0.0 || short <= 0.0 || width <= 0.0 || height <= 0.0 {
+        return 1;
+    }
+    // Row scale long / (rows * width) equals short * rows / height
+    let rows = (long * height as f64 / (short * width as f64))
+        .sqrt()
+        .round();
+    (rows as usize).clamp(1, MAX_ROWS)
+}
+
+/// Part of a line within one row: (row, start, end), points as (long, short)
+type RowPiece = (usize, (f64, f64), (f64, f64));
+
+/// Pieces of the line (l0, s0)-(l1, s1) falling in each row of length
+/// `row_len` along the long axis
+fn split_by_row(
+    (l0, s0): (f64, f64),
+    (l1, s1): (f64, f64),
+    row_len: f64,
+    rows: usize,
+) -> Vec<RowPiece> {
+    let (lo, hi) = (l0.min(l1), l0.max(l1));
+    let first = ((lo / row_len) as usize).min(rows - 1);
+    let last = ((hi / row_len) as usize).min(rows - 1);
+    let at = |l: f64| {
+        if l1 == l0 {
+            s0
+        } else {
+            s0 + (s1 - s0) * (l - l0) / (l1 - l0)
+        }
+    };
+    (first..=last)
+        .map(|row| {
+            let start = lo.max(row as f64 * row_len);
+            let end = hi.min((row + 1) as f64 * row_len);
+            (row, (start, at(start)), (end, at(end)))
+        })
+        .collect()
+}
+
+/// Draw the whole plot wrapped into `rows` rows (0 picks the count
+/// automatically), with each segment in the colour it was given
+pub fn draw_wrapped(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    plot: &RustPlot,
+    rows: usize,
+    segments: &[(AlignmentSegment, egui::Color32)],
+) {
+    let painter = painter.with_clip_rect(rect);
+    painter.rect_filled(rect, 0.0, egui::Color32::BLACK);
+
+    let (alen, blen) = (plot.get_alen() as f64, plot.get_blen() as f64);
+    if alen <= 0.0 || blen <= 0.0 {
+        return;
+    }
+    let query_long = alen >= blen;
+    let (long, short) = if query_long {
+        (alen, blen)
+    } else {
+        (blen, alen)
+    };
+    let (long_bounds, long_names, short_bounds) = if query_long {
+        (
+            &plot.query_boundaries,
+            &plot.query_sequences,
+            &plot.target_boundaries,
+        )
+    } else {
+        (
+            &plot.target_boundaries,
+            &plot.target_sequences,
+            &plot.query_boundaries,
+        )
+    };
+
+    let rows = if rows == 0 {
+        auto_rows(long, short, rect.width(), rect.height())
+    } else {
+        rows.min(MAX_ROWS)
+    };
+    let row_len = long / rows as f64;
+    let row_height = ((rect.height() - ROW_GAP * (rows - 1) as f32) / rows as f32).max(1.0);
+    let row_rect = |row: usize| {
+        let top = rect.min.y + row as f32 * (row_height + ROW_GAP);
+        egui::Rect::from_min_size(
+            egui::pos2(rect.min.x, top),
+            egui::vec2(rect.width(), row_height),
+        )
+    };
+    let to_screen = |row: usize, l: f64, s: f64| {
+        let r = row_rect(row);
+        egui::pos2(
+            r.min.x + ((l - row as f64 * row_len) / row_len) as f32 * r.width(),
+            r.max.y - (s / short) as f32 * r.height(),
+        )
+    };
+
+    // Sequence boundaries and row frames
+    let grid = egui::Stroke::new(1.0, egui::Color32::from_rgb(100, 100, 100));
+    let label_font = egui::FontId::proportional(10.0);
+    for row in 0..rows {
+        let r = row_rect(row);
+        let (row_start, row_end) = (row as f64 * row_len, (row + 1) as f64 * row_len);
+        for &b in &short_bounds[1..short_bounds.len().saturating_sub(1)] {
+            painter.hline(r.x_range(), to_screen(row, row_start, b as f64).y, grid);
+        }
+        for (idx, &b) in long_bounds.iter().enumerate() {
+            let b = b as f64;
+            if b < row_start || b > row_end {
+                continue;
+            }
+            let x = to_screen(row, b, 0.0).x;
+            painter.vline(x, r.y_range(), grid);
+            if let Some(name) = long_names.get(idx) {
+                painter.text(
+                    egui::pos2(x + 3.0, r.min.y + 2.0),
+                    egui::Align2::LEFT_TOP,
+                    extract_display_name(name, 20),
+                    label_font.clone(),
+                    egui::Color32::from_gray(200),
+                );
+            }
+        }
+        painter.rect_stroke(r, 0.0, egui::Stroke::new(1.0, egui::Color32::GRAY));
+    }
+
+    for (seg, color) in segments {
+        let (start, end) = if query_long {
+            (
+                (seg.abeg as f64, seg.bbeg as f64),
+                (seg.aend as f64, seg.bend as f64),
+            )
+        } else {
+            (
+                (seg.bbeg as f64, seg.abeg as f64),
+                (seg.bend as f64, seg.aend as f64),
+            )
+        };
+        for (row, (l0, s0), (l1, s1)) in split_by_row(start, end, row_len, rows) {
+            painter.line_segment(
+                [to_screen(row, l0, s0), to_screen(row, l1, s1)],
+                egui::Stroke::new(1.0, *color),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_rows() {
+        // 100 Mb against 1 Mb on a square canvas: 10 rows of 10 Mb
+        assert_eq!(auto_rows(100e6, 1e6, 1000.0, 1000.0), 10);
+        assert_eq!(auto_rows(1e6, 1e6, 1000.0, 1000.0), 1);
+        assert_eq!(auto_rows(1e12, 1.0, 1000.0, 1000.0), MAX_ROWS);
+    }
+
+    #[test]
+    fn test_split_by_row() {
+        let pieces = split_by_row((50.0, 0.0), (250.0, 20.0), 100.0, 3);
+        assert_eq!(
+            pieces,
+            vec![
+                (0, (50.0, 0.0), (100.0, 5.0)),
+                (1, (100.0, 5.0), (200.0, 15.0)),
+                (2, (200.0, 15.0), (250.0, 20.0)),
+            ]
+        );
+    }
+}
@@ -17,7 +17,7 @@ fn test_render_test_1aln_matches_golden() {
 
     // Run alnview to render the plot
     let status = Command::new("cargo")
-        .args(&[
+        .args([
             "run",
             "--release",
             "--",
@@ -0,0 +1,87 @@
+use std::path::Path;
+use std::process::Command;
+
+/// stdout of `alnview test.1aln --stats` with extra arguments
+fn stats(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_alnview"))
+        .args(["test.1aln", "--stats"])
+        .args(args)
+        .output()
+        .expect("Failed to run alnview");
+    assert!(output.status.success(), "alnview {args:?} failed");
+    String::from_utf8(output.stdout).expect("stdout is not UTF-8")
+}
+
+/// A whole-file value from `--stats --format tsv`
+fn tsv_value(tsv: &str, stat: &str) -> i64 {
+    tsv.lines()
+        .find_map(|line| line.strip_prefix(&format!("{stat}\t.\t")))
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| panic!("no {stat} in:\n{tsv}"))
+}
+
+/// The count after `label` in text `--stats`
+fn text_value(text: &str, label: &str) -> i64 {
+    text.lines()
+        .find_map(|line| line.trim().strip_prefix(label))
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or_else(|| panic!("no {label} in:\n{text}"))
+}
+
+/// Every format counts the alignments a plot would draw, after the sweep
+#[test]
+fn test_stats_formats_follow_the_sweep() {
+    if !Path::new("test.1aln").exists() {
+        eprintln!("Warning: test.1aln not found, skipping test");
+        return;
+    }
+
+    let all = tsv_value(&stats(&["--format", "tsv"]), "alignments");
+    let tsv = stats(&["--format", "tsv", "--filter", "1:1"]);
+    let swept = tsv_value(&tsv, "alignments");
+    assert!(swept > 0 && swept < all, "{swept} of {all}");
+    assert_eq!(
+        tsv_value(&tsv, "forward") + tsv_value(&tsv, "reverse"),
+        swept
+    );
+
+    let json = stats(&["--format", "json", "--filter", "1:1"]);
+    assert!(
+        json.starts_with(&format!("{{\"alignments\":{swept},")),
+        "{json}"
+    );
+    let text = stats(&["--filter", "1:1"]);
+    assert_eq!(text_value(&text, "Total alignments:"), swept);
+    assert_eq!(
+        text_value(&text, "Total aligned bases:"),
+        tsv_value(&tsv, "aligned_bp")
+    );
+}
+
+/// --min-identity drops alignments from every count, as from a plot
+#[test]
+fn test_stats_follow_min_identity() {
+    if !Path::new("test.1aln").exists() {
+        eprintln!("Warning: test.1aln not found, skipping test");
+        return;
+    }
+
+    let all = stats(&["--format", "tsv"]);
+    let tsv = stats(&["--format", "tsv", "--min-identity", "99"]);
+    let kept = tsv_value(&tsv, "alignments");
+    assert!(kept > 0 && kept < tsv_value(&all, "alignments"));
+    assert!(tsv_value(&tsv, "aligned_bp") < tsv_value(&all, "aligned_bp"));
+    let min_identity = tsv
+        .lines()
+        .find_map(|line| line.strip_prefix("identity_min\t.\t"))
+        .and_then(|value| value.parse::<f64>().ok())
+        .expect("no identity_min");
+    assert!(min_identity >= 99.0, "{min_identity}");
+
+    let text = stats(&["--min-identity", "99"]);
+    assert_eq!(text_value(&text, "Total alignments:"), kept);
+    assert_eq!(
+        text_value(&text, "Total aligned bases:"),
+        tsv_value(&tsv, "aligned_bp")
+    );
+}